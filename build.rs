@@ -0,0 +1,40 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// 打开 embed-assets 时把 resources 目录下的音乐和音效列成一张表，由 src/assets.rs 用 include_bytes 编进程序。
+// 关卡和平衡数据本来就用 include_str 编进去了，这里跳过 .toml 文件
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=resources");
+    let manifest_dir = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").expect("缺少 CARGO_MANIFEST_DIR"));
+    let mut files = Vec::new();
+    if env::var_os("CARGO_FEATURE_EMBED_ASSETS").is_some() {
+        collect(&manifest_dir.join("resources"), "", &mut files);
+    }
+    files.sort();
+
+    let mut table = String::from("&[\n");
+    for (name, path) in files {
+        table += &format!("    ({:?}, include_bytes!({:?})),\n", name, path);
+    }
+    table += "]\n";
+    let out = PathBuf::from(env::var_os("OUT_DIR").expect("缺少 OUT_DIR")).join("embedded_assets.rs");
+    fs::write(out, table).expect("无法写入嵌入资源表");
+}
+
+// 递归列出目录下的文件，name 是 ggez 使用的路径形式，例如 "/sfx/crit.ogg"
+fn collect(dir: &Path, prefix: &str, files: &mut Vec<(String, PathBuf)>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = format!("{}/{}", prefix, entry.file_name().to_string_lossy());
+        if path.is_dir() {
+            collect(&path, &name, files);
+        } else if path.extension().is_none_or(|extension| extension != "toml") {
+            files.push((name, path));
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use ggez::audio::{self, SoundData};
+use ggez::{Context, GameResult};
+
+// 用 embed-assets 构建时，resources 目录下的音乐和音效都编进了可执行文件，发布时只需要一个文件
+pub const EMBEDDED: bool = cfg!(feature = "embed-assets");
+
+// 嵌入的文件表：(路径, 内容)，路径和 ggez 的资源路径写法相同，例如 "/sfx/crit.ogg"。
+// 表由 build.rs 生成，没有打开 embed-assets 时是空的
+static FILES: &[(&str, &[u8])] = include!(concat!(env!("OUT_DIR"), "/embedded_assets.rs"));
+
+// 所有嵌入的文件路径
+pub fn embedded_paths() -> impl Iterator<Item = &'static str> {
+    FILES.iter().map(|(path, _)| *path)
+}
+
+// 读取嵌入的文件，没有这个文件时返回 None
+pub fn embedded(path: &str) -> Option<&'static [u8]> {
+    FILES.iter().find(|(name, _)| *name == path).map(|(_, bytes)| *bytes)
+}
+
+// 打开一个音频资源：先找嵌入的文件，找不到再从资源目录读取。
+// 平时的构建不嵌入任何文件，改了资源目录里的音乐和音效重新启动就能听到
+pub fn sound(ctx: &Context, path: &str) -> GameResult<audio::Source> {
+    match embedded(path) {
+        Some(bytes) => audio::Source::from_data(ctx, SoundData::from_bytes(bytes)),
+        None => audio::Source::new(ctx, path),
+    }
+}
+
+// 在后台线程里读取资源文件，主线程每帧取回读好的部分，读盘慢时窗口也不会卡住。
+// 嵌入的文件不用读盘，和读不到的文件一样交给 sound 处理
+pub struct AssetLoader {
+    receiver: Receiver<(String, Option<Vec<u8>>)>,
+    total: usize,
+    loaded: Vec<(String, Option<Vec<u8>>)>,
+}
+
+impl AssetLoader {
+    // dir 是资源目录，ggez 也从这里读取；paths 是要读的文件，写法和 ggez 的资源路径相同
+    pub fn start(dir: Option<PathBuf>, paths: Vec<String>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let total = paths.len();
+        thread::spawn(move || {
+            for path in paths {
+                let bytes = match embedded(&path) {
+                    Some(_) => None,
+                    None => dir.as_ref().and_then(|dir| fs::read(dir.join(path.trim_start_matches('/'))).ok()),
+                };
+                if sender.send((path, bytes)).is_err() {
+                    return;
+                }
+            }
+        });
+        Self { receiver, total, loaded: Vec::new() }
+    }
+
+    // 取回后台线程读好的文件，返回是否全部读完
+    pub fn poll(&mut self) -> bool {
+        self.loaded.extend(self.receiver.try_iter());
+        self.finished()
+    }
+
+    pub fn finished(&self) -> bool {
+        self.loaded.len() >= self.total
+    }
+
+    // 已经读进内存、还没交出去的字节数
+    pub fn bytes(&self) -> usize {
+        self.loaded.iter().filter_map(|(_, bytes)| bytes.as_ref()).map(Vec::len).sum()
+    }
+
+    // 0 到 1 的进度
+    pub fn progress(&self) -> f32 {
+        if self.total == 0 {
+            return 1.0;
+        }
+        self.loaded.len() as f32 / self.total as f32
+    }
+
+    // 用读好的内容打开音频资源，后台没读到时回到 sound，从嵌入的文件或 ggez 的文件系统读取
+    pub fn sound(&mut self, ctx: &Context, path: &str) -> GameResult<audio::Source> {
+        let bytes = self.loaded.iter_mut().find(|(name, _)| *name == path).and_then(|(_, bytes)| bytes.take());
+        match bytes {
+            Some(bytes) => audio::Source::from_data(ctx, SoundData::from(bytes)),
+            None => sound(ctx, path),
+        }
+    }
+}
+
+// 一关专用的资源组。战役中同时只保留当前这一关和下一关的两组，离开一关后整组释放，内存不会随关卡增长
+pub struct StageAssets {
+    pub stage: usize,
+    loader: AssetLoader,
+    pub bytes: usize,  // 这一组读进内存的字节数，调试界面显示
+    pub applied: bool,  // 是否已经交给音乐和音效使用
+}
+
+impl StageAssets {
+    pub fn start(stage: usize, dir: Option<PathBuf>, paths: Vec<String>) -> Self {
+        Self { stage, loader: AssetLoader::start(dir, paths), bytes: 0, applied: false }
+    }
+
+    // 取回读好的文件，返回是否全部读完
+    pub fn poll(&mut self) -> bool {
+        let finished = self.loader.poll();
+        self.bytes = self.bytes.max(self.loader.bytes());
+        finished
+    }
+
+    pub fn sound(&mut self, ctx: &Context, path: &str) -> GameResult<audio::Source> {
+        self.loader.sound(ctx, path)
+    }
+}
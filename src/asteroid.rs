@@ -0,0 +1,63 @@
+use ggez::glam::Vec2;
+use ggez::graphics::Color;
+use rand::{rngs::StdRng, Rng};
+
+use crate::collision::Collider;
+use crate::enemy::{Enemy, EnemyPath};
+use crate::playfield::PlayField;
+use crate::world::DamageSource;
+
+pub const INTERVAL: f32 = 10.0;  // 平均每隔多少秒飘来一颗小行星
+const LARGEST: u32 = 3;  // 新生成的小行星都是最大的一级
+const SPLIT_ANGLE: f32 = 0.5;  // 分裂出的两块偏离原方向的角度（弧度）
+pub const COLOR: Color = Color::new(0.55, 0.45, 0.35, 1.0);  // 灰褐色，和所有敌人都不一样
+
+// 各级小行星的大小、血量和击碎时的得分，下标是等级减 1
+const SIZES: [f32; 3] = [20.0, 34.0, 52.0];
+const HP: [u32; 3] = [1, 3, 6];
+const POINTS: [u32; 3] = [5, 10, 20];
+
+// 中立的小行星：缓缓飘下，能挡住好几发子弹，撞上玩家会扣命，敌人的子弹也能打碎它。
+// 击碎时分裂成两块小一级的，最小的一级直接碎掉。飞出底部不扣生命
+pub fn new(position: Vec2, velocity: Vec2, tier: u32) -> Enemy {
+    let index = tier.clamp(1, LARGEST) as usize - 1;
+    let mut asteroid = Enemy::new(position.x, position.y, SIZES[index], 0.0, EnemyPath::Straight);
+    asteroid.game_object.velocity = velocity;
+    asteroid.set_hp(HP[index]);
+    asteroid.points = POINTS[index];
+    asteroid.escape_penalty = false;
+    asteroid.source = DamageSource::Asteroid;
+    asteroid.collider = Collider::ASTEROID;
+    asteroid.asteroid = Some(tier);
+    asteroid
+}
+
+// 在屏幕上方随机位置生成一颗最大的小行星，稍微斜着往下飘
+pub fn spawn(field: &PlayField, rng: &mut StdRng) -> Enemy {
+    let x = rng.gen_range(field.spawn_x(SIZES[LARGEST as usize - 1] / 2.0));
+    let velocity = Vec2::new(rng.gen_range(-30.0..30.0), rng.gen_range(50.0..90.0));
+    new(Vec2::new(x, field.spawn_y(SIZES[LARGEST as usize - 1])), velocity, LARGEST)
+}
+
+// 下一颗小行星出现前的秒数，在平均间隔上下浮动一半
+pub fn next_interval(rng: &mut StdRng) -> f32 {
+    rng.gen_range(INTERVAL * 0.5..INTERVAL * 1.5)
+}
+
+// 被击碎的小行星分裂出的碎块，不是小行星或已经是最小一级时为空
+pub fn fragments(enemy: &Enemy) -> Vec<Enemy> {
+    let Some(tier) = enemy.asteroid.filter(|&tier| tier > 1) else {
+        return Vec::new();
+    };
+    let object = &enemy.game_object;
+    let offset = Vec2::new(object.size.x / 4.0, 0.0);
+    // 碎块比原来飘得快一些，向两侧散开
+    let velocity = object.velocity * 1.3;
+    [-1.0, 1.0]
+        .into_iter()
+        .map(|side| {
+            let direction = Vec2::from_angle(side * SPLIT_ANGLE).rotate(velocity);
+            new(object.position + offset * side, direction, tier - 1)
+        })
+        .collect()
+}
@@ -0,0 +1,165 @@
+use ggez::{
+    audio::{self, SoundSource},
+    Context,
+};
+
+use crate::assets::AssetLoader;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MusicTrack {
+    Stage,
+    Boss,
+}
+
+impl MusicTrack {
+    const ALL: [MusicTrack; 2] = [MusicTrack::Stage, MusicTrack::Boss];
+
+    fn path(self) -> &'static str {
+        match self {
+            MusicTrack::Stage => "/music/stage.ogg",
+            MusicTrack::Boss => "/music/boss.ogg",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SoundEffect {
+    CriticalHit,
+    ExtraLife,
+    ChargedShot,
+}
+
+impl SoundEffect {
+    const ALL: [SoundEffect; 3] = [SoundEffect::CriticalHit, SoundEffect::ExtraLife, SoundEffect::ChargedShot];
+
+    fn path(self) -> &'static str {
+        match self {
+            SoundEffect::CriticalHit => "/sfx/crit.ogg",
+            SoundEffect::ExtraLife => "/sfx/1up.ogg",
+            SoundEffect::ChargedShot => "/sfx/charge.ogg",
+        }
+    }
+}
+
+// 启动时要读取的所有音乐和音效
+pub fn asset_paths() -> Vec<String> {
+    MusicTrack::ALL
+        .into_iter()
+        .map(MusicTrack::path)
+        .chain(SoundEffect::ALL.into_iter().map(SoundEffect::path))
+        .map(String::from)
+        .collect()
+}
+
+// 音效，和背景音乐一样缺少的文件直接跳过
+#[derive(Default)]
+pub struct Sounds {
+    sources: Vec<(SoundEffect, audio::Source)>,
+}
+
+impl Sounds {
+    pub fn load(ctx: &Context, loader: &mut AssetLoader) -> Self {
+        let sources = SoundEffect::ALL
+            .into_iter()
+            .filter_map(|effect| Some((effect, loader.sound(ctx, effect.path()).ok()?)))
+            .collect();
+        Self { sources }
+    }
+
+    // 同一个音效可以叠着播放
+    pub fn play(&mut self, ctx: &Context, effect: SoundEffect) {
+        for (e, source) in &mut self.sources {
+            if *e == effect {
+                if let Err(e) = source.play_detached(ctx) {
+                    eprintln!("音效播放失败: {}", e);
+                }
+            }
+        }
+    }
+}
+
+// 背景音乐，资源目录里缺少的曲目直接跳过
+#[derive(Default)]
+pub struct Music {
+    sources: Vec<(MusicTrack, audio::Source)>,
+    current: Option<MusicTrack>,
+    theme: Option<audio::Source>,  // 当前这一关专用的曲目，有的时候代替默认的关卡音乐
+}
+
+impl Music {
+    pub fn load(ctx: &Context, loader: &mut AssetLoader) -> Self {
+        let sources = MusicTrack::ALL
+            .into_iter()
+            .filter_map(|track| {
+                let mut source = loader.sound(ctx, track.path()).ok()?;
+                source.set_repeat(true);
+                Some((track, source))
+            })
+            .collect();
+        Self {
+            sources,
+            current: None,
+            theme: None,
+        }
+    }
+
+    // 正在放的曲目：关卡音乐有专用曲目时放专用的
+    fn playing(&self) -> Option<&audio::Source> {
+        match (self.current, &self.theme) {
+            (Some(MusicTrack::Stage), Some(theme)) => Some(theme),
+            (current, _) => self.sources.iter().find(|(track, _)| Some(*track) == current).map(|(_, source)| source),
+        }
+    }
+
+    // 游戏切到后台或失去音频焦点时暂停正在放的曲目，回到前台后从暂停的地方接着放
+    pub fn suspend(&self) {
+        self.playing().into_iter().for_each(audio::Source::pause);
+    }
+
+    pub fn resume(&self) {
+        self.playing().into_iter().for_each(audio::Source::resume);
+    }
+
+    pub fn play(&mut self, ctx: &Context, track: MusicTrack) {
+        if self.current == Some(track) {
+            return;
+        }
+        self.current = Some(track);
+        self.restart(ctx);
+    }
+
+    // 换上一关的专用曲目，None 时换回默认的关卡音乐。旧的曲目在这里释放
+    pub fn set_theme(&mut self, ctx: &Context, theme: Option<audio::Source>) {
+        if let Some(old) = &mut self.theme {
+            if let Err(e) = old.stop(ctx) {
+                eprintln!("音乐播放失败: {}", e);
+            }
+        }
+        self.theme = theme.map(|mut theme| {
+            theme.set_repeat(true);
+            theme
+        });
+        self.restart(ctx);
+    }
+
+    // 从头播放当前的曲目，停下其他所有曲目
+    fn restart(&mut self, ctx: &Context) {
+        let themed = self.current == Some(MusicTrack::Stage) && self.theme.is_some();
+        for (track, source) in &mut self.sources {
+            let result = if Some(*track) == self.current && !themed {
+                source.play(ctx)
+            } else {
+                source.stop(ctx)
+            };
+            if let Err(e) = result {
+                eprintln!("音乐播放失败: {}", e);
+            }
+        }
+        if let Some(theme) = &mut self.theme {
+            let result = if themed { theme.play(ctx) } else { theme.stop(ctx) };
+            if let Err(e) = result {
+                eprintln!("音乐播放失败: {}", e);
+            }
+        }
+    }
+}
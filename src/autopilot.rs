@@ -0,0 +1,52 @@
+use crate::world::{FrameInput, World};
+
+const FIRE_INTERVAL: f32 = 0.15;
+const DODGE_DISTANCE: f32 = 80.0;  // 敌人离玩家这么近时优先躲避
+
+// 简单的自动驾驶：对准最低的敌人开火，敌人逼近时横向躲开
+#[derive(Default)]
+pub struct Autopilot {
+    fire_timer: f32,
+}
+
+impl Autopilot {
+    pub fn decide(&mut self, world: &World, dt: f32) -> FrameInput {
+        let player = world.player.game_object.position;
+
+        // 优先瞄准最接近底部的敌人，没有敌人时瞄准头目
+        let target = world
+            .enemies
+            .iter()
+            .map(|enemy| enemy.game_object.position)
+            .max_by(|a, b| a.y.total_cmp(&b.y))
+            .or(world.boss.as_ref().map(|boss| boss.game_object.position));
+        let threat = world
+            .enemies
+            .iter()
+            .map(|enemy| enemy.game_object.position)
+            .find(|position| position.distance(player) < DODGE_DISTANCE);
+
+        let direction = match (threat, target) {
+            (Some(threat), _) => -(threat.x - player.x).signum(),
+            (None, Some(target)) if (target.x - player.x).abs() > 5.0 => (target.x - player.x).signum(),
+            _ => 0.0,
+        };
+
+        self.fire_timer -= dt;
+        let fire = self.fire_timer <= 0.0 && !world.cinematic;
+        if fire {
+            self.fire_timer = FIRE_INTERVAL;
+        }
+        FrameInput {
+            move_x: direction as i8,
+            move_y: 0,
+            fire,
+            charge: false,
+            dash: false,
+            focus: false,
+            emp: false,
+            bomb: false,
+            select: 0,
+        }
+    }
+}
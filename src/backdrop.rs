@@ -0,0 +1,60 @@
+use ggez::{
+    glam::Vec2,
+    graphics::{self, Canvas, Color, DrawParam, MeshBuilder, Rect},
+    Context, GameResult,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+
+const SEED: u64 = 7;  // 星星的位置每次都一样，只影响画面，不用本局的随机数
+
+// 每层星星的数量、下落速度、大小和亮度，从最远的一层开始
+const LAYERS: [(usize, f32, f32, f32); 3] = [(60, 15.0, 1.0, 0.3), (35, 40.0, 1.5, 0.55), (15, 90.0, 2.0, 0.85)];
+
+// 向下滚动的星空背景，越近的一层越亮、落得越快，画面质量低时只画远处的几层
+pub struct Starfield {
+    layers: Vec<Vec<Vec2>>,
+}
+
+impl Default for Starfield {
+    fn default() -> Self {
+        let mut rng = StdRng::seed_from_u64(SEED);
+        let layers = LAYERS
+            .iter()
+            .map(|&(count, ..)| {
+                (0..count)
+                    .map(|_| Vec2::new(rng.gen_range(0.0..WINDOW_WIDTH), rng.gen_range(0.0..WINDOW_HEIGHT)))
+                    .collect()
+            })
+            .collect();
+        Self { layers }
+    }
+}
+
+impl Starfield {
+    pub fn update(&mut self, dt: f32) {
+        for (stars, &(_, speed, ..)) in self.layers.iter_mut().zip(&LAYERS) {
+            for star in stars {
+                star.y = (star.y + speed * dt).rem_euclid(WINDOW_HEIGHT);
+            }
+        }
+    }
+
+    // 所有星星合成一个网格绘制，layers 为 0 时不画
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas, layers: usize) -> GameResult<()> {
+        if layers == 0 {
+            return Ok(());
+        }
+        let mut builder = MeshBuilder::new();
+        for (stars, &(_, _, size, brightness)) in self.layers.iter().zip(&LAYERS).take(layers) {
+            let color = Color::new(brightness, brightness, brightness + 0.1, 1.0);
+            for star in stars {
+                builder.rectangle(graphics::DrawMode::fill(), Rect::new(star.x, star.y, size, size), color)?;
+            }
+        }
+        let mesh = graphics::Mesh::from_data(ctx, builder.build());
+        canvas.draw(&mesh, DrawParam::default());
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use ggez::glam::Vec2;
+
+use crate::enemy::{Enemy, EnemyPath};
+use crate::simulation::Simulation;
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+
+const FRAMES: u32 = 600;
+const ENEMIES: usize = 100;
+
+// 基准测试：在空场地里保持固定数量的子弹和敌人，统计每帧各阶段的平均耗时
+pub fn run(bullets: usize) -> String {
+    let mut simulation = Simulation::quiet();
+    let world = &mut simulation.world;
+    world.limits.bullets = bullets;
+    world.limits.enemies = ENEMIES;
+    // 敌人排成静止的网格，被击落后补上
+    let enemy_at = |i: usize| {
+        let x = 40.0 + (i % 20) as f32 * 38.0;
+        let y = 40.0 + (i / 20) as f32 * 40.0;
+        Enemy::new(x, y, 20.0, 0.0, EnemyPath::Straight)
+    };
+
+    let mut totals: BTreeMap<&'static str, Duration> = BTreeMap::new();
+    let started = Instant::now();
+    for frame in 0..FRAMES {
+        let world = &mut simulation.world;
+        while world.enemies.len() < ENEMIES {
+            let enemy = enemy_at(world.enemies.len());
+            world.enemies.push(enemy);
+        }
+        // 子弹从屏幕底部均匀地往上飞，飞出去的补回来
+        let mut i = world.bullets.len();
+        while world.bullets.len() < bullets {
+            let x = (i * 37 + frame as usize * 13) as f32 % WINDOW_WIDTH;
+            let y = WINDOW_HEIGHT - (i % 60) as f32 * 10.0;
+            world.bullets.push(Vec2::new(x, y), Vec2::new(0.0, -400.0));
+            i += 1;
+        }
+
+        simulation.wait(1);
+        for (name, duration) in simulation.world.profiler.take() {
+            *totals.entry(name).or_default() += duration;
+        }
+    }
+    let elapsed = started.elapsed();
+
+    let mut report = format!(
+        "基准测试: {} 颗子弹, {} 个敌人, {} 帧, 平均每帧 {:.3}ms\n",
+        bullets,
+        ENEMIES,
+        FRAMES,
+        elapsed.as_secs_f64() * 1000.0 / FRAMES as f64
+    );
+    for (name, total) in totals {
+        report += &format!("  {}: {:.3}ms\n", name, total.as_secs_f64() * 1000.0 / FRAMES as f64);
+    }
+    report
+}
@@ -0,0 +1,355 @@
+use ggez::glam::Vec2;
+use ggez::input::{gamepad::gilrs::Button, keyboard::KeyCode};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+// 可以重新绑定按键的游戏操作
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    Fire,
+    Emp,
+    Bomb,
+    Dash,
+    Focus,
+    Pause,
+}
+
+impl Action {
+    pub const ALL: [Action; 10] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::Fire,
+        Action::Emp,
+        Action::Bomb,
+        Action::Dash,
+        Action::Focus,
+        Action::Pause,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::MoveLeft => "向左移动",
+            Action::MoveRight => "向右移动",
+            Action::MoveUp => "向上移动",
+            Action::MoveDown => "向下移动",
+            Action::Fire => "发射子弹",
+            Action::Emp => "电磁脉冲",
+            Action::Bomb => "炸弹",
+            Action::Dash => "冲刺",
+            Action::Focus => "专注",
+            Action::Pause => "暂停",
+        }
+    }
+}
+
+// 允许绑定的按键，同时用于和存档中的按键名互相转换
+const BINDABLE_KEYS: &[KeyCode] = &[
+    KeyCode::A, KeyCode::B, KeyCode::C, KeyCode::D, KeyCode::E, KeyCode::F, KeyCode::G,
+    KeyCode::H, KeyCode::I, KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::M, KeyCode::N,
+    KeyCode::O, KeyCode::P, KeyCode::Q, KeyCode::R, KeyCode::S, KeyCode::T, KeyCode::U,
+    KeyCode::V, KeyCode::W, KeyCode::X, KeyCode::Y, KeyCode::Z,
+    KeyCode::Key0, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
+    KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+    KeyCode::Left, KeyCode::Right, KeyCode::Up, KeyCode::Down,
+    KeyCode::Space, KeyCode::Return, KeyCode::Tab, KeyCode::Escape,
+    KeyCode::LShift, KeyCode::RShift, KeyCode::LControl, KeyCode::RControl,
+    KeyCode::LAlt, KeyCode::RAlt,
+    KeyCode::Comma, KeyCode::Period, KeyCode::Slash, KeyCode::Semicolon,
+    KeyCode::Minus, KeyCode::Equals,
+    KeyCode::Numpad0, KeyCode::Numpad1, KeyCode::Numpad2, KeyCode::Numpad3, KeyCode::Numpad4,
+    KeyCode::Numpad5, KeyCode::Numpad6, KeyCode::Numpad7, KeyCode::Numpad8, KeyCode::Numpad9,
+];
+
+pub fn is_bindable(key: KeyCode) -> bool {
+    BINDABLE_KEYS.contains(&key)
+}
+
+pub fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key)
+}
+
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS.iter().copied().find(|&key| key_name(key) == name)
+}
+
+// 存档里以按键名保存，例如 "Left"、"Space"
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Key(pub KeyCode);
+
+impl Serialize for Key {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&key_name(self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for Key {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        key_from_name(&name)
+            .map(Key)
+            .ok_or_else(|| de::Error::custom(format!("未知按键: {}", name)))
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bindings {
+    pub move_left: Key,
+    pub move_right: Key,
+    pub move_up: Key,
+    pub move_down: Key,
+    pub fire: Key,
+    pub emp: Key,
+    pub bomb: Key,
+    pub dash: Key,
+    pub focus: Key,
+    pub pause: Key,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self {
+            move_left: Key(KeyCode::Left),
+            move_right: Key(KeyCode::Right),
+            move_up: Key(KeyCode::Up),
+            move_down: Key(KeyCode::Down),
+            fire: Key(KeyCode::Space),
+            emp: Key(KeyCode::X),
+            bomb: Key(KeyCode::B),
+            dash: Key(KeyCode::LControl),
+            focus: Key(KeyCode::LShift),
+            pause: Key(KeyCode::Escape),
+        }
+    }
+}
+
+impl Bindings {
+    pub fn key(&self, action: Action) -> KeyCode {
+        match action {
+            Action::MoveLeft => self.move_left.0,
+            Action::MoveRight => self.move_right.0,
+            Action::MoveUp => self.move_up.0,
+            Action::MoveDown => self.move_down.0,
+            Action::Fire => self.fire.0,
+            Action::Emp => self.emp.0,
+            Action::Bomb => self.bomb.0,
+            Action::Dash => self.dash.0,
+            Action::Focus => self.focus.0,
+            Action::Pause => self.pause.0,
+        }
+    }
+
+    fn slot_mut(&mut self, action: Action) -> &mut Key {
+        match action {
+            Action::MoveLeft => &mut self.move_left,
+            Action::MoveRight => &mut self.move_right,
+            Action::MoveUp => &mut self.move_up,
+            Action::MoveDown => &mut self.move_down,
+            Action::Fire => &mut self.fire,
+            Action::Emp => &mut self.emp,
+            Action::Bomb => &mut self.bomb,
+            Action::Dash => &mut self.dash,
+            Action::Focus => &mut self.focus,
+            Action::Pause => &mut self.pause,
+        }
+    }
+
+    pub fn action_for(&self, key: KeyCode) -> Option<Action> {
+        Action::ALL.into_iter().find(|&action| self.key(action) == key)
+    }
+
+    // 绑定新按键；按键已被其他操作占用时返回冲突的操作
+    pub fn rebind(&mut self, action: Action, key: KeyCode) -> Result<(), Action> {
+        match self.action_for(key) {
+            Some(other) if other != action => Err(other),
+            _ => {
+                *self.slot_mut(action) = Key(key);
+                Ok(())
+            }
+        }
+    }
+}
+
+// 当前按住的移动键（键盘和手柄十字键），松开一个方向时另一个方向照常生效
+#[derive(Clone, Copy, Default, Debug)]
+pub struct HeldMoves {
+    left: bool,
+    right: bool,
+    up: bool,
+    down: bool,
+}
+
+impl HeldMoves {
+    // 记录移动键按下或松开，不是移动操作时返回 false
+    pub fn set(&mut self, action: Action, pressed: bool) -> bool {
+        let held = match action {
+            Action::MoveLeft => &mut self.left,
+            Action::MoveRight => &mut self.right,
+            Action::MoveUp => &mut self.up,
+            Action::MoveDown => &mut self.down,
+            _ => return false,
+        };
+        *held = pressed;
+        true
+    }
+
+    // 每个分量为 -1、0 或 1，同时按住相反的两个方向时互相抵消
+    pub fn direction(&self) -> Vec2 {
+        let axis = |negative: bool, positive: bool| positive as i8 as f32 - negative as i8 as f32;
+        Vec2::new(axis(self.left, self.right), axis(self.up, self.down))
+    }
+}
+
+// 同一个移动键在这么多秒内按两下算作冲刺
+const DOUBLE_TAP_WINDOW: f32 = 0.25;
+
+// 检测移动键的连按两下，now 是按下时的秒数
+#[derive(Default)]
+pub struct DoubleTap {
+    last: Option<(Action, f32)>,
+}
+
+impl DoubleTap {
+    // 记录一次按下，和上一次是同一个移动键且间隔够短时返回 true；连按成功后要重新按两下才会再次触发
+    pub fn press(&mut self, action: Action, now: f32) -> bool {
+        if !matches!(action, Action::MoveLeft | Action::MoveRight | Action::MoveUp | Action::MoveDown) {
+            self.last = None;
+            return false;
+        }
+        if self.last.is_some_and(|(last, at)| last == action && now - at <= DOUBLE_TAP_WINDOW) {
+            self.last = None;
+            return true;
+        }
+        self.last = Some((action, now));
+        false
+    }
+}
+
+pub const RESTART_KEY: KeyCode = KeyCode::R;  // 游戏中按住这个键快速重新开始
+pub const RESTART_HOLD: f32 = 1.0;
+
+// 要按住一段时间才触发的操作，中途松开就从头计时；触发之后要先松开才能再次触发
+#[derive(Default)]
+pub struct HoldTimer {
+    held: f32,
+    fired: bool,
+}
+
+impl HoldTimer {
+    // 推进一帧，按住满 duration 秒的那一帧返回 true
+    pub fn update(&mut self, pressed: bool, dt: f32, duration: f32) -> bool {
+        if !pressed {
+            *self = Self::default();
+            return false;
+        }
+        if self.fired {
+            return false;
+        }
+        self.held += dt;
+        self.fired = self.held >= duration;
+        self.fired
+    }
+
+    // 按住的进度，0 到 1，已经触发过时为 0
+    pub fn progress(&self, duration: f32) -> f32 {
+        if self.fired {
+            0.0
+        } else {
+            (self.held / duration).min(1.0)
+        }
+    }
+}
+
+// 数字键 1 到 3 固定用来切换主武器，返回对应的栏位；已经绑定给其他操作的数字键优先执行那个操作
+pub fn weapon_slot(key: KeyCode) -> Option<u8> {
+    match key {
+        KeyCode::Key1 | KeyCode::Numpad1 => Some(1),
+        KeyCode::Key2 | KeyCode::Numpad2 => Some(2),
+        KeyCode::Key3 | KeyCode::Numpad3 => Some(3),
+        _ => None,
+    }
+}
+
+// 双人对战时第二名玩家使用固定的按键，只有移动、开火和冲刺。
+// 第一名玩家把某个操作绑到了这些键上时，对战中这些键只给第二名玩家
+const RIVAL_LAYOUT: [(KeyCode, Action); 6] = [
+    (KeyCode::A, Action::MoveLeft),
+    (KeyCode::D, Action::MoveRight),
+    (KeyCode::W, Action::MoveUp),
+    (KeyCode::S, Action::MoveDown),
+    (KeyCode::F, Action::Fire),
+    (KeyCode::G, Action::Dash),
+];
+
+pub fn rival_action(key: KeyCode) -> Option<Action> {
+    RIVAL_LAYOUT.iter().find(|&&(candidate, _)| candidate == key).map(|&(_, action)| action)
+}
+
+// 第二名玩家某个操作的按键，没有这个操作时返回 None
+pub fn rival_key(action: Action) -> Option<KeyCode> {
+    RIVAL_LAYOUT.iter().find(|&&(_, candidate)| candidate == action).map(|&(key, _)| key)
+}
+
+// 手柄使用固定的按键布局，操作说明界面也按这张表绘制
+const GAMEPAD_LAYOUT: [(Button, Action); 11] = [
+    (Button::DPadLeft, Action::MoveLeft),
+    (Button::DPadRight, Action::MoveRight),
+    (Button::DPadUp, Action::MoveUp),
+    (Button::DPadDown, Action::MoveDown),
+    (Button::South, Action::Fire),
+    (Button::RightTrigger, Action::Fire),
+    (Button::West, Action::Emp),
+    (Button::North, Action::Bomb),
+    (Button::LeftTrigger, Action::Dash),
+    (Button::LeftTrigger2, Action::Focus),
+    (Button::Start, Action::Pause),
+];
+
+pub fn gamepad_action(button: Button) -> Option<Action> {
+    GAMEPAD_LAYOUT
+        .iter()
+        .find(|&&(candidate, _)| candidate == button)
+        .map(|&(_, action)| action)
+}
+
+// 对应某个操作的所有手柄按键
+pub fn gamepad_buttons(action: Action) -> impl Iterator<Item = Button> {
+    GAMEPAD_LAYOUT
+        .into_iter()
+        .filter(move |&(_, candidate)| candidate == action)
+        .map(|(button, _)| button)
+}
+
+pub fn button_name(button: Button) -> String {
+    match button {
+        Button::DPadLeft => "十字键←".to_string(),
+        Button::DPadRight => "十字键→".to_string(),
+        Button::DPadUp => "十字键↑".to_string(),
+        Button::DPadDown => "十字键↓".to_string(),
+        Button::South => "A".to_string(),
+        Button::West => "X".to_string(),
+        Button::North => "Y".to_string(),
+        Button::RightTrigger => "右扳机".to_string(),
+        Button::LeftTrigger => "左扳机".to_string(),
+        Button::LeftTrigger2 => "左下扳机".to_string(),
+        Button::Start => "开始".to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+// 菜单中把手柄按键当作对应的键盘按键处理
+pub fn gamepad_menu_key(button: Button, bindings: &Bindings) -> Option<KeyCode> {
+    match button {
+        Button::DPadUp => Some(KeyCode::Up),
+        Button::DPadDown => Some(KeyCode::Down),
+        Button::South => Some(KeyCode::Return),
+        Button::East => Some(KeyCode::Escape),
+        Button::Start => Some(bindings.key(Action::Pause)),
+        _ => None,
+    }
+}
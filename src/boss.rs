@@ -0,0 +1,316 @@
+use ggez::{
+    glam::Vec2,
+    graphics::{self, Canvas, Color, DrawParam, Mesh, Rect},
+    Context, GameResult,
+};
+
+use serde::Deserialize;
+
+use crate::events::{EventBus, GameEvent};
+use crate::playfield::Boundary;
+use crate::status::StatusEffects;
+use crate::tween::{self, Easing, Tween};
+use crate::{GameObject, WINDOW_WIDTH};
+
+const BOSS_Y: f32 = 110.0;
+const BOSS_SWAY: f32 = 250.0;  // 左右摆动幅度
+const HEALTH_BAR_WIDTH: f32 = 400.0;
+const TURRET_INTERVAL: f32 = 2.5;  // 炮台投放水雷的间隔
+const TURRET_SIZE: f32 = 14.0;
+
+// 头目登场动画：沿曲线飞入，之后名字横幅和血条依次出现
+struct BossIntro {
+    path: Tween<f32>,
+    banner: Tween<f32>,
+    health_bar: Tween<f32>,
+    hold: f32,  // 全部出现后停留的时间
+}
+
+impl BossIntro {
+    fn new() -> Self {
+        Self {
+            path: Tween::new(0.0, 1.0, 2.0, Easing::EaseOutCubic),
+            banner: Tween::new(-300.0, WINDOW_WIDTH / 2.0 - 100.0, 0.6, Easing::EaseOutCubic),
+            health_bar: Tween::new(0.0, 1.0, 1.0, Easing::EaseInOutQuad),
+            hold: 0.8,
+        }
+    }
+
+    // 返回动画是否已经播放完
+    fn update(&mut self, dt: f32) -> bool {
+        if !self.path.finished() {
+            self.path.update(dt);
+        } else if !self.banner.finished() || !self.health_bar.finished() {
+            self.banner.update(dt);
+            self.health_bar.update(dt);
+        } else {
+            self.hold -= dt;
+        }
+        self.hold <= 0.0
+    }
+}
+
+// 头目的移动方式
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BossPattern {
+    Sway,         // 左右摆动
+    FigureEight,  // 8 字形
+    Charge,       // 摆动中不时冲到活动区底部
+}
+
+impl BossPattern {
+    // 头目练习时显示的招式名
+    pub fn label(self) -> &'static str {
+        match self {
+            BossPattern::Sway => "左右摆动",
+            BossPattern::FigureEight => "8 字盘旋",
+            BossPattern::Charge => "俯冲撞击",
+        }
+    }
+}
+
+// 血量降到 below 比例以下时进入的阶段
+#[derive(Deserialize, Clone)]
+pub struct PhaseSpec {
+    pub below: f32,
+    pub pattern: BossPattern,
+    pub speed: f32,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct BossSpec {
+    pub name: String,
+    pub hp: u32,
+    pub bonus: u32,  // 击败后的奖励分数
+    #[serde(default = "BossSpec::default_phases")]
+    pub phases: Vec<PhaseSpec>,
+    #[serde(default = "BossSpec::default_size")]
+    pub size: [f32; 2],
+    #[serde(default)]
+    pub turrets: u32,  // 机身下方的炮台数量，每个炮台会定时投放水雷
+}
+
+impl BossSpec {
+    pub fn default_phases() -> Vec<PhaseSpec> {
+        vec![PhaseSpec {
+            below: 1.0,
+            pattern: BossPattern::Sway,
+            speed: 0.8,
+        }]
+    }
+
+    pub fn default_size() -> [f32; 2] {
+        [120.0, 60.0]
+    }
+}
+
+pub struct Boss {
+    pub game_object: GameObject,
+    pub spec: BossSpec,
+    pub hp: u32,
+    pub boundary: Boundary,  // 登场动画结束后限制在头目活动区内
+    pub status: StatusEffects,  // 燃烧、减速、眩晕等状态
+    phase: usize,
+    intro: Option<BossIntro>,
+    time: f32,
+    turret_timer: f32,
+}
+
+impl Boss {
+    pub fn new(spec: BossSpec) -> Self {
+        let [width, height] = spec.size;
+        Self {
+            game_object: GameObject::new(WINDOW_WIDTH / 2.0, -100.0, width, height),
+            hp: spec.hp,
+            boundary: Boundary::CONFINED,
+            status: StatusEffects::default(),
+            spec,
+            phase: 0,
+            intro: Some(BossIntro::new()),
+            time: 0.0,
+            turret_timer: 0.0,
+        }
+    }
+
+    // 炮台沿机身底部均匀排开
+    fn turret_positions(&self) -> Vec<Vec2> {
+        let bounds = self.game_object.bounds();
+        let count = self.spec.turrets;
+        (0..count)
+            .map(|i| {
+                let x = bounds.x + bounds.w * (i as f32 + 1.0) / (count as f32 + 1.0);
+                Vec2::new(x, bounds.y + bounds.h + TURRET_SIZE / 2.0)
+            })
+            .collect()
+    }
+
+    pub fn bonus(&self) -> u32 {
+        self.spec.bonus
+    }
+
+    fn hp_fraction(&self) -> f32 {
+        self.hp as f32 / self.spec.hp.max(1) as f32
+    }
+
+    pub fn in_intro(&self) -> bool {
+        self.intro.is_some()
+    }
+
+    // 当前是第几阶段，从 0 开始
+    pub fn phase(&self) -> usize {
+        self.phase
+    }
+
+    pub fn current_phase(&self) -> Option<&PhaseSpec> {
+        self.spec.phases.get(self.phase)
+    }
+
+    // 头目练习从指定的阶段开始：血量降到这一阶段的门槛，超出范围时按最后一个阶段
+    pub fn start_at_phase(&mut self, phase: usize) {
+        let Some(last) = self.spec.phases.len().checked_sub(1) else {
+            return;
+        };
+        self.phase = phase.min(last);
+        let below = self.spec.phases[self.phase].below.min(1.0);
+        self.hp = ((self.spec.hp as f32 * below).floor() as u32).clamp(1, self.spec.hp.max(1));
+    }
+
+    // 返回这一帧燃烧造成的伤害，由游戏世界结算
+    pub fn update(&mut self, dt: f32, events: &mut EventBus) -> u32 {
+        if let Some(intro) = &mut self.intro {
+            let done = intro.update(dt);
+            self.game_object.position = tween::quadratic_bezier(
+                Vec2::new(-100.0, -100.0),
+                Vec2::new(WINDOW_WIDTH * 0.2, BOSS_Y * 2.5),
+                Vec2::new(WINDOW_WIDTH / 2.0, BOSS_Y),
+                intro.path.value(),
+            );
+            if done {
+                self.intro = None;
+                events.emit(GameEvent::BossIntroFinished);
+            }
+            return 0;
+        }
+
+        // 血量越低，进入越靠后的阶段
+        let fraction = self.hp_fraction();
+        let phase = self
+            .spec
+            .phases
+            .iter()
+            .rposition(|phase| fraction <= phase.below)
+            .unwrap_or(0);
+        if phase > self.phase {
+            self.phase = phase;
+            events.emit(GameEvent::BossPhaseChanged { phase });
+        }
+
+        // 眩晕期间停在原地，炮台也不开火；减速时移动和开炮都变慢
+        let burn = self.status.update(dt);
+        if self.status.stunned() {
+            return burn;
+        }
+        let dt = dt * self.status.speed_factor();
+
+        self.time += dt;
+        let Some(current) = self.spec.phases.get(self.phase) else {
+            return burn;
+        };
+        let t = self.time * current.speed;
+        let x = WINDOW_WIDTH / 2.0 + t.sin() * BOSS_SWAY;
+        let y = match current.pattern {
+            BossPattern::Sway => BOSS_Y,
+            BossPattern::FigureEight => BOSS_Y + (2.0 * t).sin() * 40.0,
+            BossPattern::Charge => BOSS_Y + (t * 0.7).sin().max(0.0).powi(6) * 300.0,
+        };
+        self.game_object.position = Vec2::new(x, y);
+
+        if self.spec.turrets > 0 {
+            self.turret_timer += dt;
+            if self.turret_timer >= TURRET_INTERVAL {
+                self.turret_timer = 0.0;
+                for position in self.turret_positions() {
+                    events.emit(GameEvent::BossTurretFired { position });
+                }
+            }
+        }
+        burn
+    }
+
+    // 受到伤害，返回是否被击败
+    pub fn hit(&mut self, damage: u32) -> bool {
+        self.hp = self.hp.saturating_sub(damage);
+        self.hp == 0
+    }
+
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult<()> {
+        // 每进入一个新阶段颜色更红
+        let heat = self.phase as f32 / self.spec.phases.len().max(1) as f32;
+        let body = Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            self.game_object.bounds(),
+            self.status.tint(Color::new(0.8, 0.2 * (1.0 - heat), 0.8 * (1.0 - heat), 1.0)),
+        )?;
+        canvas.draw(&body, DrawParam::default());
+        for position in self.turret_positions() {
+            let turret = Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::fill(),
+                Rect::new(
+                    position.x - TURRET_SIZE / 2.0,
+                    position.y - TURRET_SIZE / 2.0,
+                    TURRET_SIZE,
+                    TURRET_SIZE,
+                ),
+                Color::new(0.6, 0.6, 0.6, 1.0),
+            )?;
+            canvas.draw(&turret, DrawParam::default());
+        }
+        let bounds = self.game_object.bounds();
+        self.status.draw_icons(ctx, canvas, Vec2::new(bounds.center().x, bounds.top()))?;
+
+        // 登场时血条逐渐填满，之后显示实际血量
+        let fill = match &self.intro {
+            Some(intro) => intro.health_bar.value(),
+            None => self.hp_fraction(),
+        };
+        let bar_x = WINDOW_WIDTH / 2.0 - HEALTH_BAR_WIDTH / 2.0;
+        let background = Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::stroke(1.0),
+            Rect::new(bar_x, 70.0, HEALTH_BAR_WIDTH, 10.0),
+            Color::WHITE,
+        )?;
+        canvas.draw(&background, DrawParam::default());
+        if fill > 0.0 {
+            let bar = Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::fill(),
+                Rect::new(bar_x, 70.0, HEALTH_BAR_WIDTH * fill, 10.0),
+                Color::RED,
+            )?;
+            canvas.draw(&bar, DrawParam::default());
+        }
+
+        if self.intro.is_none() && self.spec.phases.len() > 1 {
+            canvas.draw(
+                &graphics::Text::new(format!("第 {}/{} 阶段", self.phase + 1, self.spec.phases.len())),
+                DrawParam::default().dest(Vec2::new(bar_x, 85.0)),
+            );
+        }
+
+        if let Some(intro) = &self.intro {
+            if intro.path.finished() {
+                canvas.draw(
+                    &graphics::Text::new(format!("警告: {} 来袭", self.spec.name)),
+                    DrawParam::default()
+                        .dest(Vec2::new(intro.banner.value(), 300.0))
+                        .color(Color::RED),
+                );
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,48 @@
+use rand::{rngs::StdRng, Rng};
+
+use crate::boss::{BossPattern, BossSpec, PhaseSpec};
+
+const NAME_PREFIXES: [&str; 6] = ["重装", "疾风", "暗影", "烈焰", "寒霜", "雷霆"];
+const NAME_SUFFIXES: [&str; 5] = ["战舰", "堡垒", "母舰", "守卫", "巨像"];
+const PATTERN_POOL: [BossPattern; 3] = [BossPattern::Sway, BossPattern::FigureEight, BossPattern::Charge];
+const TURRET_HP_COST: u32 = 8;  // 每个炮台占用的血量预算
+
+// 无尽模式每 10 波出现的头目，由机身、炮台、移动方式等部件随机拼装
+pub fn generate(wave: u32, rng: &mut StdRng) -> BossSpec {
+    let tier = wave / 10;
+
+    // 血量预算随波次增长，炮台越多机身越脆
+    let budget = 40 + wave * 6;
+    let max_turrets = (1 + tier).min(4);
+    let turrets = rng.gen_range(0..=max_turrets).min(budget / TURRET_HP_COST / 2);
+    let hp = budget - turrets * TURRET_HP_COST;
+
+    let width = rng.gen_range(90.0..=160.0);
+    let height = rng.gen_range(40.0..=80.0);
+
+    // 阶段越多，后面的阶段移动越快
+    let phase_count = (1 + tier / 2).min(3) as usize;
+    let phases = (0..phase_count)
+        .map(|i| PhaseSpec {
+            below: 1.0 - i as f32 / phase_count as f32,
+            pattern: PATTERN_POOL[rng.gen_range(0..PATTERN_POOL.len())],
+            speed: 0.7 + i as f32 * 0.3 + rng.gen_range(0.0..=0.3),
+        })
+        .collect();
+
+    let name = format!(
+        "{}{} Mk-{}",
+        NAME_PREFIXES[rng.gen_range(0..NAME_PREFIXES.len())],
+        NAME_SUFFIXES[rng.gen_range(0..NAME_SUFFIXES.len())],
+        tier.max(1)
+    );
+
+    BossSpec {
+        name,
+        hp,
+        bonus: 500 + wave * 50,
+        phases,
+        size: [width, height],
+        turrets,
+    }
+}
@@ -0,0 +1,215 @@
+use ggez::{glam::Vec2, graphics::Rect};
+
+use crate::collision::Collider;
+use crate::playfield::PlayField;
+use crate::swept_rect;
+use crate::weapon::Weapon;
+
+pub const BULLET_SIZE: Vec2 = Vec2::new(5.0, 10.0);
+
+// 子弹沿飞行方向拉长，横着飞的子弹是扁的
+fn size_for(velocity: Vec2) -> Vec2 {
+    if velocity.x.abs() > velocity.y.abs() {
+        Vec2::new(BULLET_SIZE.y, BULLET_SIZE.x)
+    } else {
+        BULLET_SIZE
+    }
+}
+
+// 子弹的附加行为，发射时由武器决定，每颗子弹各自记着剩下的次数
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Modifiers {
+    pub pierce: u32,  // 还能穿过几个敌人，0 表示打中第一个就消失
+    pub bounces: u32,  // 还能在屏幕边缘反弹几次
+}
+
+// 子弹按字段分开存放（SoA），位置和速度各是一段连续的数组，
+// 每帧的移动是一个紧凑的循环，编译器可以自动向量化，子弹成千上万时也不吃力。
+// 玩家的子弹和敌人的子弹各用一份
+#[derive(Default)]
+pub struct Bullets {
+    pub positions: Vec<Vec2>,
+    pub velocities: Vec<Vec2>,
+    weapons: Vec<Weapon>,  // 由哪种武器发射，决定暴击和附加的状态
+    damages: Vec<u32>,  // 命中时的伤害，发射时按武器和当时的增益算好
+    colliders: Vec<Collider>,  // 属于哪一方，决定能打到什么
+    grazed: Vec<bool>,  // 已经擦过飞船，每颗子弹只算一次
+    modifiers: Vec<Modifiers>,
+    serials: Vec<u32>,  // 穿透子弹的编号，敌人据此记住被哪一颗穿过，0 表示不穿透
+    origins: Vec<Vec2>,  // 发射时的位置，反射护盾把敌人的子弹弹回这里
+    alive: Vec<bool>,
+}
+
+impl Bullets {
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    // 已经预留的空间，调试界面用来观察数组有没有反复扩容
+    pub fn capacity(&self) -> usize {
+        self.positions.capacity()
+    }
+
+    pub fn push(&mut self, position: Vec2, velocity: Vec2) {
+        self.push_with(position, velocity, Weapon::Cannon, 1);
+    }
+
+    pub fn push_with(&mut self, position: Vec2, velocity: Vec2, weapon: Weapon, damage: u32) {
+        self.push_as(position, velocity, weapon, damage, Collider::PLAYER_SHOT);
+    }
+
+    // 敌人的子弹不结算暴击，武器只是占位。从开火的敌人身上发出，发射位置就是这个敌人当时的位置
+    pub fn push_hostile(&mut self, position: Vec2, velocity: Vec2) {
+        self.push_as(position, velocity, Weapon::Cannon, 1, Collider::ENEMY_SHOT);
+    }
+
+    pub fn push_as(&mut self, position: Vec2, velocity: Vec2, weapon: Weapon, damage: u32, collider: Collider) {
+        self.positions.push(position);
+        self.velocities.push(velocity);
+        self.weapons.push(weapon);
+        self.damages.push(damage);
+        self.colliders.push(collider);
+        self.grazed.push(false);
+        self.modifiers.push(Modifiers::default());
+        self.serials.push(0);
+        self.origins.push(position);
+        self.alive.push(true);
+    }
+
+    // 玩家一方带穿透或反弹的子弹，穿透的子弹要有一个不为 0 的编号
+    pub fn push_modified(&mut self, position: Vec2, velocity: Vec2, weapon: Weapon, damage: u32, modifiers: Modifiers, serial: u32) {
+        self.push_with(position, velocity, weapon, damage);
+        let index = self.len() - 1;
+        self.modifiers[index] = modifiers;
+        self.serials[index] = serial;
+    }
+
+    // 移动所有子弹，能反弹的子弹碰到屏幕边缘时折返，
+    // 再标记中心离开子弹区域（已经飞出屏幕）的，四条边都算
+    pub fn integrate(&mut self, dt: f32, field: &PlayField) {
+        for (position, velocity) in self.positions.iter_mut().zip(&self.velocities) {
+            *position += *velocity * dt;
+        }
+        let walls = field.area();
+        for index in 0..self.len() {
+            if self.modifiers[index].bounces == 0 {
+                continue;
+            }
+            let (position, velocity) = (&mut self.positions[index], &mut self.velocities[index]);
+            let off_x = position.x < walls.left() || position.x > walls.right();
+            let off_y = position.y < walls.top() || position.y > walls.bottom();
+            if !off_x && !off_y {
+                continue;
+            }
+            if off_x {
+                velocity.x = -velocity.x;
+                position.x = position.x.clamp(walls.left(), walls.right());
+            }
+            if off_y {
+                velocity.y = -velocity.y;
+                position.y = position.y.clamp(walls.top(), walls.bottom());
+            }
+            self.modifiers[index].bounces -= 1;
+        }
+        let area = field.bullet_area();
+        for (alive, position) in self.alive.iter_mut().zip(&self.positions) {
+            *alive &= position.x >= area.left()
+                && position.x <= area.right()
+                && position.y >= area.top()
+                && position.y <= area.bottom();
+        }
+    }
+
+    pub fn bounds(&self, index: usize) -> Rect {
+        let position = self.positions[index];
+        let size = size_for(self.velocities[index]);
+        Rect::new(position.x - size.x / 2.0, position.y - size.y / 2.0, size.x, size.y)
+    }
+
+    // 子弹这一帧扫过的区域，用来做连续碰撞
+    pub fn swept_bounds(&self, index: usize, dt: f32) -> Rect {
+        let velocity = self.velocities[index];
+        swept_rect(self.positions[index], velocity, size_for(velocity), dt)
+    }
+
+    pub fn weapon(&self, index: usize) -> Weapon {
+        self.weapons[index]
+    }
+
+    pub fn damage(&self, index: usize) -> u32 {
+        self.damages[index]
+    }
+
+    pub fn collider(&self, index: usize) -> Collider {
+        self.colliders[index]
+    }
+
+    pub fn modifiers(&self, index: usize) -> Modifiers {
+        self.modifiers[index]
+    }
+
+    pub fn serial(&self, index: usize) -> u32 {
+        self.serials[index]
+    }
+
+    pub fn origin(&self, index: usize) -> Vec2 {
+        self.origins[index]
+    }
+
+    // 打中一个敌人：还能穿透时消耗一次并返回 true，子弹继续飞
+    pub fn pierce(&mut self, index: usize) -> bool {
+        let pierce = &mut self.modifiers[index].pierce;
+        if *pierce == 0 {
+            return false;
+        }
+        *pierce -= 1;
+        true
+    }
+
+    pub fn is_alive(&self, index: usize) -> bool {
+        self.alive[index]
+    }
+
+    pub fn kill(&mut self, index: usize) {
+        self.alive[index] = false;
+    }
+
+    // 记一次擦弹，这颗子弹第一次擦过时返回 true
+    pub fn graze(&mut self, index: usize) -> bool {
+        !std::mem::replace(&mut self.grazed[index], true)
+    }
+
+    // 删除被标记的子弹，保持其余子弹的先后顺序，回放结果才不会变
+    pub fn remove_dead(&mut self) {
+        let mut kept = 0;
+        for index in 0..self.len() {
+            if self.alive[index] {
+                self.positions[kept] = self.positions[index];
+                self.velocities[kept] = self.velocities[index];
+                self.weapons[kept] = self.weapons[index];
+                self.damages[kept] = self.damages[index];
+                self.colliders[kept] = self.colliders[index];
+                self.grazed[kept] = self.grazed[index];
+                self.modifiers[kept] = self.modifiers[index];
+                self.serials[kept] = self.serials[index];
+                self.origins[kept] = self.origins[index];
+                self.alive[kept] = true;
+                kept += 1;
+            }
+        }
+        self.positions.truncate(kept);
+        self.velocities.truncate(kept);
+        self.weapons.truncate(kept);
+        self.damages.truncate(kept);
+        self.colliders.truncate(kept);
+        self.grazed.truncate(kept);
+        self.modifiers.truncate(kept);
+        self.serials.truncate(kept);
+        self.origins.truncate(kept);
+        self.alive.truncate(kept);
+    }
+}
@@ -0,0 +1,441 @@
+use ggez::glam::Vec2;
+use rand::{distributions::{Distribution, WeightedIndex}, rngs::StdRng, Rng};
+use serde::Deserialize;
+
+use crate::boss::BossSpec;
+use crate::enemy::EnemyPath;
+use crate::playfield::PlayField;
+use crate::registry::Registry;
+
+const STAGE_BANNER_TIME: f32 = 2.0;
+const INTERMISSION_TIME: f32 = 1.5;  // 两波之间的休息时间
+
+// 关卡数据随程序一起打包
+const CAMPAIGN_DATA: &str = include_str!("../resources/campaign.toml");
+
+// 每次同时出现的敌人队形
+#[derive(Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Formation {
+    #[default]
+    Single,
+    Line,    // 横排
+    Column,  // 纵列
+    Vee,     // V 字形
+    Swarm,   // 编队：一串敌机沿曲线飞进屏幕上方的网格，之后轮流离队俯冲
+}
+
+impl Formation {
+    // 队形中每架敌机相对队形中心的位置
+    pub fn offsets(self) -> Vec<Vec2> {
+        match self {
+            Formation::Single => vec![Vec2::ZERO],
+            Formation::Line => (-2..=2).map(|i| Vec2::new(i as f32 * 50.0, 0.0)).collect(),
+            Formation::Column => (0..4).map(|i| Vec2::new(0.0, i as f32 * -45.0)).collect(),
+            Formation::Vee => (-2..=2)
+                .map(|i: i32| Vec2::new(i as f32 * 45.0, i.abs() as f32 * -35.0))
+                .collect(),
+            // 排成一串依次入场，这里的位置只用来算总数和宽度
+            Formation::Swarm => (0..8).map(|i| Vec2::new(0.0, i as f32 * -40.0)).collect(),
+        }
+    }
+
+    // 队形的半宽，生成时用来避开屏幕边缘
+    pub fn half_width(self) -> f32 {
+        self.offsets().iter().map(|offset| offset.x.abs()).fold(0.0, f32::max)
+    }
+}
+
+pub(crate) fn default_enemy_size() -> f32 {
+    30.0
+}
+
+pub(crate) fn default_enemy_hp() -> u32 {
+    1
+}
+
+fn default_lane_weight() -> u32 {
+    1
+}
+
+// 敌人出现的一段横向区域，from 和 to 是场地宽度的比例（0 是左边缘，1 是右边缘）。
+// 一波有多条区域时每次按 weight 抽一条，区域太窄放不下整个队形时贴着场地的生成范围
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct SpawnLane {
+    pub from: f32,
+    pub to: f32,
+    #[serde(default = "default_lane_weight")]
+    pub weight: u32,
+}
+
+impl SpawnLane {
+    // 半宽为 half_width 的队形在这条区域里可以用的中心横坐标
+    pub fn range(&self, field: &PlayField, half_width: f32) -> (f32, f32) {
+        let spawn = field.spawn_x(half_width);
+        let clamp = |share: f32| (share * field.width).clamp(spawn.start, spawn.end);
+        (clamp(self.from.min(self.to)), clamp(self.from.max(self.to)))
+    }
+}
+
+// 按波次的区域随机一个中心横坐标，没有区域（或权重全为 0）时在整个场地宽度内均匀随机
+pub fn spawn_x(lanes: &[SpawnLane], field: &PlayField, half_width: f32, rng: &mut StdRng) -> f32 {
+    let Ok(index) = WeightedIndex::new(lanes.iter().map(|lane| lane.weight)) else {
+        return rng.gen_range(field.spawn_x(half_width));
+    };
+    let (from, to) = lanes[index.sample(rng)].range(field, half_width);
+    rng.gen_range(from..=to)
+}
+
+#[derive(Deserialize, Clone)]
+pub struct WaveSpec {
+    pub count: u32,
+    pub interval: f32,
+    pub speed: f32,
+    #[serde(default = "default_enemy_size")]
+    pub size: f32,
+    #[serde(default)]
+    pub formation: Formation,
+    #[serde(default)]
+    pub path: EnemyPath,
+    #[serde(default = "default_enemy_hp")]
+    pub hp: u32,
+    #[serde(default)]
+    pub armor: u32,
+    #[serde(default)]
+    pub fire_interval: f32,  // 大于 0 时敌人会开火
+    #[serde(default)]
+    pub aimed: bool,
+    #[serde(default)]
+    pub launch_interval: f32,  // 大于 0 时是母舰，每隔这么多秒放出小飞机
+    #[serde(default)]
+    pub splits: bool,  // 击落时分裂成两个更小更快的
+    #[serde(default)]
+    pub lanes: Vec<SpawnLane>,  // 敌人从哪些区域出现，为空时整个场地宽度内均匀随机
+    #[serde(default)]
+    pub enemy: Option<String>,  // 内容注册表中的敌人 ID，写了时大小、路线、血量、护甲和炮以定义为准
+}
+
+// 套用在已有波次上的变换，新游戏+之类的模式用它们改编关卡，不用另写一份数据
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WaveTransform {
+    Mirror,   // 左右翻转出现的区域
+    Reverse,  // 一关里的波次倒序
+    Double,   // 敌人数量翻倍，出现间隔减半
+}
+
+impl WaveTransform {
+    // 对一关的全部波次应用变换，返回新的波次，不修改原来的数据
+    pub fn apply(self, waves: &[WaveSpec]) -> Vec<WaveSpec> {
+        match self {
+            WaveTransform::Mirror => waves.iter().map(mirror).collect(),
+            WaveTransform::Reverse => waves.iter().rev().cloned().collect(),
+            WaveTransform::Double => waves.iter().map(double_density).collect(),
+        }
+    }
+}
+
+// 左右翻转一波：每条区域换到场地的另一侧，没有区域的波次本来就是对称的，保持不变
+pub fn mirror(wave: &WaveSpec) -> WaveSpec {
+    let lanes = wave
+        .lanes
+        .iter()
+        .map(|lane| SpawnLane { from: 1.0 - lane.to, to: 1.0 - lane.from, weight: lane.weight })
+        .collect();
+    WaveSpec { lanes, ..wave.clone() }
+}
+
+// 同样的时间里出现两倍的敌人
+pub fn double_density(wave: &WaveSpec) -> WaveSpec {
+    WaveSpec { count: wave.count * 2, interval: wave.interval / 2.0, ..wave.clone() }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct StageSpec {
+    pub name: String,
+    pub waves: Vec<WaveSpec>,
+    pub boss: BossSpec,
+    #[serde(default)]
+    pub music: Option<String>,  // 这一关专用的曲目，缺少时用默认的关卡音乐
+}
+
+impl StageSpec {
+    // 这一关专用的资源，进入这一关前读好，离开后释放
+    pub fn assets(&self) -> Vec<String> {
+        self.music.iter().cloned().collect()
+    }
+}
+
+// 头目练习菜单里的一项：从第 stage 关头目的第 phase 阶段开始
+#[derive(Clone, PartialEq, Debug)]
+pub struct BossDrill {
+    pub stage: usize,
+    pub phase: usize,
+    pub label: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct CampaignData {
+    pub stages: Vec<StageSpec>,
+}
+
+impl CampaignData {
+    pub fn load() -> Self {
+        toml::from_str(CAMPAIGN_DATA).expect("resources/campaign.toml 格式错误")
+    }
+
+    // 按注册表展开波次引用的敌人 ID，找不到定义的 ID 保留波次里原来的写法
+    pub fn resolve(mut self, registry: &Registry) -> Self {
+        for wave in self.stages.iter_mut().flat_map(|stage| &mut stage.waves) {
+            if let Some(def) = wave.enemy.as_deref().and_then(|id| registry.enemy(id)) {
+                def.apply(wave);
+            }
+        }
+        self
+    }
+
+    // 依次对每一关的波次应用变换
+    pub fn remix(mut self, transforms: &[WaveTransform]) -> Self {
+        for stage in &mut self.stages {
+            for transform in transforms {
+                stage.waves = transform.apply(&stage.waves);
+            }
+        }
+        self
+    }
+
+    // 头目车轮战：去掉每一关的全部波次，只留下头目
+    pub fn boss_rush(mut self) -> Self {
+        for stage in &mut self.stages {
+            stage.waves.clear();
+        }
+        self
+    }
+
+    // 每一关头目的每个阶段各一项，标出阶段的招式
+    pub fn boss_drills(&self) -> Vec<BossDrill> {
+        let mut drills = Vec::new();
+        for (stage, spec) in self.stages.iter().enumerate() {
+            for (phase, phase_spec) in spec.boss.phases.iter().enumerate() {
+                let label = format!("{} {}  第 {} 阶段: {}", spec.name, spec.boss.name, phase + 1, phase_spec.pattern.label());
+                drills.push(BossDrill { stage, phase, label });
+            }
+        }
+        drills
+    }
+
+    // 头目练习：只留下第 stage 关，超出范围时留下最后一关
+    pub fn only_stage(mut self, stage: usize) -> Self {
+        let stage = stage.min(self.stages.len().saturating_sub(1));
+        self.stages = self.stages.into_iter().nth(stage).into_iter().collect();
+        self
+    }
+
+    // 新游戏+：每关波次倒序重排并左右翻转，敌人更快更密，头目更耐打
+    pub fn new_game_plus(self) -> Self {
+        let mut data = self.remix(&[WaveTransform::Reverse, WaveTransform::Mirror]);
+        for stage in &mut data.stages {
+            for wave in &mut stage.waves {
+                wave.count = (wave.count as f32 * 1.5).ceil() as u32;
+                wave.interval *= 0.7;
+                wave.speed *= 1.3;
+            }
+            let boss = &mut stage.boss;
+            boss.hp = boss.hp * 3 / 2;
+            boss.bonus *= 2;
+            for phase in &mut boss.phases {
+                phase.speed *= 1.25;
+            }
+        }
+        data
+    }
+}
+
+// 战役流程要求游戏世界做的事
+pub enum CampaignSpawn {
+    Formation(WaveSpec),  // 按本波的队形生成一组敌人
+    Boss(BossSpec),
+    WaveCleared { wave: u32 },  // 本波的敌人全部清空，奖励由计分规则决定
+}
+
+#[derive(Clone)]
+enum Step {
+    StageBanner(f32),  // 显示关卡名
+    Wave,              // 按间隔生成本波敌人
+    WaitForClear,      // 等场上敌人清空再进入下一波
+    Intermission(f32), // 波次之间的休息，显示下一波的编号
+    Boss,              // 头目战
+    Finished,
+}
+
+// 按关卡数据推进战役流程
+#[derive(Clone)]
+pub struct Campaign {
+    data: CampaignData,
+    stage: usize,
+    wave: usize,
+    spawned: u32,
+    timer: f32,
+    step: Step,
+}
+
+impl Campaign {
+    pub fn new(data: CampaignData) -> Self {
+        Self {
+            data,
+            stage: 0,
+            wave: 0,
+            spawned: 0,
+            timer: 0.0,
+            step: Step::StageBanner(STAGE_BANNER_TIME),
+        }
+    }
+
+    fn current_stage(&self) -> &StageSpec {
+        &self.data.stages[self.stage]
+    }
+
+    // 当前是第几关，从 0 开始
+    pub fn stage(&self) -> usize {
+        self.stage
+    }
+
+    // 一共有几关
+    pub fn stages(&self) -> usize {
+        self.data.stages.len()
+    }
+
+    pub fn stage_spec(&self, stage: usize) -> Option<&StageSpec> {
+        self.data.stages.get(stage)
+    }
+
+    // 本关只剩头目战时返回下一关，用来提前读取下一关的资源
+    pub fn upcoming_stage(&self) -> Option<usize> {
+        let last_wave = self.wave + 1 >= self.current_stage().waves.len();
+        let closing = matches!(self.step, Step::Boss) || (last_wave && matches!(self.step, Step::Intermission(_)));
+        (closing && self.stage + 1 < self.data.stages.len()).then_some(self.stage + 1)
+    }
+
+    // 正在出场的这一波，调试时用来画出生成区域
+    pub fn current_wave(&self) -> Option<&WaveSpec> {
+        matches!(self.step, Step::Wave).then(|| &self.current_stage().waves[self.wave])
+    }
+
+    // 关卡开始时显示关卡名，波次之间显示下一波的编号
+    pub fn banner(&self) -> Option<String> {
+        match self.step {
+            Step::StageBanner(_) => Some(self.current_stage().name.clone()),
+            Step::Intermission(_) if self.wave + 1 < self.current_stage().waves.len() => {
+                Some(format!("第 {} 波", self.wave_number() + 1))
+            }
+            _ => None,
+        }
+    }
+
+    // 本波还没有出场的敌人数量
+    pub fn pending(&self) -> u32 {
+        match self.step {
+            Step::Wave => {
+                let wave = &self.current_stage().waves[self.wave];
+                wave.count.saturating_sub(self.spawned) * wave.formation.offsets().len() as u32
+            }
+            _ => 0,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32, field_clear: bool) -> Option<CampaignSpawn> {
+        match &mut self.step {
+            Step::StageBanner(remaining) => {
+                *remaining -= dt;
+                if *remaining > 0.0 {
+                    return None;
+                }
+                // 没有波次的关卡直接进入头目战
+                if self.current_stage().waves.is_empty() {
+                    self.step = Step::Boss;
+                    return Some(CampaignSpawn::Boss(self.current_stage().boss.clone()));
+                }
+                self.start_wave(0);
+                None
+            }
+            Step::Wave => {
+                let wave = self.current_stage().waves[self.wave].clone();
+                self.timer += dt;
+                if self.timer < wave.interval {
+                    return None;
+                }
+                self.timer = 0.0;
+                self.spawned += 1;
+                if self.spawned >= wave.count {
+                    self.step = Step::WaitForClear;
+                }
+                Some(CampaignSpawn::Formation(wave))
+            }
+            Step::WaitForClear => {
+                if !field_clear {
+                    return None;
+                }
+                self.step = Step::Intermission(INTERMISSION_TIME);
+                Some(CampaignSpawn::WaveCleared { wave: self.wave_number() })
+            }
+            Step::Intermission(remaining) => {
+                *remaining -= dt;
+                if *remaining > 0.0 {
+                    return None;
+                }
+                if self.wave + 1 < self.current_stage().waves.len() {
+                    self.start_wave(self.wave + 1);
+                    None
+                } else {
+                    self.step = Step::Boss;
+                    Some(CampaignSpawn::Boss(self.current_stage().boss.clone()))
+                }
+            }
+            Step::Boss | Step::Finished => None,
+        }
+    }
+
+    // 开局的关卡名还在显示时直接开始第一波，没有波次的关卡在下一帧进入头目战
+    pub fn skip_banner(&mut self) {
+        if matches!(self.step, Step::StageBanner(_)) {
+            if self.current_stage().waves.is_empty() {
+                self.step = Step::StageBanner(0.0);
+            } else {
+                self.start_wave(self.wave);
+            }
+        }
+    }
+
+    fn start_wave(&mut self, wave: usize) {
+        self.wave = wave;
+        self.spawned = 0;
+        self.timer = 0.0;
+        self.step = Step::Wave;
+    }
+
+    pub fn finished(&self) -> bool {
+        matches!(self.step, Step::Finished)
+    }
+
+    // 从第一关开始累计的波次，从 1 开始
+    pub fn wave_number(&self) -> u32 {
+        let previous: usize = self.data.stages[..self.stage].iter().map(|stage| stage.waves.len()).sum();
+        (previous + self.wave + 1) as u32
+    }
+
+    // 已经打完的关卡占全部关卡的比例，用作战役中的难度等级
+    pub fn progress(&self) -> f32 {
+        self.stage as f32 / self.data.stages.len().max(1) as f32
+    }
+
+    // 本关头目被击败，进入下一关；返回整个战役是否已经通关
+    pub fn boss_defeated(&mut self) -> bool {
+        if self.stage + 1 < self.data.stages.len() {
+            self.stage += 1;
+            self.step = Step::StageBanner(STAGE_BANNER_TIME);
+            false
+        } else {
+            self.step = Step::Finished;
+            true
+        }
+    }
+}
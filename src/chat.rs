@@ -0,0 +1,200 @@
+use std::collections::HashSet;
+#[cfg(feature = "twitch")]
+use std::io::{self, BufRead, BufReader, Write};
+#[cfg(feature = "twitch")]
+use std::net::{TcpStream, ToSocketAddrs};
+#[cfg(feature = "twitch")]
+use std::time::Duration;
+
+// 直播间观众可以投票触发的事件，在聊天里发对应的指令投票
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ChatEvent {
+    Elite,     // 出现一架带护甲、会瞄准射击的精英敌人
+    Powerup,   // 掉落一个随机道具
+    Meteors,   // 一阵流星雨，飞出底部不扣生命
+}
+
+impl ChatEvent {
+    pub const ALL: [ChatEvent; 3] = [ChatEvent::Elite, ChatEvent::Powerup, ChatEvent::Meteors];
+
+    pub fn command(self) -> &'static str {
+        match self {
+            ChatEvent::Elite => "!elite",
+            ChatEvent::Powerup => "!powerup",
+            ChatEvent::Meteors => "!meteor",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ChatEvent::Elite => "精英敌人",
+            ChatEvent::Powerup => "随机道具",
+            ChatEvent::Meteors => "流星雨",
+        }
+    }
+
+    fn from_message(text: &str) -> Option<Self> {
+        let command = text.split_whitespace().next()?.to_ascii_lowercase();
+        Self::ALL.into_iter().find(|event| event.command() == command)
+    }
+}
+
+// 聊天投票：第一票进来后开始计时，vote_window 秒后票数最多的事件生效（平票时按 ALL 的顺序），
+// 然后冷却 cooldown 秒，冷却期间的投票不算。每个观众每轮只算一票
+pub struct ChatVote {
+    vote_window: f32,
+    cooldown: f32,
+    votes: [u32; 3],
+    voters: HashSet<String>,
+    window_timer: Option<f32>,  // 本轮投票还剩的秒数，还没人投票时为 None
+    cooldown_timer: f32,
+}
+
+impl ChatVote {
+    pub fn new(vote_window: f32, cooldown: f32) -> Self {
+        Self {
+            vote_window,
+            cooldown,
+            votes: [0; 3],
+            voters: HashSet::new(),
+            window_timer: None,
+            cooldown_timer: 0.0,
+        }
+    }
+
+    // 处理一条聊天消息，算作有效投票时返回 true
+    pub fn message(&mut self, user: &str, text: &str) -> bool {
+        if self.cooldown_timer > 0.0 {
+            return false;
+        }
+        let Some(event) = ChatEvent::from_message(text) else {
+            return false;
+        };
+        if !self.voters.insert(user.to_ascii_lowercase()) {
+            return false;
+        }
+        let index = ChatEvent::ALL.iter().position(|&candidate| candidate == event).unwrap_or(0);
+        self.votes[index] += 1;
+        self.window_timer.get_or_insert(self.vote_window);
+        true
+    }
+
+    // 返回这一帧投票结束时胜出的事件
+    pub fn update(&mut self, dt: f32) -> Option<ChatEvent> {
+        self.cooldown_timer = (self.cooldown_timer - dt).max(0.0);
+        let remaining = self.window_timer.as_mut()?;
+        *remaining -= dt;
+        if *remaining > 0.0 {
+            return None;
+        }
+        let winner = (0..ChatEvent::ALL.len()).rev().max_by_key(|&index| self.votes[index]).unwrap_or(0);
+        self.votes = [0; 3];
+        self.voters.clear();
+        self.window_timer = None;
+        self.cooldown_timer = self.cooldown;
+        Some(ChatEvent::ALL[winner])
+    }
+
+    // HUD 上显示的投票情况，没有进行中的投票时返回 None
+    pub fn status(&self) -> Option<String> {
+        let remaining = self.window_timer?;
+        let tally: Vec<String> = ChatEvent::ALL
+            .iter()
+            .zip(self.votes)
+            .map(|(event, votes)| format!("{} {}", event.command(), votes))
+            .collect();
+        Some(format!("观众投票 {:.0}秒: {}", remaining.ceil(), tally.join("  ")))
+    }
+}
+
+// 从一行 IRC 消息中取出发言的用户和内容，例如
+// ":viewer!viewer@viewer.tmi.twitch.tv PRIVMSG #channel :!elite"
+pub fn parse_privmsg(line: &str) -> Option<(&str, &str)> {
+    let line = line.strip_prefix(':')?;
+    let (prefix, rest) = line.split_once(' ')?;
+    let user = prefix.split('!').next()?;
+    let rest = rest.strip_prefix("PRIVMSG ")?;
+    let (_, text) = rest.split_once(" :")?;
+    Some((user, text.trim_end()))
+}
+
+#[cfg(feature = "twitch")]
+const TWITCH_IRC: (&str, u16) = ("irc.chat.twitch.tv", 6667);
+#[cfg(feature = "twitch")]
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);  // 连接在启动时进行，连不上也不能让窗口迟迟不出现
+
+// 以匿名身份只读地连接 Twitch 聊天室，不需要令牌，也不会往聊天室里发消息
+#[cfg(feature = "twitch")]
+pub struct TwitchChat {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    line: Vec<u8>,     // 还没收完的一行，按字节存，半个 UTF-8 字符留到下次接着读
+    outgoing: Vec<u8>, // 还没发出去的回复，发送缓冲区满时留到下一帧
+}
+
+#[cfg(feature = "twitch")]
+impl TwitchChat {
+    pub fn connect(channel: &str) -> io::Result<Self> {
+        Self::connect_to(TWITCH_IRC, channel)
+    }
+
+    // 依次尝试服务器的每个地址，每个最多等 CONNECT_TIMEOUT
+    pub fn connect_to(server: impl ToSocketAddrs, channel: &str) -> io::Result<Self> {
+        let mut last_error = io::Error::from(io::ErrorKind::AddrNotAvailable);
+        for address in server.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+                Ok(writer) => return Self::join(writer, channel),
+                Err(error) => last_error = error,
+            }
+        }
+        Err(last_error)
+    }
+
+    fn join(mut writer: TcpStream, channel: &str) -> io::Result<Self> {
+        let channel = channel.trim_start_matches('#').to_ascii_lowercase();
+        writer.set_write_timeout(Some(CONNECT_TIMEOUT))?;
+        write!(writer, "NICK justinfan{}\r\nJOIN #{}\r\n", std::process::id(), channel)?;
+        writer.set_nonblocking(true)?;
+        Ok(Self { reader: BufReader::new(writer.try_clone()?), writer, line: Vec::new(), outgoing: Vec::new() })
+    }
+
+    // 每帧调用一次，取出已经收到的所有聊天消息（用户，内容），不会阻塞游戏
+    pub fn poll(&mut self) -> io::Result<Vec<(String, String)>> {
+        let mut messages = Vec::new();
+        loop {
+            match self.reader.read_until(b'\n', &mut self.line) {
+                Ok(0) => return Err(io::ErrorKind::ConnectionAborted.into()),
+                Ok(_) if self.line.ends_with(b"\n") => {
+                    let bytes = std::mem::take(&mut self.line);
+                    let line = String::from_utf8_lossy(&bytes);
+                    if let Some(server) = line.strip_prefix("PING ") {
+                        write!(self.outgoing, "PONG {}\r\n", server.trim_end())?;
+                    } else if let Some((user, text)) = parse_privmsg(&line) {
+                        messages.push((user.to_string(), text.to_string()));
+                    }
+                }
+                Ok(_) => {}  // 连接断开前收到的半行，下一次读取会返回 0
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) => return Err(error),
+            }
+        }
+        self.flush()?;
+        Ok(messages)
+    }
+
+    // 尽量发出积压的回复，发不完的下一帧再发
+    fn flush(&mut self) -> io::Result<()> {
+        while !self.outgoing.is_empty() {
+            match self.writer.write(&self.outgoing) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(written) => {
+                    self.outgoing.drain(..written);
+                }
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+// 碰撞层：每个碰撞体属于一层，用位掩码表示，可以用 with 组合
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Layers(u8);
+
+impl Layers {
+    pub const NONE: Layers = Layers(0);
+    pub const PLAYER: Layers = Layers(1 << 0);
+    pub const PLAYER_SHOT: Layers = Layers(1 << 1);  // 玩家的子弹，包括反射回去的水雷
+    pub const ENEMY: Layers = Layers(1 << 2);
+    pub const ENEMY_SHOT: Layers = Layers(1 << 3);
+    pub const MINE: Layers = Layers(1 << 4);  // 头目炮台发射的水雷
+    pub const BOSS: Layers = Layers(1 << 5);
+    pub const ASTEROID: Layers = Layers(1 << 6);  // 中立的小行星，双方的子弹都能打碎
+
+    pub const fn with(self, other: Layers) -> Layers {
+        Layers(self.0 | other.0)
+    }
+
+    pub fn contains(self, other: Layers) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+// 碰撞体所在的层和它会和哪些层发生碰撞。两个碰撞体只要有一方的 mask 包含另一方的层就算碰撞，
+// 新的组合（比如敌人的子弹打到水雷）只需要改 mask，不用再写一个专门的循环
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Collider {
+    pub layer: Layers,
+    pub mask: Layers,
+}
+
+impl Collider {
+    pub const PLAYER: Collider = Collider {
+        layer: Layers::PLAYER,
+        mask: Layers::ENEMY.with(Layers::ENEMY_SHOT).with(Layers::MINE).with(Layers::BOSS).with(Layers::ASTEROID),
+    };
+
+    pub const PLAYER_SHOT: Collider = Collider {
+        layer: Layers::PLAYER_SHOT,
+        mask: Layers::ENEMY.with(Layers::MINE).with(Layers::BOSS).with(Layers::ASTEROID),
+    };
+
+    pub const ENEMY: Collider = Collider { layer: Layers::ENEMY, mask: Layers::PLAYER };
+
+    pub const ENEMY_SHOT: Collider = Collider { layer: Layers::ENEMY_SHOT, mask: Layers::PLAYER };
+
+    pub const MINE: Collider = Collider { layer: Layers::MINE, mask: Layers::PLAYER };
+
+    pub const BOSS: Collider = Collider { layer: Layers::BOSS, mask: Layers::PLAYER };
+
+    pub const ASTEROID: Collider = Collider { layer: Layers::ASTEROID, mask: Layers::PLAYER.with(Layers::ENEMY_SHOT) };
+
+    pub fn interacts(self, other: Collider) -> bool {
+        self.mask.contains(other.layer) || other.mask.contains(self.layer)
+    }
+}
+
+// 结算一帧内的全部碰撞。pairs 是 (子弹, 目标) 下标对，按子弹、目标的顺序排好，
+// alive 标记哪些目标在结算前还活着。每颗子弹最多打中一个目标，
+// 同时碰到几个目标时打下标最小的那个；目标被击落后，后面的子弹直接穿过它去找下一个目标。
+// hit 结算一次命中并返回目标是否被击落，返回值是这一帧被击落的目标，每个只出现一次，按击落的先后排列
+pub fn resolve(
+    pairs: &[(usize, usize)],
+    shots: usize,
+    alive: &[bool],
+    mut hit: impl FnMut(usize, usize) -> bool,
+) -> Vec<usize> {
+    let mut spent = vec![false; shots];
+    let mut alive = alive.to_vec();
+    let mut killed = Vec::new();
+    for &(shot, target) in pairs {
+        if spent[shot] || !alive[target] {
+            continue;
+        }
+        spent[shot] = true;
+        if hit(shot, target) {
+            alive[target] = false;
+            killed.push(target);
+        }
+    }
+    killed
+}
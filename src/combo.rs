@@ -0,0 +1,70 @@
+const WINDOW: f32 = 2.0;  // 两次击落间隔不超过这么多秒才能接上连击
+pub const GRACE: f32 = 0.5;  // 计时走完后再等这么多秒才真的掉级，期间击落仍能接上
+const KILLS_PER_STEP: u32 = 4;  // 每连续击落这么多架，倍率加 1
+const MAX_MULTIPLIER: u32 = 5;
+
+// 连击：短时间内连续击落敌人时分数倍率上升（x2、x3……），
+// 超过 WINDOW 秒没有击落，再过 GRACE 秒的宽限就掉一级并重新计时，一直掉到 x1 为止；受到伤害时直接清零。
+// 拾取「连击保持」后一段时间内计时停住，不会掉级，但受伤照样清零
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Combo {
+    kills: u32,  // 当前连击数
+    timer: f32,  // 离倍率下降还剩的秒数，小于 0 时处在宽限期
+    kept: f32,  // 连击保持还剩的秒数
+}
+
+impl Combo {
+    // 记一次击落，返回这次击落的得分倍率
+    pub fn kill(&mut self) -> u32 {
+        self.kills += 1;
+        self.timer = WINDOW;
+        self.multiplier()
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.kept = (self.kept - dt).max(0.0);
+        if self.kills == 0 || self.kept > 0.0 {
+            return;
+        }
+        self.timer -= dt;
+        if self.timer > -GRACE {
+            return;
+        }
+        // 回到下一级的起点，x1 时连击结束
+        let multiplier = self.multiplier();
+        self.kills = multiplier.saturating_sub(2) * KILLS_PER_STEP;
+        self.timer = if self.kills > 0 { WINDOW } else { 0.0 };
+    }
+
+    // 受伤清零，连击保持的时间不受影响
+    pub fn reset(&mut self) {
+        *self = Self { kept: self.kept, ..Self::default() };
+    }
+
+    // 接下来 seconds 秒内连击不会因为时间掉级
+    pub fn keep(&mut self, seconds: f32) {
+        self.kept = self.kept.max(seconds);
+    }
+
+    pub fn kept(&self) -> bool {
+        self.kept > 0.0
+    }
+
+    // 计时已经走完，再不击落就要掉级
+    pub fn in_grace(&self) -> bool {
+        self.kills > 0 && self.timer <= 0.0
+    }
+
+    pub fn kills(&self) -> u32 {
+        self.kills
+    }
+
+    pub fn multiplier(&self) -> u32 {
+        (1 + self.kills / KILLS_PER_STEP).min(MAX_MULTIPLIER)
+    }
+
+    // HUD 计量条的长度：距离倍率下降还剩的时间比例
+    pub fn meter(&self) -> f32 {
+        (self.timer / WINDOW).clamp(0.0, 1.0)
+    }
+}
@@ -0,0 +1,47 @@
+pub const CONTINUES: u32 = 3;  // 每局最多续关几次
+pub const COUNTDOWN: f32 = 10.0;  // 游戏结束画面上等玩家决定续关的秒数
+pub const KEEP_SCORE: f32 = 0.5;  // 续关后保留的分数比例，各项得分按同样的比例打折
+
+// 续关：游戏结束后倒计时内可以从当前这一波开头接着打，生命恢复为开局时的数量，分数打折。
+// 续关次数不随检查点恢复，用完就只能重新开始
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Continues {
+    left: u32,
+    countdown: f32,
+}
+
+impl Default for Continues {
+    fn default() -> Self {
+        Self { left: CONTINUES, countdown: COUNTDOWN }
+    }
+}
+
+impl Continues {
+    // 游戏结束画面显示期间调用，倒计时到头后不再提供续关
+    pub fn update(&mut self, dt: f32) {
+        self.countdown = (self.countdown - dt).max(0.0);
+    }
+
+    pub fn available(&self) -> bool {
+        self.left > 0 && self.countdown > 0.0
+    }
+
+    // 用掉一次续关，倒计时重新开始，留给下一次游戏结束
+    pub fn take(&mut self) -> bool {
+        if !self.available() {
+            return false;
+        }
+        self.left -= 1;
+        self.countdown = COUNTDOWN;
+        true
+    }
+
+    pub fn left(&self) -> u32 {
+        self.left
+    }
+
+    // 游戏结束画面上显示的剩余秒数
+    pub fn seconds(&self) -> u32 {
+        self.countdown.ceil() as u32
+    }
+}
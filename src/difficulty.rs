@@ -0,0 +1,258 @@
+use ggez::{
+    glam::Vec2,
+    graphics::{self, Canvas, Color, DrawParam, Mesh, Rect},
+    Context, GameResult,
+};
+use rand::{rngs::StdRng, Rng};
+use serde::Deserialize;
+
+use crate::enemy::{EnemyPath, Gun};
+use crate::menu;
+use crate::WINDOW_WIDTH;
+
+// 难度曲线随程序一起打包
+const DIFFICULTY_DATA: &str = include_str!("../resources/difficulty.toml");
+
+const GRAPH_LEFT: f32 = 80.0;
+const GRAPH_WIDTH: f32 = WINDOW_WIDTH - 140.0;
+const GRAPH_HEIGHT: f32 = 120.0;
+const GRAPH_SAMPLES: usize = 120;
+
+// 某一时刻的难度参数
+#[derive(Deserialize, Clone, Copy)]
+pub struct DifficultyPoint {
+    pub time: f32,
+    pub spawn_interval: f32,
+    pub enemy_speed: f32,
+    pub rank: f32,
+}
+
+// 某种敌人的出现权重：weight + per_rank * 难度等级，小于 0 时按 0 算
+#[derive(Deserialize, Clone, Copy)]
+pub struct PathWeight {
+    pub kind: EnemyPath,
+    pub weight: f32,
+    pub per_rank: f32,
+}
+
+// 会开火的敌人：出现的比例为 per_rank * 难度等级
+#[derive(Deserialize, Clone, Copy)]
+pub struct ShooterSpec {
+    pub per_rank: f32,
+    pub interval: f32,
+    pub aimed: bool,
+}
+
+// 分裂敌人：难度等级达到 min_rank 后，新生成的敌人有 per_rank * 难度等级的比例会分裂
+#[derive(Deserialize, Clone, Copy)]
+pub struct SplitterSpec {
+    pub min_rank: f32,
+    pub per_rank: f32,
+}
+
+// 母舰：开局 start 秒后第一次出现，之后每隔 interval 秒一架
+#[derive(Deserialize, Clone, Copy)]
+pub struct CarrierSpec {
+    pub start: f32,
+    pub interval: f32,
+}
+
+// 动态难度的参数：offset 是叠加在难度曲线上的偏移，表现好时慢慢升高，失去一条命时降低
+#[derive(Deserialize, Clone, Copy)]
+pub struct RankTuning {
+    pub rise: f32,       // 每秒升高多少
+    pub per_combo: f32,  // 连击倍率每高一级，每秒再多升高多少
+    pub death: f32,      // 失去一条命时降低多少
+    pub min: f32,
+    pub max: f32,
+    pub density: f32,    // 偏移为 1 时出现间隔缩短的比例
+    pub speed: f32,      // 偏移为 1 时敌人速度加快的比例
+}
+
+// 动态难度：不显示给玩家，只在无尽模式按难度曲线生成敌人时使用，让难度跟着玩家的表现自己调整
+#[derive(Clone, Copy, Default, Debug)]
+pub struct DynamicRank {
+    offset: f32,
+}
+
+impl DynamicRank {
+    // multiplier 是当前的连击倍率
+    pub fn update(&mut self, dt: f32, multiplier: u32, tuning: &RankTuning) {
+        let rise = tuning.rise + tuning.per_combo * multiplier.saturating_sub(1) as f32;
+        self.offset = (self.offset + rise * dt).clamp(tuning.min, tuning.max);
+    }
+
+    pub fn on_death(&mut self, tuning: &RankTuning) {
+        self.offset = (self.offset - tuning.death).clamp(tuning.min, tuning.max);
+    }
+
+    pub fn offset(&self) -> f32 {
+        self.offset
+    }
+}
+
+impl DifficultyPoint {
+    // 叠加动态难度后的参数：出现得更密、更快，难度等级也跟着偏移
+    pub fn with_rank(self, rank: &DynamicRank, tuning: &RankTuning) -> Self {
+        let offset = rank.offset();
+        Self {
+            spawn_interval: self.spawn_interval * (1.0 - offset * tuning.density),
+            enemy_speed: self.enemy_speed * (1.0 + offset * tuning.speed),
+            rank: (self.rank + offset).clamp(0.0, 1.0),
+            ..self
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct DifficultyCurve {
+    points: Vec<DifficultyPoint>,
+    paths: Vec<PathWeight>,
+    pub shooters: ShooterSpec,
+    pub carriers: CarrierSpec,
+    pub splitters: SplitterSpec,
+    pub dynamic_rank: RankTuning,
+}
+
+impl DifficultyCurve {
+    pub fn load() -> Self {
+        let curve: Self = toml::from_str(DIFFICULTY_DATA).expect("resources/difficulty.toml 格式错误");
+        assert!(!curve.points.is_empty(), "resources/difficulty.toml 至少需要一个关键点");
+        curve
+    }
+
+    // 按当前难度等级的权重抽一种敌人，游戏时间越长越容易出现难缠的敌人
+    pub fn pick_path(&self, rank: f32, rng: &mut StdRng) -> EnemyPath {
+        let weights: Vec<f32> = self.paths.iter().map(|path| (path.weight + path.per_rank * rank).max(0.0)).collect();
+        let total: f32 = weights.iter().sum();
+        let mut pick = rng.gen_range(0.0..total.max(f32::EPSILON));
+        for (path, weight) in self.paths.iter().zip(weights) {
+            if pick < weight {
+                return path.kind;
+            }
+            pick -= weight;
+        }
+        EnemyPath::Straight
+    }
+
+    // 新生成的敌人有没有炮，难度越高越容易有
+    pub fn pick_gun(&self, rank: f32, rng: &mut StdRng) -> Option<Gun> {
+        let shooters = self.shooters;
+        (rng.gen::<f32>() < rank * shooters.per_rank).then(|| Gun::new(shooters.interval, shooters.aimed))
+    }
+
+    // 新生成的敌人会不会分裂，难度等级不够时不抽随机数
+    pub fn pick_splits(&self, rank: f32, rng: &mut StdRng) -> bool {
+        let splitters = self.splitters;
+        rank >= splitters.min_rank && rng.gen::<f32>() < rank * splitters.per_rank
+    }
+
+    // 到 time 秒为止应该出现过几架母舰
+    pub fn carriers_due(&self, time: f32) -> u32 {
+        if time < self.carriers.start {
+            return 0;
+        }
+        ((time - self.carriers.start) / self.carriers.interval) as u32 + 1
+    }
+
+    fn duration(&self) -> f32 {
+        self.points.last().map_or(0.0, |point| point.time)
+    }
+
+    // 在前后两个关键点之间线性插值，超出范围时取两端的值
+    pub fn at(&self, time: f32) -> DifficultyPoint {
+        let next = self.points.iter().position(|point| point.time > time);
+        match next {
+            Some(0) => self.points[0],
+            None => self.points[self.points.len() - 1],
+            Some(i) => {
+                let (a, b) = (self.points[i - 1], self.points[i]);
+                let t = (time - a.time) / (b.time - a.time);
+                let lerp = |from: f32, to: f32| from + (to - from) * t;
+                DifficultyPoint {
+                    time,
+                    spawn_interval: lerp(a.spawn_interval, b.spawn_interval),
+                    enemy_speed: lerp(a.enemy_speed, b.enemy_speed),
+                    rank: lerp(a.rank, b.rank),
+                }
+            }
+        }
+    }
+
+    // 调试界面：把三条曲线画成折线图，方便不玩游戏也能检查平衡数据
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult<()> {
+        menu::draw_backdrop(ctx, canvas)?;
+        canvas.draw(
+            &graphics::Text::new("难度曲线 (按任意键返回)"),
+            DrawParam::default().dest(Vec2::new(GRAPH_LEFT, 20.0)),
+        );
+
+        let duration = self.duration().max(1.0);
+        let samples: Vec<DifficultyPoint> = (0..=GRAPH_SAMPLES)
+            .map(|i| self.at(duration * i as f32 / GRAPH_SAMPLES as f32))
+            .collect();
+        let graphs = [
+            ("出现间隔 (秒)", samples.iter().map(|point| point.spawn_interval).collect::<Vec<_>>(), Color::CYAN),
+            ("敌人速度", samples.iter().map(|point| point.enemy_speed).collect(), Color::YELLOW),
+            ("难度等级", samples.iter().map(|point| point.rank).collect(), Color::RED),
+        ];
+        for (row, (label, values, color)) in graphs.into_iter().enumerate() {
+            let top = 60.0 + row as f32 * (GRAPH_HEIGHT + 50.0);
+            draw_graph(ctx, canvas, top, label, &values, color)?;
+        }
+
+        canvas.draw(
+            &graphics::Text::new(format!("0 秒 至 {:.0} 秒", duration)),
+            DrawParam::default().dest(Vec2::new(GRAPH_LEFT, 570.0)),
+        );
+        Ok(())
+    }
+}
+
+// 一条曲线的折线图，纵轴按曲线自身的范围缩放
+fn draw_graph(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    top: f32,
+    label: &str,
+    values: &[f32],
+    color: Color,
+) -> GameResult<()> {
+    let max = values.iter().cloned().fold(f32::MIN, f32::max);
+    let min = values.iter().cloned().fold(f32::MAX, f32::min).min(0.0);
+    let range = (max - min).max(f32::EPSILON);
+
+    let frame = Mesh::new_rectangle(
+        ctx,
+        graphics::DrawMode::stroke(1.0),
+        Rect::new(GRAPH_LEFT, top, GRAPH_WIDTH, GRAPH_HEIGHT),
+        Color::new(0.5, 0.5, 0.5, 1.0),
+    )?;
+    canvas.draw(&frame, DrawParam::default());
+
+    let points: Vec<Vec2> = values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let x = GRAPH_LEFT + GRAPH_WIDTH * i as f32 / (values.len() - 1) as f32;
+            let y = top + GRAPH_HEIGHT * (1.0 - (value - min) / range);
+            Vec2::new(x, y)
+        })
+        .collect();
+    let line = Mesh::new_line(ctx, &points, 2.0, color)?;
+    canvas.draw(&line, DrawParam::default());
+
+    canvas.draw(
+        &graphics::Text::new(label),
+        DrawParam::default().dest(Vec2::new(GRAPH_LEFT, top - 20.0)),
+    );
+    canvas.draw(
+        &graphics::Text::new(format!("{:.2}", max)),
+        DrawParam::default().dest(Vec2::new(10.0, top)),
+    );
+    canvas.draw(
+        &graphics::Text::new(format!("{:.2}", min)),
+        DrawParam::default().dest(Vec2::new(10.0, top + GRAPH_HEIGHT - 16.0)),
+    );
+    Ok(())
+}
@@ -0,0 +1,180 @@
+use ggez::{
+    conf::{FullscreenType, WindowMode},
+    glam::Vec2,
+    graphics::Rect,
+    winit::dpi::{LogicalSize, PhysicalPosition},
+    Context, GameResult,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::WindowSettings;
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+
+// 窗口、无边框窗口和独占全屏。无边框窗口铺满当前显示器但不独占，
+// 切换窗口和多显示器时比独占全屏更顺畅
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    Borderless,
+    Fullscreen,
+}
+
+impl DisplayMode {
+    const ALL: [DisplayMode; 3] = [DisplayMode::Windowed, DisplayMode::Borderless, DisplayMode::Fullscreen];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DisplayMode::Windowed => "窗口",
+            DisplayMode::Borderless => "无边框窗口",
+            DisplayMode::Fullscreen => "独占全屏",
+        }
+    }
+
+    pub fn next(self, step: isize) -> Self {
+        let index = Self::ALL.iter().position(|&mode| mode == self).unwrap_or(0) as isize;
+        Self::ALL[(index + step).rem_euclid(Self::ALL.len() as isize) as usize]
+    }
+
+    fn fullscreen_type(self) -> FullscreenType {
+        match self {
+            DisplayMode::Windowed => FullscreenType::Windowed,
+            DisplayMode::Borderless => FullscreenType::Desktop,
+            DisplayMode::Fullscreen => FullscreenType::True,
+        }
+    }
+}
+
+// 运行中切换显示模式，切回窗口时恢复原来的尺寸
+pub fn set_display_mode(ctx: &mut Context, mode: DisplayMode) -> GameResult {
+    ctx.gfx.set_fullscreen(mode.fullscreen_type())
+}
+
+// 窗口尺寸按逻辑像素计算，高分屏上会按缩放系数自动放大
+pub fn window_mode(window: &WindowSettings) -> WindowMode {
+    let mut mode = WindowMode::default()
+        .dimensions(WINDOW_WIDTH, WINDOW_HEIGHT)
+        .resizable(true)
+        .resize_on_scale_factor_change(true)
+        .fullscreen_type(window.mode.fullscreen_type());
+    mode.logical_size = Some(LogicalSize::new(
+        window.width.max(WINDOW_WIDTH / 2.0),
+        window.height.max(WINDOW_HEIGHT / 2.0),
+    ));
+    mode
+}
+
+// 把窗口放回上次所在的显示器和位置；显示器已断开时保持系统默认位置
+pub fn restore_placement(ctx: &Context, window: &WindowSettings) {
+    let (Some(x), Some(y)) = (window.x, window.y) else {
+        return;
+    };
+    let win = ctx.gfx.window();
+    let target = win.available_monitors().find(|monitor| {
+        let position = monitor.position();
+        let size = monitor.size();
+        let on_monitor = x >= position.x
+            && y >= position.y
+            && x < position.x + size.width as i32
+            && y < position.y + size.height as i32;
+        let same_name = window.monitor.is_none() || monitor.name() == window.monitor;
+        on_monitor && same_name
+    });
+    if target.is_some() {
+        win.set_outer_position(PhysicalPosition::new(x, y));
+    }
+}
+
+// 记录当前窗口的位置、逻辑尺寸和所在显示器，退出时写入设置。
+// 全屏时记下的是显示器的尺寸，所以保留上次窗口模式下的值
+pub fn capture_placement(ctx: &Context, window: &mut WindowSettings) {
+    if window.mode != DisplayMode::Windowed {
+        return;
+    }
+    let win = ctx.gfx.window();
+    if let Ok(position) = win.outer_position() {
+        window.x = Some(position.x);
+        window.y = Some(position.y);
+    }
+    let size: LogicalSize<f32> = win.inner_size().to_logical(win.scale_factor());
+    window.width = size.width;
+    window.height = size.height;
+    window.monitor = win.current_monitor().and_then(|monitor| monitor.name());
+}
+
+pub const GAME_TITLE: &str = "太空射击游戏";
+
+// 标题栏显示的游戏状态，直播或切到其他窗口时也能看到
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WindowStatus {
+    Menu,
+    Playing { wave: u32, score: u32 },
+    Paused,
+    GameOver { score: u32 },
+}
+
+impl WindowStatus {
+    pub fn title(self) -> String {
+        match self {
+            WindowStatus::Menu => GAME_TITLE.to_string(),
+            WindowStatus::Playing { wave, score } => {
+                format!("{} — 第 {} 波 — 分数 {}", GAME_TITLE, wave, group_digits(score))
+            }
+            WindowStatus::Paused => format!("{} — 已暂停", GAME_TITLE),
+            WindowStatus::GameOver { score } => format!("{} — 游戏结束 — 分数 {}", GAME_TITLE, group_digits(score)),
+        }
+    }
+}
+
+// 每三位加一个逗号，例如 4,210
+pub fn group_digits(value: u32) -> String {
+    let digits = value.to_string();
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+// 只在状态变化时才改标题，不必每帧都重新格式化
+#[derive(Default)]
+pub struct WindowTitle {
+    current: Option<WindowStatus>,
+}
+
+impl WindowTitle {
+    pub fn update(&mut self, ctx: &Context, status: WindowStatus) {
+        if self.current != Some(status) {
+            self.current = Some(status);
+            ctx.gfx.window().set_title(&status.title());
+        }
+    }
+}
+
+// 把窗口中的像素位置（例如触摸点）换算成游戏坐标
+pub fn to_game(ctx: &Context, x: f32, y: f32) -> Vec2 {
+    let (width, height) = ctx.gfx.drawable_size();
+    let screen = screen_coordinates(ctx);
+    Vec2::new(screen.x + x / width.max(1.0) * screen.w, screen.y + y / height.max(1.0) * screen.h)
+}
+
+// 游戏逻辑始终使用 800x600 的坐标系，窗口比例不同时在两侧留黑边
+pub fn screen_coordinates(ctx: &Context) -> Rect {
+    let (width, height) = ctx.gfx.drawable_size();
+    if width < 1.0 || height < 1.0 {
+        return Rect::new(0.0, 0.0, WINDOW_WIDTH, WINDOW_HEIGHT);
+    }
+    let scale = (width / WINDOW_WIDTH).min(height / WINDOW_HEIGHT);
+    let view_width = width / scale;
+    let view_height = height / scale;
+    Rect::new(
+        -(view_width - WINDOW_WIDTH) / 2.0,
+        -(view_height - WINDOW_HEIGHT) / 2.0,
+        view_width,
+        view_height,
+    )
+}
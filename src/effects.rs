@@ -0,0 +1,258 @@
+use std::collections::VecDeque;
+
+use ggez::{
+    glam::Vec2,
+    graphics::{self, Canvas, Color, DrawParam, MeshBuilder, Rect},
+    Context, GameResult,
+};
+
+use crate::weapon::Hit;
+
+const PARTICLES_PER_EXPLOSION: usize = 12;
+const PARTICLE_LIFE: f32 = 0.6;
+const PARTICLE_SPEED: f32 = 140.0;
+const POPUP_LIFE: f32 = 0.8;
+const POPUP_RISE: f32 = 40.0;  // 分数提示每秒上升的像素
+const SHOCKWAVE_LIFE: f32 = 0.4;
+const SPARKS_PER_GRAZE: usize = 4;
+const SPARK_LIFE: f32 = 0.25;
+const SPARK_SPEED: f32 = 90.0;
+
+const TRAIL_WIDTH: f32 = 8.0;
+
+struct Particle {
+    position: Vec2,
+    velocity: Vec2,
+    life: f32,
+    spark: bool,  // 擦弹的火花，更小更快消失，颜色偏蓝
+}
+
+// 电磁脉冲扩散的圆环
+struct Shockwave {
+    position: Vec2,
+    radius: f32,  // 最终半径
+    life: f32,
+}
+
+// 打在头目身上的伤害数字，暴击时更大更醒目
+struct DamageNumber {
+    position: Vec2,
+    hit: Hit,
+    life: f32,
+}
+
+struct ScorePopup {
+    position: Vec2,
+    points: u32,
+    life: f32,
+}
+
+// 爆炸碎片和得分提示，只影响画面，不参与游戏逻辑也不消耗随机数
+#[derive(Default)]
+pub struct Effects {
+    particles: VecDeque<Particle>,
+    popups: Vec<ScorePopup>,
+    shockwaves: Vec<Shockwave>,
+    damage_numbers: Vec<DamageNumber>,
+}
+
+impl Effects {
+    // 超出上限时先淘汰最早的碎片
+    pub fn explode(&mut self, position: Vec2, max_particles: usize) {
+        for i in 0..PARTICLES_PER_EXPLOSION {
+            let angle = i as f32 / PARTICLES_PER_EXPLOSION as f32 * std::f32::consts::TAU;
+            let speed = PARTICLE_SPEED * (0.6 + 0.4 * (i % 3) as f32 / 2.0);
+            self.particles.push_back(Particle {
+                position,
+                velocity: Vec2::from_angle(angle) * speed,
+                life: PARTICLE_LIFE,
+                spark: false,
+            });
+        }
+        while self.particles.len() > max_particles {
+            self.particles.pop_front();
+        }
+    }
+
+    // 擦弹的几点火花，和爆炸碎片共用上限
+    pub fn spark(&mut self, position: Vec2, max_particles: usize) {
+        for i in 0..SPARKS_PER_GRAZE {
+            let angle = (i as f32 + 0.5) / SPARKS_PER_GRAZE as f32 * std::f32::consts::TAU;
+            self.particles.push_back(Particle {
+                position,
+                velocity: Vec2::from_angle(angle) * SPARK_SPEED,
+                life: SPARK_LIFE,
+                spark: true,
+            });
+        }
+        while self.particles.len() > max_particles {
+            self.particles.pop_front();
+        }
+    }
+
+    // 数量达到上限时丢掉最早的一个
+    pub fn damage_number(&mut self, position: Vec2, hit: Hit, max_numbers: usize) {
+        if self.damage_numbers.len() >= max_numbers && !self.damage_numbers.is_empty() {
+            self.damage_numbers.remove(0);
+        }
+        self.damage_numbers.push(DamageNumber {
+            position,
+            hit,
+            life: POPUP_LIFE,
+        });
+    }
+
+    pub fn shockwave(&mut self, position: Vec2, radius: f32) {
+        self.shockwaves.push(Shockwave {
+            position,
+            radius,
+            life: SHOCKWAVE_LIFE,
+        });
+    }
+
+    // 提示数量达到上限时，把分数并入离得最近的一个提示
+    pub fn popup(&mut self, position: Vec2, points: u32, max_popups: usize) {
+        if self.popups.len() >= max_popups {
+            if let Some(nearest) = self
+                .popups
+                .iter_mut()
+                .min_by(|a, b| a.position.distance(position).total_cmp(&b.position.distance(position)))
+            {
+                nearest.points += points;
+                nearest.life = POPUP_LIFE;
+                return;
+            }
+        }
+        self.popups.push(ScorePopup {
+            position,
+            points,
+            life: POPUP_LIFE,
+        });
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for particle in &mut self.particles {
+            particle.position += particle.velocity * dt;
+            particle.life -= dt;
+        }
+        self.particles.retain(|particle| particle.life > 0.0);
+        for popup in &mut self.popups {
+            popup.position.y -= POPUP_RISE * dt;
+            popup.life -= dt;
+        }
+        self.popups.retain(|popup| popup.life > 0.0);
+        for shockwave in &mut self.shockwaves {
+            shockwave.life -= dt;
+        }
+        self.shockwaves.retain(|shockwave| shockwave.life > 0.0);
+        for number in &mut self.damage_numbers {
+            number.position.y -= POPUP_RISE * dt;
+            number.life -= dt;
+        }
+        self.damage_numbers.retain(|number| number.life > 0.0);
+    }
+
+    pub fn particle_count(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn popup_count(&self) -> usize {
+        self.popups.len()
+    }
+
+    // 所有碎片合成一个网格绘制，数量很多时也只有一次绘制调用。
+    // density 小于 1 时每隔几块才画一块，画面质量低时用
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas, density: f32) -> GameResult<()> {
+        if !self.particles.is_empty() {
+            let stride = (1.0 / density.max(0.01)).round().max(1.0) as usize;
+            let mut builder = MeshBuilder::new();
+            for particle in self.particles.iter().step_by(stride) {
+                let (rect, color) = if particle.spark {
+                    let fade = particle.life / SPARK_LIFE;
+                    let rect = Rect::new(particle.position.x - 1.0, particle.position.y - 1.0, 2.0, 2.0);
+                    (rect, Color::new(0.7, 0.8, 1.0, fade))
+                } else {
+                    let fade = particle.life / PARTICLE_LIFE;
+                    let rect = Rect::new(particle.position.x - 2.0, particle.position.y - 2.0, 4.0, 4.0);
+                    (rect, Color::new(1.0, 0.5 + 0.5 * fade, 0.2, fade))
+                };
+                builder.rectangle(graphics::DrawMode::fill(), rect, color)?;
+            }
+            let mesh = graphics::Mesh::from_data(ctx, builder.build());
+            canvas.draw(&mesh, DrawParam::default());
+        }
+
+        for shockwave in &self.shockwaves {
+            let progress = 1.0 - shockwave.life / SHOCKWAVE_LIFE;
+            let ring = graphics::Mesh::new_circle(
+                ctx,
+                graphics::DrawMode::stroke(3.0),
+                shockwave.position,
+                (shockwave.radius * progress).max(1.0),
+                1.0,
+                Color::new(0.4, 0.7, 1.0, 1.0 - progress),
+            )?;
+            canvas.draw(&ring, DrawParam::default());
+        }
+
+        for number in &self.damage_numbers {
+            let fade = number.life / POPUP_LIFE;
+            let (label, size, color) = if number.hit.critical {
+                (format!("{}!", number.hit.damage), 28.0, Color::new(1.0, 0.6, 0.1, fade))
+            } else {
+                (number.hit.damage.to_string(), 14.0, Color::new(1.0, 1.0, 1.0, fade))
+            };
+            let mut text = graphics::Text::new(label);
+            text.set_scale(size);
+            canvas.draw(&text, DrawParam::default().dest(number.position).color(color));
+        }
+
+        for popup in &self.popups {
+            canvas.draw(
+                &graphics::Text::new(format!("+{}", popup.points)),
+                DrawParam::default()
+                    .dest(popup.position)
+                    .color(Color::new(1.0, 1.0, 0.6, popup.life / POPUP_LIFE)),
+            );
+        }
+        Ok(())
+    }
+}
+
+// 飞船引擎的尾迹：记下最近几帧的位置，越旧越细越淡。只在外层绘制时使用，不属于游戏世界
+#[derive(Default)]
+pub struct Trail {
+    points: VecDeque<Vec2>,
+}
+
+impl Trail {
+    // 记下这一帧的位置，只保留最近 length 帧
+    pub fn push(&mut self, position: Vec2, length: usize) {
+        self.points.push_front(position);
+        self.points.truncate(length);
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult<()> {
+        if self.points.is_empty() {
+            return Ok(());
+        }
+        let mut builder = MeshBuilder::new();
+        let count = self.points.len() as f32;
+        for (age, point) in self.points.iter().enumerate() {
+            let fade = 1.0 - age as f32 / count;
+            let width = TRAIL_WIDTH * fade;
+            builder.rectangle(
+                graphics::DrawMode::fill(),
+                Rect::new(point.x - width / 2.0, point.y + 12.0, width, 6.0),
+                Color::new(0.4, 0.7, 1.0, 0.5 * fade),
+            )?;
+        }
+        let mesh = graphics::Mesh::from_data(ctx, builder.build());
+        canvas.draw(&mesh, DrawParam::default());
+        Ok(())
+    }
+}
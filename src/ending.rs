@@ -0,0 +1,128 @@
+use ggez::{
+    glam::Vec2,
+    graphics::{self, Canvas, Color, DrawParam},
+    Context, GameResult,
+};
+
+use crate::menu;
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+
+const TALLY_TIME: f32 = 3.0;
+const UNLOCK_TIME: f32 = 3.0;
+const CREDITS_SPEED: f32 = 40.0;  // 字幕每秒上移的像素
+
+const CREDITS: [&str; 9] = [
+    "太空射击游戏",
+    "",
+    "程序与设计",
+    "luozijian1223",
+    "",
+    "使用 Rust 与 ggez 制作",
+    "",
+    "",
+    "感谢游玩!",
+];
+
+// 战役通关后的结算：分数统计、解锁展示、滚动字幕
+pub struct Ending {
+    elapsed: f32,
+    tally: Vec<(String, u32)>,
+    total: u32,
+    unlocks: Vec<String>,
+}
+
+impl Ending {
+    pub fn new(tally: Vec<(String, u32)>, unlocks: Vec<String>) -> Self {
+        let total = tally.iter().map(|(_, points)| points).sum();
+        Self {
+            elapsed: 0.0,
+            tally,
+            total,
+            unlocks,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.elapsed += dt;
+    }
+
+    fn credits_start(&self) -> f32 {
+        let unlock_time = if self.unlocks.is_empty() { 0.0 } else { UNLOCK_TIME };
+        TALLY_TIME + unlock_time
+    }
+
+    fn credits_end(&self) -> f32 {
+        self.credits_start() + (WINDOW_HEIGHT + CREDITS.len() as f32 * 30.0) / CREDITS_SPEED
+    }
+
+    pub fn finished(&self) -> bool {
+        self.elapsed >= self.credits_end()
+    }
+
+    // 按键跳到下一段
+    pub fn skip(&mut self) {
+        self.elapsed = if self.elapsed < TALLY_TIME {
+            TALLY_TIME
+        } else if self.elapsed < self.credits_start() {
+            self.credits_start()
+        } else {
+            self.credits_end()
+        };
+    }
+
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult<()> {
+        menu::draw_backdrop(ctx, canvas)?;
+        let x = WINDOW_WIDTH / 2.0 - 150.0;
+
+        if self.elapsed < TALLY_TIME {
+            // 各项分数依次滚动到最终值
+            let progress = (self.elapsed / (TALLY_TIME * 0.8)).min(1.0);
+            canvas.draw(
+                &graphics::Text::new("战役完成!"),
+                DrawParam::default().dest(Vec2::new(x, 150.0)).color(Color::YELLOW),
+            );
+            for (i, (label, points)) in self.tally.iter().enumerate() {
+                let shown = (*points as f32 * progress) as u32;
+                canvas.draw(
+                    &graphics::Text::new(format!("{}: {}", label, shown)),
+                    DrawParam::default().dest(Vec2::new(x, 200.0 + i as f32 * 30.0)),
+                );
+            }
+            let total = (self.total as f32 * progress) as u32;
+            canvas.draw(
+                &graphics::Text::new(format!("总分: {}", total)),
+                DrawParam::default()
+                    .dest(Vec2::new(x, 220.0 + self.tally.len() as f32 * 30.0))
+                    .color(Color::YELLOW),
+            );
+        } else if self.elapsed < self.credits_start() {
+            canvas.draw(
+                &graphics::Text::new("解锁新内容!"),
+                DrawParam::default().dest(Vec2::new(x, 200.0)).color(Color::YELLOW),
+            );
+            for (i, unlock) in self.unlocks.iter().enumerate() {
+                canvas.draw(
+                    &graphics::Text::new(unlock.as_str()),
+                    DrawParam::default().dest(Vec2::new(x, 250.0 + i as f32 * 30.0)),
+                );
+            }
+        } else {
+            let offset = (self.elapsed - self.credits_start()) * CREDITS_SPEED;
+            for (i, line) in CREDITS.iter().enumerate() {
+                let y = WINDOW_HEIGHT + i as f32 * 30.0 - offset;
+                canvas.draw(
+                    &graphics::Text::new(*line),
+                    DrawParam::default().dest(Vec2::new(x, y)),
+                );
+            }
+        }
+
+        canvas.draw(
+            &graphics::Text::new("按回车键继续，E键导出得分记录"),
+            DrawParam::default()
+                .dest(Vec2::new(WINDOW_WIDTH - 300.0, WINDOW_HEIGHT - 30.0))
+                .color(Color::new(1.0, 1.0, 1.0, 0.6)),
+        );
+        Ok(())
+    }
+}
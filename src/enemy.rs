@@ -0,0 +1,420 @@
+use ggez::glam::Vec2;
+use ggez::graphics::Color;
+use rand::{rngs::StdRng, Rng};
+use serde::Deserialize;
+
+use crate::collision::Collider;
+use crate::playfield::Boundary;
+use crate::status::{Immunity, StatusEffects};
+use crate::swarm::Flight;
+use crate::world::DamageSource;
+use crate::{GameObject, WINDOW_WIDTH};
+
+// 敌人的移动路线
+#[derive(Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum EnemyPath {
+    #[default]
+    Straight,  // 直线下落
+    Drift,     // 斜向飘移
+    Zigzag,    // 左右摆动着下落
+    Diver,     // 先慢慢进场，随后高速俯冲
+    Kamikaze,  // 飞到屏幕上方停下，锁定玩家当时的位置后加速冲过去
+}
+
+const DIVE_DELAY: f32 = 1.0;  // 俯冲型敌人进场后多久开始俯冲
+const KAMIKAZE_HOLD_Y: f32 = 90.0;  // 自爆机停下来瞄准的高度
+const KAMIKAZE_HOVER: f32 = 0.6;  // 停下多久后锁定玩家
+pub const KAMIKAZE_WARNING: f32 = 0.5;  // 锁定后显示预警线的秒数，之后开始冲刺
+const KAMIKAZE_ACCEL: f32 = 900.0;  // 冲刺时每秒加快的速度
+pub const CARRIER_COLOR: Color = Color::new(0.55, 0.35, 0.8, 1.0);
+pub const CARRIER_BONUS: u32 = 90;  // 母舰在路线的分数之外多得的分
+const LAUNCH_COUNT: usize = 2;  // 母舰每次放出几架小飞机
+const MINION_SIZE: f32 = 14.0;
+const MINION_SPEED: f32 = 260.0;
+const MINION_POINTS: u32 = 5;
+pub const SPLITTER_COLOR: Color = Color::new(0.35, 0.9, 0.45, 1.0);
+const SPLIT_SCALE: f32 = 0.7;  // 分出来的每一个是原来大小的多少
+const SPLIT_SPEEDUP: f32 = 1.4;  // 分出来的比原来快多少
+const SPLIT_SPREAD: f32 = 60.0;  // 分出来的两个向两侧散开的横向速度
+const MIN_SPLIT_SIZE: f32 = 14.0;  // 再分就比这还小时不再分裂
+pub const BOUNTY_COLOR: Color = Color::new(1.0, 0.85, 0.2, 1.0);  // 悬赏目标是金色的，外面再加一圈光晕
+const HIT_FLASH: f32 = 0.1;  // 被打中但没被击落时闪白的秒数
+
+// 敌人携带的炮：每隔 interval 秒开一炮，aimed 时朝玩家当前的位置，否则直接向下
+#[derive(Clone, Copy, Debug)]
+pub struct Gun {
+    pub interval: f32,
+    pub aimed: bool,
+    timer: f32,
+}
+
+impl Gun {
+    // 第一炮在进场后满一个间隔才开
+    pub fn new(interval: f32, aimed: bool) -> Self {
+        Self { interval, aimed, timer: interval }
+    }
+
+    fn tick(&mut self, dt: f32) -> bool {
+        self.timer -= dt;
+        if self.timer > 0.0 {
+            return false;
+        }
+        self.timer += self.interval;
+        true
+    }
+}
+
+impl EnemyPath {
+    // 击落时的得分，越难打的越值钱
+    pub fn points(self) -> u32 {
+        match self {
+            EnemyPath::Straight => 10,
+            EnemyPath::Drift => 15,
+            EnemyPath::Zigzag => 20,
+            EnemyPath::Diver => 25,
+            EnemyPath::Kamikaze => 30,
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            EnemyPath::Straight => Color::RED,
+            EnemyPath::Drift => Color::new(1.0, 0.45, 0.1, 1.0),
+            EnemyPath::Zigzag => Color::new(0.9, 0.2, 0.6, 1.0),
+            EnemyPath::Diver => Color::new(1.0, 0.85, 0.2, 1.0),
+            EnemyPath::Kamikaze => Color::new(1.0, 0.55, 0.55, 1.0),
+        }
+    }
+
+    // 无尽模式中这种敌人的大小和相对基础速度的倍数，战役的大小和速度由关卡数据决定
+    pub fn endless_size(self) -> f32 {
+        match self {
+            EnemyPath::Straight | EnemyPath::Drift => 30.0,
+            EnemyPath::Zigzag => 26.0,
+            EnemyPath::Diver | EnemyPath::Kamikaze => 22.0,
+        }
+    }
+
+    pub fn endless_speed(self) -> f32 {
+        match self {
+            EnemyPath::Straight | EnemyPath::Drift | EnemyPath::Kamikaze => 1.0,
+            EnemyPath::Zigzag => 0.9,
+            EnemyPath::Diver => 1.2,
+        }
+    }
+}
+
+// 母舰的机库：每隔 interval 秒放出一批小飞机，第一批在进场后满一个间隔才放
+#[derive(Clone, Copy, Debug)]
+pub struct Carrier {
+    pub interval: f32,
+    timer: f32,
+}
+
+impl Carrier {
+    pub fn new(interval: f32) -> Self {
+        Self { interval, timer: interval }
+    }
+}
+
+// 母舰放出的小飞机：个头小、飞得快，从母舰两侧斜着往下冲，飞出底部不扣生命
+pub fn minion(position: Vec2, side: f32) -> Enemy {
+    let mut minion = Enemy::new(position.x, position.y, MINION_SIZE, MINION_SPEED, EnemyPath::Straight);
+    minion.game_object.velocity.x = side * MINION_SPEED * 0.3;
+    minion.points = MINION_POINTS;
+    minion.escape_penalty = false;
+    minion.source = DamageSource::Minion;
+    minion
+}
+
+// 被击落的分裂敌人分出的两个：各小一圈、快一些、分数减半，向两侧散开，
+// 小到不能再分之前也会分裂。不是分裂敌人或已经最小时为空
+pub fn splinters(enemy: &Enemy) -> Vec<Enemy> {
+    let object = &enemy.game_object;
+    let size = object.size.x * SPLIT_SCALE;
+    if !enemy.splits || size < MIN_SPLIT_SIZE {
+        return Vec::new();
+    }
+    [-1.0, 1.0]
+        .into_iter()
+        .map(|side| {
+            let position = object.position + Vec2::new(side * object.size.x / 4.0, 0.0);
+            let mut splinter = Enemy::new(position.x, position.y, size, object.velocity.y.max(60.0) * SPLIT_SPEEDUP, EnemyPath::Straight);
+            splinter.game_object.velocity.x = side * SPLIT_SPREAD;
+            splinter.set_hp((enemy.max_hp / 2).max(1));
+            splinter.points = (enemy.points / 2).max(1);
+            splinter.escape_penalty = enemy.escape_penalty;
+            splinter.make_splitter();
+            splinter
+        })
+        .collect()
+}
+
+// 悬赏目标：平均每隔 interval 秒，场上一个普通敌人被挂上悬赏，击落得 multiplier 倍的分数。
+// 它只停留 linger 秒，时间到了或者被打中没被击落就以 flee_speed 的速度往上逃走
+#[derive(Deserialize, Clone, Copy)]
+pub struct BountyBalance {
+    pub interval: f32,
+    pub multiplier: u32,
+    pub linger: f32,
+    pub flee_speed: f32,
+}
+
+impl BountyBalance {
+    // 距离下一个悬赏目标的秒数
+    pub fn next_interval(&self, rng: &mut StdRng) -> f32 {
+        rng.gen_range(0.5..1.5) * self.interval
+    }
+}
+
+// 悬赏目标还能停留几秒，小于等于 0 时正在逃走
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Bounty {
+    pub remaining: f32,
+    flee_speed: f32,
+}
+
+// 自爆机的行动阶段
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Kamikaze {
+    Entering,  // 按自己的速度飞到瞄准的高度
+    Hovering(f32),  // 停在原地，还剩几秒锁定
+    Locked { direction: Vec2, remaining: f32 },  // 已经锁定方向，显示预警线
+    Diving { direction: Vec2, speed: f32 },  // 沿锁定的方向越飞越快
+}
+
+pub struct Enemy {
+    pub game_object: GameObject,
+    pub escape_penalty: bool,  // 飞出屏幕底部时玩家是否损失生命
+    pub boundary: Boundary,  // 碰到屏幕边缘时调头还是删除，由游戏世界统一处理
+    pub source: DamageSource,  // 撞上玩家时记为哪种伤害来源
+    pub status: StatusEffects,  // 燃烧、减速、眩晕等状态
+    pub hp: u32,
+    pub max_hp: u32,  // 满血时的血量，用来画血条
+    pub armor: u32,  // 每点护甲挡掉 1 点伤害，可以被穿甲抵消
+    pub points: u32,  // 击落时的得分
+    pub gun: Option<Gun>,  // 会还击的敌人才有
+    pub collider: Collider,
+    pub asteroid: Option<u32>,  // 小行星的大小等级，普通敌人为 None
+    pub flight: Option<Flight>,  // 编队敌机的入场和俯冲状态，由 Swarm 负责移动
+    pub pierced: u32,  // 最后一次穿过它的蓄力弹或穿透子弹的编号，0 表示还没被穿过
+    pub kamikaze: Option<Kamikaze>,  // 自爆机才有
+    pub carrier: Option<Carrier>,  // 母舰才有
+    pub bounty: Option<Bounty>,  // 悬赏目标才有
+    pub splits: bool,  // 击落时分裂成两个更小的
+    pub grazed: bool,  // 已经擦过飞船，每个敌人只算一次
+    path: EnemyPath,
+    origin_x: f32,
+    age: f32,
+    flash: f32,  // 闪白还剩的秒数
+}
+
+impl Enemy {
+    pub fn path(&self) -> EnemyPath {
+        self.path
+    }
+
+    pub fn new(x: f32, y: f32, size: f32, speed: f32, path: EnemyPath) -> Self {
+        let mut game_object = GameObject::new(x, y, size, size);
+        game_object.velocity.y = speed;
+        if path == EnemyPath::Drift {
+            // 朝屏幕中间飘过去
+            let direction = if x < WINDOW_WIDTH / 2.0 { 1.0 } else { -1.0 };
+            game_object.velocity.x = direction * speed * 0.5;
+        }
+        let kamikaze = path == EnemyPath::Kamikaze;
+        Self {
+            game_object,
+            // 自爆机冲过头飞出屏幕不算突破防线
+            escape_penalty: !kamikaze,
+            boundary: if kamikaze { Boundary::KAMIKAZE } else { Boundary::ENEMY },
+            source: DamageSource::from_path(path),
+            status: StatusEffects::new(if path == EnemyPath::Zigzag { Immunity::ZIGZAG } else { Immunity::NONE }),
+            hp: 1,
+            max_hp: 1,
+            armor: 0,
+            points: path.points(),
+            gun: None,
+            collider: Collider::ENEMY,
+            asteroid: None,
+            flight: None,
+            pierced: 0,
+            kamikaze: kamikaze.then_some(Kamikaze::Entering),
+            carrier: None,
+            bounty: None,
+            splits: false,
+            grazed: false,
+            path,
+            origin_x: x,
+            age: 0.0,
+            flash: 0.0,
+        }
+    }
+
+    // 设置满血血量，能挨好几发的敌人用
+    pub fn set_hp(&mut self, hp: u32) {
+        self.hp = hp;
+        self.max_hp = hp;
+    }
+
+    // 受过伤时返回剩余血量的比例，满血或只有 1 点血的敌人不画血条
+    pub fn health(&self) -> Option<f32> {
+        (self.hp < self.max_hp).then(|| self.hp as f32 / self.max_hp as f32)
+    }
+
+    pub fn flashing(&self) -> bool {
+        self.flash > 0.0
+    }
+
+    // 推进炮的计时，返回这一帧是否开火，眩晕期间不开火
+    pub fn trigger(&mut self, dt: f32) -> Option<Gun> {
+        if self.status.stunned() {
+            return None;
+        }
+        let gun = self.gun.as_mut()?;
+        gun.tick(dt).then_some(*gun)
+    }
+
+    // 推进机库的计时，到时间时返回这一批小飞机，由游戏世界在这一帧结束前加入。眩晕期间不放
+    pub fn launch(&mut self, dt: f32) -> Vec<Enemy> {
+        if self.status.stunned() {
+            return Vec::new();
+        }
+        let Some(carrier) = &mut self.carrier else {
+            return Vec::new();
+        };
+        carrier.timer -= dt;
+        if carrier.timer > 0.0 {
+            return Vec::new();
+        }
+        carrier.timer += carrier.interval;
+        let bounds = self.game_object.bounds();
+        (0..LAUNCH_COUNT)
+            .map(|index| {
+                let side = if index % 2 == 0 { -1.0 } else { 1.0 };
+                minion(Vec2::new(bounds.center().x + side * bounds.w / 4.0, bounds.bottom()), side)
+            })
+            .collect()
+    }
+
+    // 受到伤害，返回是否被击落。悬赏目标被打中没被击落时马上逃走
+    pub fn hit(&mut self, damage: u32) -> bool {
+        self.hp = self.hp.saturating_sub(damage);
+        if damage > 0 {
+            self.flash = HIT_FLASH;
+            if let Some(bounty) = &mut self.bounty {
+                bounty.remaining = 0.0;
+            }
+        }
+        self.hp == 0
+    }
+
+    // 击落时分裂成两个更小更快的，也记成分裂敌人造成的伤害
+    pub fn make_splitter(&mut self) {
+        self.splits = true;
+        self.source = DamageSource::Splitter;
+    }
+
+    // 只有按路线飞行的普通敌人能挂上悬赏，小行星、编队、自爆机、母舰、分裂敌人和不扣生命的敌人不行
+    pub fn can_carry_bounty(&self) -> bool {
+        self.escape_penalty
+            && !self.splits
+            && self.path != EnemyPath::Kamikaze
+            && self.asteroid.is_none()
+            && self.flight.is_none()
+            && self.carrier.is_none()
+            && self.bounty.is_none()
+    }
+
+    // 挂上悬赏：分数乘以悬赏倍数，逃走或飞出底部都不扣生命
+    pub fn mark_bounty(&mut self, balance: &BountyBalance) {
+        self.points *= balance.multiplier;
+        self.escape_penalty = false;
+        self.bounty = Some(Bounty { remaining: balance.linger, flee_speed: balance.flee_speed });
+    }
+
+    pub fn fleeing(&self) -> bool {
+        self.bounty.is_some_and(|bounty| bounty.remaining <= 0.0)
+    }
+
+    // 停够了的自爆机锁定玩家现在的位置，之后不再跟着玩家转向
+    pub fn lock_on(&mut self, player: Vec2) {
+        if let Some(Kamikaze::Hovering(remaining)) = self.kamikaze {
+            if remaining <= 0.0 {
+                let direction = (player - self.game_object.position).try_normalize().unwrap_or(Vec2::Y);
+                self.kamikaze = Some(Kamikaze::Locked { direction, remaining: KAMIKAZE_WARNING });
+            }
+        }
+    }
+
+    // 锁定之后、冲刺之前的冲刺方向，用来画预警线
+    pub fn warning(&self) -> Option<Vec2> {
+        match self.kamikaze {
+            Some(Kamikaze::Locked { direction, .. }) => Some(direction),
+            _ => None,
+        }
+    }
+
+    // 只负责按路线移动，边界由 boundary 处理，返回这一帧燃烧造成的伤害。
+    // 减速时移动变慢，眩晕期间不再转向，只是直线下落
+    pub fn update(&mut self, dt: f32) -> u32 {
+        let burn = self.status.update(dt);
+        self.flash = (self.flash - dt).max(0.0);
+        let dt = dt * self.status.speed_factor();
+        let object = &mut self.game_object;
+        if self.flight.is_some() {
+            return burn;
+        }
+        if self.status.stunned() {
+            object.position.y += object.velocity.y * dt;
+            return burn;
+        }
+        self.age += dt;
+        if let Some(bounty) = &mut self.bounty {
+            bounty.remaining -= dt;
+            if bounty.remaining <= 0.0 {
+                // 掉头往上飞，飞出顶部后删除
+                self.boundary = Boundary::FLEEING;
+                object.position.y -= bounty.flee_speed * dt;
+                return burn;
+            }
+        }
+        match self.path {
+            EnemyPath::Straight | EnemyPath::Drift => object.position += object.velocity * dt,
+            EnemyPath::Zigzag => {
+                object.position.y += object.velocity.y * dt;
+                object.position.x = self.origin_x + (self.age * 3.0).sin() * 60.0;
+            }
+            EnemyPath::Diver => {
+                let factor = if self.age < DIVE_DELAY { 0.4 } else { 2.5 };
+                object.position.y += object.velocity.y * factor * dt;
+            }
+            EnemyPath::Kamikaze => {
+                let Some(kamikaze) = &mut self.kamikaze else {
+                    return burn;
+                };
+                match kamikaze {
+                    Kamikaze::Entering => {
+                        object.position.y += object.velocity.y * dt;
+                        if object.position.y >= KAMIKAZE_HOLD_Y {
+                            *kamikaze = Kamikaze::Hovering(KAMIKAZE_HOVER);
+                        }
+                    }
+                    Kamikaze::Hovering(remaining) => *remaining -= dt,
+                    Kamikaze::Locked { direction, remaining } => {
+                        *remaining -= dt;
+                        if *remaining <= 0.0 {
+                            *kamikaze = Kamikaze::Diving { direction: *direction, speed: object.velocity.y };
+                        }
+                    }
+                    Kamikaze::Diving { direction, speed } => {
+                        *speed += KAMIKAZE_ACCEL * dt;
+                        object.position += *direction * *speed * dt;
+                    }
+                }
+            }
+        }
+        burn
+    }
+}
@@ -0,0 +1,53 @@
+use ggez::glam::Vec2;
+
+use crate::chat::ChatEvent;
+use crate::milestone::Milestone;
+use crate::powerup::PowerupKind;
+use crate::quest::QuestOutcome;
+use crate::shop::ShopItem;
+use crate::world::DamageSource;
+
+// 各个系统之间通过事件通信，事件在每帧末尾统一处理
+pub enum GameEvent {
+    RunStarted,
+    BossSpawned,
+    BossIntroFinished,
+    BossDefeated { bonus: u32 },
+    BossPhaseChanged { phase: usize },
+    BossTurretFired { position: Vec2 },
+    PowerupCollected { kind: PowerupKind, position: Vec2 },
+    PlayerDamaged { source: DamageSource },
+    WeaponDowngraded { level: u32 },  // 受伤时火力降级代替掉命
+    ShieldDamaged { source: DamageSource },  // 护盾条模式下护盾挡下了一击
+    Quest { outcome: QuestOutcome },  // 局内任务开始、完成或失败
+    BountyMarked { points: u32 },  // 场上一个敌人挂上了悬赏，points 是击落它的得分
+    ExtraLife { lives: u32 },  // 分数达到奖励线或拾取道具后多了一条命，lives 是之后的生命数
+    CriticalHit { position: Vec2 },
+    Grazed { position: Vec2 },  // 敌人或敌人的子弹擦过飞船，position 是擦过的位置
+    ChargedShotFired { damage: u32 },  // 松开开火键打出一发蓄力弹
+    WaveCleared { wave: u32, bonus: u32 },  // 战役中清空一波敌人
+    CoinCollected { wallet: u32 },  // 拾取金币后身上的金币数
+    ItemBought { item: ShopItem },  // 在波次之间的商店里买了东西
+    MilestoneReached { milestone: Milestone },  // 无尽模式到了第 10、20……波，记下这时的状态
+    CampaignCleared,
+    WaveRestarted,  // 辅助模式下从本波开头重来
+    Continued { left: u32 },  // 游戏结束后续关，left 是还能续几次
+    ChatVoteWon { event: ChatEvent },  // 直播间观众投票选出的事件
+    GameOver,
+}
+
+#[derive(Default)]
+pub struct EventBus {
+    queue: Vec<GameEvent>,
+}
+
+impl EventBus {
+    pub fn emit(&mut self, event: GameEvent) {
+        self.queue.push(event);
+    }
+
+    // 取出本帧所有事件，处理过程中产生的新事件留到下一帧
+    pub fn take(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.queue)
+    }
+}
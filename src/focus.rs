@@ -0,0 +1,59 @@
+pub const SLOW_SCALE: f32 = 0.3;  // 专注期间敌人和敌人子弹的速度倍数
+const DRAIN_TIME: f32 = 2.5;  // 满槽能连续专注的秒数
+const REFILL_TIME: f32 = 6.0;  // 从空槽恢复到满槽的秒数
+
+// 专注：按住专注键时敌人和敌人的子弹放慢，玩家照常行动，同时消耗专注槽。
+// 松开后专注槽慢慢恢复；用空之后要松开再按才能重新专注，不会一直卡在槽底时断时续
+#[derive(Clone, Copy, Debug)]
+pub struct Focus {
+    meter: f32,  // 0 到 1
+    active: bool,
+    exhausted: bool,  // 用空后还没松开
+}
+
+impl Default for Focus {
+    fn default() -> Self {
+        Self { meter: 1.0, active: false, exhausted: false }
+    }
+}
+
+impl Focus {
+    // 每帧告诉专注键是否按住
+    pub fn hold(&mut self, held: bool) {
+        if !held {
+            self.exhausted = false;
+        }
+        self.active = held && !self.exhausted && self.meter > 0.0;
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if self.active {
+            self.meter -= dt / DRAIN_TIME;
+            if self.meter <= 0.0 {
+                self.meter = 0.0;
+                self.active = false;
+                self.exhausted = true;
+            }
+        } else {
+            self.meter = (self.meter + dt / REFILL_TIME).min(1.0);
+        }
+    }
+
+    pub fn active(&self) -> bool {
+        self.active
+    }
+
+    // HUD 计量条的长度
+    pub fn meter(&self) -> f32 {
+        self.meter
+    }
+
+    // 敌方这一帧的时间倍数
+    pub fn time_scale(&self) -> f32 {
+        if self.active {
+            SLOW_SCALE
+        } else {
+            1.0
+        }
+    }
+}
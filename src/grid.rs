@@ -0,0 +1,77 @@
+use ggez::graphics::Rect;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+
+const CELL_SIZE: f32 = 80.0;
+const COLS: usize = (WINDOW_WIDTH / CELL_SIZE) as usize;
+const ROWS: usize = (WINDOW_HEIGHT / CELL_SIZE) as usize + 1;
+
+// 碰撞粗筛用的均匀网格：只有落在同一格子里的子弹和敌人才需要精确检测。
+// 屏幕外的物体归到最靠边的格子，互相重叠的两个矩形总会共享至少一个格子
+#[derive(Default)]
+pub struct Grid {
+    cells: Vec<(Vec<usize>, Vec<usize>)>,  // 每个格子里的子弹和敌人下标
+}
+
+fn cell_range(rect: &Rect) -> (usize, usize, usize, usize) {
+    let col = |x: f32| ((x / CELL_SIZE).max(0.0) as usize).min(COLS - 1);
+    let row = |y: f32| ((y / CELL_SIZE).max(0.0) as usize).min(ROWS - 1);
+    (col(rect.left()), col(rect.right()), row(rect.top()), row(rect.bottom()))
+}
+
+impl Grid {
+    fn insert(&mut self, rect: &Rect, index: usize, bullet: bool) {
+        let (left, right, top, bottom) = cell_range(rect);
+        for row in top..=bottom {
+            for col in left..=right {
+                let (bullets, enemies) = &mut self.cells[row * COLS + col];
+                if bullet {
+                    bullets.push(index);
+                } else {
+                    enemies.push(index);
+                }
+            }
+        }
+    }
+
+    // 找出所有互相重叠的 (子弹, 敌人) 下标对，按下标排序并去重。
+    // 开启 parallel 功能时各个格子分给多个线程检测，结果和单线程完全一致
+    pub fn overlapping_pairs(&mut self, bullets: &[Rect], enemies: &[Rect]) -> Vec<(usize, usize)> {
+        // 保留上一帧分配好的格子，只清空内容
+        self.cells.resize_with(COLS * ROWS, Default::default);
+        for (cell_bullets, cell_enemies) in &mut self.cells {
+            cell_bullets.clear();
+            cell_enemies.clear();
+        }
+        for (index, rect) in bullets.iter().enumerate() {
+            self.insert(rect, index, true);
+        }
+        for (index, rect) in enemies.iter().enumerate() {
+            self.insert(rect, index, false);
+        }
+
+        let check_cell = |(cell_bullets, cell_enemies): &(Vec<usize>, Vec<usize>)| {
+            let mut pairs = Vec::new();
+            for &bullet in cell_bullets {
+                for &enemy in cell_enemies {
+                    if bullets[bullet].overlaps(&enemies[enemy]) {
+                        pairs.push((bullet, enemy));
+                    }
+                }
+            }
+            pairs
+        };
+
+        #[cfg(feature = "parallel")]
+        let mut pairs: Vec<(usize, usize)> = self.cells.par_iter().flat_map_iter(check_cell).collect();
+        #[cfg(not(feature = "parallel"))]
+        let mut pairs: Vec<(usize, usize)> = self.cells.iter().flat_map(check_cell).collect();
+
+        pairs.sort_unstable();
+        pairs.dedup();
+        pairs
+    }
+}
@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+
+const HIT_COST: f32 = 0.35;  // 每次被打中扣掉的护盾，满条能吸收三下
+const REGEN_DELAY: f32 = 3.0;  // 这么多秒没被打中后护盾开始恢复
+const REGEN_RATE: f32 = 0.25;  // 恢复时每秒回复的比例
+pub const HIT_INVINCIBLE: f32 = 1.0;  // 护盾挡下一击后的无敌时间，比掉命短
+pub const SHIELD_LIVES: u32 = 1;  // 护盾条模式开局的生命数，永久强化照样加在上面
+
+// 生命的规则：默认是几条命，另一种是一条会自己恢复的护盾条
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthModel {
+    #[default]
+    Lives,
+    Shield,  // 只有一条命，护盾条先挡下攻击，打空了才掉命
+}
+
+impl HealthModel {
+    pub fn label(self) -> &'static str {
+        match self {
+            HealthModel::Lives => "生命",
+            HealthModel::Shield => "护盾条",
+        }
+    }
+}
+
+// 护盾条：被打中扣掉一截，一段时间没被打中后慢慢回满。
+// 打空之后的下一击才会造成伤害，掉命后重新充满
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ShieldBar {
+    value: f32,  // 0 到 1
+    since_hit: f32,  // 距离上次被打中的秒数
+}
+
+impl Default for ShieldBar {
+    fn default() -> Self {
+        Self { value: 1.0, since_hit: REGEN_DELAY }
+    }
+}
+
+impl ShieldBar {
+    // 挡下一击，已经打空时返回 false
+    pub fn absorb(&mut self) -> bool {
+        self.since_hit = 0.0;
+        if self.value <= 0.0 {
+            return false;
+        }
+        self.value = (self.value - HIT_COST).max(0.0);
+        true
+    }
+
+    pub fn refill(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.since_hit += dt;
+        if self.since_hit >= REGEN_DELAY {
+            self.value = (self.value + REGEN_RATE * dt).min(1.0);
+        }
+    }
+
+    // HUD 护盾条的长度
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    pub fn regenerating(&self) -> bool {
+        self.since_hit >= REGEN_DELAY && self.value < 1.0
+    }
+}
@@ -0,0 +1,87 @@
+use ggez::{
+    glam::Vec2,
+    graphics::{self, Canvas, Color, DrawParam, MeshBuilder, Rect},
+    Context, GameResult,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::menu;
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+
+pub const COLUMNS: usize = 16;  // 画面横向分成的格数
+pub const ROWS: usize = 12;
+const AREA: Rect = Rect { x: 60.0, y: 80.0, w: WINDOW_WIDTH - 120.0, h: WINDOW_HEIGHT - 150.0 };  // 热图画在屏幕上的区域
+
+// 跨局累计的受击位置：把画面分成 COLUMNS x ROWS 格，记下每一格被打中和失去最后一条命的次数。
+// 只存计数，玩得再多文件也不会变大
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct HeatMap {
+    pub hits: Vec<u32>,  // 按行排列，旧存档或还没记录时为空
+    pub deaths: Vec<u32>,
+}
+
+impl HeatMap {
+    // 飞船在 position 处被打中，size 是游戏区域的大小，超出区域的位置算在最近的格子里
+    pub fn record(&mut self, position: Vec2, size: Vec2, death: bool) {
+        let cells = COLUMNS * ROWS;
+        self.hits.resize(cells, 0);
+        self.deaths.resize(cells, 0);
+        let cell = Self::cell(position, size);
+        self.hits[cell] += 1;
+        if death {
+            self.deaths[cell] += 1;
+        }
+    }
+
+    pub fn cell(position: Vec2, size: Vec2) -> usize {
+        let column = ((position.x / size.x * COLUMNS as f32) as usize).min(COLUMNS - 1);
+        let row = ((position.y / size.y * ROWS as f32) as usize).min(ROWS - 1);
+        row * COLUMNS + column
+    }
+
+    pub fn total_hits(&self) -> u32 {
+        self.hits.iter().sum()
+    }
+
+    pub fn total_deaths(&self) -> u32 {
+        self.deaths.iter().sum()
+    }
+
+    // 统计界面：被打中越多的格子越红，失去最后一条命的格子标出次数
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult<()> {
+        menu::draw_backdrop(ctx, canvas)?;
+        canvas.draw(
+            &graphics::Text::new(format!(
+                "受击热图    被击中 {} 次，坠毁 {} 次    按任意键返回",
+                self.total_hits(),
+                self.total_deaths()
+            )),
+            DrawParam::default().dest(Vec2::new(AREA.x, 40.0)),
+        );
+
+        let (width, height) = (AREA.w / COLUMNS as f32, AREA.h / ROWS as f32);
+        let max = self.hits.iter().copied().max().unwrap_or(0).max(1) as f32;
+        let mut builder = MeshBuilder::new();
+        builder.rectangle(graphics::DrawMode::stroke(1.0), AREA, Color::new(0.5, 0.5, 0.5, 1.0))?;
+        for (cell, &hits) in self.hits.iter().enumerate().filter(|(_, &hits)| hits > 0) {
+            let heat = hits as f32 / max;
+            let rect = Rect::new(AREA.x + (cell % COLUMNS) as f32 * width, AREA.y + (cell / COLUMNS) as f32 * height, width, height);
+            builder.rectangle(graphics::DrawMode::fill(), rect, Color::new(1.0, 1.0 - heat, 0.2 * (1.0 - heat), 0.2 + 0.6 * heat))?;
+        }
+        let mesh = graphics::Mesh::from_data(ctx, builder.build());
+        canvas.draw(&mesh, DrawParam::default());
+
+        for (cell, &deaths) in self.deaths.iter().enumerate().filter(|(_, &deaths)| deaths > 0) {
+            let position = Vec2::new(AREA.x + (cell % COLUMNS) as f32 * width + 4.0, AREA.y + (cell / COLUMNS) as f32 * height + 4.0);
+            canvas.draw(&graphics::Text::new(format!("x{}", deaths)), DrawParam::default().dest(position));
+        }
+        if self.hits.is_empty() {
+            canvas.draw(
+                &graphics::Text::new("还没有受击记录"),
+                DrawParam::default().dest(Vec2::new(AREA.x + 20.0, AREA.y + 20.0)),
+            );
+        }
+        Ok(())
+    }
+}
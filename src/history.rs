@@ -0,0 +1,177 @@
+use ggez::{
+    glam::Vec2,
+    graphics::{self, Canvas, Color, DrawParam},
+    input::keyboard::KeyCode,
+    Context, GameResult,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::menu;
+use crate::weapon::CritStats;
+use crate::world::{DamageSource, DeathCause, GameMode};
+use crate::WINDOW_WIDTH;
+
+pub const MAX_RUNS: usize = 100;
+const VISIBLE_ROWS: usize = 14;
+const COLUMNS: [f32; 5] = [0.0, 140.0, 240.0, 320.0, 420.0];  // 各列相对左边的位置
+
+// 一局游戏的摘要，统计和成就也从这里读取
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RunSummary {
+    pub mode: String,
+    pub score: u32,
+    pub waves: u32,  // 到达的波次
+    pub duration: f32,  // 游戏时间（秒）
+    pub cleared: bool,  // 战役通关
+    pub cause: Option<DeathCause>,  // 没有通关也没有死亡时是中途放弃
+    pub killed_by: Option<DamageSource>,  // 旧版本的记录没有这一项
+    pub finished_at: u64,  // 结束时间，Unix 秒
+    #[serde(default)]
+    pub crits: CritStats,  // 命中和暴击次数，旧版本的记录没有这一项
+}
+
+impl RunSummary {
+    pub fn outcome(&self) -> &'static str {
+        match (self.cleared, self.cause, self.killed_by) {
+            (true, ..) => "通关",
+            (false, _, Some(source)) => source.label(),
+            (false, Some(cause), None) => cause.label(),
+            (false, None, None) => "放弃",
+        }
+    }
+}
+
+// 最近若干局的记录，从旧到新排列，超出上限时丢弃最旧的
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct RunHistory {
+    pub runs: Vec<RunSummary>,
+}
+
+impl RunHistory {
+    pub fn push(&mut self, run: RunSummary) {
+        self.runs.push(run);
+        if self.runs.len() > MAX_RUNS {
+            let excess = self.runs.len() - MAX_RUNS;
+            self.runs.drain(..excess);
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortKey {
+    #[default]
+    Recent,
+    Score,
+    Waves,
+    Duration,
+}
+
+impl SortKey {
+    const ALL: [SortKey; 4] = [SortKey::Recent, SortKey::Score, SortKey::Waves, SortKey::Duration];
+
+    fn label(self) -> &'static str {
+        match self {
+            SortKey::Recent => "最近",
+            SortKey::Score => "分数",
+            SortKey::Waves => "波次",
+            SortKey::Duration => "时长",
+        }
+    }
+
+    fn next(self, step: isize) -> Self {
+        let index = Self::ALL.iter().position(|&key| key == self).unwrap_or(0) as isize;
+        let count = Self::ALL.len() as isize;
+        Self::ALL[(index + step).rem_euclid(count) as usize]
+    }
+}
+
+// 历史记录界面：左右键切换排序方式，上下键滚动，Esc 返回
+#[derive(Default)]
+pub struct HistoryView {
+    pub sort: SortKey,
+    scroll: usize,
+}
+
+impl HistoryView {
+    // 返回 false 表示要离开这个界面
+    pub fn handle_key(&mut self, key: KeyCode, history: &RunHistory) -> bool {
+        match key {
+            KeyCode::Left => self.sort = self.sort.next(-1),
+            KeyCode::Right => self.sort = self.sort.next(1),
+            KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down => {
+                let max = history.runs.len().saturating_sub(VISIBLE_ROWS);
+                self.scroll = (self.scroll + 1).min(max);
+            }
+            KeyCode::Escape | KeyCode::Return | KeyCode::NumpadEnter => return false,
+            _ => (),
+        }
+        if matches!(key, KeyCode::Left | KeyCode::Right) {
+            self.scroll = 0;
+        }
+        true
+    }
+
+    // 按当前排序方式排好的记录，并列时较新的在前
+    pub fn sorted<'a>(&self, history: &'a RunHistory) -> Vec<&'a RunSummary> {
+        let mut runs: Vec<&RunSummary> = history.runs.iter().rev().collect();
+        match self.sort {
+            SortKey::Recent => (),
+            SortKey::Score => runs.sort_by_key(|run| std::cmp::Reverse(run.score)),
+            SortKey::Waves => runs.sort_by_key(|run| std::cmp::Reverse(run.waves)),
+            SortKey::Duration => runs.sort_by(|a, b| b.duration.total_cmp(&a.duration)),
+        }
+        runs
+    }
+
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas, history: &RunHistory) -> GameResult<()> {
+        menu::draw_backdrop(ctx, canvas)?;
+        let x = 60.0;
+        canvas.draw(
+            &graphics::Text::new(format!(
+                "历史记录 (最近 {} 局)    排序: < {} >    Esc 返回",
+                MAX_RUNS,
+                self.sort.label()
+            )),
+            DrawParam::default().dest(Vec2::new(x, 40.0)),
+        );
+        let header = Color::new(0.7, 0.7, 0.7, 1.0);
+        for (column, title) in COLUMNS.iter().zip(["模式", "分数", "波次", "时长", "结果"]) {
+            canvas.draw(
+                &graphics::Text::new(title),
+                DrawParam::default().dest(Vec2::new(x + column, 80.0)).color(header),
+            );
+        }
+
+        let runs = self.sorted(history);
+        if runs.is_empty() {
+            canvas.draw(
+                &graphics::Text::new("还没有记录，先去玩一局吧"),
+                DrawParam::default().dest(Vec2::new(x, 120.0)),
+            );
+        }
+        for (row, run) in runs.iter().skip(self.scroll).take(VISIBLE_ROWS).enumerate() {
+            let mode = GameMode::from_key(&run.mode).map_or(run.mode.clone(), |mode| mode.label().to_string());
+            let duration = run.duration as u32;
+            let cells = [
+                mode,
+                run.score.to_string(),
+                run.waves.to_string(),
+                format!("{}:{:02}", duration / 60, duration % 60),
+                run.outcome().to_string(),
+            ];
+            let y = 110.0 + row as f32 * 30.0;
+            for (column, cell) in COLUMNS.iter().zip(cells) {
+                canvas.draw(&graphics::Text::new(cell), DrawParam::default().dest(Vec2::new(x + column, y)));
+            }
+        }
+        if runs.len() > VISIBLE_ROWS {
+            canvas.draw(
+                &graphics::Text::new(format!("{}-{} / {}", self.scroll + 1, (self.scroll + VISIBLE_ROWS).min(runs.len()), runs.len())),
+                DrawParam::default().dest(Vec2::new(WINDOW_WIDTH - 160.0, 560.0)),
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::VecDeque;
+
+use ggez::{
+    glam::Vec2,
+    graphics::{self, Canvas, Color, DrawParam, MeshBuilder, Rect},
+    Context, GameResult,
+};
+
+use crate::world::World;
+
+pub const CLIP_SECONDS: f32 = 1.0;  // 回放被击中前这么多秒的画面
+pub const SLOW: f32 = 0.5;  // 回放的速度倍数，1 秒的画面放 2 秒
+const PIP_SIZE: Vec2 = Vec2::new(240.0, 180.0);  // 角落小窗的大小
+const BULLET_SIZE: f32 = 4.0;
+
+// 回放需要的一帧画面，只记位置和大小
+#[derive(Clone, Debug)]
+pub struct ClipFrame {
+    pub time: f32,  // 记录时的累计真实时间
+    pub player: Rect,
+    pub enemies: Vec<Rect>,
+    pub bullets: Vec<Vec2>,  // 敌人的子弹
+    pub boss: Option<Rect>,
+}
+
+impl ClipFrame {
+    fn of(world: &World, time: f32) -> Self {
+        Self {
+            time,
+            player: world.player.game_object.bounds(),
+            enemies: world.enemies.iter().map(|enemy| enemy.game_object.bounds()).collect(),
+            bullets: world.enemy_bullets.positions.clone(),
+            boss: world.boss.as_ref().map(|boss| boss.game_object.bounds()),
+        }
+    }
+}
+
+// 每帧记下画面，只保留最近 CLIP_SECONDS 秒，失去一条命时从这里剪出回放。
+// 只在外层绘制时使用，不属于游戏世界
+#[derive(Default)]
+pub struct ClipRecorder {
+    frames: VecDeque<ClipFrame>,
+    time: f32,
+}
+
+impl ClipRecorder {
+    pub fn record(&mut self, dt: f32, world: &World) {
+        self.time += dt;
+        self.frames.push_back(ClipFrame::of(world, self.time));
+        while self.frames.front().is_some_and(|frame| frame.time < self.time - CLIP_SECONDS) {
+            self.frames.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    // 剪出到现在为止的回放，还没记下画面时返回 None
+    pub fn clip(&self) -> Option<HitReplay> {
+        let start = self.frames.front()?.time;
+        Some(HitReplay { frames: self.frames.iter().cloned().collect(), start, elapsed: 0.0 })
+    }
+}
+
+// 失去一条命后在角落慢放被击中前的画面，放完才继续游戏
+pub struct HitReplay {
+    frames: Vec<ClipFrame>,
+    start: f32,
+    elapsed: f32,  // 已经放了多少真实秒
+}
+
+impl HitReplay {
+    pub fn update(&mut self, dt: f32) {
+        self.elapsed += dt;
+    }
+
+    // 画面放到最后一帧就结束
+    pub fn finished(&self) -> bool {
+        self.frames.last().is_none_or(|last| self.start + self.elapsed * SLOW >= last.time)
+    }
+
+    // 现在应该显示的一帧
+    pub fn frame(&self) -> &ClipFrame {
+        let time = self.start + self.elapsed * SLOW;
+        let index = self.frames.partition_point(|frame| frame.time <= time).saturating_sub(1);
+        &self.frames[index]
+    }
+
+    // 在右下角的小窗里画出这一帧，field 是游戏区域的大小
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas, screen: Vec2, field: Vec2) -> GameResult<()> {
+        let frame = self.frame();
+        let corner = Rect::new(screen.x - PIP_SIZE.x - 10.0, screen.y - PIP_SIZE.y - 40.0, PIP_SIZE.x, PIP_SIZE.y);
+        let scale = Vec2::new(corner.w / field.x, corner.h / field.y);
+        let place = |rect: Rect| Rect::new(corner.x + rect.x * scale.x, corner.y + rect.y * scale.y, rect.w * scale.x, rect.h * scale.y);
+
+        let mut builder = MeshBuilder::new();
+        builder.rectangle(graphics::DrawMode::fill(), corner, Color::new(0.0, 0.0, 0.0, 0.85))?;
+        for &enemy in &frame.enemies {
+            builder.rectangle(graphics::DrawMode::fill(), place(enemy), Color::RED)?;
+        }
+        if let Some(boss) = frame.boss {
+            builder.rectangle(graphics::DrawMode::fill(), place(boss), Color::new(0.8, 0.2, 0.8, 1.0))?;
+        }
+        for &bullet in &frame.bullets {
+            let dot = Rect::new(bullet.x - BULLET_SIZE / 2.0, bullet.y - BULLET_SIZE / 2.0, BULLET_SIZE, BULLET_SIZE);
+            builder.rectangle(graphics::DrawMode::fill(), place(dot), Color::MAGENTA)?;
+        }
+        builder.rectangle(graphics::DrawMode::fill(), place(frame.player), Color::WHITE)?;
+        builder.rectangle(graphics::DrawMode::stroke(2.0), corner, Color::new(1.0, 1.0, 1.0, 0.8))?;
+        let mesh = graphics::Mesh::from_data(ctx, builder.build());
+        canvas.draw(&mesh, DrawParam::default());
+        canvas.draw(
+            &graphics::Text::new("受击回放 (慢放)"),
+            DrawParam::default().dest(Vec2::new(corner.x, corner.y - 22.0)),
+        );
+        Ok(())
+    }
+}
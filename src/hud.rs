@@ -0,0 +1,289 @@
+use ggez::{
+    glam::Vec2,
+    graphics::{self, Canvas, Color, DrawParam, Rect},
+};
+
+use crate::bindings::{self, Action, Bindings};
+use crate::ledger;
+use crate::storage::StreamerSettings;
+use crate::streamer;
+use crate::world::World;
+use crate::{Dash, WINDOW_HEIGHT, WINDOW_WIDTH};
+
+const LINE_HEIGHT: f32 = 26.0;
+const COMBO_METER_WIDTH: f32 = 120.0;
+const FOCUS_METER_WIDTH: f32 = 100.0;
+const SHIELD_METER_WIDTH: f32 = 100.0;
+
+// HUD 上的各个部件，游戏画面和暂停界面共用同一套文字
+
+pub fn score(world: &World) -> String {
+    format!("分数: {}", world.score)
+}
+
+// 战役模式同时显示本波还剩多少敌人
+pub fn wave(world: &World) -> String {
+    match world.wave_remaining() {
+        Some(remaining) => format!("波次: {}  剩余: {}", world.wave(), remaining),
+        None => format!("波次: {}", world.wave()),
+    }
+}
+
+// 护盾条模式同时显示护盾剩下的百分比
+pub fn lives(world: &World) -> String {
+    match world.player.shield {
+        Some(shield) => format!("生命: {}  护盾: {:.0}%", world.player.lives, shield.value() * 100.0),
+        None => format!("生命: {}", world.player.lives),
+    }
+}
+
+// 和最高分那一局在同一时刻的分数差，还没有最高分时不显示
+pub fn pace(world: &World) -> Option<String> {
+    let best = ledger::pace_at(world.best_pace.as_deref()?, world.run_time);
+    let difference = world.score as i64 - best as i64;
+    Some(format!("最佳进度: {:+}", difference))
+}
+
+pub fn weapon(world: &World) -> String {
+    format!("火力: {}/{}", world.player.weapon_level, world.max_weapon_level())
+}
+
+// 身上的金币，只在有商店的战役类模式显示
+pub fn wallet(world: &World) -> Option<String> {
+    world.wave_remaining().map(|_| format!("金币: {}", world.wallet))
+}
+
+// 本局的擦弹次数，还没擦过时不显示
+pub fn grazes(world: &World) -> Option<String> {
+    (world.grazes > 0).then(|| format!("擦弹: {}", world.grazes))
+}
+
+pub fn bombs(world: &World) -> String {
+    format!("炸弹: {}", world.player.bombs)
+}
+
+// 当前的主武器，数字键 1 到 3 切换
+pub fn loadout(world: &World) -> String {
+    format!("武器: {}", world.weapon.name())
+}
+
+// 连击倍率大于 1 时才显示
+pub fn combo(world: &World) -> Option<String> {
+    let multiplier = world.combo.multiplier();
+    (multiplier > 1).then(|| format!("连击 x{}  ({})", multiplier, world.combo.kills()))
+}
+
+// 练习局里头目正在用的招式，登场动画期间不显示
+pub fn boss_pattern(world: &World) -> Option<String> {
+    let boss = world.boss.as_ref().filter(|boss| world.practice && !boss.in_intro())?;
+    let phase = boss.current_phase()?;
+    Some(format!("招式: {}  (第 {}/{} 阶段)", phase.pattern.label(), boss.phase() + 1, boss.spec.phases.len()))
+}
+
+// 进行中的局内任务
+pub fn quest(world: &World) -> Option<String> {
+    world.quests.active().map(|quest| format!("任务: {}", quest.label()))
+}
+
+// 生效中的限时道具及剩余秒数
+pub fn buffs(world: &World) -> Vec<String> {
+    world
+        .buffs
+        .active()
+        .map(|(name, remaining)| format!("{} {:.0}s", name, remaining.ceil()))
+        .collect()
+}
+
+// 电磁脉冲冷却中时显示剩余秒数
+pub fn emp(world: &World) -> String {
+    if world.emp_cooldown > 0.0 {
+        format!("电磁脉冲 {:.0}s", world.emp_cooldown.ceil())
+    } else {
+        "电磁脉冲 就绪".to_string()
+    }
+}
+
+// 冲刺的冷却比电磁脉冲短得多，剩余时间精确到 0.1 秒
+pub fn dash(world: &World) -> String {
+    match world.player.dash {
+        Dash::Ready => "冲刺 就绪".to_string(),
+        Dash::Dashing { .. } => "冲刺中".to_string(),
+        Dash::Cooldown(remaining) => format!("冲刺 {:.1}s", remaining),
+    }
+}
+
+pub fn controls(bindings: &Bindings) -> String {
+    Action::ALL
+        .into_iter()
+        .map(|action| format!("{}: {}", action.label(), bindings::key_name(bindings.key(action))))
+        .collect::<Vec<_>>()
+        .join("   ")
+}
+
+// HUD 的样式：直播模式下放大文字、隐藏种子，并避开留给摄像头的角落
+#[derive(Clone, Copy, Debug)]
+pub struct HudStyle {
+    pub scale: f32,
+    pub show_seed: bool,  // 直播时隐藏种子，防止观众按种子抢先练习同一局
+    pub blank: Option<Rect>,  // 留空给摄像头的区域，HUD 不会画在这里
+    pub accent: Color,  // 连击和头目招式等需要突出的文字，取自配色设置
+}
+
+impl Default for HudStyle {
+    fn default() -> Self {
+        Self { scale: 1.0, show_seed: true, blank: None, accent: Color::YELLOW }
+    }
+}
+
+impl HudStyle {
+    pub fn new(streamer: &StreamerSettings) -> Self {
+        if !streamer.enabled {
+            return Self::default();
+        }
+        Self {
+            scale: streamer.hud_scale.max(1.0),
+            show_seed: false,
+            blank: streamer.webcam_corner.map(streamer::webcam_rect),
+            ..Self::default()
+        }
+    }
+
+    // 一块大小为 size 的文字放在 position 时，如果压到摄像头区域就挪到区域的上方或下方
+    pub fn place(&self, position: Vec2, size: Vec2) -> Vec2 {
+        let Some(blank) = self.blank else {
+            return position;
+        };
+        if !Rect::new(position.x, position.y, size.x, size.y).overlaps(&blank) {
+            return position;
+        }
+        if blank.y > 0.0 {
+            Vec2::new(position.x, blank.y - size.y)
+        } else {
+            Vec2::new(position.x, blank.bottom())
+        }
+    }
+
+    fn text(&self, canvas: &mut Canvas, text: String, position: Vec2, width: f32, color: Color) {
+        let size = Vec2::new(width, LINE_HEIGHT) * self.scale;
+        canvas.draw(
+            &graphics::Text::new(text),
+            DrawParam::default()
+                .dest(self.place(position, size))
+                .scale(Vec2::splat(self.scale))
+                .color(color),
+        );
+    }
+}
+
+// 游戏中左上角的分数、波次、生命和道具计时，随机波次模式在右上角显示种子
+pub fn draw(canvas: &mut Canvas, world: &World, style: &HudStyle) {
+    let scale = style.scale;
+    let row = |index: f32| 10.0 + index * 30.0 * scale;
+    let header = format!("{}   {}", score(world), wave(world));
+    style.text(canvas, header, Vec2::new(10.0, row(0.0)), 360.0, Color::WHITE);
+
+    // 由模式决定是否显示种子，例如随机波次模式，方便分享
+    let right = WINDOW_WIDTH - 220.0 * scale;
+    if world.rules.shows_seed() && style.show_seed {
+        style.text(canvas, format!("种子: {}", world.seed), Vec2::new(right, row(0.0)), 210.0, Color::WHITE);
+    }
+
+    // 最佳进度用淡灰色显示在右上角，不抢分数的注意力
+    if let Some(pace) = pace(world) {
+        style.text(canvas, pace, Vec2::new(right, row(1.0)), 210.0, Color::new(0.7, 0.7, 0.7, 0.8));
+    }
+
+    // 模式自己的信息，例如计时赛的剩余时间
+    if let Some(extra) = world.rules.hud_extra(world) {
+        style.text(canvas, extra, Vec2::new(right, row(2.0)), 210.0, Color::WHITE);
+    }
+    if world.practice {
+        style.text(canvas, "练习 (不计分)".to_string(), Vec2::new(right, row(3.0)), 210.0, Color::new(0.7, 0.7, 0.7, 0.8));
+    }
+    // 练习头目时在血条下面标出当前的招式
+    if let Some(pattern) = boss_pattern(world) {
+        style.text(canvas, pattern, Vec2::new(WINDOW_WIDTH / 2.0 - 200.0, 105.0), 400.0, style.accent);
+    }
+
+    let mut line = format!("{}  {}  {}  {}", lives(world), bombs(world), loadout(world), weapon(world));
+    if let Some(wallet) = wallet(world) {
+        line += &format!("  {}", wallet);
+    }
+    if let Some(grazes) = grazes(world) {
+        line += &format!("  {}", grazes);
+    }
+    for buff in buffs(world) {
+        line += &format!("  {}", buff);
+    }
+    style.text(canvas, line, Vec2::new(10.0, row(1.0)), WINDOW_WIDTH / scale - 20.0, Color::WHITE);
+
+    // 连击倍率下面的计量条随时间缩短，缩到头后字变红，宽限期过了倍率就掉一级。
+    // 连击保持期间计量条变成青色，停住不动
+    if let Some(combo) = combo(world) {
+        let size = Vec2::new(COMBO_METER_WIDTH, LINE_HEIGHT + 6.0) * scale;
+        let position = style.place(Vec2::new(10.0, row(2.0)), size);
+        let color = if world.combo.in_grace() { Color::RED } else { style.accent };
+        style.text(canvas, combo, position, COMBO_METER_WIDTH, color);
+        let width = COMBO_METER_WIDTH * world.combo.meter() * scale;
+        let meter = Rect::new(position.x, position.y + 24.0 * scale, width, 6.0 * scale);
+        let color = if world.combo.kept() { Color::CYAN } else { style.accent };
+        canvas.draw(&graphics::Quad, DrawParam::default().dest_rect(meter).color(color));
+    }
+    // 进行中的任务显示在连击下面
+    if let Some(quest) = quest(world) {
+        style.text(canvas, quest, Vec2::new(10.0, row(3.0)), 360.0, Color::new(0.6, 1.0, 0.6, 1.0));
+    }
+    // 左下角的专注槽，专注中变成青色
+    let size = Vec2::new(FOCUS_METER_WIDTH + 60.0, LINE_HEIGHT) * scale;
+    let position = style.place(Vec2::new(10.0, WINDOW_HEIGHT - 30.0 * scale), size);
+    style.text(canvas, "专注".to_string(), position, 60.0, Color::WHITE);
+    let color = if world.focus.active() { Color::CYAN } else { Color::new(0.6, 0.6, 0.6, 1.0) };
+    let meter = Rect::new(position.x + 50.0 * scale, position.y + 8.0 * scale, FOCUS_METER_WIDTH * world.focus.meter() * scale, 6.0 * scale);
+    canvas.draw(&graphics::Quad, DrawParam::default().dest_rect(meter).color(color));
+    // 护盾条模式在专注槽上面画护盾条，恢复中变成青色
+    if let Some(shield) = world.player.shield {
+        let size = Vec2::new(SHIELD_METER_WIDTH + 60.0, LINE_HEIGHT) * scale;
+        let position = style.place(Vec2::new(10.0, WINDOW_HEIGHT - 56.0 * scale), size);
+        style.text(canvas, "护盾".to_string(), position, 60.0, Color::WHITE);
+        let color = if shield.regenerating() { Color::CYAN } else { Color::new(0.4, 0.6, 1.0, 1.0) };
+        let meter = Rect::new(position.x + 50.0 * scale, position.y + 8.0 * scale, SHIELD_METER_WIDTH * shield.value() * scale, 6.0 * scale);
+        canvas.draw(&graphics::Quad, DrawParam::default().dest_rect(meter).color(color));
+    }
+
+    let position = Vec2::new(WINDOW_WIDTH - 160.0 * scale, WINDOW_HEIGHT - 60.0 * scale);
+    style.text(canvas, dash(world), position, 150.0, Color::WHITE);
+    let position = Vec2::new(WINDOW_WIDTH - 160.0 * scale, WINDOW_HEIGHT - 30.0 * scale);
+    style.text(canvas, emp(world), position, 150.0, Color::WHITE);
+}
+
+// 暂停界面左侧的本局信息和底部的按键提示，方便休息后重新进入状态
+pub fn draw_run_info(canvas: &mut Canvas, world: &World, bindings: &Bindings) {
+    let mut lines = vec![
+        world.mode.label().to_string(),
+        wave(world),
+        score(world),
+        lives(world),
+        bombs(world),
+        loadout(world),
+        weapon(world),
+    ];
+    lines.extend(wallet(world));
+    let active = buffs(world);
+    if !active.is_empty() {
+        lines.push(String::new());
+        lines.push("道具:".to_string());
+        lines.extend(active.into_iter().map(|buff| format!("  {}", buff)));
+    }
+    for (row, line) in lines.into_iter().enumerate() {
+        canvas.draw(
+            &graphics::Text::new(line),
+            DrawParam::default().dest(Vec2::new(40.0, 150.0 + row as f32 * LINE_HEIGHT)),
+        );
+    }
+    canvas.draw(
+        &graphics::Text::new(controls(bindings)),
+        DrawParam::default()
+            .dest(Vec2::new(40.0, WINDOW_HEIGHT - 60.0))
+            .color(Color::new(0.7, 0.7, 0.7, 1.0)),
+    );
+}
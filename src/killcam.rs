@@ -0,0 +1,47 @@
+use ggez::{glam::Vec2, graphics::Rect};
+
+use crate::tween::{Easing, Tween};
+
+const DURATION: f32 = 2.0;  // 慢镜头持续的真实秒数，结束后显示结算画面
+pub const TIME_SCALE: f32 = 0.2;  // 慢镜头期间所有物体的时间倍数
+const ZOOM: f32 = 2.0;
+const ZOOM_TIME: f32 = 0.6;
+
+// 失去最后一条命时的慢镜头：镜头拉近到玩家身上，高亮造成最后一击的物体
+#[derive(Clone, Copy)]
+pub struct KillCam {
+    pub focus: Vec2,  // 玩家被击中时的位置
+    pub killer: Rect,  // 最后一击的来源在被击中那一刻的范围
+    zoom: Tween<f32>,
+    elapsed: f32,
+}
+
+impl KillCam {
+    pub fn new(focus: Vec2, killer: Rect) -> Self {
+        Self {
+            focus,
+            killer,
+            zoom: Tween::new(1.0, ZOOM, ZOOM_TIME, Easing::EaseOutCubic),
+            elapsed: 0.0,
+        }
+    }
+
+    // 按真实时间推进，不受慢镜头本身影响
+    pub fn update(&mut self, dt: f32) {
+        self.zoom.update(dt);
+        self.elapsed += dt;
+    }
+
+    pub fn finished(&self) -> bool {
+        self.elapsed >= DURATION
+    }
+
+    // 镜头拉近后看到的区域：base 是平时的整个画面，中心从画面中间移到玩家身上
+    pub fn view(&self, base: Rect) -> Rect {
+        let zoom = self.zoom.value();
+        let t = (zoom - 1.0) / (ZOOM - 1.0);
+        let center = Vec2::new(base.x + base.w / 2.0, base.y + base.h / 2.0).lerp(self.focus, t);
+        let (width, height) = (base.w / zoom, base.h / zoom);
+        Rect::new(center.x - width / 2.0, center.y - height / 2.0, width, height)
+    }
+}
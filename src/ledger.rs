@@ -0,0 +1,193 @@
+use ggez::{
+    glam::Vec2,
+    graphics::{self, Canvas, Color, DrawParam, Mesh, Rect},
+    Context, GameResult,
+};
+use serde::Serialize;
+
+const CHART_WIDTH: f32 = 300.0;
+const CHART_ROW: f32 = 24.0;
+pub const PACE_INTERVAL: f32 = 5.0;  // 得分进度每隔多少秒记一次
+
+// 得分的来源
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoreReason {
+    Enemy,      // 击落敌人
+    Combo,      // 连击倍率多出来的分数
+    Bomb,       // 炸弹清场
+    Boss,       // 击败头目的奖励
+    WaveBonus,  // 战役中清空一波的奖励
+    LifeBonus,  // 通关时的剩余生命奖励
+    Graze,      // 敌人的子弹擦过飞船
+    Quest,      // 完成局内任务的奖励
+}
+
+impl ScoreReason {
+    pub const ALL: [ScoreReason; 8] = [
+        ScoreReason::Enemy,
+        ScoreReason::Combo,
+        ScoreReason::Bomb,
+        ScoreReason::Boss,
+        ScoreReason::WaveBonus,
+        ScoreReason::LifeBonus,
+        ScoreReason::Graze,
+        ScoreReason::Quest,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ScoreReason::Enemy => "击落敌人",
+            ScoreReason::Combo => "连击加成",
+            ScoreReason::Bomb => "炸弹",
+            ScoreReason::Boss => "头目奖励",
+            ScoreReason::WaveBonus => "波次奖励",
+            ScoreReason::LifeBonus => "剩余生命",
+            ScoreReason::Graze => "擦弹",
+            ScoreReason::Quest => "任务奖励",
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            ScoreReason::Enemy => "enemy",
+            ScoreReason::Combo => "combo",
+            ScoreReason::Bomb => "bomb",
+            ScoreReason::Boss => "boss",
+            ScoreReason::WaveBonus => "wave_bonus",
+            ScoreReason::LifeBonus => "life_bonus",
+            ScoreReason::Graze => "graze",
+            ScoreReason::Quest => "quest",
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            ScoreReason::Enemy => Color::RED,
+            ScoreReason::Combo => Color::YELLOW,
+            ScoreReason::Bomb => Color::new(1.0, 0.5, 0.1, 1.0),
+            ScoreReason::Boss => Color::new(0.8, 0.2, 0.8, 1.0),
+            ScoreReason::WaveBonus => Color::CYAN,
+            ScoreReason::LifeBonus => Color::GREEN,
+            ScoreReason::Graze => Color::new(0.7, 0.7, 1.0, 1.0),
+            ScoreReason::Quest => Color::new(0.6, 1.0, 0.6, 1.0),
+        }
+    }
+}
+
+// 一次得分，time 为本局开始后的秒数
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct ScoreEntry {
+    pub time: f32,
+    pub points: u32,
+    pub reason: ScoreReason,
+}
+
+// 一局中的所有得分记录，结算时显示分项统计，也可以导出给玩家自己分析
+#[derive(Serialize, Default)]
+pub struct Ledger {
+    pub entries: Vec<ScoreEntry>,
+}
+
+impl Ledger {
+    pub fn record(&mut self, time: f32, points: u32, reason: ScoreReason) {
+        self.entries.push(ScoreEntry { time, points, reason });
+    }
+
+    // 每一项得分按 keep 的比例打折，续关时使用，合计仍然等于总分
+    pub fn scale(&mut self, keep: f32) {
+        for entry in &mut self.entries {
+            entry.points = (entry.points as f32 * keep) as u32;
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        self.entries.iter().map(|entry| entry.points).sum()
+    }
+
+    // 各来源的得分合计，没有得分的来源不列出
+    pub fn totals(&self) -> Vec<(ScoreReason, u32)> {
+        ScoreReason::ALL
+            .into_iter()
+            .map(|reason| {
+                let points = self
+                    .entries
+                    .iter()
+                    .filter(|entry| entry.reason == reason)
+                    .map(|entry| entry.points)
+                    .sum();
+                (reason, points)
+            })
+            .filter(|&(_, points)| points > 0)
+            .collect()
+    }
+
+    // 每隔 PACE_INTERVAL 秒的累计得分，最后一项是整局的总分，duration 为本局时长
+    pub fn pace(&self, duration: f32) -> Vec<u32> {
+        let samples = (duration / PACE_INTERVAL).ceil().max(1.0) as usize;
+        let mut entries = self.entries.iter().peekable();
+        let mut total = 0;
+        (1..=samples)
+            .map(|sample| {
+                let until = sample as f32 * PACE_INTERVAL;
+                while let Some(entry) = entries.next_if(|entry| entry.time <= until || sample == samples) {
+                    total += entry.points;
+                }
+                total
+            })
+            .collect()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("time,points,reason\n");
+        for entry in &self.entries {
+            csv += &format!("{:.3},{},{}\n", entry.time, entry.points, entry.reason.key());
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    // 结算画面上的分项条形图，左上角在 origin
+    pub fn draw_breakdown(&self, ctx: &mut Context, canvas: &mut Canvas, origin: Vec2) -> GameResult<()> {
+        let totals = self.totals();
+        let max = totals.iter().map(|&(_, points)| points).max().unwrap_or(1).max(1);
+        for (row, (reason, points)) in totals.into_iter().enumerate() {
+            let y = origin.y + row as f32 * CHART_ROW;
+            canvas.draw(
+                &graphics::Text::new(reason.label()),
+                DrawParam::default().dest(Vec2::new(origin.x, y)),
+            );
+            let width = CHART_WIDTH * points as f32 / max as f32;
+            let bar = Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::fill(),
+                Rect::new(origin.x + 90.0, y + 2.0, width.max(1.0), CHART_ROW - 8.0),
+                reason.color(),
+            )?;
+            canvas.draw(&bar, DrawParam::default());
+            canvas.draw(
+                &graphics::Text::new(points.to_string()),
+                DrawParam::default().dest(Vec2::new(origin.x + 100.0 + width, y)),
+            );
+        }
+        Ok(())
+    }
+}
+
+// 按 Ledger::pace 记下的进度估算某一时刻的累计得分，两次记录之间线性插值，超过那一局的时长后取总分
+pub fn pace_at(pace: &[u32], time: f32) -> u32 {
+    let Some(&last) = pace.last() else {
+        return 0;
+    };
+    let position = time.max(0.0) / PACE_INTERVAL;
+    let index = position as usize;
+    if index >= pace.len() {
+        return last;
+    }
+    let from = if index == 0 { 0 } else { pace[index - 1] };
+    let to = pace[index];
+    (from as f32 + (to as f32 - from as f32) * position.fract()).round() as u32
+}
@@ -0,0 +1,309 @@
+use std::collections::VecDeque;
+
+use ggez::{glam::Vec2, graphics::Rect};
+
+use crate::health::{ShieldBar, HIT_INVINCIBLE};
+use crate::meta::MetaUpgrades;
+
+pub mod assets;
+pub mod asteroid;
+pub mod audio;
+pub mod autopilot;
+pub mod backdrop;
+pub mod bench;
+pub mod bindings;
+pub mod boss;
+pub mod bullets;
+pub mod bossgen;
+pub mod campaign;
+pub mod chat;
+pub mod collision;
+pub mod combo;
+pub mod continues;
+pub mod difficulty;
+pub mod display;
+pub mod enemy;
+pub mod ending;
+pub mod effects;
+pub mod events;
+pub mod focus;
+pub mod grid;
+pub mod health;
+pub mod heatmap;
+pub mod hitreplay;
+pub mod history;
+pub mod hud;
+pub mod killcam;
+pub mod ledger;
+pub mod lifecycle;
+pub mod memory;
+pub mod menu;
+pub mod meta;
+pub mod milestone;
+pub mod modes;
+pub mod platform;
+pub mod playfield;
+pub mod powerup;
+pub mod profiling;
+pub mod quality;
+pub mod quest;
+pub mod randomizer;
+pub mod registry;
+pub mod replay;
+pub mod resources;
+pub mod scoring;
+pub mod sharecode;
+pub mod shop;
+pub mod simulation;
+pub mod soak;
+pub mod status;
+pub mod storage;
+pub mod streamer;
+pub mod swarm;
+pub mod theme;
+pub mod touch;
+pub mod tween;
+pub mod versus;
+pub mod weapon;
+pub mod world;
+
+pub const WINDOW_WIDTH: f32 = 800.0;
+pub const WINDOW_HEIGHT: f32 = 600.0;
+pub const PLAYER_SPEED: f32 = 300.0;
+pub const START_LIVES: u32 = 3;  // 默认生命规则下开局的生命数
+pub const MAX_BOMBS: u32 = 3;  // 最多同时携带的炸弹
+pub const MAX_OPTIONS: u32 = 2;  // 最多同时跟随的分身
+const OPTION_DELAY: usize = 14;  // 每个分身落后前一个多少步
+const DASH_TIME: f32 = 0.15;  // 冲刺持续的秒数，期间无敌
+const DASH_SPEED: f32 = 1200.0;
+pub const DASH_COOLDOWN: f32 = 1.0;  // 冲刺结束后再次可用前的秒数
+
+// 冲刺的状态，和受伤后的无敌时间分开计时：就绪 -> 冲刺中 -> 冷却 -> 就绪
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum Dash {
+    #[default]
+    Ready,
+    // 沿 direction 高速移动，direction 为零时原地闪避，只有无敌没有位移
+    Dashing { direction: Vec2, remaining: f32 },
+    Cooldown(f32),
+}
+
+#[derive(Debug)]
+pub struct GameObject {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub size: Vec2,
+    pub alive: bool,
+}
+
+impl GameObject {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            position: Vec2::new(x, y),
+            velocity: Vec2::ZERO,
+            size: Vec2::new(width, height),
+            alive: true,
+        }
+    }
+
+    pub fn bounds(&self) -> Rect {
+        Rect::new(
+            self.position.x - self.size.x / 2.0,
+            self.position.y - self.size.y / 2.0,
+            self.size.x,
+            self.size.y,
+        )
+    }
+
+    pub fn collides_with(&self, other: &GameObject) -> bool {
+        self.bounds().overlaps(&other.bounds())
+    }
+
+    // 这一帧移动扫过的整个区域，帧率低时高速子弹也不会穿过敌人
+    pub fn swept_bounds(&self, dt: f32) -> Rect {
+        swept_rect(self.position, self.velocity, self.size, dt)
+    }
+
+    pub fn sweep_collides_with(&self, other: &GameObject, dt: f32) -> bool {
+        self.swept_bounds(dt).overlaps(&other.bounds())
+    }
+
+    // 把物体整个限制在 min_x 到 max_x 之间
+    pub fn clamp_x(&mut self, min_x: f32, max_x: f32) {
+        let half = self.size.x / 2.0;
+        self.position.x = self.position.x.clamp(min_x + half, (max_x - half).max(min_x + half));
+    }
+
+    pub fn clamp_y(&mut self, min_y: f32, max_y: f32) {
+        let half = self.size.y / 2.0;
+        self.position.y = self.position.y.clamp(min_y + half, (max_y - half).max(min_y + half));
+    }
+}
+
+// 以 position 为中心、大小为 size 的矩形在这一帧从上一个位置移动过来时扫过的区域
+pub fn swept_rect(position: Vec2, velocity: Vec2, size: Vec2, dt: f32) -> Rect {
+    let previous = position - velocity * dt;
+    Rect::new(
+        position.x.min(previous.x) - size.x / 2.0,
+        position.y.min(previous.y) - size.y / 2.0,
+        size.x + (position.x - previous.x).abs(),
+        size.y + (position.y - previous.y).abs(),
+    )
+}
+
+pub struct Player {
+    pub game_object: GameObject,
+    pub lives: u32,
+    pub invincible_timer: f32,  // 受伤后的短暂无敌时间
+    pub weapon_level: u32,  // 主炮火力等级，从 1 开始
+    pub bombs: u32,  // 剩余的炸弹，按 B 键清场
+    pub fire_cooldown: f32,  // 主武器再次可以开火前的秒数，切换武器时不清零
+    pub options: u32,  // 跟随的分身，失去一条命时全部消失
+    pub dash: Dash,
+    pub shield: Option<ShieldBar>,  // 护盾条模式才有
+    path: VecDeque<Vec2>,  // 最近走过的位置，最新的在前，分身沿着它跟在后面
+}
+
+impl Player {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self {
+            game_object: GameObject::new(x, y, 30.0, 30.0),
+            lives: START_LIVES,
+            invincible_timer: 0.0,
+            weapon_level: 1,
+            bombs: 2,
+            fire_cooldown: 0.0,
+            options: 0,
+            dash: Dash::Ready,
+            shield: None,
+            path: VecDeque::new(),
+        }
+    }
+
+    // 开局时的飞船，带上标题菜单里买下的永久强化
+    pub fn with_upgrades(x: f32, y: f32, meta: &MetaUpgrades) -> Self {
+        let mut player = Self::new(x, y);
+        player.lives += meta.lives;
+        player
+    }
+    
+    // 当玩家受到伤害时调用
+    pub fn take_damage(&mut self) -> bool {
+        if !self.is_invincible() {
+            self.lives -= 1;
+            self.invincible_timer = 2.0;  // 2秒无敌时间
+            self.options = 0;
+            if let Some(shield) = &mut self.shield {
+                shield.refill();
+            }
+            return true;
+        }
+        false
+    }
+
+    // 护盾条模式下先让护盾挡下这一击，挡下时有一小段无敌时间；护盾已经打空时返回 false
+    pub fn absorb_hit(&mut self) -> bool {
+        if self.is_invincible() {
+            return false;
+        }
+        let Some(shield) = &mut self.shield else {
+            return false;
+        };
+        if !shield.absorb() {
+            return false;
+        }
+        self.invincible_timer = HIT_INVINCIBLE;
+        true
+    }
+    
+    // 火力高于 1 级时受伤只降一级，不掉命，同样有一段无敌时间
+    pub fn downgrade(&mut self) -> bool {
+        if self.is_invincible() || self.weapon_level <= 1 {
+            return false;
+        }
+        self.weapon_level -= 1;
+        self.invincible_timer = 2.0;
+        true
+    }
+
+    pub fn upgrade(&mut self, max_level: u32) {
+        self.weapon_level = (self.weapon_level + 1).min(max_level);
+    }
+
+    pub fn add_bomb(&mut self) {
+        self.bombs = (self.bombs + 1).min(MAX_BOMBS);
+    }
+
+    // 多一个分身，已经满了时返回 false
+    pub fn add_option(&mut self) -> bool {
+        if self.options >= MAX_OPTIONS {
+            return false;
+        }
+        self.options += 1;
+        true
+    }
+
+    // 记下这一帧的位置。和 Gradius 一样只在飞船移动时记录，停下来时分身也停在原地
+    pub fn record_position(&mut self) {
+        let position = self.game_object.position;
+        if self.path.front() != Some(&position) {
+            self.path.push_front(position);
+            self.path.truncate(MAX_OPTIONS as usize * OPTION_DELAY + 1);
+        }
+    }
+
+    // 每个分身的位置：第 n 个停在 n * OPTION_DELAY 步之前的位置，走过的路还不够长时停在最早的一步
+    pub fn option_positions(&self) -> Vec<Vec2> {
+        (1..=self.options as usize)
+            .map(|index| {
+                let step = (index * OPTION_DELAY).min(self.path.len().saturating_sub(1));
+                self.path.get(step).copied().unwrap_or(self.game_object.position)
+            })
+            .collect()
+    }
+
+    // 更新玩家状态，包括无敌时间
+    pub fn update(&mut self, dt: f32) {
+        if self.invincible_timer > 0.0 {
+            self.invincible_timer -= dt;
+        }
+        if let Some(shield) = &mut self.shield {
+            shield.update(dt);
+        }
+        self.fire_cooldown = (self.fire_cooldown - dt).max(0.0);
+        self.dash = match self.dash {
+            Dash::Dashing { remaining, .. } if remaining <= dt => Dash::Cooldown(DASH_COOLDOWN),
+            Dash::Dashing { direction, remaining } => Dash::Dashing { direction, remaining: remaining - dt },
+            Dash::Cooldown(remaining) if remaining <= dt => Dash::Ready,
+            Dash::Cooldown(remaining) => Dash::Cooldown(remaining - dt),
+            Dash::Ready => Dash::Ready,
+        };
+    }
+
+    // 冲刺就绪时朝 direction 冲出去，返回是否冲刺了
+    pub fn start_dash(&mut self, direction: Vec2) -> bool {
+        if self.dash != Dash::Ready {
+            return false;
+        }
+        self.dash = Dash::Dashing { direction: direction.normalize_or_zero(), remaining: DASH_TIME };
+        true
+    }
+
+    pub fn dashing(&self) -> bool {
+        matches!(self.dash, Dash::Dashing { .. })
+    }
+
+    // 冲刺中的速度，不受道具和强化的速度倍数影响
+    pub fn dash_velocity(&self) -> Option<Vec2> {
+        match self.dash {
+            Dash::Dashing { direction, .. } => Some(direction * DASH_SPEED),
+            _ => None,
+        }
+    }
+
+    // 检查玩家是否处于无敌状态，受伤后的无敌时间和冲刺都算
+    pub fn is_invincible(&self) -> bool {
+        self.invincible_timer > 0.0 || self.dashing()
+    }
+}
+
@@ -0,0 +1,208 @@
+#[cfg(feature = "android")]
+use ggez::{
+    event::{self, ErrorOrigin, EventHandler, EventLoop},
+    input::{
+        gamepad::gilrs,
+        keyboard::{KeyInput, KeyMods},
+    },
+    winit::{
+        dpi::LogicalPosition,
+        event::{ElementState, Event, KeyboardInput, MouseScrollDelta, WindowEvent},
+        event_loop::ControlFlow,
+    },
+    Context, GameError,
+};
+
+// 应用是否在前台。安卓上切到后台时系统发出 Suspended，绘制表面随之失效，回到前台时发出 Resumed；
+// 桌面上启动时也会收到一次 Resumed，之前没有 Suspended 的 Resumed 不算回到前台
+#[derive(Default, Debug)]
+pub struct Lifecycle {
+    suspended: bool,
+}
+
+impl Lifecycle {
+    // 切到后台，之前在前台时返回 true
+    pub fn suspend(&mut self) -> bool {
+        !std::mem::replace(&mut self.suspended, true)
+    }
+
+    // 回到前台，之前在后台时返回 true
+    pub fn resume(&mut self) -> bool {
+        std::mem::replace(&mut self.suspended, false)
+    }
+
+    // 在前台时照常更新和绘制
+    pub fn running(&self) -> bool {
+        !self.suspended
+    }
+}
+
+// 游戏对切到后台和回到前台的反应
+#[cfg(feature = "android")]
+pub trait LifecycleHandler: EventHandler {
+    fn suspended(&mut self, ctx: &mut Context);
+    fn resumed(&mut self, ctx: &mut Context);
+}
+
+#[cfg(feature = "android")]
+type Outcome = Result<(), (ErrorOrigin, GameError)>;
+
+// ggez 0.9 自带的事件循环忽略 Suspended 和 Resumed，这里照着它分发其余的事件，另外处理这两个：
+// 切到后台时交给游戏暂停、停下音乐，后台期间不更新也不绘制，回来后重新开始计时，不会一下子跳过很长的一帧
+#[cfg(feature = "android")]
+pub fn run<S: LifecycleHandler + 'static>(mut ctx: Context, event_loop: EventLoop<()>, mut state: S) -> ! {
+    let mut lifecycle = Lifecycle::default();
+    event_loop.run(move |mut event, _, control_flow| {
+        let ctx = &mut ctx;
+        if ctx.quit_requested {
+            ctx.quit_requested = false;
+            if let Err((origin, error)) = quit(ctx, &mut state) {
+                if failed(ctx, &mut state, origin, error) {
+                    *control_flow = ControlFlow::Exit;
+                    return;
+                }
+            }
+        }
+        if !ctx.continuing {
+            *control_flow = ControlFlow::Exit;
+            return;
+        }
+        *control_flow = ControlFlow::Poll;
+
+        event::process_event(ctx, &mut event);
+        let outcome = match event {
+            Event::Suspended => {
+                if lifecycle.suspend() {
+                    state.suspended(ctx);
+                }
+                Ok(())
+            }
+            Event::Resumed => {
+                if lifecycle.resume() {
+                    ctx.time.tick();
+                    state.resumed(ctx);
+                }
+                Ok(())
+            }
+            Event::WindowEvent { event, .. } => window_event(ctx, &mut state, event),
+            Event::MainEventsCleared if lifecycle.running() => frame(ctx, &mut state),
+            _ => Ok(()),
+        };
+        if let Err((origin, error)) = outcome {
+            if failed(ctx, &mut state, origin, error) {
+                *control_flow = ControlFlow::Exit;
+            }
+        }
+    })
+}
+
+// 事件处理出错时打印出来，由游戏决定是否退出，和 ggez 自带的事件循环一样
+#[cfg(feature = "android")]
+fn failed<S: EventHandler>(ctx: &mut Context, state: &mut S, origin: ErrorOrigin, error: GameError) -> bool {
+    eprintln!("Error on EventHandler {:?}: {:?}", origin, error);
+    state.on_error(ctx, origin, error)
+}
+
+// quit_event 返回 false 时退出，返回 true 时取消退出
+#[cfg(feature = "android")]
+fn quit<S: EventHandler>(ctx: &mut Context, state: &mut S) -> Outcome {
+    match state.quit_event(ctx) {
+        Ok(false) => ctx.continuing = false,
+        Ok(true) => {}
+        Err(error) => return Err((ErrorOrigin::QuitEvent, error)),
+    }
+    Ok(())
+}
+
+#[cfg(feature = "android")]
+fn window_event<S: EventHandler>(ctx: &mut Context, state: &mut S, event: WindowEvent) -> Outcome {
+    match event {
+        WindowEvent::Resized(size) => {
+            state.resize_event(ctx, size.width as f32, size.height as f32).map_err(|e| (ErrorOrigin::ResizeEvent, e))
+        }
+        WindowEvent::CloseRequested => quit(ctx, state),
+        WindowEvent::Focused(gained) => state.focus_event(ctx, gained).map_err(|e| (ErrorOrigin::FocusEvent, e)),
+        WindowEvent::ReceivedCharacter(character) => {
+            state.text_input_event(ctx, character).map_err(|e| (ErrorOrigin::TextInputEvent, e))
+        }
+        WindowEvent::ModifiersChanged(mods) => {
+            ctx.keyboard.set_modifiers(KeyMods::from(mods));
+            Ok(())
+        }
+        WindowEvent::KeyboardInput { input: KeyboardInput { state: pressed, virtual_keycode, scancode, .. }, .. } => {
+            let input = KeyInput { scancode, keycode: virtual_keycode, mods: ctx.keyboard.active_mods() };
+            match pressed {
+                ElementState::Pressed => {
+                    let repeat = ctx.keyboard.is_key_repeated();
+                    state.key_down_event(ctx, input, repeat).map_err(|e| (ErrorOrigin::KeyDownEvent, e))
+                }
+                ElementState::Released => state.key_up_event(ctx, input).map_err(|e| (ErrorOrigin::KeyUpEvent, e)),
+            }
+        }
+        WindowEvent::MouseWheel { delta, .. } => {
+            let (x, y) = match delta {
+                MouseScrollDelta::LineDelta(x, y) => (x, y),
+                MouseScrollDelta::PixelDelta(position) => {
+                    let LogicalPosition { x, y } = position.to_logical::<f32>(ctx.gfx.window().scale_factor());
+                    (x, y)
+                }
+            };
+            state.mouse_wheel_event(ctx, x, y).map_err(|e| (ErrorOrigin::MouseWheelEvent, e))
+        }
+        WindowEvent::MouseInput { state: pressed, button, .. } => {
+            let position = ctx.mouse.position();
+            match pressed {
+                ElementState::Pressed => state
+                    .mouse_button_down_event(ctx, button, position.x, position.y)
+                    .map_err(|e| (ErrorOrigin::MouseButtonDownEvent, e)),
+                ElementState::Released => state
+                    .mouse_button_up_event(ctx, button, position.x, position.y)
+                    .map_err(|e| (ErrorOrigin::MouseButtonUpEvent, e)),
+            }
+        }
+        WindowEvent::CursorMoved { .. } => {
+            let (position, delta) = (ctx.mouse.position(), ctx.mouse.last_delta());
+            state
+                .mouse_motion_event(ctx, position.x, position.y, delta.x, delta.y)
+                .map_err(|e| (ErrorOrigin::MouseMotionEvent, e))
+        }
+        WindowEvent::CursorEntered { .. } => state.mouse_enter_or_leave(ctx, true).map_err(|e| (ErrorOrigin::MouseEnterOrLeave, e)),
+        WindowEvent::CursorLeft { .. } => state.mouse_enter_or_leave(ctx, false).map_err(|e| (ErrorOrigin::MouseEnterOrLeave, e)),
+        WindowEvent::Touch(touch) => {
+            state.touch_event(ctx, touch.phase, touch.location.x, touch.location.y).map_err(|e| (ErrorOrigin::TouchEvent, e))
+        }
+        _ => Ok(()),
+    }
+}
+
+// 一帧：手柄事件、更新、绘制，最后保存这一帧的键盘和鼠标状态
+#[cfg(feature = "android")]
+fn frame<S: EventHandler>(ctx: &mut Context, state: &mut S) -> Outcome {
+    ctx.time.tick();
+    while let Some(gilrs::Event { id, event, .. }) = ctx.gamepad.next_event() {
+        // ggez 的手柄编号只能从已连接的手柄里取
+        let Some(pad) = ctx.gamepad.gamepads().find(|(_, gamepad)| gamepad.id() == id).map(|(pad, _)| pad) else {
+            continue;
+        };
+        match event {
+            gilrs::EventType::ButtonPressed(button, _) => {
+                state.gamepad_button_down_event(ctx, button, pad).map_err(|e| (ErrorOrigin::GamepadButtonDownEvent, e))?
+            }
+            gilrs::EventType::ButtonReleased(button, _) => {
+                state.gamepad_button_up_event(ctx, button, pad).map_err(|e| (ErrorOrigin::GamepadButtonUpEvent, e))?
+            }
+            gilrs::EventType::AxisChanged(axis, value, _) => {
+                state.gamepad_axis_event(ctx, axis, value, pad).map_err(|e| (ErrorOrigin::GamepadAxisEvent, e))?
+            }
+            _ => {}
+        }
+    }
+    state.update(ctx).map_err(|e| (ErrorOrigin::Update, e))?;
+    ctx.gfx.begin_frame().map_err(|e| (ErrorOrigin::Draw, e))?;
+    state.draw(ctx).map_err(|e| (ErrorOrigin::Draw, e))?;
+    ctx.gfx.end_frame().map_err(|e| (ErrorOrigin::Draw, e))?;
+    ctx.mouse.reset_delta();
+    ctx.keyboard.save_keyboard_state();
+    ctx.mouse.save_mouse_state();
+    Ok(())
+}
@@ -1,343 +1,2054 @@
+use std::path::PathBuf;
+use std::time::Instant;
+
 use ggez::{
-    conf::{WindowMode, WindowSetup},
-    event::{self, EventHandler},
+    conf::WindowSetup,
+    event::{winit_event::TouchPhase, EventHandler},
     glam::Vec2,
-    graphics::{self, Color, DrawParam, Mesh, Rect},
-    input::keyboard::{KeyCode, KeyInput},
-    timer, Context, GameError, GameResult,
+    graphics::{self, Color, DrawParam, Mesh, MeshBuilder, Rect},
+    input::{
+        gamepad::{
+            gilrs::{Axis, Button},
+            GamepadId,
+        },
+        keyboard::{KeyCode, KeyInput},
+    },
+    Context, GameResult,
 };
+#[cfg(not(feature = "android"))]
+use ggez::event::run;
 use rand::{self, Rng};
 
-const WINDOW_WIDTH: f32 = 800.0;
-const WINDOW_HEIGHT: f32 = 600.0;
-const PLAYER_SPEED: f32 = 300.0;
-const BULLET_SPEED: f32 = 400.0;
-const ENEMY_SPEED: f32 = 100.0;
-const ENEMY_SPAWN_INTERVAL: f32 = 1.0;
-
-struct GameObject {
-    position: Vec2,
-    velocity: Vec2,
-    size: Vec2,
-    alive: bool,
+use space_shooter::assets::{self, AssetLoader, StageAssets};
+use space_shooter::audio;
+use space_shooter::asteroid;
+use space_shooter::audio::{Music, MusicTrack, SoundEffect, Sounds};
+use space_shooter::backdrop::Starfield;
+use space_shooter::bench;
+use space_shooter::bindings::{self, Action, DoubleTap, HeldMoves, HoldTimer, RESTART_HOLD, RESTART_KEY};
+use space_shooter::campaign::{BossDrill, Campaign, CampaignData};
+#[cfg(feature = "twitch")]
+use space_shooter::chat::{ChatVote, TwitchChat};
+use space_shooter::continues;
+use space_shooter::difficulty::DifficultyCurve;
+use space_shooter::display::{self, WindowStatus, WindowTitle};
+use space_shooter::effects::Trail;
+use space_shooter::enemy;
+use space_shooter::ending::Ending;
+use space_shooter::events::GameEvent;
+use space_shooter::history::{HistoryView, RunSummary};
+use space_shooter::hitreplay::{ClipRecorder, HitReplay};
+use space_shooter::hud::{self, HudStyle};
+use space_shooter::ledger::ScoreReason;
+#[cfg(feature = "android")]
+use space_shooter::lifecycle::{self, run};
+use space_shooter::memory::MemoryMeter;
+use space_shooter::menu::{self, BindingsMenu, BindingsResult, Menu, MenuInput};
+use space_shooter::meta::{self, MetaUpgrade};
+use space_shooter::milestone::Milestone;
+use space_shooter::platform::{self, Attention};
+use space_shooter::powerup::{DRONE_SIZE, OPTION_COLOR, OPTION_RADIUS};
+use space_shooter::profiling::{Profiler, Watchdog};
+use space_shooter::quality::{Quality, QualityControl};
+use space_shooter::registry::Registry;
+use space_shooter::resources::ResourceHints;
+use space_shooter::sharecode::{Mutators, ShareCode};
+use space_shooter::shop::{self, ShopItem};
+use space_shooter::soak;
+use space_shooter::storage::{self, Profile};
+use space_shooter::streamer::OverlayFile;
+#[cfg(feature = "state-socket")]
+use space_shooter::streamer::StateServer;
+use space_shooter::theme::{Rgb, Theme, ThemePart};
+use space_shooter::touch::{self, TouchButton, TouchControls};
+use space_shooter::weapon::Weapon;
+use space_shooter::world::{self, FrameInput, GameMode, World};
+use space_shooter::{WINDOW_HEIGHT, WINDOW_WIDTH};
+
+// 调试构建统计内存分配，发布构建直接使用系统分配器
+#[cfg(debug_assertions)]
+#[global_allocator]
+static ALLOCATOR: space_shooter::memory::CountingAllocator = space_shooter::memory::CountingAllocator;
+
+const STICK_DEADZONE: f32 = 0.25;
+const SHARE_CODE_INPUT: usize = 15;  // 分享码的长度，分隔符不用输入
+const HEX_INPUT: usize = 6;  // 十六进制颜色的位数，# 不用输入
+
+const PAUSE_MENU_ITEMS: [&str; 4] = ["继续游戏", "按键设置", "重新开始", "返回标题"];
+
+// 标题菜单的选项，新游戏+ 通关后才出现
+#[derive(Clone, Copy)]
+enum TitleItem {
+    Start(GameMode),
+    ShareCode,  // 输入朋友的分享码，打同样的一局
+    Upgrades,  // 用积分购买永久强化
+    Practice,  // 从无尽模式的里程碑开始练习，到过第 10 波后才列出
+    BossPractice,  // 从某个头目的某个阶段开始练习，通关后才列出
+    History,
+    Heatmap,  // 跨局累计的受击热图
+    Display,
+    Theme,  // 飞船、子弹和 HUD 强调色的配色
+    Controls,
+    Bindings,
+    Quit,
 }
 
-impl GameObject {
-    fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
-        Self {
-            position: Vec2::new(x, y),
-            velocity: Vec2::ZERO,
-            size: Vec2::new(width, height),
-            alive: true,
+impl TitleItem {
+    fn label(self) -> &'static str {
+        match self {
+            TitleItem::Start(mode) => mode.label(),
+            TitleItem::ShareCode => "输入分享码",
+            TitleItem::Upgrades => "机体强化",
+            TitleItem::Practice => "里程碑练习",
+            TitleItem::BossPractice => "头目练习",
+            TitleItem::History => "历史记录",
+            TitleItem::Heatmap => "受击热图",
+            TitleItem::Display => "显示设置",
+            TitleItem::Theme => "配色",
+            TitleItem::Controls => "操作说明",
+            TitleItem::Bindings => "按键设置",
+            TitleItem::Quit => "退出游戏",
         }
     }
+}
 
-    fn bounds(&self) -> Rect {
-        Rect::new(
-            self.position.x - self.size.x / 2.0,
-            self.position.y - self.size.y / 2.0,
-            self.size.x,
-            self.size.y,
-        )
-    }
+// 练习局从哪里开始
+#[derive(Clone, Copy)]
+enum Practice {
+    Milestone(Milestone),
+    Boss { stage: usize, phase: usize },
+}
 
-    fn collides_with(&self, other: &GameObject) -> bool {
-        self.bounds().overlaps(&other.bounds())
-    }
+// 盖在游戏画面上的界面，打开期间游戏逻辑暂停
+enum Overlay {
+    Title(Menu),
+    IdlePrompt,  // 长时间无操作后的提示
+    GamepadDisconnected,  // 正在使用的手柄断开，等待重新连接
+    Suspended,  // 切到后台或按下触屏的暂停按钮后的提示
+    PauseMenu(Menu),
+    Bindings { menu: BindingsMenu, from_title: bool },
+    Ending(Ending),  // 战役通关结算
+    DifficultyCurves,  // 调试用：查看无尽模式的难度曲线
+    History(HistoryView),  // 最近几局的记录
+    Heatmap,  // 跨局累计的受击热图
+    DisplaySettings(Menu),
+    Theme(Menu),  // 配色：左右切换调色板里的颜色
+    ThemeHex { part: ThemePart, text: String, error: Option<String> },  // 正在输入的十六进制颜色
+    Controls,  // 按当前绑定画出的操作说明
+    ShareCode { text: String, error: Option<String> },  // 正在输入的分享码和上一次输入的错误
+    Shop { menu: Menu, message: Option<String> },  // 波次之间的商店和上一次购买的结果
+    Upgrades { menu: Menu, message: Option<String> },  // 标题菜单的永久强化和上一次购买的结果
+    Practice(Menu),  // 选择从哪个里程碑开始练习
+    BossPractice(Menu),  // 选择练习哪个头目的哪个阶段
+    Loading { then: Option<Box<Overlay>> },  // 启动时等后台读完音乐和音效，读完后换成 then
 }
 
-struct Player {
-    game_object: GameObject,
-    lives: u32,
-    invincible_timer: f32,  // 受伤后的短暂无敌时间
+struct MainState {
+    world: World,
+    next_seed: Option<u64>,  // 下一局指定使用的种子
+    next_mutators: Option<Mutators>,  // 下一局指定使用的附加规则，来自分享码或重新开始
+    practice: Option<Practice>,  // 正在练习的里程碑或头目阶段，重新开始时从同一处开始
+    mutators: Mutators,  // 本局的附加规则，没有分享码时取自设置
+    overlay: Option<Overlay>,
+    active_gamepad: Option<GamepadId>,  // 最近一次输入来自的手柄
+    stick: Vec2,  // 左摇杆的方向，y 向下为正
+    held: HeldMoves,  // 按住的移动键
+    double_tap: DoubleTap,  // 连按两下移动键冲刺
+    restart_hold: HoldTimer,  // 按住 R 快速重新开始的进度
+    touch: TouchControls,
+    idle_timer: f32,  // 距离上次输入的时间，菜单的演示模式也可以复用
+    difficulty: DifficultyCurve,  // 调试界面展示用
+    profile: Profile,  // 设置与排行榜，重新开始时保留
+    music: Music,
+    sounds: Sounds,
+    loader: Option<AssetLoader>,  // 正在后台读取的资源，读完后交给 music 和 sounds
+    tip: &'static str,  // 加载画面上的提示
+    resource_dir: Option<PathBuf>,  // 关卡专用的资源也从这里读取
+    stage_assets: Vec<StageAssets>,  // 战役中当前这一关和下一关的专用资源
+    themed_stage: Option<usize>,  // 音乐正在使用哪一关的专用曲目
+    storage_error: Option<String>,  // 存档读写失败时弹出的提示
+    storage_notice: Option<String>,  // 存档损坏、已从备份恢复或 mods 读不了时弹出的提示
+    content: Registry,  // 内置内容加上 mods 文件夹中的定义
+    watchdog: Watchdog,  // 帧时间超出预算时发出警告
+    quality: QualityControl,  // 画面质量，自动档位根据帧时间监视的结果降档
+    starfield: Starfield,
+    trail: Trail,  // 飞船引擎的尾迹
+    clips: ClipRecorder,  // 最近一秒的画面，失去一条命时剪出回放
+    hit_replay: Option<HitReplay>,  // 正在角落里慢放的受击回放，放完才继续游戏
+    draw_profiler: Profiler,
+    memory: MemoryMeter,  // 每帧的内存分配统计
+    export_message: Option<String>,  // 结算画面上导出得分记录后的提示
+    run_recorded: bool,  // 本局是否已经写入历史记录
+    credited_score: u32,  // 本局已经换成积分的分数，从某一波重来后只有超出的部分再换
+    window_title: WindowTitle,
+    stream_overlay: OverlayFile,  // 直播模式上次写给 OBS 的内容
+    #[cfg(feature = "state-socket")]
+    state_server: Option<StateServer>,
+    #[cfg(feature = "twitch")]
+    chat: Option<(TwitchChat, ChatVote)>,  // 直播间聊天和进行中的投票
 }
 
-impl Player {
-    fn new(x: f32, y: f32) -> Self {
+impl MainState {
+    fn new(profile: Profile) -> Self {
+        let budget_ms = profile.settings.debug.frame_budget_ms;
+        let quality = QualityControl::new(profile.settings.quality);
+        #[cfg(feature = "twitch")]
+        let chat = profile.settings.twitch.channel.as_deref().and_then(|channel| match TwitchChat::connect(channel) {
+            Ok(chat) => {
+                let twitch = &profile.settings.twitch;
+                Some((chat, ChatVote::new(twitch.vote_window, twitch.cooldown)))
+            }
+            Err(error) => {
+                eprintln!("无法连接 Twitch 聊天室 {}: {}", channel, error);
+                None
+            }
+        });
+        #[cfg(feature = "state-socket")]
+        let state_server = profile.settings.streamer.state_port.and_then(|port| match StateServer::bind(port) {
+            Ok(server) => Some(server),
+            Err(error) => {
+                eprintln!("无法在端口 {} 上开放状态接口: {}", port, error);
+                None
+            }
+        });
         Self {
-            game_object: GameObject::new(x, y, 30.0, 30.0),
-            lives: 3,  // 初始3条命
-            invincible_timer: 0.0,
+            world: World::new(GameMode::Endless, 0),
+            next_seed: None,
+            next_mutators: None,
+            practice: None,
+            mutators: Mutators::default(),
+            overlay: None,
+            active_gamepad: None,
+            stick: Vec2::ZERO,
+            held: HeldMoves::default(),
+            double_tap: DoubleTap::default(),
+            restart_hold: HoldTimer::default(),
+            touch: TouchControls::default(),
+            idle_timer: 0.0,
+            difficulty: DifficultyCurve::load(),
+            profile,
+            music: Music::default(),
+            sounds: Sounds::default(),
+            loader: None,
+            tip: menu::LOADING_TIPS[0],
+            resource_dir: None,
+            stage_assets: Vec::new(),
+            themed_stage: None,
+            storage_error: None,
+            storage_notice: None,
+            content: Registry::builtin(),
+            watchdog: Watchdog::new(budget_ms),
+            quality,
+            starfield: Starfield::default(),
+            trail: Trail::default(),
+            clips: ClipRecorder::default(),
+            hit_replay: None,
+            draw_profiler: Profiler::default(),
+            memory: MemoryMeter::default(),
+            export_message: None,
+            run_recorded: true,  // 标题画面背后的空场地不算一局
+            credited_score: 0,
+            window_title: WindowTitle::default(),
+            stream_overlay: OverlayFile::default(),
+            #[cfg(feature = "state-socket")]
+            state_server,
+            #[cfg(feature = "twitch")]
+            chat,
         }
     }
-    
-    // 当玩家受到伤害时调用
-    fn take_damage(&mut self) -> bool {
-        if self.invincible_timer <= 0.0 {
-            self.lives -= 1;
-            self.invincible_timer = 2.0;  // 2秒无敌时间
-            return true;
+
+    // 当前应当显示在标题栏上的状态
+    fn window_status(&self) -> WindowStatus {
+        match &self.overlay {
+            _ if self.world.game_over => WindowStatus::GameOver { score: self.world.score },
+            None => WindowStatus::Playing { wave: self.world.wave(), score: self.world.score },
+            Some(Overlay::PauseMenu(_) | Overlay::IdlePrompt | Overlay::GamepadDisconnected | Overlay::Suspended) => {
+                WindowStatus::Paused
+            }
+            Some(Overlay::Bindings { from_title: false, .. }) => WindowStatus::Paused,
+            Some(_) => WindowStatus::Menu,
         }
-        false
     }
-    
-    // 更新玩家状态，包括无敌时间
-    fn update(&mut self, dt: f32) {
-        if self.invincible_timer > 0.0 {
-            self.invincible_timer -= dt;
+
+    // 键盘和手柄共用的游戏操作
+    fn perform_action(&mut self, action: Action) {
+        if (self.world.cinematic || self.world.game_over) && action != Action::Pause {
+            return;
+        }
+        match action {
+            Action::MoveLeft | Action::MoveRight | Action::MoveUp | Action::MoveDown => self.steer(),
+            Action::Fire => self.world.fire_bullet(),
+            Action::Emp => self.world.fire_emp(),
+            Action::Bomb => self.world.use_bomb(),
+            Action::Dash => self.world.dash(),
+            Action::Focus => (),  // 按住期间才生效，由 hold_actions 每帧检查
+            Action::Pause => self.overlay = Some(Overlay::PauseMenu(Menu::new(PAUSE_MENU_ITEMS))),
         }
     }
-    
-    // 检查玩家是否处于无敌状态
-    fn is_invincible(&self) -> bool {
-        self.invincible_timer > 0.0
+
+    // 键盘或正在使用的手柄上是否按住了某个操作的按键
+    fn action_held(&self, ctx: &Context, action: Action) -> bool {
+        let key = ctx.keyboard.is_key_pressed(self.profile.settings.bindings.key(action));
+        key || ctx
+            .gamepad
+            .gamepads()
+            .find(|&(id, _)| Some(id) == self.active_gamepad)
+            .is_some_and(|(_, gamepad)| bindings::gamepad_buttons(action).any(|button| gamepad.is_pressed(button)))
     }
-}
 
-struct MainState {
-    player: Player,
-    bullets: Vec<GameObject>,
-    enemies: Vec<GameObject>,
-    powerups: Vec<GameObject>,  // 新增道具列表
-    score: u32,
-    game_over: bool,
-    spawn_timer: f32,
-    powerup_timer: f32,  // 道具生成计时器
-}
+    // 按住开火键时蓄力：按下的那一下已经由 perform_action 打出普通子弹，蓄够了再松开时打出蓄力弹。
+    // 触屏没有松手的操作，按住期间每帧尝试开火，射速由当前武器的冷却决定。按住专注键时敌方变慢
+    fn hold_actions(&mut self, ctx: &Context) {
+        if self.world.cinematic || self.world.game_over {
+            return;
+        }
+        self.world.hold_charge(self.action_held(ctx, Action::Fire));
+        self.world.focus.hold(self.action_held(ctx, Action::Focus));
+        if self.touch.held() {
+            self.world.fire_bullet();
+        }
+    }
 
-impl MainState {
-    fn new() -> Self {
-        let player = Player::new(
-            WINDOW_WIDTH / 2.0,
-            WINDOW_HEIGHT - 50.0,
-        );
+    // 双人对战时第二名玩家这一帧的输入：按住的键决定移动，开火和冲刺在按下的那一帧生效
+    fn rival_input(&self, ctx: &Context) -> FrameInput {
+        let held = |action| bindings::rival_key(action).is_some_and(|key| ctx.keyboard.is_key_pressed(key));
+        let pressed = |action| bindings::rival_key(action).is_some_and(|key| ctx.keyboard.is_key_just_pressed(key));
+        let axis = |negative, positive| held(positive) as i8 - held(negative) as i8;
+        FrameInput {
+            move_x: axis(Action::MoveLeft, Action::MoveRight),
+            move_y: axis(Action::MoveUp, Action::MoveDown),
+            fire: pressed(Action::Fire),
+            dash: pressed(Action::Dash),
+            ..FrameInput::default()
+        }
+    }
 
-        Self {
-            player,
-            bullets: Vec::new(),
-            enemies: Vec::new(),
-            powerups: Vec::new(),  // 初始化为空列表
-            score: 0,
-            game_over: false,
-            spawn_timer: 0.0,
-            powerup_timer: 0.0,
-        }
-    }
-
-    // 添加生成道具的方法
-    fn spawn_powerup(&mut self) {
-        let mut rng = rand::thread_rng();
-        let x = rng.gen_range(20.0..WINDOW_WIDTH - 20.0);
-        
-        let powerup = GameObject::new(x, -20.0, 20.0, 20.0);
-        self.powerups.push(powerup);
-    }
-    
-
-    fn spawn_enemy(&mut self) {
-        let mut rng = rand::thread_rng();
-        let x = rng.gen_range(20.0..WINDOW_WIDTH - 20.0);
-        
-        let enemy = GameObject::new(x, -20.0, 30.0, 30.0);
-        self.enemies.push(enemy);
-    }
-
-    fn fire_bullet(&mut self) {
-        let bullet = GameObject {
-            position: self.player.game_object.position - Vec2::new(0.0, 20.0),
-            velocity: Vec2::new(0.0, -BULLET_SPEED),
-            size: Vec2::new(5.0, 10.0),
-            alive: true,
+    // 切到后台时松开所有按键，收不到的松开事件不会让飞船一直往一个方向飞
+    fn release_inputs(&mut self) {
+        self.held = HeldMoves::default();
+        self.stick = Vec2::ZERO;
+        self.touch.release();
+        self.world.charge.cancel();
+        self.world.focus.hold(false);
+        self.restart_hold = HoldTimer::default();
+        self.world.player.game_object.velocity = Vec2::ZERO;
+        if let Some(rival) = &mut self.world.rival {
+            rival.player.game_object.velocity = Vec2::ZERO;
+        }
+    }
+
+    // 正在游戏时暂停，等玩家按键或轻触屏幕后继续
+    fn suspend(&mut self) {
+        if self.overlay.is_none() && !self.world.game_over {
+            self.overlay = Some(Overlay::Suspended);
+        }
+    }
+
+    // 读取直播间的聊天投票，投票结束时让胜出的事件生效；断线后不再重连
+    #[cfg(feature = "twitch")]
+    fn poll_chat(&mut self, dt: f32) {
+        let Some((chat, vote)) = &mut self.chat else {
+            return;
+        };
+        match chat.poll() {
+            Ok(messages) => {
+                for (user, text) in messages {
+                    vote.message(&user, &text);
+                }
+            }
+            Err(error) => {
+                eprintln!("Twitch 聊天室连接断开: {}", error);
+                self.chat = None;
+                return;
+            }
+        }
+        if let Some(event) = vote.update(dt).filter(|_| !self.world.game_over) {
+            self.world.chat_event(event);
+        }
+    }
+
+    // 按摇杆或按住的移动键设置飞船速度，摇杆推动时以摇杆为准
+    fn steer(&mut self) {
+        let direction = if self.touch.held() {
+            self.touch.direction(self.world.player.game_object.position)
+        } else if self.stick != Vec2::ZERO {
+            self.stick
+        } else {
+            self.held.direction()
+        };
+        self.world.player.game_object.velocity = world::move_velocity(direction);
+    }
+
+    fn title_items(&self) -> Vec<TitleItem> {
+        // 没解锁的模式不列出
+        let mut items: Vec<TitleItem> = GameMode::ALL
+            .into_iter()
+            .filter(|mode| mode.rules().unlocked(&self.profile.progress))
+            .map(TitleItem::Start)
+            .collect();
+        items.push(TitleItem::ShareCode);
+        items.push(TitleItem::Upgrades);
+        if !self.profile.progress.milestones.is_empty() {
+            items.push(TitleItem::Practice);
+        }
+        if self.profile.progress.campaign_cleared {
+            items.push(TitleItem::BossPractice);
+        }
+        items.push(TitleItem::History);
+        items.push(TitleItem::Heatmap);
+        items.push(TitleItem::Display);
+        items.push(TitleItem::Theme);
+        items.push(TitleItem::Controls);
+        items.push(TitleItem::Bindings);
+        items.push(TitleItem::Quit);
+        items
+    }
+
+    fn title_menu(&self) -> Overlay {
+        Overlay::Title(Menu::new(self.title_items().into_iter().map(TitleItem::label)))
+    }
+
+    // 商店菜单：每件东西一行，写明价格，买不了的注明原因，最后一行继续游戏
+    fn shop_menu(&self, selected: usize, message: Option<String>) -> Overlay {
+        let mut items: Vec<String> = ShopItem::ALL
+            .iter()
+            .map(|&item| match self.world.can_buy(item) {
+                Ok(price) => format!("{}  {} 金币", item.label(), price),
+                Err(reason) => format!("{}  {} 金币 ({})", item.label(), item.price(&self.world.upgrades), reason),
+            })
+            .collect();
+        items.push("继续".to_string());
+        let mut menu = Menu::new(items);
+        menu.selected = selected;
+        Overlay::Shop { menu, message }
+    }
+
+    // 永久强化菜单：每项一行，写明当前等级和下一级的价格，最后一行返回
+    fn upgrades_menu(&self, selected: usize, message: Option<String>) -> Overlay {
+        let meta = &self.profile.progress.meta;
+        let mut items: Vec<String> = MetaUpgrade::ALL
+            .iter()
+            .map(|&upgrade| {
+                let level = meta.level(upgrade);
+                if level >= upgrade.max_level() {
+                    format!("{}  {}/{}  (已满级)", upgrade.label(), level, upgrade.max_level())
+                } else {
+                    format!("{}  {}/{}  {} 积分", upgrade.label(), level, upgrade.max_level(), upgrade.price(level))
+                }
+            })
+            .collect();
+        items.push("返回".to_string());
+        let mut menu = Menu::new(items);
+        menu.selected = selected;
+        Overlay::Upgrades { menu, message }
+    }
+
+    // 里程碑练习菜单：每个到过的里程碑一行，最后一行返回
+    fn practice_menu(&self) -> Overlay {
+        let mut items: Vec<String> = self.profile.progress.milestones.iter().map(Milestone::label).collect();
+        items.push("返回".to_string());
+        Overlay::Practice(Menu::new(items))
+    }
+
+    fn boss_practice_menu(&self) -> Overlay {
+        let mut items: Vec<String> = self.boss_drills().into_iter().map(|drill| drill.label).collect();
+        items.push("返回".to_string());
+        Overlay::BossPractice(Menu::new(items))
+    }
+
+    fn boss_drills(&self) -> Vec<BossDrill> {
+        CampaignData::load().boss_drills()
+    }
+
+    fn display_menu(&self, selected: usize) -> Overlay {
+        let mode = self.profile.settings.window.mode;
+        let quality = match self.quality.setting() {
+            Quality::Auto => format!("画面质量: < 自动 > (当前: {})", self.quality.level().label()),
+            setting => format!("画面质量: < {} >", setting.label()),
         };
-        
-        self.bullets.push(bullet);
+        let mut menu = Menu::new([format!("显示模式: < {} >", mode.label()), quality, "返回".to_string()]);
+        menu.selected = selected;
+        Overlay::DisplaySettings(menu)
+    }
+
+    fn theme_menu(&self, selected: usize) -> Overlay {
+        let theme = self.profile.settings.theme;
+        let mut items: Vec<String> =
+            ThemePart::ALL.iter().map(|&part| format!("{}: < {} >", part.label(), theme.get(part).label())).collect();
+        items.push("恢复默认".to_string());
+        items.push("返回".to_string());
+        let mut menu = Menu::new(items);
+        menu.selected = selected;
+        Overlay::Theme(menu)
+    }
+
+    // 换一种颜色并保存，下一帧起就按新的配色绘制
+    fn change_theme(&mut self, part: ThemePart, rgb: Rgb) {
+        self.profile.settings.theme.set(part, rgb);
+        self.storage_error = self.profile.save_settings();
+    }
+
+    // 切换显示模式并保存，切换失败时保持原来的模式
+    fn change_display_mode(&mut self, ctx: &mut Context, step: isize) {
+        let mode = self.profile.settings.window.mode.next(step);
+        match display::set_display_mode(ctx, mode) {
+            Ok(()) => {
+                self.profile.settings.window.mode = mode;
+                self.storage_error = self.profile.save_settings();
+            }
+            Err(error) => eprintln!("无法切换到{}: {}", mode.label(), error),
+        }
+    }
+
+    // 切换画面质量并保存，选回自动时从高档重新开始
+    fn change_quality(&mut self, step: isize) {
+        let quality = self.quality.setting().next(step);
+        self.quality = QualityControl::new(quality);
+        self.profile.settings.quality = quality;
+        self.storage_error = self.profile.save_settings();
+    }
+
+    // 处理界面上的按键，返回之后要显示的界面
+    fn overlay_key(&mut self, ctx: &mut Context, overlay: Overlay, key: KeyCode) -> Option<Overlay> {
+        match overlay {
+            Overlay::Title(mut menu) => {
+                if key == KeyCode::F3 {
+                    return Some(Overlay::DifficultyCurves);
+                }
+                let MenuInput::Select(i) = menu.handle_key(key) else {
+                    return Some(Overlay::Title(menu));
+                };
+                match self.title_items()[i] {
+                    TitleItem::Start(mode) => {
+                        self.start_run(mode);
+                        None
+                    }
+                    TitleItem::ShareCode => Some(Overlay::ShareCode { text: String::new(), error: None }),
+                    TitleItem::Upgrades => Some(self.upgrades_menu(0, None)),
+                    TitleItem::Practice => Some(self.practice_menu()),
+                    TitleItem::BossPractice => Some(self.boss_practice_menu()),
+                    TitleItem::History => Some(Overlay::History(HistoryView::default())),
+                    TitleItem::Heatmap => Some(Overlay::Heatmap),
+                    TitleItem::Display => Some(self.display_menu(0)),
+                    TitleItem::Theme => Some(self.theme_menu(0)),
+                    TitleItem::Controls => Some(Overlay::Controls),
+                    TitleItem::Bindings => Some(Overlay::Bindings {
+                        menu: BindingsMenu::new(&self.profile.settings.bindings),
+                        from_title: true,
+                    }),
+                    TitleItem::Quit => {
+                        ctx.request_quit();
+                        Some(Overlay::Title(menu))
+                    }
+                }
+            }
+            Overlay::IdlePrompt | Overlay::Suspended => None,
+            loading @ Overlay::Loading { .. } => Some(loading),
+            Overlay::ShareCode { mut text, error } => match key {
+                KeyCode::Escape => Some(self.title_menu()),
+                KeyCode::Back => {
+                    text.pop();
+                    Some(Overlay::ShareCode { text, error })
+                }
+                KeyCode::Return | KeyCode::NumpadEnter => match self.start_shared_run(&text) {
+                    Ok(()) => None,
+                    Err(error) => Some(Overlay::ShareCode { text, error: Some(error) }),
+                },
+                _ => {
+                    if let Some(c) = code_char(key).filter(|_| text.len() < SHARE_CODE_INPUT) {
+                        text.push(c);
+                    }
+                    Some(Overlay::ShareCode { text, error })
+                }
+            },
+            Overlay::DifficultyCurves | Overlay::Controls | Overlay::Heatmap => Some(self.title_menu()),
+            Overlay::History(mut view) => {
+                if view.handle_key(key, &self.profile.history) {
+                    Some(Overlay::History(view))
+                } else {
+                    Some(self.title_menu())
+                }
+            }
+            Overlay::DisplaySettings(mut menu) => {
+                let step = match (key, menu.handle_key(key)) {
+                    (_, MenuInput::Back) | (_, MenuInput::Select(2)) => return Some(self.title_menu()),
+                    (KeyCode::Left, _) if menu.selected < 2 => -1,
+                    (KeyCode::Right, _) | (_, MenuInput::Select(_)) if menu.selected < 2 => 1,
+                    _ => return Some(Overlay::DisplaySettings(menu)),
+                };
+                if menu.selected == 0 {
+                    self.change_display_mode(ctx, step);
+                } else {
+                    self.change_quality(step);
+                }
+                Some(self.display_menu(menu.selected))
+            }
+            Overlay::Theme(mut menu) => {
+                let parts = ThemePart::ALL.len();
+                match (key, menu.handle_key(key)) {
+                    (_, MenuInput::Back) => Some(self.title_menu()),
+                    (_, MenuInput::Select(index)) if index < parts => {
+                        Some(Overlay::ThemeHex { part: ThemePart::ALL[index], text: String::new(), error: None })
+                    }
+                    (_, MenuInput::Select(index)) if index == parts => {
+                        self.profile.settings.theme = Theme::default();
+                        self.storage_error = self.profile.save_settings();
+                        Some(self.theme_menu(index))
+                    }
+                    (_, MenuInput::Select(_)) => Some(self.title_menu()),
+                    (KeyCode::Left | KeyCode::Right, _) if menu.selected < parts => {
+                        let part = ThemePart::ALL[menu.selected];
+                        let step = if key == KeyCode::Left { -1 } else { 1 };
+                        let rgb = self.profile.settings.theme.get(part).cycle(step);
+                        self.change_theme(part, rgb);
+                        Some(self.theme_menu(menu.selected))
+                    }
+                    _ => Some(Overlay::Theme(menu)),
+                }
+            }
+            Overlay::ThemeHex { part, mut text, error } => {
+                let selected = ThemePart::ALL.iter().position(|&other| other == part).unwrap_or(0);
+                match key {
+                    KeyCode::Escape => Some(self.theme_menu(selected)),
+                    KeyCode::Back => {
+                        text.pop();
+                        Some(Overlay::ThemeHex { part, text, error })
+                    }
+                    KeyCode::Return | KeyCode::NumpadEnter => match Rgb::parse(&text) {
+                        Ok(rgb) => {
+                            self.change_theme(part, rgb);
+                            Some(self.theme_menu(selected))
+                        }
+                        Err(error) => Some(Overlay::ThemeHex { part, text, error: Some(error) }),
+                    },
+                    _ => {
+                        if let Some(c) = code_char(key).filter(|c| c.is_ascii_hexdigit() && text.len() < HEX_INPUT) {
+                            text.push(c.to_ascii_uppercase());
+                        }
+                        Some(Overlay::ThemeHex { part, text, error })
+                    }
+                }
+            }
+            Overlay::GamepadDisconnected => {
+                // 玩家选择改用键盘继续
+                self.active_gamepad = None;
+                None
+            }
+            Overlay::Shop { mut menu, message } => match menu.handle_key(key) {
+                MenuInput::Select(index) if index < ShopItem::ALL.len() => {
+                    let item = ShopItem::ALL[index];
+                    let message = match self.world.buy(item) {
+                        Ok(()) => format!("购买了{}", item.label()),
+                        Err(reason) => reason.to_string(),
+                    };
+                    Some(self.shop_menu(index, Some(message)))
+                }
+                MenuInput::Select(_) | MenuInput::Back => None,
+                MenuInput::None => Some(Overlay::Shop { menu, message }),
+            },
+            Overlay::Upgrades { mut menu, message } => match menu.handle_key(key) {
+                MenuInput::Select(index) if index < MetaUpgrade::ALL.len() => {
+                    let upgrade = MetaUpgrade::ALL[index];
+                    let progress = &mut self.profile.progress;
+                    let message = match progress.meta.buy(upgrade, &mut progress.credits) {
+                        Ok(_) => {
+                            self.storage_error = self.profile.save_progress();
+                            format!("{}升到了 {} 级", upgrade.label(), self.profile.progress.meta.level(upgrade))
+                        }
+                        Err(reason) => reason.to_string(),
+                    };
+                    Some(self.upgrades_menu(index, Some(message)))
+                }
+                MenuInput::Select(_) | MenuInput::Back => Some(self.title_menu()),
+                MenuInput::None => Some(Overlay::Upgrades { menu, message }),
+            },
+            Overlay::Practice(mut menu) => match menu.handle_key(key) {
+                MenuInput::Select(index) if index < self.profile.progress.milestones.len() => {
+                    self.start_practice(self.profile.progress.milestones[index]);
+                    None
+                }
+                MenuInput::Select(_) | MenuInput::Back => Some(self.title_menu()),
+                MenuInput::None => Some(Overlay::Practice(menu)),
+            },
+            Overlay::BossPractice(mut menu) => match menu.handle_key(key) {
+                MenuInput::Select(index) => match self.boss_drills().get(index) {
+                    Some(drill) => {
+                        self.start_boss_practice(drill.stage, drill.phase);
+                        None
+                    }
+                    None => Some(self.title_menu()),
+                },
+                MenuInput::Back => Some(self.title_menu()),
+                MenuInput::None => Some(Overlay::BossPractice(menu)),
+            },
+            Overlay::PauseMenu(mut menu) => {
+                if key == self.profile.settings.bindings.key(Action::Pause) {
+                    return None;
+                }
+                match menu.handle_key(key) {
+                    MenuInput::Select(1) => Some(Overlay::Bindings {
+                        menu: BindingsMenu::new(&self.profile.settings.bindings),
+                        from_title: false,
+                    }),
+                    MenuInput::Select(2) => {
+                        self.record_run();
+                        self.reset();
+                        None
+                    }
+                    MenuInput::Select(3) => {
+                        self.record_run();
+                        Some(self.title_menu())
+                    }
+                    MenuInput::Select(_) | MenuInput::Back => None,
+                    MenuInput::None => Some(Overlay::PauseMenu(menu)),
+                }
+            }
+            Overlay::Bindings { mut menu, from_title } => {
+                match menu.handle_key(key, &mut self.profile.settings.bindings) {
+                    BindingsResult::Stay => Some(Overlay::Bindings { menu, from_title }),
+                    BindingsResult::Changed => {
+                        self.storage_error = self.profile.save_settings();
+                        Some(Overlay::Bindings { menu, from_title })
+                    }
+                    BindingsResult::Back if from_title => {
+                        Some(self.title_menu())
+                    }
+                    BindingsResult::Back => {
+                        Some(Overlay::PauseMenu(Menu::new(PAUSE_MENU_ITEMS)))
+                    }
+                }
+            }
+            Overlay::Ending(mut ending) => {
+                if key == KeyCode::E {
+                    self.export_ledger();
+                    return Some(Overlay::Ending(ending));
+                }
+                if !matches!(key, KeyCode::Return | KeyCode::NumpadEnter | KeyCode::Escape) {
+                    return Some(Overlay::Ending(ending));
+                }
+                ending.skip();
+                if ending.finished() {
+                    Some(self.title_menu())
+                } else {
+                    Some(Overlay::Ending(ending))
+                }
+            }
+        }
     }
 
+    // 检测手柄热插拔：正在使用的手柄断开时暂停，重新连上后自动切换过去
+    fn poll_gamepads(&mut self, ctx: &Context) {
+        let connected: Vec<GamepadId> = ctx.gamepad.gamepads().map(|(id, _)| id).collect();
+
+        if let Some(id) = self.active_gamepad {
+            if !connected.contains(&id) {
+                self.active_gamepad = None;
+                self.stick = Vec2::ZERO;
+                if !self.world.game_over {
+                    self.overlay = Some(Overlay::GamepadDisconnected);
+                }
+            }
+        } else if let Some(Overlay::GamepadDisconnected) = self.overlay {
+            if let Some(&id) = connected.first() {
+                self.active_gamepad = Some(id);
+                self.overlay = Some(Overlay::PauseMenu(Menu::new(PAUSE_MENU_ITEMS)));
+            }
+        }
+    }
+
+    // 处理本帧游戏世界发出的事件：音乐、音效、存档和结算画面
+    fn handle_events(&mut self, ctx: &Context, events: Vec<GameEvent>) {
+        for event in events {
+            match event {
+                GameEvent::RunStarted | GameEvent::WaveRestarted | GameEvent::Continued { .. } => {
+                    self.music.play(ctx, MusicTrack::Stage)
+                }
+                GameEvent::BossSpawned => self.music.play(ctx, MusicTrack::Boss),
+                GameEvent::BossDefeated { .. } => {
+                    self.music.play(ctx, MusicTrack::Stage);
+                    platform::request_attention(ctx, Attention::BossDefeated);
+                }
+                GameEvent::CampaignCleared => self.finish_campaign(),
+                // 清空一波之后打开商店，用攒下的金币买强化
+                GameEvent::WaveCleared { .. } if !self.world.game_over => self.overlay = Some(self.shop_menu(0, None)),
+                GameEvent::CriticalHit { .. } => self.sounds.play(ctx, SoundEffect::CriticalHit),
+                GameEvent::ExtraLife { .. } => self.sounds.play(ctx, SoundEffect::ExtraLife),
+                GameEvent::ChargedShotFired { .. } => self.sounds.play(ctx, SoundEffect::ChargedShot),
+                // 被打中的位置记到受击热图上，火力降级和护盾挡下的也算被打中
+                GameEvent::PlayerDamaged { .. } | GameEvent::WeaponDowngraded { .. } | GameEvent::ShieldDamaged { .. } => {
+                    let size = Vec2::new(self.world.field.width, self.world.field.height);
+                    let death = matches!(event, GameEvent::PlayerDamaged { .. }) && self.world.player.lives == 0;
+                    if let Some(error) = self.profile.record_hit(self.world.player.game_object.position, size, death) {
+                        self.storage_error = Some(error);
+                    }
+                    // 还有命时回放被击中前的一秒，最后一条命由慢镜头负责
+                    let lost_life = matches!(event, GameEvent::PlayerDamaged { .. }) && !death;
+                    if lost_life && self.profile.settings.hit_replay {
+                        self.hit_replay = self.clips.clip();
+                        self.clips.clear();
+                    }
+                }
+                // 无尽模式的里程碑存进解锁进度
+                GameEvent::MilestoneReached { milestone } => {
+                    self.profile.progress.reach_milestone(milestone);
+                    if let Some(error) = self.profile.save_progress() {
+                        self.storage_error = Some(error);
+                    }
+                }
+                // 本局刚刚结束，记录分数，练习局和双人对战不计分
+                GameEvent::GameOver if self.world.practice || self.world.rival.is_some() => (),
+                GameEvent::GameOver => {
+                    let pace = self.world.ledger.pace(self.world.run_time);
+                    if let Some(error) = self.profile.record_score(self.world.mode.key(), self.world.score, pace) {
+                        self.storage_error = Some(error);
+                    }
+                    self.record_run();
+                }
+                _ => (),
+            }
+        }
+    }
+
+    // 战役通关：剩余生命奖励已经计入分数，这里记录分数并展示解锁内容
+    fn finish_campaign(&mut self) {
+        let pace = self.world.ledger.pace(self.world.run_time);
+        if let Some(error) = self.profile.record_score(self.world.mode.key(), self.world.score, pace) {
+            self.storage_error = Some(error);
+        }
+        self.record_run();
+
+        let mut unlocks = Vec::new();
+        if !self.profile.progress.campaign_cleared {
+            self.profile.progress.campaign_cleared = true;
+            unlocks.push("金色战机涂装".to_string());
+            unlocks.push("新游戏+ (标题菜单)".to_string());
+            unlocks.push("头目车轮战 (标题菜单)".to_string());
+            if let Some(error) = self.profile.save_progress() {
+                self.storage_error = Some(error);
+            }
+        }
+
+        // 按得分来源列出各项
+        let lives = self.world.player.lives;
+        let tally = self
+            .world
+            .ledger
+            .totals()
+            .into_iter()
+            .map(|(reason, points)| match reason {
+                ScoreReason::LifeBonus => (format!("{} x{}", reason.label(), lives), points),
+                _ => (reason.label().to_string(), points),
+            })
+            .collect();
+        self.overlay = Some(Overlay::Ending(Ending::new(tally, unlocks)));
+    }
+
+    // 战役中按关卡读取和释放专用资源：下一关的在本关最后一次休息和头目战期间读好，
+    // 进入下一关时换上它的曲目，上一关的整组释放
+    fn stream_stage_assets(&mut self, ctx: &Context) {
+        let Some(campaign) = &self.world.campaign else {
+            self.stage_assets.clear();
+            if self.themed_stage.take().is_some() {
+                self.music.set_theme(ctx, None);
+            }
+            return;
+        };
+        let current = campaign.stage();
+        let wanted: Vec<usize> = std::iter::once(current).chain(campaign.upcoming_stage()).collect();
+        self.stage_assets.retain(|group| wanted.contains(&group.stage));
+        for &stage in &wanted {
+            let paths = campaign.stage_spec(stage).map(|spec| spec.assets()).unwrap_or_default();
+            if !paths.is_empty() && !self.stage_assets.iter().any(|group| group.stage == stage) {
+                self.stage_assets.push(StageAssets::start(stage, self.resource_dir.clone(), paths));
+            }
+        }
+        let finished: Vec<bool> = self.stage_assets.iter_mut().map(StageAssets::poll).collect();
+
+        if self.themed_stage.is_some_and(|stage| stage != current) {
+            self.themed_stage = None;
+            self.music.set_theme(ctx, None);
+        }
+        let music = campaign.stage_spec(current).and_then(|spec| spec.music.clone());
+        let ready = self.stage_assets.iter_mut().zip(finished).find(|(group, done)| *done && group.stage == current && !group.applied);
+        if let (Some((group, _)), Some(path)) = (ready, music) {
+            group.applied = true;
+            if let Ok(theme) = group.sound(ctx, &path) {
+                self.themed_stage = Some(current);
+                self.music.set_theme(ctx, Some(theme));
+            }
+        }
+    }
+
+    // 开始新的一局，设置、音乐和手柄状态保持不变
+    fn start_run(&mut self, mode: GameMode) {
+        let seed = self.next_seed.take().or_else(|| mode.rules().seed()).unwrap_or_else(|| rand::thread_rng().gen());
+        self.world = World::with_content(mode, seed, self.profile.progress.meta, self.content.clone());
+        self.practice = None;
+        self.prepare_run();
+    }
+
+    // 从里程碑开始一局练习，重新开始时回到同一个里程碑
+    fn start_practice(&mut self, milestone: Milestone) {
+        self.world = World::practice(&milestone, self.profile.progress.meta, self.content.clone());
+        self.practice = Some(Practice::Milestone(milestone));
+        self.next_seed = None;
+        self.prepare_run();
+    }
+
+    // 练习头目的某个阶段，重新开始时回到同一个阶段
+    fn start_boss_practice(&mut self, stage: usize, phase: usize) {
+        self.world = World::boss_practice(stage, phase, self.profile.progress.meta, self.content.clone());
+        self.practice = Some(Practice::Boss { stage, phase });
+        self.next_seed = None;
+        self.prepare_run();
+    }
+
+    // 换上新的一局之后重置本局相关的状态
+    fn prepare_run(&mut self) {
+        let mode = self.world.mode;
+        self.stage_assets.clear();  // 专用曲目等新一局的资源读好后再换
+        self.trail.clear();
+        self.clips.clear();
+        self.hit_replay = None;
+        self.world.limits = self.profile.settings.limits;
+        self.mutators = self.next_mutators.take().unwrap_or_else(|| Mutators::from_settings(&self.profile.settings));
+        // 检查点只记第一架飞船，双人对战时不能从本波重来
+        if self.world.rival.is_some() {
+            self.mutators.retry_wave = false;
+        }
+        self.world.field.player_edges = self.mutators.player_edges;
+        self.world.set_health(self.mutators.health);
+        if !self.world.practice {
+            self.world.best_pace = self.profile.high_scores.paces.get(mode.key()).cloned();
+        }
+        self.stick = Vec2::ZERO;
+        self.idle_timer = 0.0;
+        self.export_message = None;
+        self.run_recorded = false;
+        self.credited_score = 0;
+    }
+
+    // 按分享码开始一局，新游戏+ 和头目车轮战要先通关战役才能玩
+    fn start_shared_run(&mut self, text: &str) -> Result<(), String> {
+        let code = ShareCode::decode(text)?;
+        if !code.mode.rules().unlocked(&self.profile.progress) {
+            return Err(format!("还没有解锁{}", code.mode.label()));
+        }
+        self.next_seed = Some(code.seed);
+        self.next_mutators = Some(code.mutators);
+        self.start_run(code.mode);
+        Ok(())
+    }
+
+    // 直播模式把分数和波次写给 OBS，写入失败时提示一次，之后不再写
+    fn write_stream_overlay(&mut self) {
+        let streamer = &self.profile.settings.streamer;
+        if !streamer.enabled || !streamer.overlay_file {
+            return;
+        }
+        let Some(text) = self.stream_overlay.update(&self.world) else {
+            return;
+        };
+        if let Some(error) = self.profile.write_stream_overlay(&text) {
+            self.storage_error = Some(error);
+            self.profile.settings.streamer.overlay_file = false;
+        }
+    }
+
+    // 本局的分享码，结算画面上显示
+    fn share_code(&self) -> ShareCode {
+        ShareCode { mode: self.world.mode, seed: self.world.seed, mutators: self.mutators }
+    }
+
+    // 把本局写入历史记录：死亡、通关或中途放弃时各调用一次
+    fn record_run(&mut self) {
+        if self.run_recorded || self.world.practice || self.world.rival.is_some() {
+            return;
+        }
+        self.run_recorded = true;
+        let cleared = self.world.campaign.as_ref().is_some_and(Campaign::finished);
+        let run = RunSummary {
+            mode: self.world.mode.key().to_string(),
+            score: self.world.score,
+            waves: self.world.wave(),
+            duration: self.world.run_time,
+            cleared,
+            cause: self.world.death_cause(),
+            killed_by: self.world.killed_by,
+            finished_at: storage::unix_time(),
+            crits: self.world.crits,
+        };
+        if let Some(error) = self.profile.record_run(run) {
+            self.storage_error = Some(error);
+        }
+        self.earn_credits();
+    }
+
+    // 把本局还没换过的分数换成积分并保存
+    fn earn_credits(&mut self) {
+        let score = self.world.score;
+        if score <= self.credited_score || self.world.practice || self.world.rival.is_some() {
+            return;
+        }
+        let credits = meta::credits_for(score) - meta::credits_for(self.credited_score);
+        self.credited_score = score;
+        if credits > 0 {
+            self.profile.progress.credits += credits;
+            if let Some(error) = self.profile.save_progress() {
+                self.storage_error = Some(error);
+            }
+        }
+    }
+
+    // 把本局的得分记录导出成 CSV 和 JSON
+    fn export_ledger(&mut self) {
+        match self.profile.export_ledger(self.world.mode.key(), &self.world.ledger) {
+            Ok(dir) => self.export_message = Some(format!("得分记录已导出到 {}", dir.display())),
+            Err(error) => self.storage_error = Some(error),
+        }
+    }
+
+    // 辅助模式：从当前这一波开头继续，重来的这一段单独写入历史记录
+    fn retry_wave(&mut self) {
+        self.world.retry_wave();
+        self.stick = Vec2::ZERO;
+        self.idle_timer = 0.0;
+        self.export_message = None;
+        self.run_recorded = false;
+    }
+
+    // 续关接着这一局打，不像重新开始那样换一个世界
+    fn continue_run(&mut self) {
+        if !self.world.continue_run() {
+            return;
+        }
+        self.stick = Vec2::ZERO;
+        self.idle_timer = 0.0;
+        self.export_message = None;
+        self.run_recorded = false;
+    }
+
+    // 重新开始时沿用同一个种子，方便反复练习同一套关卡
     fn reset(&mut self) {
-        *self = MainState::new();
+        match self.practice {
+            Some(Practice::Milestone(milestone)) => return self.start_practice(milestone),
+            Some(Practice::Boss { stage, phase }) => return self.start_boss_practice(stage, phase),
+            None => (),
+        }
+        self.next_seed = Some(self.world.seed);
+        self.next_mutators = Some(self.mutators);
+        self.start_run(self.world.mode);
+    }
+
+    // 游戏中按住 R 重新开始：同样的种子和附加规则，不经过菜单，也不显示开局的关卡名。
+    // 放弃的这一局不记入排行榜
+    fn quick_restart(&mut self) {
+        self.reset();
+        self.world.skip_intro();
+    }
+
+    // 调试用：右上角显示每帧分配次数和各实体池的大小
+    // 调试用：把正在出场的这一波的生成区域画成竖条，权重越大越亮，标出各自的权重
+    fn draw_spawn_lanes(&self, ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult<()> {
+        let Some(wave) = self.world.campaign.as_ref().and_then(Campaign::current_wave) else {
+            return Ok(());
+        };
+        let total: u32 = wave.lanes.iter().map(|lane| lane.weight).sum();
+        let field = &self.world.field;
+        let half_width = wave.formation.half_width() + wave.size / 2.0;
+        for lane in wave.lanes.iter().filter(|_| total > 0) {
+            let (from, to) = lane.range(field, half_width);
+            let share = lane.weight as f32 / total as f32;
+            let rect = Rect::new(from - half_width, field.hud_top, to - from + half_width * 2.0, field.height - field.hud_top);
+            let mesh = Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), rect, Color::new(0.3, 1.0, 0.4, 0.05 + 0.25 * share))?;
+            canvas.draw(&mesh, DrawParam::default());
+            canvas.draw(
+                &graphics::Text::new(format!("权重 {}", lane.weight)),
+                DrawParam::default().dest(Vec2::new(rect.x + 4.0, field.hud_top + 4.0)).color(Color::GREEN),
+            );
+        }
+        Ok(())
+    }
+
+    fn draw_memory(&self, canvas: &mut graphics::Canvas) {
+        let frame = self.memory.last;
+        let allocations = if MemoryMeter::available() {
+            format!(
+                "每帧分配: {} 次 / {:.1} KB\n占用: {:.1} MB",
+                frame.allocations,
+                frame.allocated_bytes as f32 / 1024.0,
+                frame.live_bytes as f32 / (1024.0 * 1024.0)
+            )
+        } else {
+            "分配统计仅在调试构建中可用".to_string()
+        };
+        let pools = format!(
+            "子弹: {}/{}\n敌人: {}/{}\n碎片: {}\n分数提示: {}\n关卡资源: {} 组 / {:.1} KB",
+            self.world.bullets.len(),
+            self.world.bullets.capacity(),
+            self.world.enemies.len(),
+            self.world.enemies.capacity(),
+            self.world.effects.particle_count(),
+            self.world.effects.popup_count(),
+            self.stage_assets.len(),
+            self.stage_assets.iter().map(|group| group.bytes).sum::<usize>() as f32 / 1024.0
+        );
+        canvas.draw(
+            &graphics::Text::new(format!("{}\n{}", allocations, pools)),
+            DrawParam::default()
+                .dest(Vec2::new(WINDOW_WIDTH - 230.0, self.world.field.safe_area().y))
+                .color(Color::GREEN),
+        );
     }
 }
 
 impl EventHandler for MainState {
     fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
-        let dt = timer::delta(ctx).as_secs_f32();
+        let dt = ctx.time.delta().as_secs_f32();
 
-        // 检查游戏是否结束（生命值为0）
-        if self.game_over {
-            if ctx.keyboard.is_key_just_pressed(KeyCode::R) {
-                self.reset();
+        self.watchdog.update(dt);
+        // 统计的是上一次更新开始到现在，包括上一帧的绘制
+        self.memory.frame();
+        self.poll_gamepads(ctx);
+        self.window_title.update(ctx, self.window_status());
+        self.write_stream_overlay();
+        #[cfg(feature = "state-socket")]
+        if let Some(server) = &mut self.state_server {
+            server.publish(&self.world);
+        }
+
+        // 按住按键也算有操作，避免长按移动时被误判为挂机
+        if ctx.keyboard.pressed_keys().is_empty() && self.stick == Vec2::ZERO && !self.touch.held() {
+            self.idle_timer += dt;
+        } else {
+            self.idle_timer = 0.0;
+        }
+
+        // 资源读完之前只更新加载画面
+        if let Some(loader) = &mut self.loader {
+            if loader.poll() {
+                let mut loader = self.loader.take().unwrap();
+                self.music = Music::load(ctx, &mut loader);
+                self.sounds = Sounds::load(ctx, &mut loader);
+                if let Some(Overlay::Loading { then }) = self.overlay.take() {
+                    self.overlay = then.map(|overlay| *overlay);
+                }
             }
             return Ok(());
         }
 
-        // 更新玩家状态，包括无敌时间
-        self.player.update(dt);
+        // 商店等界面打开期间也接着读，波次之间的休息正好用来准备下一关
+        self.stream_stage_assets(ctx);
 
-        // 更新玩家位置
-        self.player.game_object.position += self.player.game_object.velocity * dt;
-        
-        // 保持玩家在屏幕内
-        self.player.game_object.position.x = self.player.game_object.position.x.clamp(
-            self.player.game_object.size.x / 2.0, 
-            WINDOW_WIDTH - self.player.game_object.size.x / 2.0
-        );
+        // 存档提示显示期间暂停游戏
+        if self.storage_error.is_some() || self.storage_notice.is_some() {
+            return Ok(());
+        }
 
-        // 更新子弹位置
-        for bullet in &mut self.bullets {
-            bullet.position += bullet.velocity * dt;
-            
-            // 删除离开屏幕的子弹
-            if bullet.position.y < -10.0 {
-                bullet.alive = false;
+        // 检查游戏是否结束（生命值为0），先放完慢镜头
+        if self.world.game_over && self.world.kill_cam.is_some() {
+            let events = self.world.update(dt);
+            self.handle_events(ctx, events);
+            return Ok(());
+        }
+        if self.world.game_over {
+            self.world.continues.update(dt);
+            if ctx.keyboard.is_key_just_pressed(KeyCode::Return) && self.world.can_continue() {
+                self.continue_run();
+            } else if ctx.keyboard.is_key_just_pressed(KeyCode::R) {
+                self.reset();
+            } else if ctx.keyboard.is_key_just_pressed(KeyCode::C) && self.mutators.retry_wave {
+                self.retry_wave();
+            } else if ctx.keyboard.is_key_just_pressed(KeyCode::E) {
+                self.export_ledger();
+            } else if ctx.keyboard.is_key_just_pressed(KeyCode::Escape) {
+                self.world.game_over = false;
+                self.overlay = Some(self.title_menu());
             }
+            return Ok(());
         }
-        self.bullets.retain(|bullet| bullet.alive);
 
-        // 更新敌人位置
-        for enemy in &mut self.enemies {
-            enemy.position.y += ENEMY_SPEED * dt;
-            
-            // 敌人到达底部，玩家损失一条命
-            if enemy.position.y > WINDOW_HEIGHT + 15.0 {
-                enemy.alive = false;
-                if self.player.take_damage() && self.player.lives == 0 {
-                    self.game_over = true;
-                }
+        // 结算动画播放完后回到标题
+        if let Some(Overlay::Ending(ending)) = &mut self.overlay {
+            ending.update(dt);
+            if ending.finished() {
+                self.overlay = Some(self.title_menu());
             }
+        }
 
-            // 检测玩家与敌人碰撞
-            if !self.player.is_invincible() && 
-               self.player.game_object.collides_with(enemy) {
-                enemy.alive = false;
-                if self.player.take_damage() && self.player.lives == 0 {
-                    self.game_over = true;
-                }
-            }
+        // 长时间无操作时自动暂停，避免玩家离开后白白死亡
+        if self.overlay.is_none() && self.idle_timer >= self.profile.settings.idle_timeout {
+            self.overlay = Some(Overlay::IdlePrompt);
+            platform::request_attention(ctx, Attention::AutoPaused);
+        }
+        if self.overlay.is_some() {
+            self.world.player.game_object.velocity = Vec2::ZERO;
+            return Ok(());
         }
 
-        // 检测子弹与敌人碰撞
-        for bullet in &mut self.bullets {
-            for enemy in &mut self.enemies {
-                if bullet.collides_with(enemy) && enemy.alive {
-                    bullet.alive = false;
-                    enemy.alive = false;
-                    self.score += 10;
-                }
+        // 受击回放期间游戏停住
+        if let Some(replay) = &mut self.hit_replay {
+            replay.update(dt);
+            if replay.finished() {
+                self.hit_replay = None;
             }
+            return Ok(());
         }
-        self.enemies.retain(|enemy| enemy.alive);
 
-        // 生成新敌人
-        self.spawn_timer += dt;
-        if self.spawn_timer >= ENEMY_SPAWN_INTERVAL {
-            self.spawn_enemy();
-            self.spawn_timer = 0.0;
+        if self.restart_hold.update(ctx.keyboard.is_key_pressed(RESTART_KEY), dt, RESTART_HOLD) {
+            self.quick_restart();
+            return Ok(());
         }
 
+        // 手指按住不动时飞船也要继续往目标位置移动
+        if self.touch.held() {
+            self.steer();
+        }
+        self.hold_actions(ctx);
+        let rival = self.rival_input(ctx);
+        self.world.apply_rival_input(rival);
+        #[cfg(feature = "twitch")]
+        self.poll_chat(dt);
+        let started = Instant::now();
+        let events = self.world.update(dt);
+        self.clips.record(dt, &self.world);
+        self.handle_events(ctx, events);
+        self.starfield.update(dt);
+        self.trail.push(self.world.player.game_object.position, self.quality.detail().trail_length);
+        let spans = self.world.profiler.take();
+        self.watchdog.check("更新", started.elapsed(), &spans);
         Ok(())
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let started = Instant::now();
+        self.draw_profiler.start("游戏画面");
         let mut canvas = graphics::Canvas::from_frame(ctx, Color::BLACK);
+        let screen = display::screen_coordinates(ctx);
+        // 慢镜头期间游戏画面拉近到玩家身上，界面文字不跟着缩放
+        canvas.set_screen_coordinates(self.world.kill_cam.map_or(screen, |kill_cam| kill_cam.view(screen)));
+        let detail = self.quality.detail();
+        self.starfield.draw(ctx, &mut canvas, detail.background_layers)?;
+        if self.profile.settings.debug.show_spawn_lanes {
+            self.draw_spawn_lanes(ctx, &mut canvas)?;
+        }
+        self.trail.draw(ctx, &mut canvas)?;
 
-        // 绘制玩家，无敌时闪烁效果
-        if !self.player.is_invincible() || 
-           (self.player.is_invincible() && (self.player.invincible_timer * 10.0) as i32 % 2 == 0) {
+        // 绘制玩家，受伤后的无敌时间内闪烁，冲刺时不闪烁
+        let hurt = self.world.player.invincible_timer > 0.0;
+        if !hurt || (self.world.player.invincible_timer * 10.0) as i32 % 2 == 0 {
             
-            let player_color = if self.player.is_invincible() {
+            let player_color = if hurt {
                 Color::new(1.0, 1.0, 0.5, 0.8)  // 受伤后呈现黄色半透明
+            } else if self.world.player.dashing() {
+                Color::new(0.6, 0.9, 1.0, 0.6)  // 冲刺中呈现淡蓝色半透明
             } else {
-                Color::WHITE
+                self.profile.settings.theme.ship_color(self.profile.progress.campaign_cleared)
             };
             
-            let player_mesh = Mesh::new_rectangle(
+            // 穿越边缘时两侧各画一半
+            let (bounds, ghost) = self.world.field.player_bounds(&self.world.player.game_object);
+            for bounds in std::iter::once(bounds).chain(ghost) {
+                let player_mesh = Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), bounds, player_color)?;
+                canvas.draw(&player_mesh, DrawParam::default());
+            }
+        }
+
+        // 双人对战的第二架飞船画成橙色，受伤后同样闪烁
+        if let Some(rival) = &self.world.rival {
+            let hurt = rival.player.invincible_timer > 0.0;
+            if !hurt || (rival.player.invincible_timer * 10.0) as i32 % 2 == 0 {
+                let color = if hurt { Color::new(1.0, 0.7, 0.4, 0.6) } else { Color::new(1.0, 0.55, 0.1, 1.0) };
+                let (bounds, ghost) = self.world.field.player_bounds(&rival.player.game_object);
+                for bounds in std::iter::once(bounds).chain(ghost) {
+                    let mesh = Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), bounds, color)?;
+                    canvas.draw(&mesh, DrawParam::default());
+                }
+            }
+        }
+
+        // 蓄力时飞船周围发光，蓄够之后变亮，飞船下方显示蓄力条
+        let charge = self.world.charge;
+        if charge.held > 0.0 {
+            let bounds = self.world.player.game_object.bounds();
+            let alpha = if charge.ready() { 0.45 } else { 0.2 };
+            // 画面质量高时外面再叠几圈更大更淡的光晕
+            for pass in 0..=detail.glow_passes {
+                let glow = Mesh::new_circle(
+                    ctx,
+                    graphics::DrawMode::fill(),
+                    self.world.player.game_object.position,
+                    18.0 + 12.0 * charge.ratio() + pass as f32 * 6.0,
+                    0.5,
+                    Color::new(0.5, 0.85, 1.0, alpha / (pass + 1) as f32),
+                )?;
+                canvas.draw(&glow, DrawParam::default());
+            }
+            let back = Rect::new(bounds.x, bounds.bottom() + 4.0, bounds.w, 3.0);
+            let bar = Rect { w: bounds.w * charge.ratio(), ..back };
+            let color = if charge.ready() { Color::new(0.5, 0.85, 1.0, 1.0) } else { Color::new(0.6, 0.6, 0.6, 1.0) };
+            let back = Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), back, Color::new(0.3, 0.3, 0.3, 1.0))?;
+            let bar = Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), bar, color)?;
+            canvas.draw(&back, DrawParam::default());
+            canvas.draw(&bar, DrawParam::default());
+        }
+
+        // 护盾画成玩家周围实心的淡色圆
+        if self.world.buffs.shielded() {
+            let shield = Mesh::new_circle(
                 ctx,
                 graphics::DrawMode::fill(),
-                Rect::new(
-                    self.player.game_object.position.x - self.player.game_object.size.x / 2.0,
-                    self.player.game_object.position.y - self.player.game_object.size.y / 2.0,
-                    self.player.game_object.size.x,
-                    self.player.game_object.size.y,
-                ),
-                player_color,
+                self.world.player.game_object.position,
+                24.0,
+                0.5,
+                Color::new(0.2, 0.9, 0.9, 0.3),
             )?;
-            canvas.draw(&player_mesh, DrawParam::default());
+            canvas.draw(&shield, DrawParam::default());
         }
 
-        // 绘制子弹
-        for bullet in &self.bullets {
-            let bullet_mesh = Mesh::new_rectangle(
+        // 反射护盾画成玩家周围的一圈
+        if self.world.buffs.reflecting() {
+            let shield = Mesh::new_circle(
                 ctx,
-                graphics::DrawMode::fill(),
-                bullet.bounds(),
-                Color::YELLOW,
+                graphics::DrawMode::stroke(2.0),
+                self.world.player.game_object.position,
+                28.0,
+                0.5,
+                Color::new(0.7, 0.9, 1.0, 0.8),
             )?;
+            canvas.draw(&shield, DrawParam::default());
+        }
+
+        // 绘制子弹，所有子弹合成一个网格，霰弹画成橙色，其余按配色设置
+        if !self.world.bullets.is_empty() {
+            let mut builder = MeshBuilder::new();
+            let bullets = self.profile.settings.theme.bullets.color();
+            for bullet in 0..self.world.bullets.len() {
+                let color = match self.world.bullets.weapon(bullet) {
+                    Weapon::Scatter => Color::new(1.0, 0.55, 0.1, 1.0),
+                    _ => bullets,
+                };
+                builder.rectangle(graphics::DrawMode::fill(), self.world.bullets.bounds(bullet), color)?;
+            }
+            let bullet_mesh = Mesh::from_data(ctx, builder.build());
+            canvas.draw(&bullet_mesh, DrawParam::default());
+        }
+        // 第二架飞船的子弹和飞船一样是橙色
+        if let Some(rival) = self.world.rival.as_ref().filter(|rival| !rival.bullets.is_empty()) {
+            let mut builder = MeshBuilder::new();
+            for bullet in 0..rival.bullets.len() {
+                builder.rectangle(graphics::DrawMode::fill(), rival.bullets.bounds(bullet), Color::new(1.0, 0.55, 0.1, 1.0))?;
+            }
+            let bullet_mesh = Mesh::from_data(ctx, builder.build());
+            canvas.draw(&bullet_mesh, DrawParam::default());
+        }
+
+        // 蓄力弹画成外面一圈淡蓝、中间发白的长条
+        for shot in &self.world.charged_shots {
+            let bounds = shot.game_object.bounds();
+            draw_glow(ctx, &mut canvas, bounds, Color::new(0.5, 0.85, 1.0, 0.3), detail.glow_passes)?;
+            let core = Rect::new(bounds.x + bounds.w / 4.0, bounds.y + 4.0, bounds.w / 2.0, bounds.h - 8.0);
+            let outer = Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), bounds, Color::new(0.5, 0.85, 1.0, 0.7))?;
+            let core = Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), core, Color::WHITE)?;
+            canvas.draw(&outer, DrawParam::default());
+            canvas.draw(&core, DrawParam::default());
+        }
+
+        // 激光的光束停留一小会儿，逐渐变淡
+        if let Some(beam) = &self.world.beam {
+            let color = Color::new(0.3, 0.9, 1.0, beam.fade() * 0.8);
+            draw_glow(ctx, &mut canvas, beam.bounds, Color { a: color.a * 0.3, ..color }, detail.glow_passes)?;
+            let rect = Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), beam.bounds, color)?;
+            canvas.draw(&rect, DrawParam::default());
+        }
+
+        // 敌人的子弹画成洋红色的圆点，和玩家的黄色子弹区分开
+        if !self.world.enemy_bullets.is_empty() {
+            let mut builder = MeshBuilder::new();
+            for &position in &self.world.enemy_bullets.positions {
+                builder.circle(graphics::DrawMode::fill(), position, 4.0, 0.5, Color::MAGENTA)?;
+            }
+            let bullet_mesh = Mesh::from_data(ctx, builder.build());
             canvas.draw(&bullet_mesh, DrawParam::default());
         }
 
-        // 绘制敌人
-        for enemy in &self.enemies {
-            let enemy_mesh = Mesh::new_rectangle(
+        // 绘制敌人，每种敌人颜色不同，悬赏目标是金色的，时间冻结期间和被电磁脉冲眩晕的敌人显示为蓝色
+        let frozen = self.world.buffs.freezing();
+        for enemy in &self.world.enemies {
+            let enemy_color = if frozen {
+                Color::new(0.4, 0.6, 1.0, 1.0)
+            } else if enemy.asteroid.is_some() {
+                asteroid::COLOR
+            } else if enemy.bounty.is_some() {
+                enemy::BOUNTY_COLOR
+            } else if enemy.carrier.is_some() {
+                enemy::CARRIER_COLOR
+            } else if enemy.splits {
+                enemy::SPLITTER_COLOR
+            } else {
+                enemy.path().color()
+            };
+            // 被打中但没被击落时闪白一下
+            let enemy_color = if enemy.flashing() { Color::WHITE } else { enemy.status.tint(enemy_color) };
+            // 悬赏目标发光，远远就能看到
+            if enemy.bounty.is_some() {
+                draw_glow(ctx, &mut canvas, enemy.game_object.bounds(), Color { a: 0.35, ..enemy::BOUNTY_COLOR }, detail.glow_passes)?;
+            }
+            let enemy_mesh = Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), enemy.game_object.bounds(), enemy_color)?;
+            canvas.draw(&enemy_mesh, DrawParam::default());
+            let bounds = enemy.game_object.bounds();
+            // 护甲越厚边框越粗
+            if enemy.armor > 0 {
+                let plating = Mesh::new_rectangle(
+                    ctx,
+                    graphics::DrawMode::stroke(2.0 * enemy.armor as f32),
+                    bounds,
+                    Color::new(0.6, 0.6, 0.65, 1.0),
+                )?;
+                canvas.draw(&plating, DrawParam::default());
+            }
+            // 受过伤的敌人在下方显示血条
+            if let Some(health) = enemy.health() {
+                let back = Rect::new(bounds.x, bounds.bottom() + 3.0, bounds.w, 3.0);
+                let bar = Rect { w: bounds.w * health, ..back };
+                let back = Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), back, Color::new(0.3, 0.3, 0.3, 1.0))?;
+                let bar = Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), bar, Color::GREEN)?;
+                canvas.draw(&back, DrawParam::default());
+                canvas.draw(&bar, DrawParam::default());
+            }
+            enemy.status.draw_icons(ctx, &mut canvas, Vec2::new(bounds.center().x, bounds.top()))?;
+            // 自爆机锁定后沿冲刺方向画一条红色预警线，一直画到屏幕外
+            if let Some(direction) = enemy.warning() {
+                let start = enemy.game_object.position;
+                let end = start + direction * (WINDOW_WIDTH + WINDOW_HEIGHT);
+                let line = Mesh::new_line(ctx, &[start, end], 2.0, Color::new(1.0, 0.1, 0.1, 0.7))?;
+                canvas.draw(&line, DrawParam::default());
+            }
+        }
+
+        // 绘制道具、金币和僚机
+        for powerup in &self.world.powerups {
+            powerup.draw(ctx, &mut canvas)?;
+        }
+        for coin in &self.world.coins {
+            let gold = Color::new(1.0, 0.84, 0.0, 1.0);
+            let mesh = Mesh::new_circle(ctx, graphics::DrawMode::fill(), coin.position, shop::COIN_SIZE / 2.0, 0.3, gold)?;
+            canvas.draw(&mesh, DrawParam::default());
+        }
+        if let Some(position) = self.world.buffs.drone_position(self.world.player.game_object.position) {
+            let drone = Mesh::new_rectangle(
                 ctx,
                 graphics::DrawMode::fill(),
-                enemy.bounds(),
+                Rect::new(position.x - DRONE_SIZE / 2.0, position.y - DRONE_SIZE / 2.0, DRONE_SIZE, DRONE_SIZE),
+                Color::CYAN,
+            )?;
+            canvas.draw(&drone, DrawParam::default());
+        }
+        for position in self.world.player.option_positions() {
+            let option = Mesh::new_circle(ctx, graphics::DrawMode::fill(), position, OPTION_RADIUS, 0.5, OPTION_COLOR)?;
+            canvas.draw(&option, DrawParam::default());
+        }
+
+        // 绘制头目
+        if let Some(boss) = &self.world.boss {
+            boss.draw(ctx, &mut canvas)?;
+        }
+        self.world.effects.draw(ctx, &mut canvas, detail.particle_density)?;
+
+        // 慢镜头中用红框圈出造成最后一击的物体
+        if let Some(kill_cam) = &self.world.kill_cam {
+            let killer = kill_cam.killer;
+            let outline = Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::stroke(2.0),
+                Rect::new(killer.x - 4.0, killer.y - 4.0, killer.w + 8.0, killer.h + 8.0),
                 Color::RED,
             )?;
-            canvas.draw(&enemy_mesh, DrawParam::default());
+            canvas.draw(&outline, DrawParam::default());
         }
+        canvas.set_screen_coordinates(screen);
 
-        // 绘制分数
-        let score_text = graphics::Text::new(format!("分数: {}", self.score));
-        canvas.draw(
-            &score_text,
-            DrawParam::default().dest(Vec2::new(10.0, 10.0)),
-        );
-        
-        // 绘制生命值
-        let lives_text = graphics::Text::new(format!("生命: {}", self.player.lives));
-        canvas.draw(
-            &lives_text,
-            DrawParam::default().dest(Vec2::new(10.0, 40.0)),
-        );
+        // 战役关卡名和波次之间的下一波编号
+        if let Some(banner) = self.world.campaign.as_ref().and_then(Campaign::banner) {
+            canvas.draw(
+                &graphics::Text::new(banner),
+                DrawParam::default().dest(Vec2::new(WINDOW_WIDTH / 2.0 - 80.0, WINDOW_HEIGHT / 2.0 - 40.0)),
+            );
+        }
 
-        // 游戏结束提示
-        if self.game_over {
-            let game_over_text = graphics::Text::new("游戏结束! 按R键重新开始");
+        #[cfg(feature = "twitch")]
+        if let Some(status) = self.chat.as_ref().and_then(|(_, vote)| vote.status()) {
+            canvas.draw(
+                &graphics::Text::new(status),
+                DrawParam::default().dest(Vec2::new(WINDOW_WIDTH / 2.0 - 160.0, self.world.field.safe_area().y + 20.0)),
+            );
+        }
+
+        if let Some((notice, _)) = &self.world.notice {
+            canvas.draw(
+                &graphics::Text::new(notice.as_str()),
+                DrawParam::default()
+                    .dest(Vec2::new(WINDOW_WIDTH / 2.0 - 80.0, self.world.field.safe_area().y + 50.0))
+                    .color(Color::YELLOW),
+            );
+        }
+
+        // 直播模式留给摄像头的角落涂黑，HUD 也会避开这里
+        let style = HudStyle { accent: self.profile.settings.theme.accent.color(), ..HudStyle::new(&self.profile.settings.streamer) };
+        if let Some(blank) = style.blank {
+            let rect = Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), blank, Color::BLACK)?;
+            canvas.draw(&rect, DrawParam::default());
+        }
+        hud::draw(&mut canvas, &self.world, &style);
+        if let Some(replay) = &self.hit_replay {
+            let field = Vec2::new(self.world.field.width, self.world.field.height);
+            replay.draw(ctx, &mut canvas, Vec2::new(WINDOW_WIDTH, WINDOW_HEIGHT), field)?;
+        }
+
+        // 按住 R 期间在屏幕中央显示重新开始的进度
+        let restart = self.restart_hold.progress(RESTART_HOLD);
+        if restart > 0.0 && self.overlay.is_none() {
+            let center = Vec2::new(WINDOW_WIDTH / 2.0, WINDOW_HEIGHT / 2.0);
+            let text = graphics::Text::new("按住重新开始");
+            canvas.draw(&text, DrawParam::default().dest(center - Vec2::new(48.0, 30.0)));
+            let bar = Rect::new(center.x - 60.0, center.y, 120.0 * restart, 6.0);
+            canvas.draw(&graphics::Quad, DrawParam::default().dest_rect(bar).color(Color::WHITE));
+        }
+
+        // 用过触屏后画出触摸按钮
+        if self.touch.used() && self.overlay.is_none() && !self.world.game_over {
+            for button in TouchButton::ALL {
+                let center = button.center();
+                let ring = Mesh::new_circle(
+                    ctx,
+                    graphics::DrawMode::stroke(2.0),
+                    center,
+                    touch::BUTTON_RADIUS,
+                    0.5,
+                    Color::new(1.0, 1.0, 1.0, 0.5),
+                )?;
+                canvas.draw(&ring, DrawParam::default());
+                canvas.draw(
+                    &graphics::Text::new(button.label()),
+                    DrawParam::default().dest(center - Vec2::new(16.0, 8.0)).color(Color::new(1.0, 1.0, 1.0, 0.7)),
+                );
+            }
+        }
+
+        // 游戏结束提示，慢镜头放完才显示
+        if self.world.game_over && self.world.kill_cam.is_none() {
+            // 双人对战只写出胜负和两边的分数，不计最高分和积分
+            let message = if let (Some(winner), Some(rival)) = (self.world.winner(), &self.world.rival) {
+                format!("{}! 按R键再来一局，Esc返回标题\n1P: {} 分\n2P: {} 分", winner.label(), self.world.score, rival.score)
+            } else {
+                let mut message = format!(
+                    "游戏结束! 按R键重新开始，Esc返回标题\n最高分: {}\n按E键导出本局得分记录",
+                    self.profile.high_scores.best(self.world.mode.key())
+                );
+                if self.world.can_continue() {
+                    let continues = self.world.continues;
+                    message += &format!(
+                        "\n按回车续关 ({} 秒，还能续 {} 次，分数保留 {:.0}%)",
+                        continues.seconds(),
+                        continues.left(),
+                        continues::KEEP_SCORE * 100.0
+                    );
+                }
+                if self.mutators.retry_wave {
+                    message += &format!(
+                        "\n按C键从第 {} 波重来 (分数恢复为 {})",
+                        self.world.wave(),
+                        self.world.wave_start_score()
+                    );
+                }
+                message += &format!("\n积分: {} (在标题菜单的「机体强化」中使用)", self.profile.progress.credits);
+                let crits = self.world.crits;
+                message += &format!("\n暴击: {} / {} 次命中 ({:.0}%)", crits.crits, crits.hits, crits.rate() * 100.0);
+                if style.show_seed {
+                    message += &format!("\n分享码: {}", self.share_code().encode());
+                }
+                if let Some(source) = self.world.killed_by {
+                    message += &format!("\n\n被击落: {}\n提示: {}", source.label(), source.tip());
+                }
+                message
+            };
+            let game_over_text = graphics::Text::new(message);
             canvas.draw(
                 &game_over_text,
                 DrawParam::default().dest(Vec2::new(
                     WINDOW_WIDTH / 2.0 - 120.0,
-                    WINDOW_HEIGHT / 2.0,
+                    WINDOW_HEIGHT / 2.0 - 100.0,
                 )),
             );
+            self.world.ledger.draw_breakdown(
+                ctx,
+                &mut canvas,
+                Vec2::new(WINDOW_WIDTH / 2.0 - 120.0, WINDOW_HEIGHT / 2.0 + 60.0),
+            )?;
+        }
+
+        // 暂停界面
+        self.draw_profiler.start("界面");
+        match &self.overlay {
+            Some(Overlay::Title(menu)) => menu.draw(ctx, &mut canvas, "太空射击游戏")?,
+            Some(Overlay::PauseMenu(menu)) => {
+                menu.draw(ctx, &mut canvas, "游戏暂停")?;
+                hud::draw_run_info(&mut canvas, &self.world, &self.profile.settings.bindings);
+            }
+            Some(Overlay::Bindings { menu, .. }) => menu.draw(ctx, &mut canvas)?,
+            Some(Overlay::Ending(ending)) => ending.draw(ctx, &mut canvas)?,
+            Some(Overlay::DifficultyCurves) => self.difficulty.draw(ctx, &mut canvas)?,
+            Some(Overlay::History(view)) => view.draw(ctx, &mut canvas, &self.profile.history)?,
+            Some(Overlay::Heatmap) => self.profile.heatmap.draw(ctx, &mut canvas)?,
+            Some(Overlay::DisplaySettings(menu)) => menu.draw(ctx, &mut canvas, "显示设置")?,
+            Some(Overlay::Controls) => menu::draw_controls(ctx, &mut canvas, &self.profile.settings.bindings)?,
+            Some(Overlay::Theme(menu)) => {
+                menu.draw(ctx, &mut canvas, "配色")?;
+                // 每种颜色后面画一块色样
+                let theme = self.profile.settings.theme;
+                let mut builder = MeshBuilder::new();
+                for (row, &part) in ThemePart::ALL.iter().enumerate() {
+                    let swatch = Rect::new(WINDOW_WIDTH / 2.0 + 160.0, 202.0 + row as f32 * 30.0, 24.0, 16.0);
+                    builder.rectangle(graphics::DrawMode::fill(), swatch, theme.get(part).color())?;
+                }
+                let swatches = Mesh::from_data(ctx, builder.build());
+                canvas.draw(&swatches, DrawParam::default());
+                canvas.draw(
+                    &graphics::Text::new("左右键切换调色板里的颜色，回车输入十六进制颜色"),
+                    DrawParam::default().dest(Vec2::new(WINDOW_WIDTH / 2.0 - 200.0, WINDOW_HEIGHT - 120.0)),
+                );
+            }
+            Some(Overlay::ThemeHex { part, text, error }) => {
+                menu::draw_backdrop(ctx, &mut canvas)?;
+                let mut message = format!("输入{}的颜色: #{}_\n回车确定，Esc返回", part.label(), text);
+                if let Some(error) = error {
+                    message += &format!("\n\n{}", error);
+                }
+                draw_prompt(&mut canvas, &message);
+            }
+            Some(Overlay::ShareCode { text, error }) => {
+                menu::draw_backdrop(ctx, &mut canvas)?;
+                let mut message = format!("输入分享码: {}_\n回车开始，Esc返回", text);
+                if let Some(error) = error {
+                    message += &format!("\n\n{}", error);
+                }
+                draw_prompt(&mut canvas, &message);
+            }
+            Some(Overlay::Upgrades { menu, message }) => {
+                menu.draw(ctx, &mut canvas, "机体强化")?;
+                let mut line = format!("积分: {}    每 {} 分换 1 点积分", self.profile.progress.credits, meta::CREDIT_SCORE);
+                if let Some(message) = message {
+                    line += &format!("    {}", message);
+                }
+                canvas.draw(
+                    &graphics::Text::new(line),
+                    DrawParam::default().dest(Vec2::new(WINDOW_WIDTH / 2.0 - 200.0, WINDOW_HEIGHT - 120.0)),
+                );
+            }
+            Some(Overlay::Practice(menu)) => menu.draw(ctx, &mut canvas, "里程碑练习 (不计分)")?,
+            Some(Overlay::BossPractice(menu)) => menu.draw(ctx, &mut canvas, "头目练习 (不计分)")?,
+            Some(Overlay::Shop { menu, message }) => {
+                menu.draw(ctx, &mut canvas, "商店")?;
+                let mut line = format!("金币: {}", self.world.wallet);
+                if let Some(message) = message {
+                    line += &format!("    {}", message);
+                }
+                canvas.draw(
+                    &graphics::Text::new(line),
+                    DrawParam::default().dest(Vec2::new(WINDOW_WIDTH / 2.0 - 120.0, WINDOW_HEIGHT - 120.0)),
+                );
+            }
+            Some(Overlay::Loading { .. }) => {
+                let progress = self.loader.as_ref().map_or(1.0, AssetLoader::progress);
+                menu::draw_loading(ctx, &mut canvas, progress, self.tip)?;
+            }
+            Some(Overlay::IdlePrompt) => draw_prompt(&mut canvas, "你还在吗? 按任意键继续"),
+            Some(Overlay::Suspended) => draw_prompt(&mut canvas, "游戏已暂停\n按任意键或轻触屏幕继续"),
+            Some(Overlay::GamepadDisconnected) => {
+                draw_prompt(&mut canvas, "手柄已断开，请重新连接\n或按任意键改用键盘继续")
+            }
+            None => (),
+        }
+
+        if let Some(message) = &self.export_message {
+            canvas.draw(
+                &graphics::Text::new(message.as_str()),
+                DrawParam::default().dest(Vec2::new(10.0, self.world.field.safe_area().bottom() - 20.0)),
+            );
         }
 
+        // 存档错误对话框，恢复备份的提示也用同一个对话框
+        let error = self.storage_error.as_ref().map(|error| format!("{}\n本次游戏的进度可能不会被保存", error));
+        if let Some(message) = error.or_else(|| self.storage_notice.clone()) {
+            let dialog = Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::fill(),
+                Rect::new(100.0, WINDOW_HEIGHT / 2.0 - 60.0, WINDOW_WIDTH - 200.0, 120.0),
+                Color::new(0.2, 0.2, 0.3, 0.95),
+            )?;
+            canvas.draw(&dialog, DrawParam::default());
+
+            let mut dialog_text = graphics::Text::new(format!("{}\n\n按任意键继续", message));
+            dialog_text.set_bounds(Vec2::new(WINDOW_WIDTH - 240.0, 100.0));
+            canvas.draw(
+                &dialog_text,
+                DrawParam::default().dest(Vec2::new(120.0, WINDOW_HEIGHT / 2.0 - 45.0)),
+            );
+        }
+
+        if self.profile.settings.debug.show_budget_warnings {
+            if let Some((warning, _)) = &self.watchdog.warning {
+                canvas.draw(
+                    &graphics::Text::new(warning.as_str()),
+                    DrawParam::default()
+                        .dest(Vec2::new(10.0, self.world.field.safe_area().bottom() + 10.0))
+                        .color(Color::RED),
+                );
+            }
+        }
+
+        if self.profile.settings.debug.show_memory {
+            self.draw_memory(&mut canvas);
+        }
+
+        self.draw_profiler.start("提交");
         canvas.finish(ctx)?;
+        let spans = self.draw_profiler.take();
+        self.watchdog.check("绘制", started.elapsed(), &spans);
+        if let Some(level) = self.quality.record(self.watchdog.take_overrun()) {
+            eprintln!("帧时间持续超出预算，画面质量自动降到{}", level.label());
+        }
         Ok(())
     }
 
     // 修改key_down_event和key_up_event以使用player.game_object
-    fn key_down_event(&mut self, _ctx: &mut Context, input: KeyInput, _repeat: bool) -> GameResult<()> {
-        if self.game_over {
+    fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, repeat: bool) -> GameResult<()> {
+        self.idle_timer = 0.0;
+        // 双人对战时第二名玩家的按键只给第二架飞船，由 rival_input 每帧读取
+        let rival = self.world.rival.is_some() && input.keycode.and_then(bindings::rival_action).is_some();
+        // 菜单里按下的移动键也要记住，关闭菜单后松开时才能正确停下
+        if let Some(action) = input.keycode.and_then(|key| self.profile.settings.bindings.action_for(key)).filter(|_| !rival) {
+            self.held.set(action, true);
+        }
+
+        // 先关闭存档错误和恢复的提示
+        if self.storage_error.take().or_else(|| self.storage_notice.take()).is_some() {
+            return Ok(());
+        }
+
+        let Some(key) = input.keycode else {
+            return Ok(());
+        };
+
+        // 暂停界面打开时按键只交给界面处理
+        if let Some(overlay) = self.overlay.take() {
+            self.overlay = self.overlay_key(ctx, overlay, key);
             return Ok(());
         }
 
-        match input.keycode {
-            Some(KeyCode::Left) => self.player.game_object.velocity.x = -PLAYER_SPEED,
-            Some(KeyCode::Right) => self.player.game_object.velocity.x = PLAYER_SPEED,
-            Some(KeyCode::Space) => self.fire_bullet(),
-            _ => (),
+        if self.world.game_over || rival {
+            return Ok(());
+        }
+
+        if key == KeyCode::F2 {
+            self.profile.settings.debug.show_memory = !self.profile.settings.debug.show_memory;
+            return Ok(());
+        }
+        if key == KeyCode::F4 {
+            self.profile.settings.debug.show_spawn_lanes = !self.profile.settings.debug.show_spawn_lanes;
+            return Ok(());
+        }
+
+        if let Some(action) = self.profile.settings.bindings.action_for(key) {
+            self.perform_action(action);
+            // 按住不放时的自动重复不算连按
+            if !repeat && self.double_tap.press(action, ctx.time.time_since_start().as_secs_f32()) {
+                self.perform_action(Action::Dash);
+            }
+        } else if let Some(slot) = bindings::weapon_slot(key) {
+            if !self.world.cinematic {
+                self.world.select_weapon(slot);
+            }
         }
         Ok(())
     }
 
-    fn key_up_event(&mut self, _ctx: &mut Context, input: KeyInput) -> GameResult<()> {
-        match input.keycode {
-            Some(KeyCode::Left) | Some(KeyCode::Right) => {
-                self.player.game_object.velocity.x = 0.0;
+    fn gamepad_button_down_event(&mut self, ctx: &mut Context, btn: Button, id: GamepadId) -> GameResult<()> {
+        self.idle_timer = 0.0;
+        self.active_gamepad = Some(id);
+        if let Some(action) = bindings::gamepad_action(btn) {
+            self.held.set(action, true);
+        }
+
+        if self.storage_error.take().or_else(|| self.storage_notice.take()).is_some() {
+            return Ok(());
+        }
+
+        if let Some(overlay) = self.overlay.take() {
+            let capturing = matches!(&overlay, Overlay::Bindings { menu, .. } if menu.is_capturing());
+            let menu_key = bindings::gamepad_menu_key(btn, &self.profile.settings.bindings);
+            // 等待键盘按键绑定时忽略手柄
+            self.overlay = match menu_key {
+                Some(key) if !capturing => self.overlay_key(ctx, overlay, key),
+                _ => Some(overlay),
+            };
+            return Ok(());
+        }
+
+        // 手柄用开始键重新开始，A 键续关，辅助模式下用选择键从本波重来
+        if self.world.game_over {
+            if btn == Button::South && self.world.can_continue() {
+                self.continue_run();
+            } else if btn == Button::Start {
+                self.reset();
+            } else if btn == Button::Select && self.mutators.retry_wave {
+                self.retry_wave();
+            }
+            return Ok(());
+        }
+
+        if let Some(action) = bindings::gamepad_action(btn) {
+            self.perform_action(action);
+        }
+        Ok(())
+    }
+
+    fn gamepad_button_up_event(&mut self, _ctx: &mut Context, btn: Button, _id: GamepadId) -> GameResult<()> {
+        if let Some(action) = bindings::gamepad_action(btn) {
+            if self.held.set(action, false) {
+                self.steer();
             }
-            _ => (),
         }
         Ok(())
     }
+
+    fn gamepad_axis_event(&mut self, _ctx: &mut Context, axis: Axis, value: f32, id: GamepadId) -> GameResult<()> {
+        let value = if value.abs() < STICK_DEADZONE { 0.0 } else { value };
+        match axis {
+            Axis::LeftStickX => self.stick.x = value,
+            Axis::LeftStickY => self.stick.y = -value,  // 摇杆向上推时读数为正，画面上向上是负方向
+            _ => return Ok(()),
+        }
+        self.active_gamepad = Some(id);
+        self.idle_timer = 0.0;
+        if self.overlay.is_none() && !self.world.game_over {
+            self.steer();
+        }
+        Ok(())
+    }
+
+    // 退出前记住窗口位置和大小，正在进行的一局记为放弃
+    fn quit_event(&mut self, ctx: &mut Context) -> GameResult<bool> {
+        self.record_run();
+        if let Some(error) = self.storage_error.take() {
+            eprintln!("{}", error);
+        }
+        display::capture_placement(ctx, &mut self.profile.settings.window);
+        if let Some(error) = self.profile.save_settings() {
+            eprintln!("{}", error);
+        }
+        Ok(false)
+    }
+
+    // 触屏：暂停类的提示轻触任意位置关闭，游戏中拖动移动、按住开火，按钮放炸弹或暂停
+    fn touch_event(&mut self, ctx: &mut Context, phase: TouchPhase, x: f64, y: f64) -> GameResult<()> {
+        self.idle_timer = 0.0;
+        if self.storage_error.is_some() || self.storage_notice.is_some() {
+            return Ok(());
+        }
+        if matches!(self.overlay, Some(Overlay::IdlePrompt | Overlay::Suspended | Overlay::GamepadDisconnected)) {
+            if phase == TouchPhase::Started {
+                if matches!(self.overlay, Some(Overlay::GamepadDisconnected)) {
+                    self.active_gamepad = None;
+                }
+                self.overlay = None;
+            }
+            return Ok(());
+        }
+        if self.overlay.is_some() || self.world.game_over {
+            self.touch.release();
+            return Ok(());
+        }
+        let position = display::to_game(ctx, x as f32, y as f32);
+        let ship = self.world.player.game_object.position;
+        match self.touch.touch(phase, position, ship, self.world.field.player_area()) {
+            Some(TouchButton::Bomb) => self.perform_action(Action::Bomb),
+            Some(TouchButton::Pause) => self.suspend(),
+            None => self.steer(),
+        }
+        Ok(())
+    }
+
+    // 窗口切到后台：手机上按下主屏幕键、来电或被别的应用抢走音频焦点时也会收到
+    fn focus_event(&mut self, _ctx: &mut Context, gained: bool) -> GameResult<()> {
+        if !self.profile.settings.pause_on_focus_loss {
+            return Ok(());
+        }
+        if gained {
+            self.music.resume();
+        } else {
+            self.release_inputs();
+            self.suspend();
+            self.music.suspend();
+        }
+        Ok(())
+    }
+
+    fn key_up_event(&mut self, _ctx: &mut Context, input: KeyInput) -> GameResult<()> {
+        let action = input
+            .keycode
+            .and_then(|key| self.profile.settings.bindings.action_for(key));
+        if action.is_some_and(|action| self.held.set(action, false)) {
+            self.steer();
+        }
+        Ok(())
+    }
+}
+
+// 切到后台时绘制表面已经失效，不管 pause_on_focus_loss 怎么设置都暂停并停下音乐
+#[cfg(feature = "android")]
+impl lifecycle::LifecycleHandler for MainState {
+    fn suspended(&mut self, _ctx: &mut Context) {
+        self.release_inputs();
+        self.suspend();
+        self.music.suspend();
+    }
+
+    fn resumed(&mut self, _ctx: &mut Context) {
+        self.music.resume();
+    }
+}
+
+// 屏幕中央的提示文字
+// 分享码输入框里的字符，只接受字母和数字
+fn code_char(key: KeyCode) -> Option<char> {
+    let name = bindings::key_name(key);
+    let name = name.strip_prefix("Key").or_else(|| name.strip_prefix("Numpad")).unwrap_or(&name);
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphanumeric() => Some(c),
+        _ => None,
+    }
+}
+
+fn draw_prompt(canvas: &mut graphics::Canvas, message: &str) {
+    canvas.draw(
+        &graphics::Text::new(message),
+        DrawParam::default().dest(Vec2::new(
+            WINDOW_WIDTH / 2.0 - 110.0,
+            WINDOW_HEIGHT / 2.0,
+        )),
+    );
+}
+
+// 光晕：在 bounds 外面叠几层越来越大、越来越淡的矩形，passes 为 0 时不画
+fn draw_glow(ctx: &mut Context, canvas: &mut graphics::Canvas, bounds: Rect, color: Color, passes: u32) -> GameResult<()> {
+    for pass in 1..=passes {
+        let grow = pass as f32 * 4.0;
+        let rect = Rect::new(bounds.x - grow, bounds.y - grow, bounds.w + grow * 2.0, bounds.h + grow * 2.0);
+        let glow = Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), rect, Color { a: color.a / pass as f32, ..color })?;
+        canvas.draw(&glow, DrawParam::default());
+    }
+    Ok(())
+}
+
+// 命令行参数
+struct LaunchOptions {
+    portable: bool,  // 把存档和设置保存在可执行文件旁边
+    seed: Option<u64>,  // 第一局使用的随机种子
+    code: Option<String>,  // 启动后直接按这个分享码开始
+    soak: Option<f32>,  // 不开窗口，让自动驾驶连续游玩这么多分钟
+    bench: Option<usize>,  // 不开窗口，用这么多颗子弹跑基准测试
+    resources: Option<PathBuf>,  // 从这个目录读取音乐和音效
+}
+
+impl LaunchOptions {
+    fn from_args() -> Self {
+        let mut options = Self {
+            portable: false,
+            seed: None,
+            code: None,
+            soak: None,
+            bench: None,
+            resources: None,
+        };
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--portable" => options.portable = true,
+                "--seed" => options.seed = args.next().and_then(|seed| seed.parse().ok()),
+                "--code" => options.code = args.next(),
+                "--soak" => options.soak = args.next().and_then(|minutes| minutes.parse().ok()),
+                "--resources" => options.resources = args.next().map(PathBuf::from),
+                "--bench" => options.bench = Some(args.next().and_then(|bullets| bullets.parse().ok()).unwrap_or(2000)),
+                _ => (),
+            }
+        }
+        options
+    }
 }
 
 fn main() -> GameResult {
-    let (ctx, event_loop) = ggez::ContextBuilder::new("space_shooter", "luozijian1223")
-        .window_setup(WindowSetup::default().title("太空射击游戏"))
-        .window_mode(WindowMode::default().dimensions(WINDOW_WIDTH, WINDOW_HEIGHT))
-        .build()?;
+    let options = LaunchOptions::from_args();
+    if let Some(bullets) = options.bench {
+        print!("{}", bench::run(bullets));
+        return Ok(());
+    }
+    if let Some(minutes) = options.soak {
+        match soak::run(minutes) {
+            Ok(runs) => {
+                println!("浸泡测试通过: {} 分钟, 完成 {} 局", minutes, runs);
+                return Ok(());
+            }
+            Err(error) => {
+                eprintln!("浸泡测试失败: {}", error);
+                std::process::exit(1);
+            }
+        }
+    }
+    let (profile, storage_error) = Profile::load(options.portable);
 
-    let state = MainState::new();
-    event::run(ctx, event_loop, state)
+    let mut builder = ggez::ContextBuilder::new("space_shooter", "luozijian1223")
+        .window_setup(WindowSetup::default().title(display::GAME_TITLE))
+        .window_mode(display::window_mode(&profile.settings.window));
+    let resource_dir = match ResourceHints::from_env(options.resources).resolve() {
+        Ok(dir) => dir,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+    match &resource_dir {
+        Some(dir) => builder = builder.add_resource_path(dir),
+        None if !assets::EMBEDDED => eprintln!("找不到资源目录，游戏将没有音乐和音效"),
+        None => (),
+    }
+    let (ctx, event_loop) = builder.build()?;
+    display::restore_placement(&ctx, &profile.settings.window);
+
+    let mut state = MainState::new(profile);
+    state.storage_error = storage_error;
+    if !state.profile.restored.is_empty() {
+        state.storage_notice = Some(format!("{}存档已损坏，已恢复为上一次完好的备份", state.profile.restored.join("、")));
+    }
+    if let Some(storage) = &state.profile.storage {
+        let (content, errors) = Registry::with_mods(&storage.mods_dir());
+        state.content = content;
+        if !errors.is_empty() && state.storage_notice.is_none() {
+            state.storage_notice = Some(format!("以下 mods 没有读入: {}", errors.join("；")));
+        }
+    }
+    state.next_seed = options.seed;
+    state.loader = Some(AssetLoader::start(resource_dir.clone(), audio::asset_paths()));
+    state.resource_dir = resource_dir;
+    state.tip = menu::LOADING_TIPS[rand::thread_rng().gen_range(0..menu::LOADING_TIPS.len())];
+    let mut then = Some(Box::new(state.title_menu()));
+    if let Some(code) = options.code {
+        match state.start_shared_run(&code) {
+            Ok(()) => then = None,
+            Err(error) => eprintln!("{}", error),
+        }
+    }
+    state.overlay = Some(Overlay::Loading { then });
+    run(ctx, event_loop, state)
 }
\ No newline at end of file
@@ -0,0 +1,85 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+// 包一层系统分配器，统计分配次数和字节数；调试构建时在 main.rs 里注册为全局分配器，
+// 发布构建不注册，计数一直为 0
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    // 扩容也算一次分配，热循环里的 Vec 增长同样能看出来
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+            record_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
+fn record_alloc(size: usize) {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    ALLOCATED_BYTES.fetch_add(size, Ordering::Relaxed);
+    LIVE_BYTES.fetch_add(size, Ordering::Relaxed);
+}
+
+// 一帧之内的内存使用情况
+#[derive(Clone, Copy, Default)]
+pub struct FrameMemory {
+    pub allocations: usize,  // 这一帧的分配次数
+    pub allocated_bytes: usize,  // 这一帧新分配的字节数
+    pub live_bytes: usize,  // 当前仍未释放的字节数
+}
+
+// 每帧调用一次 frame，和上一帧的计数相减得到这一帧的分配量
+#[derive(Default)]
+pub struct MemoryMeter {
+    allocations: usize,
+    allocated_bytes: usize,
+    pub last: FrameMemory,
+}
+
+impl MemoryMeter {
+    // 没有注册计数分配器时（发布构建）统计不可用
+    pub fn available() -> bool {
+        ALLOCATIONS.load(Ordering::Relaxed) > 0
+    }
+
+    pub fn frame(&mut self) -> FrameMemory {
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+        let allocated_bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+        self.last = FrameMemory {
+            allocations: allocations - self.allocations,
+            allocated_bytes: allocated_bytes - self.allocated_bytes,
+            live_bytes: LIVE_BYTES.load(Ordering::Relaxed),
+        };
+        self.allocations = allocations;
+        self.allocated_bytes = allocated_bytes;
+        self.last
+    }
+}
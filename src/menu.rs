@@ -0,0 +1,263 @@
+use ggez::{
+    glam::Vec2,
+    graphics::{self, Canvas, Color, DrawParam, Mesh, Rect},
+    input::keyboard::KeyCode,
+    Context, GameResult,
+};
+
+use crate::bindings::{self, Action, Bindings};
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+
+pub enum MenuInput {
+    None,
+    Select(usize),
+    Back,
+}
+
+// 上下键选择、回车确认、Esc 返回的简单列表菜单
+pub struct Menu {
+    pub items: Vec<String>,
+    pub selected: usize,
+}
+
+impl Menu {
+    pub fn new<S: Into<String>>(items: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            items: items.into_iter().map(Into::into).collect(),
+            selected: 0,
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyCode) -> MenuInput {
+        if self.items.is_empty() {
+            return MenuInput::Back;
+        }
+        match key {
+            KeyCode::Up => {
+                self.selected = (self.selected + self.items.len() - 1) % self.items.len();
+                MenuInput::None
+            }
+            KeyCode::Down => {
+                self.selected = (self.selected + 1) % self.items.len();
+                MenuInput::None
+            }
+            KeyCode::Return | KeyCode::NumpadEnter => MenuInput::Select(self.selected),
+            KeyCode::Escape => MenuInput::Back,
+            _ => MenuInput::None,
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas, title: &str) -> GameResult<()> {
+        draw_backdrop(ctx, canvas)?;
+
+        let title_text = graphics::Text::new(title);
+        canvas.draw(
+            &title_text,
+            DrawParam::default().dest(Vec2::new(WINDOW_WIDTH / 2.0 - 100.0, 150.0)),
+        );
+
+        // 选项多时行距缩小，最后一行不超出窗口底部
+        let spacing = ((WINDOW_HEIGHT - 240.0) / self.items.len() as f32).min(30.0);
+        for (i, item) in self.items.iter().enumerate() {
+            let (label, color) = if i == self.selected {
+                (format!("> {}", item), Color::YELLOW)
+            } else {
+                (format!("  {}", item), Color::WHITE)
+            };
+            canvas.draw(
+                &graphics::Text::new(label),
+                DrawParam::default()
+                    .dest(Vec2::new(WINDOW_WIDTH / 2.0 - 100.0, 200.0 + i as f32 * spacing))
+                    .color(color),
+            );
+        }
+        Ok(())
+    }
+}
+
+// 半透明遮罩，让菜单盖在游戏画面上
+pub fn draw_backdrop(ctx: &mut Context, canvas: &mut Canvas) -> GameResult<()> {
+    let backdrop = Mesh::new_rectangle(
+        ctx,
+        graphics::DrawMode::fill(),
+        Rect::new(0.0, 0.0, WINDOW_WIDTH, WINDOW_HEIGHT),
+        Color::new(0.0, 0.0, 0.0, 0.7),
+    )?;
+    canvas.draw(&backdrop, DrawParam::default());
+    Ok(())
+}
+
+pub enum BindingsResult {
+    Stay,
+    Changed,  // 绑定有变化，需要保存设置
+    Back,
+}
+
+// 按键设置界面：选择操作后按下新按键完成绑定
+pub struct BindingsMenu {
+    menu: Menu,
+    capturing: Option<Action>,
+    message: Option<String>,
+}
+
+impl BindingsMenu {
+    pub fn new(bindings: &Bindings) -> Self {
+        Self {
+            menu: Menu::new(Self::items(bindings)),
+            capturing: None,
+            message: None,
+        }
+    }
+
+    fn items(bindings: &Bindings) -> Vec<String> {
+        let mut items: Vec<String> = Action::ALL
+            .iter()
+            .map(|&action| format!("{}: {}", action.label(), bindings::key_name(bindings.key(action))))
+            .collect();
+        items.push("恢复默认".to_string());
+        items.push("返回".to_string());
+        items
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.capturing.is_some()
+    }
+
+    pub fn handle_key(&mut self, key: KeyCode, bindings: &mut Bindings) -> BindingsResult {
+        // 等待玩家按下新按键，退格键取消
+        if let Some(action) = self.capturing {
+            if key == KeyCode::Back {
+                self.capturing = None;
+                self.message = None;
+                return BindingsResult::Stay;
+            }
+            if !bindings::is_bindable(key) {
+                self.message = Some(format!("{} 不能被绑定", bindings::key_name(key)));
+                return BindingsResult::Stay;
+            }
+            return match bindings.rebind(action, key) {
+                Ok(()) => {
+                    self.capturing = None;
+                    self.message = None;
+                    self.menu.items = Self::items(bindings);
+                    BindingsResult::Changed
+                }
+                Err(other) => {
+                    self.message = Some(format!(
+                        "{} 已被「{}」使用，请换一个按键",
+                        bindings::key_name(key),
+                        other.label()
+                    ));
+                    BindingsResult::Stay
+                }
+            };
+        }
+
+        match self.menu.handle_key(key) {
+            MenuInput::Select(i) if i < Action::ALL.len() => {
+                self.capturing = Some(Action::ALL[i]);
+                self.message = None;
+                BindingsResult::Stay
+            }
+            MenuInput::Select(i) if i == Action::ALL.len() => {
+                *bindings = Bindings::default();
+                self.menu.items = Self::items(bindings);
+                self.message = Some("已恢复默认按键".to_string());
+                BindingsResult::Changed
+            }
+            MenuInput::Select(_) | MenuInput::Back => BindingsResult::Back,
+            MenuInput::None => BindingsResult::Stay,
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult<()> {
+        self.menu.draw(ctx, canvas, "按键设置")?;
+
+        let hint = match (self.capturing, &self.message) {
+            (Some(action), None) => format!("请按下「{}」的新按键 (退格键取消)", action.label()),
+            (_, Some(message)) => message.clone(),
+            (None, None) => "回车键修改，Esc 返回".to_string(),
+        };
+        canvas.draw(
+            &graphics::Text::new(hint),
+            DrawParam::default().dest(Vec2::new(WINDOW_WIDTH / 2.0 - 180.0, WINDOW_HEIGHT - 40.0)),
+        );
+        Ok(())
+    }
+}
+
+// 操作说明：按当前的绑定画出每个操作对应的键盘按键和手柄按键，改键之后自动更新
+pub fn draw_controls(ctx: &mut Context, canvas: &mut Canvas, bindings: &Bindings) -> GameResult<()> {
+    draw_backdrop(ctx, canvas)?;
+    canvas.draw(
+        &graphics::Text::new("操作说明"),
+        DrawParam::default().dest(Vec2::new(WINDOW_WIDTH / 2.0 - 40.0, 80.0)),
+    );
+    let header = Color::new(0.7, 0.7, 0.7, 1.0);
+    for (x, title) in [(320.0, "键盘"), (480.0, "手柄")] {
+        canvas.draw(&graphics::Text::new(title), DrawParam::default().dest(Vec2::new(x, 140.0)).color(header));
+    }
+
+    for (row, action) in Action::ALL.into_iter().enumerate() {
+        let y = 165.0 + row as f32 * 35.0;
+        canvas.draw(&graphics::Text::new(action.label()), DrawParam::default().dest(Vec2::new(140.0, y + 8.0)));
+        let mut x = 320.0;
+        x += draw_keycap(ctx, canvas, Vec2::new(x, y), &bindings::key_name(bindings.key(action)))?;
+        x = x.max(480.0);
+        for button in bindings::gamepad_buttons(action) {
+            x += draw_keycap(ctx, canvas, Vec2::new(x, y), &bindings::button_name(button))?;
+        }
+    }
+    canvas.draw(
+        &graphics::Text::new("手柄的左摇杆也可以移动    在「按键设置」中修改键盘按键    Esc 返回"),
+        DrawParam::default().dest(Vec2::new(140.0, WINDOW_HEIGHT - 80.0)).color(header),
+    );
+    Ok(())
+}
+
+// 带边框的按键，返回占用的宽度
+fn draw_keycap(ctx: &mut Context, canvas: &mut Canvas, origin: Vec2, name: &str) -> GameResult<f32> {
+    let text = graphics::Text::new(name);
+    let size = text.measure(ctx)?;
+    let width = size.x + 20.0;
+    let cap = Mesh::new_rounded_rectangle(
+        ctx,
+        graphics::DrawMode::stroke(2.0),
+        Rect::new(origin.x, origin.y, width, 34.0),
+        6.0,
+        Color::WHITE,
+    )?;
+    canvas.draw(&cap, DrawParam::default());
+    canvas.draw(&text, DrawParam::default().dest(Vec2::new(origin.x + 10.0, origin.y + 8.0)));
+    Ok(width + 10.0)
+}
+
+// 加载画面上轮流显示的小提示
+pub const LOADING_TIPS: [&str; 5] = [
+    "按住开火键可以连续射击",
+    "炸弹可以一次清场，留到危急的时候再用",
+    "小行星被击碎后会分裂成更小的碎块",
+    "战役里击落敌人掉落的金币可以在波次之间的商店里花掉",
+    "在设置文件里可以关掉切到后台时的自动暂停",
+];
+
+// 加载画面：进度条和一条提示
+pub fn draw_loading(ctx: &mut Context, canvas: &mut Canvas, progress: f32, tip: &str) -> GameResult<()> {
+    let bar = Rect::new(WINDOW_WIDTH / 2.0 - 200.0, WINDOW_HEIGHT / 2.0, 400.0, 16.0);
+    canvas.draw(
+        &graphics::Text::new("加载中..."),
+        DrawParam::default().dest(Vec2::new(bar.x, bar.y - 30.0)),
+    );
+    let outline = Mesh::new_rectangle(ctx, graphics::DrawMode::stroke(2.0), bar, Color::WHITE)?;
+    canvas.draw(&outline, DrawParam::default());
+    if progress > 0.0 {
+        let filled = Rect::new(bar.x, bar.y, bar.w * progress.min(1.0), bar.h);
+        let fill = Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), filled, Color::WHITE)?;
+        canvas.draw(&fill, DrawParam::default());
+    }
+    canvas.draw(
+        &graphics::Text::new(format!("提示: {}", tip)),
+        DrawParam::default().dest(Vec2::new(bar.x, bar.y + 40.0)).color(Color::new(0.7, 0.7, 0.7, 1.0)),
+    );
+    Ok(())
+}
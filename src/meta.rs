@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+pub const CREDIT_SCORE: u32 = 500;  // 每得多少分换 1 点积分
+const SPEED_STEP: f32 = 0.05;  // 每级机动强化让移动速度加快的比例
+
+// 标题菜单「机体强化」里用积分购买的永久强化，每局开始时生效
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MetaUpgrade {
+    Lives,   // 开局多 1 条命
+    Damage,  // 所有武器的基础伤害加 1
+    Speed,   // 移动速度加快
+}
+
+impl MetaUpgrade {
+    pub const ALL: [MetaUpgrade; 3] = [MetaUpgrade::Lives, MetaUpgrade::Damage, MetaUpgrade::Speed];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MetaUpgrade::Lives => "初始生命",
+            MetaUpgrade::Damage => "基础伤害",
+            MetaUpgrade::Speed => "机动性能",
+        }
+    }
+
+    pub fn max_level(self) -> u32 {
+        match self {
+            MetaUpgrade::Lives => 2,
+            MetaUpgrade::Damage => 2,
+            MetaUpgrade::Speed => 4,
+        }
+    }
+
+    // 从 level 升到下一级的价格，伤害最贵
+    pub fn price(self, level: u32) -> u32 {
+        let base = match self {
+            MetaUpgrade::Lives => 20,
+            MetaUpgrade::Damage => 40,
+            MetaUpgrade::Speed => 10,
+        };
+        base * (level + 1)
+    }
+}
+
+// 已经买下的永久强化，保存在解锁进度中
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(default)]
+pub struct MetaUpgrades {
+    pub lives: u32,
+    pub damage: u32,
+    pub speed: u32,
+}
+
+impl MetaUpgrades {
+    pub fn level(&self, upgrade: MetaUpgrade) -> u32 {
+        match upgrade {
+            MetaUpgrade::Lives => self.lives,
+            MetaUpgrade::Damage => self.damage,
+            MetaUpgrade::Speed => self.speed,
+        }
+    }
+
+    // 用 credits 中的积分升一级，返回花掉的积分；到了上限或积分不够时返回原因
+    pub fn buy(&mut self, upgrade: MetaUpgrade, credits: &mut u32) -> Result<u32, &'static str> {
+        let level = self.level(upgrade);
+        if level >= upgrade.max_level() {
+            return Err("已经到上限了");
+        }
+        let price = upgrade.price(level);
+        if *credits < price {
+            return Err("积分不够");
+        }
+        *credits -= price;
+        match upgrade {
+            MetaUpgrade::Lives => self.lives += 1,
+            MetaUpgrade::Damage => self.damage += 1,
+            MetaUpgrade::Speed => self.speed += 1,
+        }
+        Ok(price)
+    }
+
+    // 移动速度的倍数
+    pub fn speed_factor(&self) -> f32 {
+        1.0 + self.speed as f32 * SPEED_STEP
+    }
+}
+
+// 一局得到的积分，按分数折算
+pub fn credits_for(score: u32) -> u32 {
+    score / CREDIT_SCORE
+}
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub const MILESTONE_WAVES: u32 = 10;  // 无尽模式每隔这么多波记一个里程碑
+
+// 无尽模式的里程碑：第 10、20……波开始时的状态，存进解锁进度，
+// 之后可以在标题菜单从这里开始一局不计分的练习。
+// 随机数没法存盘，练习局按种子和波次重新播种，敌人的分布和原来那一局不同
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct Milestone {
+    pub wave: u32,
+    #[serde(with = "hex_seed")]
+    pub seed: u64,  // 原来那一局的种子
+    pub score: u32,  // 练习局从这个分数继续，头目和奖励生命的分数线才对得上
+    pub lives: u32,
+    pub weapon_level: u32,
+    pub bombs: u32,
+    pub run_time: f32,  // 难度曲线按它查询
+    pub enemies_spawned: u32,
+    pub carriers_spawned: u32,
+    pub bosses_defeated: u32,
+    pub next_boss_score: u32,
+    pub next_life_score: u32,
+}
+
+// 是否轮到记里程碑，第 1 波不算
+pub fn due(wave: u32) -> bool {
+    wave > 1 && wave.is_multiple_of(MILESTONE_WAVES)
+}
+
+impl Milestone {
+    // 标题菜单里显示的一行
+    pub fn label(&self) -> String {
+        format!("第 {} 波  生命 {}  火力 {}  炸弹 {}", self.wave, self.lives, self.weapon_level, self.bombs)
+    }
+}
+
+// TOML 容不下大于 i64 的整数，种子按十六进制字符串保存
+mod hex_seed {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(seed: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{:016x}", seed))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        u64::from_str_radix(&text, 16).map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,197 @@
+use crate::campaign::CampaignData;
+use crate::randomizer::{self, RandomizerParams};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::scoring::{Classic, Daily, ScoringRules, TimeAttack};
+use crate::storage::Progress;
+use crate::world::World;
+
+pub const TIME_ATTACK_LIMIT: f32 = 180.0;  // 计时赛一局的秒数
+pub const VERSUS_LIMIT: f32 = 180.0;  // 双人对战一局的秒数，时间到了比分数
+
+// 一种模式的玩法：敌人从哪里来、怎么计分、什么时候结束、HUD 上多显示什么。
+// World 和标题菜单只通过它区分模式，加一种新模式只要多写一个实现
+pub trait ModeRules {
+    // 按关卡数据推进时返回这一局的关卡，返回 None 时按难度曲线不断生成敌人
+    fn campaign(&self, seed: u64) -> Option<CampaignData>;
+
+    // 分数达到阈值时出现头目，只有无尽模式这样做
+    fn bosses_by_score(&self) -> bool {
+        false
+    }
+
+    // 每隔几波记一个里程碑，之后可以从那里练习，只有无尽模式这样做
+    fn milestones(&self) -> bool {
+        false
+    }
+
+    fn scoring(&self) -> Box<dyn ScoringRules> {
+        Box::new(Classic)
+    }
+
+    // 生命耗尽和通关之外的结束条件，返回 true 时这一局照常结算
+    fn finished(&self, _world: &World) -> bool {
+        false
+    }
+
+    // 右上角额外显示的一行，例如剩余时间
+    fn hud_extra(&self, _world: &World) -> Option<String> {
+        None
+    }
+
+    // 这个模式固定使用的种子，返回 None 时每局随机。分享码、--seed 和重新开始指定的种子优先
+    fn seed(&self) -> Option<u64> {
+        None
+    }
+
+    // 右上角是否显示这一局的种子，种子决定了关卡内容的模式才有分享的意义
+    fn shows_seed(&self) -> bool {
+        false
+    }
+
+    // 同屏的飞船数，两架时第二架由第二名玩家操作，各自计分
+    fn players(&self) -> u32 {
+        1
+    }
+
+    // 标题菜单里是否列出，分享码也不能绕过
+    fn unlocked(&self, _progress: &Progress) -> bool {
+        true
+    }
+}
+
+pub struct EndlessMode;
+
+impl ModeRules for EndlessMode {
+    fn campaign(&self, _seed: u64) -> Option<CampaignData> {
+        None
+    }
+
+    fn bosses_by_score(&self) -> bool {
+        true
+    }
+
+    fn milestones(&self) -> bool {
+        true
+    }
+}
+
+pub struct CampaignMode;
+
+impl ModeRules for CampaignMode {
+    fn campaign(&self, _seed: u64) -> Option<CampaignData> {
+        Some(CampaignData::load())
+    }
+}
+
+// 通关战役后解锁
+pub struct NewGamePlusMode;
+
+impl ModeRules for NewGamePlusMode {
+    fn campaign(&self, _seed: u64) -> Option<CampaignData> {
+        Some(CampaignData::load().new_game_plus())
+    }
+
+    fn unlocked(&self, progress: &Progress) -> bool {
+        progress.campaign_cleared
+    }
+}
+
+pub struct RandomizerMode;
+
+impl ModeRules for RandomizerMode {
+    fn campaign(&self, seed: u64) -> Option<CampaignData> {
+        Some(randomizer::generate(&RandomizerParams::load(), seed))
+    }
+
+    fn shows_seed(&self) -> bool {
+        true
+    }
+}
+
+// 计时赛：敌人和无尽模式一样不断出现，但没有头目，时间到了就结算，用计时赛的计分规则
+pub struct TimeAttackMode;
+
+impl ModeRules for TimeAttackMode {
+    fn campaign(&self, _seed: u64) -> Option<CampaignData> {
+        None
+    }
+
+    fn scoring(&self) -> Box<dyn ScoringRules> {
+        Box::new(TimeAttack)
+    }
+
+    fn finished(&self, world: &World) -> bool {
+        world.run_time >= TIME_ATTACK_LIMIT
+    }
+
+    fn hud_extra(&self, world: &World) -> Option<String> {
+        let remaining = (TIME_ATTACK_LIMIT - world.run_time).max(0.0).ceil() as u32;
+        Some(format!("剩余时间 {}:{:02}", remaining / 60, remaining % 60))
+    }
+}
+
+// 头目车轮战：战役的各关去掉所有波次，只剩头目一个接一个登场，通关战役后解锁
+pub struct BossRushMode;
+
+impl ModeRules for BossRushMode {
+    fn campaign(&self, _seed: u64) -> Option<CampaignData> {
+        Some(CampaignData::load().boss_rush())
+    }
+
+    fn hud_extra(&self, world: &World) -> Option<String> {
+        let campaign = world.campaign.as_ref()?;
+        Some(format!("头目 {}/{}", campaign.stage() + 1, campaign.stages()))
+    }
+
+    fn unlocked(&self, progress: &Progress) -> bool {
+        progress.campaign_cleared
+    }
+}
+
+// 双人对战：两名玩家各开一架飞船，敌人和无尽模式一样不断出现，但没有头目和道具。
+// 先被击落的一方输，时间到了比分数
+pub struct VersusMode;
+
+impl ModeRules for VersusMode {
+    fn campaign(&self, _seed: u64) -> Option<CampaignData> {
+        None
+    }
+
+    fn finished(&self, world: &World) -> bool {
+        world.run_time >= VERSUS_LIMIT
+    }
+
+    // 第一名玩家的分数和生命照常显示在左上角，这里显示第二名玩家的
+    fn hud_extra(&self, world: &World) -> Option<String> {
+        let rival = world.rival.as_ref()?;
+        let remaining = (VERSUS_LIMIT - world.run_time).max(0.0).ceil() as u32;
+        Some(format!("2P {}  生命 {}  剩余 {}:{:02}", rival.score, rival.player.lives, remaining / 60, remaining % 60))
+    }
+
+    fn players(&self) -> u32 {
+        2
+    }
+}
+
+// 每日挑战：当天所有人打同一套随机波次关卡，过了零点（UTC）换一套，用每日挑战的计分规则
+pub struct DailyMode;
+
+impl ModeRules for DailyMode {
+    fn campaign(&self, seed: u64) -> Option<CampaignData> {
+        Some(randomizer::generate(&RandomizerParams::load(), seed))
+    }
+
+    fn scoring(&self) -> Box<dyn ScoringRules> {
+        Box::new(Daily)
+    }
+
+    fn seed(&self) -> Option<u64> {
+        Some(today())
+    }
+}
+
+// 从 1970 年 1 月 1 日（UTC）算起的天数，每日挑战用它当种子
+pub fn today() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs() / 86_400)
+}
@@ -0,0 +1,29 @@
+use ggez::Context;
+#[cfg(feature = "platform")]
+use ggez::winit::window::UserAttentionType;
+
+// 需要把玩家叫回游戏的情况
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Attention {
+    BossDefeated,  // 提醒一下就好
+    AutoPaused,    // 游戏在等玩家回来
+}
+
+// 窗口在后台时请求系统提醒玩家：Windows 上任务栏图标闪烁，macOS 上程序坞图标跳动，
+// Linux 上由窗口管理器决定。窗口在前台或没有启用 platform 特性时什么也不做
+pub fn request_attention(ctx: &Context, attention: Attention) {
+    #[cfg(feature = "platform")]
+    {
+        let window = ctx.gfx.window();
+        if window.has_focus() {
+            return;
+        }
+        let kind = match attention {
+            Attention::BossDefeated => UserAttentionType::Informational,
+            Attention::AutoPaused => UserAttentionType::Critical,
+        };
+        window.request_user_attention(Some(kind));
+    }
+    #[cfg(not(feature = "platform"))]
+    let _ = (ctx, attention);
+}
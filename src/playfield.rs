@@ -0,0 +1,220 @@
+use std::ops::Range;
+
+use ggez::graphics::Rect;
+use serde::{Deserialize, Serialize};
+
+use crate::{GameObject, WINDOW_HEIGHT, WINDOW_WIDTH};
+
+// 玩家碰到左右边缘时的处理方式
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeMode {
+    #[default]
+    Clamp,  // 停在边缘
+    Wrap,   // 从一侧飞出，从另一侧进入
+}
+
+// 物体越过某一条边时的处理方式
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EdgeRule {
+    Ignore,   // 不管，比如敌人从屏幕上方进入
+    Despawn,  // 中心越过边缘一段距离后删除
+    Bounce,   // 调头，并退回到边缘以内
+    Clamp,    // 停在边缘
+}
+
+// 每种物体各自的边界规则，取代原来写死在各处的判断
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Boundary {
+    pub sides: EdgeRule,
+    pub top: EdgeRule,
+    pub bottom: EdgeRule,
+}
+
+impl Boundary {
+    // 普通敌人：从上方进入，碰到左右边缘调头，飞出底部后删除
+    pub const ENEMY: Boundary = Boundary {
+        sides: EdgeRule::Bounce,
+        top: EdgeRule::Ignore,
+        bottom: EdgeRule::Despawn,
+    };
+
+    // 自爆机：冲刺时可能斜着飞出去，从左右两边或底部离开都删除
+    pub const KAMIKAZE: Boundary = Boundary {
+        sides: EdgeRule::Despawn,
+        top: EdgeRule::Ignore,
+        bottom: EdgeRule::Despawn,
+    };
+
+    // 逃走的悬赏目标：往上飞出顶部后删除，其他边缘和普通敌人一样
+    pub const FLEEING: Boundary = Boundary {
+        sides: EdgeRule::Bounce,
+        top: EdgeRule::Despawn,
+        bottom: EdgeRule::Despawn,
+    };
+
+    // 不受边缘限制，位置完全由路线决定，编队敌机使用
+    pub const FREE: Boundary = Boundary {
+        sides: EdgeRule::Ignore,
+        top: EdgeRule::Ignore,
+        bottom: EdgeRule::Ignore,
+    };
+
+    // 整个机身都留在指定区域内，头目使用
+    pub const CONFINED: Boundary = Boundary {
+        sides: EdgeRule::Clamp,
+        top: EdgeRule::Clamp,
+        bottom: EdgeRule::Clamp,
+    };
+
+    // 按规则把物体限制在 area 内，返回是否应当删除。
+    // 调头和停下按机身边缘判断，删除按中心越过边缘 despawn 距离判断
+    pub fn apply(&self, object: &mut GameObject, area: Rect, despawn: f32) -> bool {
+        let half = object.size / 2.0;
+        let (min_x, max_x) = (area.left() + half.x, (area.right() - half.x).max(area.left() + half.x));
+        let (min_y, max_y) = (area.top() + half.y, (area.bottom() - half.y).max(area.top() + half.y));
+
+        let mut removed = false;
+        if object.position.x < min_x {
+            removed |= Self::cross(self.sides, &mut object.position.x, &mut object.velocity.x, min_x, 1.0, area.left() - despawn);
+        } else if object.position.x > max_x {
+            removed |= Self::cross(self.sides, &mut object.position.x, &mut object.velocity.x, max_x, -1.0, area.right() + despawn);
+        }
+        if object.position.y < min_y {
+            removed |= Self::cross(self.top, &mut object.position.y, &mut object.velocity.y, min_y, 1.0, area.top() - despawn);
+        } else if object.position.y > max_y {
+            removed |= Self::cross(self.bottom, &mut object.position.y, &mut object.velocity.y, max_y, -1.0, area.bottom() + despawn);
+        }
+        removed
+    }
+
+    // 处理一个坐标轴上越过的一条边，inward 是朝场地内的方向，limit 是删除线
+    fn cross(rule: EdgeRule, position: &mut f32, velocity: &mut f32, edge: f32, inward: f32, limit: f32) -> bool {
+        match rule {
+            EdgeRule::Ignore => false,
+            EdgeRule::Despawn => (*position - limit) * inward < 0.0,
+            EdgeRule::Bounce => {
+                *velocity = velocity.abs() * inward;
+                *position = edge;
+                false
+            }
+            EdgeRule::Clamp => {
+                *position = edge;
+                false
+            }
+        }
+    }
+}
+
+// 游戏区域的各种边界：物体从哪里生成、飞出多远后删除、玩家能移动到哪里、
+// 界面文字占用哪些地方。生成、限位和删除都从这里取值，不再各自写死数字
+#[derive(Clone, Copy)]
+pub struct PlayField {
+    pub width: f32,
+    pub height: f32,
+    pub spawn_gap: f32,  // 新生成的物体离左右和上边缘至少留出的空隙
+    pub enemy_despawn: f32,  // 敌人飞出底部这么远后删除
+    pub bullet_despawn: f32,  // 子弹飞出屏幕这么远后删除
+    pub player_inset: f32,  // 玩家离左右边缘至少保持的距离，穿越边缘时不起作用
+    pub player_edges: EdgeMode,
+    pub player_y: f32,  // 玩家开局时所在的高度
+    pub player_top: f32,  // 玩家能飞到的最高处，只能在屏幕下方三分之一内上下移动
+    pub hud_top: f32,  // 顶部留给分数、生命和头目血条的高度
+    pub hud_bottom: f32,  // 底部留给提示文字的高度
+}
+
+impl Default for PlayField {
+    fn default() -> Self {
+        Self {
+            width: WINDOW_WIDTH,
+            height: WINDOW_HEIGHT,
+            spawn_gap: 5.0,
+            enemy_despawn: 15.0,
+            bullet_despawn: 10.0,
+            player_inset: 0.0,
+            player_edges: EdgeMode::Clamp,
+            player_y: WINDOW_HEIGHT - 50.0,
+            player_top: WINDOW_HEIGHT * 2.0 / 3.0,
+            hud_top: 70.0,
+            hud_bottom: 40.0,
+        }
+    }
+}
+
+impl PlayField {
+    // 宽度为 2 * half_width 的物体可以生成的中心横坐标，物体太宽时只剩中间一点
+    pub fn spawn_x(&self, half_width: f32) -> Range<f32> {
+        let min = half_width + self.spawn_gap;
+        let max = self.width - half_width - self.spawn_gap;
+        min..max.max(min + 1.0)
+    }
+
+    // 高度为 height 的物体完全藏在屏幕上方时的中心纵坐标
+    pub fn spawn_y(&self, height: f32) -> f32 {
+        -(height / 2.0 + self.spawn_gap)
+    }
+
+    // 玩家能到达的范围：整个宽度、屏幕下方三分之一
+    pub fn player_area(&self) -> Rect {
+        Rect::new(0.0, self.player_top, self.width, self.height - self.player_top)
+    }
+
+    // 移动之后把玩家放回场地内
+    pub fn confine_player(&self, object: &mut GameObject) {
+        match self.player_edges {
+            EdgeMode::Clamp => object.clamp_x(self.player_inset, self.width - self.player_inset),
+            EdgeMode::Wrap => object.position.x = object.position.x.rem_euclid(self.width),
+        }
+        object.clamp_y(self.player_top, self.height);
+    }
+
+    // 玩家占据的区域。穿越边缘时机身被分成两半，伸出边缘的部分出现在另一侧，
+    // 两边都要参与碰撞和绘制
+    pub fn player_bounds(&self, object: &GameObject) -> (Rect, Option<Rect>) {
+        let bounds = object.bounds();
+        if self.player_edges == EdgeMode::Clamp {
+            return (bounds, None);
+        }
+        let mut ghost = bounds;
+        if bounds.left() < 0.0 {
+            ghost.x += self.width;
+        } else if bounds.right() > self.width {
+            ghost.x -= self.width;
+        } else {
+            return (bounds, None);
+        }
+        (bounds, Some(ghost))
+    }
+
+    pub fn player_overlaps(&self, object: &GameObject, other: &Rect) -> bool {
+        let (bounds, ghost) = self.player_bounds(object);
+        bounds.overlaps(other) || ghost.is_some_and(|ghost| ghost.overlaps(other))
+    }
+
+    // 飞船外面再大一圈 margin 的擦弹框碰到 other，出了边缘的分身也算
+    pub fn player_grazes(&self, object: &GameObject, other: &Rect, margin: f32) -> bool {
+        let grow = |rect: Rect| Rect::new(rect.x - margin, rect.y - margin, rect.w + margin * 2.0, rect.h + margin * 2.0);
+        let (bounds, ghost) = self.player_bounds(object);
+        grow(bounds).overlaps(other) || ghost.is_some_and(|ghost| grow(ghost).overlaps(other))
+    }
+
+    pub fn area(&self) -> Rect {
+        Rect::new(0.0, 0.0, self.width, self.height)
+    }
+
+    // 头目只在屏幕上方三分之一活动，给玩家留出躲避的空间
+    pub fn boss_arena(&self) -> Rect {
+        Rect::new(0.0, 0.0, self.width, self.height / 3.0)
+    }
+
+    // 子弹的中心离开这个区域时删除
+    pub fn bullet_area(&self) -> Rect {
+        let margin = self.bullet_despawn;
+        Rect::new(-margin, -margin, self.width + margin * 2.0, self.height + margin * 2.0)
+    }
+
+    // 不被界面文字遮挡的区域
+    pub fn safe_area(&self) -> Rect {
+        Rect::new(0.0, self.hud_top, self.width, self.height - self.hud_top - self.hud_bottom)
+    }
+}
@@ -0,0 +1,410 @@
+use ggez::{
+    glam::Vec2,
+    graphics::{self, Canvas, Color, DrawParam, Mesh, Rect},
+    Context, GameResult,
+};
+use rand::{rngs::StdRng, Rng};
+use serde::Deserialize;
+
+use crate::enemy::BountyBalance;
+use crate::weapon::{WeaponBalance, WeaponLevel};
+use crate::GameObject;
+
+// 平衡参数随程序一起打包
+const BALANCE_DATA: &str = include_str!("../resources/balance.toml");
+
+const POWERUP_SIZE: f32 = 20.0;
+const RAPID_FIRE_INTERVAL: f32 = 0.12;  // 连射期间自动开火的间隔
+const DRONE_FIRE_INTERVAL: f32 = 0.4;
+const GUNS_FIRE_INTERVAL: f32 = 0.3;
+pub const SPREAD_ANGLE: f32 = 0.25;  // 散射时两侧子弹偏离正前方的弧度
+const DRONE_OFFSET: Vec2 = Vec2::new(-35.0, 10.0);  // 僚机跟在玩家左后方
+pub const DRONE_SIZE: f32 = 12.0;
+pub const OPTION_RADIUS: f32 = 7.0;
+pub const OPTION_COLOR: Color = Color::new(1.0, 0.6, 0.2, 1.0);  // 分身和它的道具都是橙色
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Rarity {
+    Common,
+    Rare,
+    Epic,
+    Cursed,  // 不算稀有度，单独的一类，有好处也有代价
+}
+
+impl Rarity {
+    // 道具外圈光环的颜色，越稀有越醒目
+    fn aura(self) -> Color {
+        match self {
+            Rarity::Common => Color::new(0.6, 0.9, 0.6, 0.5),
+            Rarity::Rare => Color::new(0.3, 0.6, 1.0, 0.7),
+            Rarity::Epic => Color::new(0.8, 0.4, 1.0, 0.9),
+            Rarity::Cursed => Color::new(0.9, 0.0, 0.1, 0.9),
+        }
+    }
+
+    // 这一稀有度包含的道具，诅咒道具单独判断
+    fn kinds(self) -> &'static [PowerupKind] {
+        match self {
+            Rarity::Common => &[PowerupKind::RapidFire, PowerupKind::Spread, PowerupKind::Upgrade],
+            Rarity::Rare => &[
+                PowerupKind::Bomb,
+                PowerupKind::BombRefill,
+                PowerupKind::Guns,
+                PowerupKind::Freeze,
+                PowerupKind::Shield,
+                PowerupKind::Option,
+                PowerupKind::ComboKeeper,
+            ],
+            Rarity::Epic => &[PowerupKind::Drone, PowerupKind::Reflector, PowerupKind::ExtraLife],
+            Rarity::Cursed => &[PowerupKind::Berserk],
+        }
+    }
+
+    // 光环的层数
+    fn rings(self) -> u32 {
+        match self {
+            Rarity::Common => 1,
+            Rarity::Rare => 2,
+            Rarity::Epic | Rarity::Cursed => 3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PowerupKind {
+    RapidFire,  // 一段时间内自动连射
+    Spread,     // 一段时间内每次开火同时向两侧斜前方各多射一颗
+    Upgrade,    // 主炮火力升一级，一直保留到受伤
+    Shield,     // 一段时间内撞上敌人或头目不掉命
+    Bomb,       // 立即清除全场敌人
+    BombRefill, // 补充一颗炸弹，留着按 B 键自己用
+    Drone,      // 一段时间内有僚机跟随开火
+    Guns,       // 一段时间内向后方和两侧同时开火
+    Reflector,  // 一段时间内碰到的水雷会被弹回去打向头目
+    Freeze,     // 敌人和水雷停住几秒，玩家和子弹照常行动
+    ExtraLife,  // 生命加 1
+    Option,     // 多一个跟在身后的分身，玩家开火时一起开火，最多两个
+    ComboKeeper,  // 一段时间内连击不会因为时间掉级
+    Berserk,    // 诅咒：一段时间内伤害翻倍，但移动速度减半
+}
+
+impl PowerupKind {
+    pub const ALL: [PowerupKind; 14] = [
+        PowerupKind::RapidFire,
+        PowerupKind::Spread,
+        PowerupKind::Upgrade,
+        PowerupKind::Shield,
+        PowerupKind::Bomb,
+        PowerupKind::BombRefill,
+        PowerupKind::Drone,
+        PowerupKind::Guns,
+        PowerupKind::Reflector,
+        PowerupKind::Freeze,
+        PowerupKind::ExtraLife,
+        PowerupKind::Option,
+        PowerupKind::ComboKeeper,
+        PowerupKind::Berserk,
+    ];
+
+    // 内容注册表中的 ID
+    pub fn id(self) -> &'static str {
+        match self {
+            PowerupKind::RapidFire => "powerup.rapid_fire",
+            PowerupKind::Spread => "powerup.spread",
+            PowerupKind::Upgrade => "powerup.upgrade",
+            PowerupKind::Shield => "powerup.shield",
+            PowerupKind::Bomb => "powerup.bomb",
+            PowerupKind::BombRefill => "powerup.bomb_refill",
+            PowerupKind::Drone => "powerup.drone",
+            PowerupKind::Guns => "powerup.guns",
+            PowerupKind::Reflector => "powerup.reflector",
+            PowerupKind::Freeze => "powerup.freeze",
+            PowerupKind::ExtraLife => "powerup.extra_life",
+            PowerupKind::Option => "powerup.option",
+            PowerupKind::ComboKeeper => "powerup.combo_keeper",
+            PowerupKind::Berserk => "powerup.berserk",
+        }
+    }
+
+    pub fn rarity(self) -> Rarity {
+        match self {
+            PowerupKind::RapidFire | PowerupKind::Spread | PowerupKind::Upgrade => Rarity::Common,
+            PowerupKind::Bomb
+            | PowerupKind::BombRefill
+            | PowerupKind::Guns
+            | PowerupKind::Freeze
+            | PowerupKind::Shield
+            | PowerupKind::Option
+            | PowerupKind::ComboKeeper => Rarity::Rare,
+            PowerupKind::Drone | PowerupKind::Reflector | PowerupKind::ExtraLife => Rarity::Epic,
+            PowerupKind::Berserk => Rarity::Cursed,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PowerupKind::RapidFire => "连射",
+            PowerupKind::Spread => "散射",
+            PowerupKind::Upgrade => "火力升级",
+            PowerupKind::Shield => "护盾",
+            PowerupKind::Bomb => "炸弹",
+            PowerupKind::BombRefill => "炸弹补给",
+            PowerupKind::Drone => "僚机",
+            PowerupKind::Guns => "侧后炮",
+            PowerupKind::Reflector => "反射护盾",
+            PowerupKind::Freeze => "时间冻结",
+            PowerupKind::ExtraLife => "奖励生命",
+            PowerupKind::Option => "分身",
+            PowerupKind::ComboKeeper => "连击保持",
+            PowerupKind::Berserk => "狂暴 (诅咒: 伤害翻倍，速度减半)",
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            PowerupKind::RapidFire => Color::YELLOW,
+            PowerupKind::Spread => Color::new(1.0, 0.9, 0.5, 1.0),
+            PowerupKind::Upgrade => Color::new(1.0, 0.3, 0.2, 1.0),
+            PowerupKind::Shield => Color::new(0.2, 0.9, 0.9, 1.0),
+            PowerupKind::Bomb => Color::new(1.0, 0.5, 0.1, 1.0),
+            PowerupKind::BombRefill => Color::new(1.0, 0.75, 0.3, 1.0),
+            PowerupKind::Drone => Color::CYAN,
+            PowerupKind::Guns => Color::new(0.4, 1.0, 0.4, 1.0),
+            PowerupKind::Reflector => Color::new(0.7, 0.9, 1.0, 1.0),
+            PowerupKind::Freeze => Color::new(0.3, 0.5, 1.0, 1.0),
+            PowerupKind::ExtraLife => Color::new(1.0, 0.4, 0.7, 1.0),
+            PowerupKind::Option => OPTION_COLOR,
+            PowerupKind::ComboKeeper => Color::new(1.0, 1.0, 0.6, 1.0),
+            PowerupKind::Berserk => Color::new(0.25, 0.0, 0.3, 1.0),
+        }
+    }
+}
+
+// 某一稀有度的掉落权重，随难度等级线性变化
+#[derive(Deserialize, Clone, Copy)]
+pub struct TierWeight {
+    pub weight: f32,
+    pub per_rank: f32,
+}
+
+impl TierWeight {
+    fn at(self, rank: f32) -> f32 {
+        (self.weight + self.per_rank * rank).max(0.0)
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct PowerupBalance {
+    pub interval: f32,
+    pub fall_speed: f32,
+    pub rapid_fire_time: f32,
+    pub spread_time: f32,
+    pub shield_time: f32,
+    pub drone_time: f32,
+    pub guns_time: f32,
+    pub reflector_time: f32,
+    pub freeze_time: f32,
+    pub combo_keeper_time: f32,
+    pub cursed_chance: f32,
+    pub cursed_time: f32,
+    pub common: TierWeight,
+    pub rare: TierWeight,
+    pub epic: TierWeight,
+}
+
+impl PowerupBalance {
+    // 先判断是否掉落诅咒道具，否则按难度等级调整后的权重选出稀有度，
+    // 再从这一稀有度的道具中等概率选一种
+    pub fn roll(&self, rank: f32, rng: &mut StdRng) -> PowerupKind {
+        let rarity = if rng.gen::<f32>() < self.cursed_chance {
+            Rarity::Cursed
+        } else {
+            self.roll_rarity(rank, rng)
+        };
+        let kinds = rarity.kinds();
+        kinds[rng.gen_range(0..kinds.len())]
+    }
+
+    fn roll_rarity(&self, rank: f32, rng: &mut StdRng) -> Rarity {
+        let tiers = [
+            (Rarity::Common, self.common.at(rank)),
+            (Rarity::Rare, self.rare.at(rank)),
+            (Rarity::Epic, self.epic.at(rank)),
+        ];
+        let total: f32 = tiers.iter().map(|(_, weight)| weight).sum();
+        let mut pick = rng.gen_range(0.0..total.max(f32::EPSILON));
+        for (rarity, weight) in tiers {
+            if pick < weight {
+                return rarity;
+            }
+            pick -= weight;
+        }
+        Rarity::Common
+    }
+}
+
+// 按分数奖励生命
+#[derive(Deserialize, Clone, Copy)]
+pub struct ExtraLifeBalance {
+    pub score_interval: u32,  // 每得多少分奖励一条命，0 表示不奖励
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Balance {
+    pub powerups: PowerupBalance,
+    pub extra_life: ExtraLifeBalance,
+    pub bounty: BountyBalance,
+    pub weapons: WeaponBalance,
+    pub weapon_levels: Vec<WeaponLevel>,  // 从 1 级开始，共有几项就有几级
+}
+
+impl Balance {
+    pub fn load() -> Self {
+        let balance: Self = toml::from_str(BALANCE_DATA).expect("resources/balance.toml 格式错误");
+        assert!(!balance.weapon_levels.is_empty(), "resources/balance.toml 至少需要一级火力");
+        balance
+    }
+}
+
+pub struct Powerup {
+    pub game_object: GameObject,
+    pub kind: PowerupKind,
+}
+
+impl Powerup {
+    pub fn new(x: f32, y: f32, kind: PowerupKind, fall_speed: f32) -> Self {
+        let mut game_object = GameObject::new(x, y, POWERUP_SIZE, POWERUP_SIZE);
+        game_object.velocity.y = fall_speed;
+        Self { game_object, kind }
+    }
+
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult<()> {
+        let bounds = self.game_object.bounds();
+        let rarity = self.kind.rarity();
+        for ring in 1..=rarity.rings() {
+            let grow = ring as f32 * 4.0;
+            let aura = Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::stroke(2.0),
+                Rect::new(bounds.x - grow, bounds.y - grow, bounds.w + grow * 2.0, bounds.h + grow * 2.0),
+                rarity.aura(),
+            )?;
+            canvas.draw(&aura, DrawParam::default());
+        }
+        let body = Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), bounds, self.kind.color())?;
+        canvas.draw(&body, DrawParam::default());
+        // 诅咒道具画一个红叉，一眼就能和普通道具区分开
+        if rarity == Rarity::Cursed {
+            let diagonals = [
+                [Vec2::new(bounds.left(), bounds.top()), Vec2::new(bounds.right(), bounds.bottom())],
+                [Vec2::new(bounds.right(), bounds.top()), Vec2::new(bounds.left(), bounds.bottom())],
+            ];
+            for line in diagonals {
+                let cross = Mesh::new_line(ctx, &line, 2.0, Color::RED)?;
+                canvas.draw(&cross, DrawParam::default());
+            }
+        }
+        Ok(())
+    }
+}
+
+// 这一帧由道具自动发射的子弹
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct AutoFire {
+    pub rapid: bool,  // 玩家向前开火
+    pub drone: bool,  // 僚机开火
+    pub guns: bool,   // 侧后炮向后方和两侧开火
+}
+
+// 限时道具的剩余时间和各自的开火计时
+#[derive(Default)]
+pub struct Buffs {
+    pub rapid_fire: f32,
+    pub spread: f32,
+    pub shield: f32,
+    pub drone: f32,
+    pub guns: f32,
+    pub reflector: f32,
+    pub freeze: f32,
+    pub berserk: f32,
+    rapid_fire_timer: f32,
+    drone_timer: f32,
+    guns_timer: f32,
+}
+
+impl Buffs {
+    // 推进计时，返回这一帧哪些道具要开火。fire_rate 是主炮火力等级带来的连射速度倍数
+    pub fn update(&mut self, dt: f32, fire_rate: f32) -> AutoFire {
+        let fire = AutoFire {
+            rapid: Self::tick(&mut self.rapid_fire, &mut self.rapid_fire_timer, RAPID_FIRE_INTERVAL / fire_rate, dt),
+            drone: Self::tick(&mut self.drone, &mut self.drone_timer, DRONE_FIRE_INTERVAL, dt),
+            guns: Self::tick(&mut self.guns, &mut self.guns_timer, GUNS_FIRE_INTERVAL, dt),
+        };
+        self.spread = (self.spread - dt).max(0.0);
+        self.shield = (self.shield - dt).max(0.0);
+        self.reflector = (self.reflector - dt).max(0.0);
+        self.freeze = (self.freeze - dt).max(0.0);
+        self.berserk = (self.berserk - dt).max(0.0);
+        fire
+    }
+
+    // 每颗子弹对头目造成的伤害
+    pub fn damage(&self) -> u32 {
+        if self.berserk > 0.0 { 2 } else { 1 }
+    }
+
+    pub fn freezing(&self) -> bool {
+        self.freeze > 0.0
+    }
+
+    pub fn spreading(&self) -> bool {
+        self.spread > 0.0
+    }
+
+    pub fn shielded(&self) -> bool {
+        self.shield > 0.0
+    }
+
+    pub fn reflecting(&self) -> bool {
+        self.reflector > 0.0
+    }
+
+    // 玩家移动速度的倍数
+    pub fn speed_factor(&self) -> f32 {
+        if self.berserk > 0.0 { 0.5 } else { 1.0 }
+    }
+
+    fn tick(remaining: &mut f32, timer: &mut f32, interval: f32, dt: f32) -> bool {
+        if *remaining <= 0.0 {
+            *timer = 0.0;
+            return false;
+        }
+        *remaining -= dt;
+        *timer -= dt;
+        if *timer <= 0.0 {
+            *timer = interval;
+            return true;
+        }
+        false
+    }
+
+    // 生效中的限时道具的简称和剩余时间
+    pub fn active(&self) -> impl Iterator<Item = (&'static str, f32)> {
+        [
+            ("连射", self.rapid_fire),
+            ("散射", self.spread),
+            ("护盾", self.shield),
+            ("僚机", self.drone),
+            ("侧后炮", self.guns),
+            ("反射", self.reflector),
+            ("冻结", self.freeze),
+            ("狂暴", self.berserk),
+        ]
+        .into_iter()
+            .filter(|&(_, remaining)| remaining > 0.0)
+    }
+
+    pub fn drone_position(&self, player: Vec2) -> Option<Vec2> {
+        (self.drone > 0.0).then_some(player + DRONE_OFFSET)
+    }
+}
@@ -0,0 +1,95 @@
+use std::time::{Duration, Instant};
+
+// 把一帧分成若干阶段计时，开始下一个阶段时自动结束上一个
+#[derive(Default)]
+pub struct Profiler {
+    spans: Vec<(&'static str, Duration)>,
+    current: Option<(&'static str, Instant)>,
+}
+
+impl Profiler {
+    pub fn start(&mut self, name: &'static str) {
+        self.finish();
+        self.current = Some((name, Instant::now()));
+    }
+
+    pub fn finish(&mut self) {
+        if let Some((name, started)) = self.current.take() {
+            self.spans.push((name, started.elapsed()));
+        }
+    }
+
+    // 取出已经记录的阶段耗时
+    pub fn take(&mut self) -> Vec<(&'static str, Duration)> {
+        self.finish();
+        std::mem::take(&mut self.spans)
+    }
+}
+
+const LOG_INTERVAL: f32 = 1.0;  // 同类警告最多每秒打印一次
+const WARNING_TIME: f32 = 2.0;  // 画面上的警告停留时间
+
+// 帧时间监视：更新或绘制超出预算时打印警告，指出耗时最多的阶段
+pub struct Watchdog {
+    budget: Duration,
+    since_log: f32,
+    overruns: u32,  // 上次打印之后超出预算的次数
+    overrun: bool,  // 这一帧的更新或绘制超出了预算，自动画面质量据此降档
+    pub warning: Option<(String, f32)>,
+}
+
+impl Watchdog {
+    pub fn new(budget_ms: f32) -> Self {
+        Self {
+            budget: Duration::from_secs_f32(budget_ms.max(0.0) / 1000.0),
+            since_log: LOG_INTERVAL,
+            overruns: 0,
+            overrun: false,
+            warning: None,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.since_log += dt;
+        if let Some((_, remaining)) = &mut self.warning {
+            *remaining -= dt;
+            if *remaining <= 0.0 {
+                self.warning = None;
+            }
+        }
+    }
+
+    // 取出这一帧是否超出过预算，每帧绘制完调用一次
+    pub fn take_overrun(&mut self) -> bool {
+        std::mem::take(&mut self.overrun)
+    }
+
+    // stage 为 "更新" 或 "绘制"，spans 为这一帧各阶段的耗时
+    pub fn check(&mut self, stage: &str, total: Duration, spans: &[(&'static str, Duration)]) {
+        if total <= self.budget {
+            return;
+        }
+        self.overruns += 1;
+        self.overrun = true;
+        if self.since_log < LOG_INTERVAL {
+            return;
+        }
+
+        let slowest = spans
+            .iter()
+            .max_by_key(|(_, duration)| *duration)
+            .map(|(name, duration)| format!("，最慢的阶段是{} {:.2}ms", name, duration.as_secs_f32() * 1000.0))
+            .unwrap_or_default();
+        let message = format!(
+            "{}耗时 {:.2}ms，超出预算 {:.2}ms{}",
+            stage,
+            total.as_secs_f32() * 1000.0,
+            self.budget.as_secs_f32() * 1000.0,
+            slowest
+        );
+        eprintln!("帧时间警告: {} (一秒内超出 {} 次)", message, self.overruns);
+        self.warning = Some((message, WARNING_TIME));
+        self.since_log = 0.0;
+        self.overruns = 0;
+    }
+}
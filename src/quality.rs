@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+
+const WINDOW_FRAMES: u32 = 120;  // 自动档位每隔这么多帧统计一次
+const SLOW_SHARE: f32 = 0.25;  // 一次统计里超出预算的帧超过这个比例时降一档
+
+// 画面质量档位，自动档位从高档开始，帧时间持续超出预算时逐档降低
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Quality {
+    #[default]
+    Auto,
+    High,
+    Medium,
+    Low,
+}
+
+// 一个档位具体画多少东西
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Detail {
+    pub particle_density: f32,  // 爆炸碎片实际画出的比例
+    pub trail_length: usize,  // 飞船尾迹保留的帧数，0 表示不画
+    pub glow_passes: u32,  // 蓄力弹、光束外面叠几层光晕
+    pub background_layers: usize,  // 星空背景画几层，从最远的一层开始
+}
+
+impl Quality {
+    const ALL: [Quality; 4] = [Quality::Auto, Quality::High, Quality::Medium, Quality::Low];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Quality::Auto => "自动",
+            Quality::High => "高",
+            Quality::Medium => "中",
+            Quality::Low => "低",
+        }
+    }
+
+    pub fn next(self, step: isize) -> Self {
+        let index = Self::ALL.iter().position(|&quality| quality == self).unwrap_or(0) as isize;
+        Self::ALL[(index + step).rem_euclid(Self::ALL.len() as isize) as usize]
+    }
+
+    // 自动档位按高档处理
+    pub fn detail(self) -> Detail {
+        match self {
+            Quality::Auto | Quality::High => Detail { particle_density: 1.0, trail_length: 12, glow_passes: 3, background_layers: 3 },
+            Quality::Medium => Detail { particle_density: 0.5, trail_length: 6, glow_passes: 1, background_layers: 2 },
+            Quality::Low => Detail { particle_density: 0.25, trail_length: 0, glow_passes: 0, background_layers: 1 },
+        }
+    }
+
+    fn lower(self) -> Option<Self> {
+        match self {
+            Quality::Auto | Quality::High => Some(Quality::Medium),
+            Quality::Medium => Some(Quality::Low),
+            Quality::Low => None,
+        }
+    }
+}
+
+// 当前实际使用的档位。自动档位降下来之后不再自动升回去，免得画面在两档之间来回跳，
+// 重新选一次自动档位时从高档重新开始
+pub struct QualityControl {
+    setting: Quality,
+    level: Quality,
+    frames: u32,
+    slow: u32,
+}
+
+impl QualityControl {
+    pub fn new(setting: Quality) -> Self {
+        let level = if setting == Quality::Auto { Quality::High } else { setting };
+        Self { setting, level, frames: 0, slow: 0 }
+    }
+
+    pub fn setting(&self) -> Quality {
+        self.setting
+    }
+
+    // 实际使用的档位，不会是自动
+    pub fn level(&self) -> Quality {
+        self.level
+    }
+
+    pub fn detail(&self) -> Detail {
+        self.level.detail()
+    }
+
+    // 每帧记录一次是否超出了帧时间预算，自动档位因此降档时返回新的档位
+    pub fn record(&mut self, slow: bool) -> Option<Quality> {
+        if self.setting != Quality::Auto {
+            return None;
+        }
+        self.frames += 1;
+        self.slow += slow as u32;
+        if self.frames < WINDOW_FRAMES {
+            return None;
+        }
+        let lowered = (self.slow as f32 > self.frames as f32 * SLOW_SHARE).then(|| self.level.lower()).flatten();
+        self.frames = 0;
+        self.slow = 0;
+        if let Some(level) = lowered {
+            self.level = level;
+        }
+        lowered
+    }
+}
@@ -0,0 +1,182 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::Deserialize;
+
+// 任务模板随程序一起打包
+const QUEST_DATA: &str = include_str!("../resources/quests.toml");
+const QUEST_SEED: u64 = 0x85eb_ca6b_c2b2_ae35;  // 任务也单独使用一组随机数，不打乱敌人的分布
+
+// 任务的目标
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Goal {
+    Kills(u32),       // 击落这么多个敌人
+    Grazes(u32),      // 擦弹这么多次
+    HoldStill(f32),   // 连续这么多秒不移动
+    NoDamage(f32),    // 连续这么多秒不受伤，受伤就失败
+}
+
+// 完成任务的奖励
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Reward {
+    Score(u32),
+    Powerup(String),  // 道具的 ID，完成时直接生效
+}
+
+// 一种任务：limit 秒内达成 goal 就得到 reward
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+pub struct QuestTemplate {
+    pub goal: Goal,
+    pub limit: f32,
+    pub reward: Reward,
+}
+
+// 任务池：上一个任务结束 interval 秒后从 quests 中随机抽一个新的
+#[derive(Deserialize, Clone)]
+pub struct QuestPool {
+    pub interval: f32,
+    pub quests: Vec<QuestTemplate>,
+}
+
+impl QuestPool {
+    pub fn load() -> Self {
+        let pool: Self = toml::from_str(QUEST_DATA).expect("resources/quests.toml 格式错误");
+        assert!(!pool.quests.is_empty(), "resources/quests.toml 至少需要一个任务");
+        pool
+    }
+}
+
+// 进行中的任务
+#[derive(Clone, Debug)]
+pub struct Quest {
+    pub template: QuestTemplate,
+    progress: u32,  // 击落数或擦弹数
+    still: f32,  // 已经连续多少秒没动或没受伤
+    remaining: f32,  // 离时限还剩的秒数
+}
+
+impl Quest {
+    pub fn new(template: QuestTemplate) -> Self {
+        Self { remaining: template.limit, template, progress: 0, still: 0.0 }
+    }
+
+    fn done(&self) -> bool {
+        match self.template.goal {
+            Goal::Kills(count) | Goal::Grazes(count) => self.progress >= count,
+            Goal::HoldStill(seconds) | Goal::NoDamage(seconds) => self.still >= seconds,
+        }
+    }
+
+    // HUD 上显示的说明和进度
+    pub fn label(&self) -> String {
+        let remaining = self.remaining.max(0.0).ceil() as u32;
+        match self.template.goal {
+            Goal::Kills(count) => format!("{} 秒内击落 {} 个敌人 ({}/{})", remaining, count, self.progress, count),
+            Goal::Grazes(count) => format!("{} 秒内擦弹 {} 次 ({}/{})", remaining, count, self.progress, count),
+            Goal::HoldStill(seconds) => format!("原地不动 {} 秒 ({:.1})", seconds, self.still),
+            Goal::NoDamage(seconds) => format!("{} 秒内不受伤 ({:.0})", seconds, self.still),
+        }
+    }
+}
+
+// 任务结束时的结果
+#[derive(Clone, PartialEq, Debug)]
+pub enum QuestOutcome {
+    Started(String),  // 新任务的说明
+    Completed(Reward),
+    Failed,
+}
+
+// 局内任务：不定时出现一个可选的小目标，完成后有奖励，超时或失败就算了。
+// 游戏世界把击落、擦弹和受伤告诉它，它自己计时
+#[derive(Clone)]
+pub struct Quests {
+    pool: QuestPool,
+    rng: StdRng,
+    active: Option<Quest>,
+    timer: f32,  // 离下一个任务出现还剩的秒数
+}
+
+impl Quests {
+    pub fn new(seed: u64) -> Self {
+        let pool = QuestPool::load();
+        Self { timer: pool.interval, pool, rng: StdRng::seed_from_u64(seed ^ QUEST_SEED), active: None }
+    }
+
+    pub fn active(&self) -> Option<&Quest> {
+        self.active.as_ref()
+    }
+
+    // 直接开始一个任务，测试时使用
+    pub fn start(&mut self, template: QuestTemplate) {
+        self.active = Some(Quest::new(template));
+    }
+
+    // 推进计时。moving 为玩家这一帧是否在移动，offer 为 false 时不出新任务（关闭了生成时）
+    pub fn update(&mut self, dt: f32, moving: bool, offer: bool) -> Option<QuestOutcome> {
+        let Some(quest) = &mut self.active else {
+            if !offer {
+                return None;
+            }
+            self.timer -= dt;
+            if self.timer > 0.0 {
+                return None;
+            }
+            let template = self.pool.quests[self.rng.gen_range(0..self.pool.quests.len())].clone();
+            let quest = Quest::new(template);
+            let label = quest.label();
+            self.active = Some(quest);
+            return Some(QuestOutcome::Started(label));
+        };
+        quest.remaining -= dt;
+        match quest.template.goal {
+            Goal::HoldStill(_) if moving => quest.still = 0.0,
+            Goal::HoldStill(_) | Goal::NoDamage(_) => quest.still += dt,
+            Goal::Kills(_) | Goal::Grazes(_) => (),
+        }
+        if quest.done() {
+            let reward = quest.template.reward.clone();
+            self.finish();
+            Some(QuestOutcome::Completed(reward))
+        } else if quest.remaining <= 0.0 {
+            self.finish();
+            Some(QuestOutcome::Failed)
+        } else {
+            None
+        }
+    }
+
+    pub fn on_kill(&mut self) {
+        if let Some(quest) = &mut self.active {
+            if matches!(quest.template.goal, Goal::Kills(_)) {
+                quest.progress += 1;
+            }
+        }
+    }
+
+    pub fn on_graze(&mut self) {
+        if let Some(quest) = &mut self.active {
+            if matches!(quest.template.goal, Goal::Grazes(_)) {
+                quest.progress += 1;
+            }
+        }
+    }
+
+    // 不受伤的任务被打中就失败，下一帧结算
+    pub fn on_damage(&mut self) {
+        if let Some(quest) = &mut self.active {
+            if matches!(quest.template.goal, Goal::NoDamage(_)) {
+                quest.remaining = 0.0;
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        self.active = None;
+        self.timer = self.pool.interval;
+    }
+
+    pub fn templates(&self) -> &[QuestTemplate] {
+        &self.pool.quests
+    }
+}
@@ -0,0 +1,127 @@
+use rand::{distributions::WeightedIndex, rngs::StdRng, prelude::Distribution, Rng, SeedableRng};
+use serde::Deserialize;
+
+use crate::boss::{BossPattern, BossSpec, PhaseSpec};
+use crate::campaign::{CampaignData, Formation, StageSpec, WaveSpec};
+use crate::enemy::EnemyPath;
+
+const RANDOMIZER_PARAMS: &str = include_str!("../resources/randomizer.toml");
+const BOSS_NAMES: [&str; 4] = ["游荡者", "裂隙守卫", "混沌核心", "变异母舰"];
+const BOSS_PATTERNS: [BossPattern; 3] = [BossPattern::Sway, BossPattern::FigureEight, BossPattern::Charge];
+
+// 参数文件里的 name 只是方便阅读，生成时不使用
+#[derive(Deserialize)]
+struct EnemySet {
+    weight: u32,
+    speed: [f32; 2],
+    size: f32,
+    #[serde(default = "default_hp")]
+    hp: u32,
+    #[serde(default)]
+    armor: u32,
+    #[serde(default)]
+    fire_interval: f32,
+    #[serde(default)]
+    aimed: bool,
+}
+
+fn default_hp() -> u32 {
+    1
+}
+
+#[derive(Deserialize)]
+struct Weighted<T> {
+    kind: T,
+    weight: u32,
+}
+
+// resources/randomizer.toml 中的生成参数
+#[derive(Deserialize)]
+pub struct RandomizerParams {
+    stages: u32,
+    waves_per_stage: [u32; 2],
+    count: [u32; 2],
+    interval: [f32; 2],
+    difficulty_step: f32,
+    boss_hp: [u32; 2],
+    boss_hp_per_stage: u32,
+    boss_bonus: u32,
+    enemy_sets: Vec<EnemySet>,
+    formations: Vec<Weighted<Formation>>,
+    paths: Vec<Weighted<EnemyPath>>,
+}
+
+impl RandomizerParams {
+    pub fn load() -> Self {
+        toml::from_str(RANDOMIZER_PARAMS).expect("resources/randomizer.toml 格式错误")
+    }
+}
+
+// 按权重抽取一项
+fn pick<'a, T>(rng: &mut StdRng, items: &'a [T], weight: impl Fn(&T) -> u32) -> &'a T {
+    let index = WeightedIndex::new(items.iter().map(weight))
+        .expect("随机波次参数的权重必须为正数");
+    &items[index.sample(rng)]
+}
+
+// 用种子生成一整套关卡，同样的种子和参数总会得到同样的结果
+pub fn generate(params: &RandomizerParams, seed: u64) -> CampaignData {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let stages = (0..params.stages)
+        .map(|stage| {
+            let scale = 1.0 + stage as f32 * params.difficulty_step;
+            let wave_count = rng.gen_range(params.waves_per_stage[0]..=params.waves_per_stage[1]);
+            let waves = (0..wave_count)
+                .map(|_| {
+                    let set = pick(&mut rng, &params.enemy_sets, |set| set.weight);
+                    let formation = pick(&mut rng, &params.formations, |f| f.weight).kind;
+                    let path = pick(&mut rng, &params.paths, |p| p.weight).kind;
+                    let count = rng.gen_range(params.count[0]..=params.count[1]);
+                    WaveSpec {
+                        count: (count as f32 * scale).round() as u32,
+                        interval: rng.gen_range(params.interval[0]..=params.interval[1]),
+                        speed: rng.gen_range(set.speed[0]..=set.speed[1]) * scale,
+                        size: set.size,
+                        formation,
+                        path,
+                        hp: set.hp,
+                        armor: set.armor,
+                        fire_interval: set.fire_interval,
+                        aimed: set.aimed,
+                        launch_interval: 0.0,
+                        splits: false,
+                        lanes: Vec::new(),
+                        enemy: None,
+                    }
+                })
+                .collect();
+
+            // 越往后的头目阶段越多
+            let phase_count = (stage as usize + 1).min(BOSS_PATTERNS.len());
+            let phases = (0..phase_count)
+                .map(|i| PhaseSpec {
+                    below: 1.0 - i as f32 / phase_count as f32,
+                    pattern: BOSS_PATTERNS[rng.gen_range(0..BOSS_PATTERNS.len())],
+                    speed: rng.gen_range(0.7..=1.4) * scale,
+                })
+                .collect();
+            let boss = BossSpec {
+                name: BOSS_NAMES[rng.gen_range(0..BOSS_NAMES.len())].to_string(),
+                hp: rng.gen_range(params.boss_hp[0]..=params.boss_hp[1])
+                    + stage * params.boss_hp_per_stage,
+                bonus: params.boss_bonus * (stage + 1),
+                phases,
+                size: BossSpec::default_size(),
+                turrets: stage,
+            };
+
+            StageSpec {
+                name: format!("随机第 {} 关", stage + 1),
+                waves,
+                boss,
+                music: None,
+            }
+        })
+        .collect();
+    CampaignData { stages }
+}
@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::campaign::{self, CampaignData, WaveSpec};
+use crate::enemy::EnemyPath;
+use crate::powerup::PowerupKind;
+
+// 内置的内容定义随程序一起打包
+const CONTENT_DATA: &str = include_str!("../resources/content.toml");
+const ENEMY_PREFIX: &str = "enemy.";
+
+fn default_speed() -> f32 {
+    100.0
+}
+
+// 一种敌人的定义，波次写了 enemy 时用它代替波次里的大小、路线、血量、护甲、炮、机库和分裂，
+// 写了 points 时这种敌人不管出现在哪里都值这么多分
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct EnemyDef {
+    #[serde(default)]
+    pub path: EnemyPath,
+    #[serde(default = "campaign::default_enemy_size")]
+    pub size: f32,
+    #[serde(default = "default_speed")]
+    pub speed: f32,
+    #[serde(default = "campaign::default_enemy_hp")]
+    pub hp: u32,
+    #[serde(default)]
+    pub armor: u32,
+    #[serde(default)]
+    pub fire_interval: f32,
+    #[serde(default)]
+    pub aimed: bool,
+    #[serde(default)]
+    pub launch_interval: f32,
+    #[serde(default)]
+    pub splits: bool,
+    #[serde(default)]
+    pub points: Option<u32>,  // 击落的得分，省略时按路线、护甲和机库算
+}
+
+impl EnemyDef {
+    pub fn apply(&self, wave: &mut WaveSpec) {
+        wave.path = self.path;
+        wave.size = self.size;
+        wave.hp = self.hp;
+        wave.armor = self.armor;
+        wave.fire_interval = self.fire_interval;
+        wave.aimed = self.aimed;
+        wave.launch_interval = self.launch_interval;
+        wave.splits = self.splits;
+    }
+}
+
+#[derive(Deserialize)]
+struct ContentFile {
+    #[serde(default)]
+    enemies: BTreeMap<String, EnemyDef>,
+}
+
+// 按字符串 ID 查找敌人和道具。敌人的定义来自 resources/content.toml 和 mods 文件夹，
+// 道具的效果写在代码里，只能按 ID 引用，不能新增
+#[derive(Clone, Debug)]
+pub struct Registry {
+    enemies: BTreeMap<String, EnemyDef>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl Registry {
+    pub fn builtin() -> Self {
+        let mut registry = Self { enemies: BTreeMap::new() };
+        registry.merge(CONTENT_DATA).expect("resources/content.toml 格式错误");
+        registry
+    }
+
+    // 内置内容加上 dir 中的全部 .toml 文件，目录不存在时只有内置内容。
+    // 读不了或格式错误的文件跳过，返回给玩家看的错误信息
+    pub fn with_mods(dir: &Path) -> (Self, Vec<String>) {
+        let mut registry = Self::builtin();
+        let mut errors = Vec::new();
+        let Ok(entries) = fs::read_dir(dir) else {
+            return (registry, errors);
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "toml"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+            let result = fs::read_to_string(&path).map_err(|error| error.to_string()).and_then(|text| registry.merge(&text));
+            if let Err(error) = result {
+                errors.push(format!("mods/{}: {}", name, error));
+            }
+        }
+        (registry, errors)
+    }
+
+    // 读入一份内容定义，同名的 ID 覆盖原来的定义。出错时一项也不读入
+    pub fn merge(&mut self, text: &str) -> Result<(), String> {
+        let file: ContentFile = toml::from_str(text).map_err(|error| error.to_string())?;
+        if let Some(id) = file.enemies.keys().find(|id| !id.starts_with(ENEMY_PREFIX)) {
+            return Err(format!("敌人的 ID 要以 {} 开头: {}", ENEMY_PREFIX, id));
+        }
+        self.enemies.extend(file.enemies);
+        Ok(())
+    }
+
+    pub fn enemy(&self, id: &str) -> Option<&EnemyDef> {
+        self.enemies.get(id)
+    }
+
+    pub fn enemy_ids(&self) -> impl Iterator<Item = &str> {
+        self.enemies.keys().map(String::as_str)
+    }
+
+    pub fn powerup(&self, id: &str) -> Option<PowerupKind> {
+        PowerupKind::ALL.into_iter().find(|kind| kind.id() == id)
+    }
+
+    // 关卡数据里引用了但没有定义的敌人 ID，每条写明在第几关第几波
+    pub fn check(&self, data: &CampaignData) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (stage, spec) in data.stages.iter().enumerate() {
+            for (wave, spec) in spec.waves.iter().enumerate() {
+                if let Some(id) = spec.enemy.as_deref().filter(|id| self.enemy(id).is_none()) {
+                    errors.push(format!("第 {} 关第 {} 波: 未知的敌人 ID {}", stage + 1, wave + 1, id));
+                }
+            }
+        }
+        errors
+    }
+}
@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+
+use crate::autopilot::Autopilot;
+use crate::world::{FrameInput, GameMode, World, FIXED_STEP};
+
+// 一局游戏的录像：模式、种子和逐帧输入，用固定步长重放总会得到同样的结果
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Replay {
+    pub mode: String,
+    pub seed: u64,
+    // 输入按连续相同的帧压缩，例如 "L12 RF1 -30"：
+    // 左右方向 L/R/-，之后可以跟 U 或 D 表示向上或向下，带 F 表示开火，C 表示按住蓄力，E 表示电磁脉冲，B 表示炸弹，W 加一位数字表示切换到第几个栏位的主武器，
+    // 后面是持续的帧数，例如 "-W21" 是原地不动切换到激光
+    inputs: String,
+}
+
+impl Replay {
+    pub fn new(mode: GameMode, seed: u64) -> Self {
+        Self {
+            mode: mode.key().to_string(),
+            seed,
+            inputs: String::new(),
+        }
+    }
+
+    // 让自动驾驶玩若干帧并录下输入，游戏结束时提前停止
+    pub fn record(mode: GameMode, seed: u64, frames: u32) -> Self {
+        let mut replay = Self::new(mode, seed);
+        let mut world = World::new(mode, seed);
+        let mut autopilot = Autopilot::default();
+        let mut inputs = Vec::new();
+        for _ in 0..frames {
+            if world.game_over {
+                break;
+            }
+            let input = autopilot.decide(&world, FIXED_STEP);
+            world.apply_input(input);
+            world.update(FIXED_STEP);
+            inputs.push(input);
+        }
+        replay.set_inputs(&inputs);
+        replay
+    }
+
+    fn set_inputs(&mut self, inputs: &[FrameInput]) {
+        let mut runs: Vec<(FrameInput, u32)> = Vec::new();
+        for &input in inputs {
+            match runs.last_mut() {
+                Some((last, count)) if *last == input => *count += 1,
+                _ => runs.push((input, 1)),
+            }
+        }
+        self.inputs = runs
+            .iter()
+            .map(|(input, count)| {
+                let direction = match input.move_x.signum() {
+                    -1 => "L",
+                    1 => "R",
+                    _ => "-",
+                };
+                let vertical = match input.move_y.signum() {
+                    -1 => "U",
+                    1 => "D",
+                    _ => "",
+                };
+                let fire = if input.fire { "F" } else { "" };
+                let charge = if input.charge { "C" } else { "" };
+                let dash = if input.dash { "S" } else { "" };
+                let focus = if input.focus { "T" } else { "" };
+                let emp = if input.emp { "E" } else { "" };
+                let bomb = if input.bomb { "B" } else { "" };
+                let select = if input.select > 0 { format!("W{}", input.select) } else { String::new() };
+                format!("{}{}{}{}{}{}{}{}{}{}", direction, vertical, fire, charge, dash, focus, emp, bomb, select, count)
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+    }
+
+    pub fn inputs(&self) -> Result<Vec<FrameInput>, String> {
+        let mut inputs = Vec::new();
+        for token in self.inputs.split_whitespace() {
+            let mut chars = token.chars();
+            let move_x = match chars.next() {
+                Some('L') => -1,
+                Some('R') => 1,
+                Some('-') => 0,
+                _ => return Err(format!("录像输入格式错误: {}", token)),
+            };
+            let rest = chars.as_str();
+            let (move_y, rest) = match rest.strip_prefix('U') {
+                Some(rest) => (-1, rest),
+                None => match rest.strip_prefix('D') {
+                    Some(rest) => (1, rest),
+                    None => (0, rest),
+                },
+            };
+            let (fire, rest) = match rest.strip_prefix('F') {
+                Some(rest) => (true, rest),
+                None => (false, rest),
+            };
+            let (charge, rest) = match rest.strip_prefix('C') {
+                Some(rest) => (true, rest),
+                None => (false, rest),
+            };
+            let (dash, rest) = match rest.strip_prefix('S') {
+                Some(rest) => (true, rest),
+                None => (false, rest),
+            };
+            let (focus, rest) = match rest.strip_prefix('T') {
+                Some(rest) => (true, rest),
+                None => (false, rest),
+            };
+            let (emp, rest) = match rest.strip_prefix('E') {
+                Some(rest) => (true, rest),
+                None => (false, rest),
+            };
+            let (bomb, rest) = match rest.strip_prefix('B') {
+                Some(rest) => (true, rest),
+                None => (false, rest),
+            };
+            let (select, count) = match rest.strip_prefix('W') {
+                Some(rest) => {
+                    let slot = rest.chars().next().and_then(|slot| slot.to_digit(10));
+                    let slot = slot.ok_or_else(|| format!("录像输入格式错误: {}", token))?;
+                    (slot as u8, &rest[1..])
+                }
+                None => (0, rest),
+            };
+            let count: usize = count
+                .parse()
+                .map_err(|_| format!("录像输入格式错误: {}", token))?;
+            inputs.extend(std::iter::repeat_n(FrameInput { move_x, move_y, fire, charge, dash, focus, emp, bomb, select }, count));
+        }
+        Ok(inputs)
+    }
+
+    // 从头重放整段录像，返回结束时的游戏状态
+    pub fn play(&self) -> Result<World, String> {
+        let mode = GameMode::from_key(&self.mode).ok_or_else(|| format!("未知的模式: {}", self.mode))?;
+        let mut world = World::new(mode, self.seed);
+        // 和录制时一样在游戏结束时停下，不把结束后的慢镜头算进结果
+        for input in self.inputs()? {
+            if world.game_over {
+                break;
+            }
+            world.apply_input(input);
+            world.update(FIXED_STEP);
+        }
+        Ok(world)
+    }
+}
+
+// 用来比较两个游戏状态是否一致的摘要，包含所有物体坐标的哈希
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct Snapshot {
+    pub score: u32,
+    pub lives: u32,
+    pub game_over: bool,
+    pub enemies: usize,
+    pub bullets: usize,
+    pub boss_hp: Option<u32>,
+    pub position_hash: String,
+}
+
+impl Snapshot {
+    pub fn of(world: &World) -> Self {
+        // FNV-1a，逐个混入坐标的二进制表示，任何一点浮点差异都会改变结果
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut mix = |value: f32| {
+            for byte in value.to_bits().to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        };
+        let positions = std::iter::once(world.player.game_object.position)
+            .chain(world.bullets.positions.iter().copied())
+            .chain(world.charged_shots.iter().map(|shot| shot.game_object.position))
+            .chain(world.enemies.iter().map(|enemy| enemy.game_object.position))
+            .chain(world.boss.iter().map(|boss| boss.game_object.position));
+        for position in positions {
+            mix(position.x);
+            mix(position.y);
+        }
+
+        Self {
+            score: world.score,
+            lives: world.player.lives,
+            game_over: world.game_over,
+            enemies: world.enemies.len(),
+            bullets: world.bullets.len(),
+            boss_hp: world.boss.as_ref().map(|boss| boss.hp),
+            position_hash: format!("{:016x}", hash),
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use std::path::{Path, PathBuf};
+
+pub const RESOURCES_ENV: &str = "SPACE_SHOOTER_RESOURCES";  // 用环境变量指定资源目录
+const DIR_NAME: &str = "resources";
+
+// 查找资源目录（音乐和音效）时可以提供的线索，按优先级从高到低
+#[derive(Default)]
+pub struct ResourceHints {
+    pub flag: Option<PathBuf>,  // 命令行的 --resources
+    pub env: Option<PathBuf>,  // 环境变量 SPACE_SHOOTER_RESOURCES
+    pub manifest_dir: Option<PathBuf>,  // 用 cargo 启动或调试构建时的项目目录
+    pub exe: Option<PathBuf>,  // 可执行文件的路径
+}
+
+impl ResourceHints {
+    // 从当前进程读取所有线索
+    pub fn from_env(flag: Option<PathBuf>) -> Self {
+        let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR")
+            .map(PathBuf::from)
+            .or_else(|| cfg!(debug_assertions).then(|| PathBuf::from(env!("CARGO_MANIFEST_DIR"))));
+        Self {
+            flag,
+            env: std::env::var_os(RESOURCES_ENV).map(PathBuf::from),
+            manifest_dir,
+            exe: std::env::current_exe().ok(),
+        }
+    }
+
+    // 依次尝试的目录：显式指定的目录、项目目录，最后是发布包中相对可执行文件的位置：
+    // Windows 和便携包在可执行文件旁边，macOS 应用包在 Contents/Resources 中，
+    // Linux 安装到 bin 目录时在 ../share/space_shooter 中
+    pub fn candidates(&self) -> Vec<PathBuf> {
+        let mut candidates: Vec<PathBuf> = self.flag.iter().chain(&self.env).cloned().collect();
+        candidates.extend(self.manifest_dir.as_ref().map(|dir| dir.join(DIR_NAME)));
+        if let Some(exe_dir) = self.exe.as_deref().and_then(Path::parent) {
+            candidates.push(exe_dir.join(DIR_NAME));
+            candidates.push(exe_dir.join("../Resources").join(DIR_NAME));
+            candidates.push(exe_dir.join("../Resources"));
+            candidates.push(exe_dir.join("../share/space_shooter").join(DIR_NAME));
+        }
+        candidates
+    }
+
+    // 第一个存在的目录；显式指定的目录不存在时直接报错，不悄悄换成别的目录
+    pub fn resolve(&self) -> Result<Option<PathBuf>, String> {
+        if let Some(dir) = self.flag.iter().chain(&self.env).find(|dir| !dir.is_dir()) {
+            return Err(format!("资源目录不存在: {}", dir.display()));
+        }
+        Ok(self.candidates().into_iter().find(|dir| dir.is_dir()))
+    }
+}
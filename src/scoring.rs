@@ -0,0 +1,88 @@
+pub const WAVE_CLEAR_BONUS: u32 = 50;  // 清空一波的奖励，乘以累计的波次
+
+// 击落一个敌人的得分，连击加成单独记账
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct KillScore {
+    pub base: u32,
+    pub combo: u32,
+}
+
+// 计分规则：碰撞和战役流程只报告发生了什么，得多少分由规则决定，
+// 新的模式换一套规则就能改变计分，不用改碰撞的代码
+pub trait ScoringRules {
+    // 击落一个值 points 分的敌人，multiplier 是算上这一次之后的连击倍率
+    fn on_kill(&self, points: u32, multiplier: u32) -> KillScore;
+
+    // 敌人的子弹从飞船身边擦过，没有打中
+    fn on_graze(&self) -> u32;
+
+    // 清空第 wave 波（从开局算起的累计波次）的奖励
+    fn on_wave_clear(&self, wave: u32) -> u32;
+
+    // 受到伤害时是否清空连击
+    fn on_damage(&self) -> bool;
+}
+
+// 经典规则，现有的模式都用它。擦弹只给 1 分，不至于让擦弹压过击落
+pub struct Classic;
+
+impl ScoringRules for Classic {
+    fn on_kill(&self, points: u32, multiplier: u32) -> KillScore {
+        KillScore { base: points, combo: points * (multiplier - 1) }
+    }
+
+    fn on_graze(&self) -> u32 {
+        1
+    }
+
+    fn on_wave_clear(&self, wave: u32) -> u32 {
+        WAVE_CLEAR_BONUS * wave
+    }
+
+    fn on_damage(&self) -> bool {
+        true
+    }
+}
+
+// 计时赛：时间有限，分数靠连击和快速清波，连击加成和清波奖励都翻倍，擦弹也有少量分数。
+// 受伤已经耽误了时间，不再清空连击
+pub struct TimeAttack;
+
+impl ScoringRules for TimeAttack {
+    fn on_kill(&self, points: u32, multiplier: u32) -> KillScore {
+        KillScore { base: points, combo: points * (multiplier - 1) * 2 }
+    }
+
+    fn on_graze(&self) -> u32 {
+        2
+    }
+
+    fn on_wave_clear(&self, wave: u32) -> u32 {
+        WAVE_CLEAR_BONUS * wave * 2
+    }
+
+    fn on_damage(&self) -> bool {
+        false
+    }
+}
+
+// 每日挑战：所有人打同一局，比的是稳定，去掉连击加成，擦弹分数更高
+pub struct Daily;
+
+impl ScoringRules for Daily {
+    fn on_kill(&self, points: u32, _multiplier: u32) -> KillScore {
+        KillScore { base: points, combo: 0 }
+    }
+
+    fn on_graze(&self) -> u32 {
+        5
+    }
+
+    fn on_wave_clear(&self, wave: u32) -> u32 {
+        WAVE_CLEAR_BONUS * wave
+    }
+
+    fn on_damage(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,111 @@
+use crate::health::HealthModel;
+use crate::playfield::EdgeMode;
+use crate::storage::Settings;
+use crate::world::GameMode;
+
+// Crockford Base32：去掉容易看错的 I、L、O、U，输入时不区分大小写
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const PAYLOAD_CHARS: usize = 14;  // 64 位种子 + 2 位模式 + 2 位规则 + 模式的第 3 位 + 生命规则 1 位，每个字符 5 位
+const FIELD_POLY: u8 = 0b10_0101;  // GF(32) 的生成多项式 x^5 + x^2 + 1
+const GROUP: usize = 5;
+
+// 末尾的校验位：把每个字符看成 GF(32) 上多项式的系数，在 x = 2 处求值。
+// 抄错任意一个字符或把相邻两个字符写反时，校验位一定会变
+fn checksum(values: &[u8]) -> u8 {
+    values.iter().fold(0, |check, &value| double(check) ^ value)
+}
+
+fn double(value: u8) -> u8 {
+    let value = value << 1;
+    if value & 32 != 0 { value ^ FIELD_POLY } else { value }
+}
+
+// 会影响一局结果的附加规则，分享码里要带上，否则同一个种子打出来也不一样
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Mutators {
+    pub player_edges: EdgeMode,
+    pub retry_wave: bool,  // 游戏结束后可以从当前这一波重来
+    pub health: HealthModel,
+}
+
+impl Mutators {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            player_edges: settings.player_edges,
+            retry_wave: settings.assist.retry_wave,
+            health: settings.health,
+        }
+    }
+
+    fn bits(self) -> u128 {
+        let wrap = (self.player_edges == EdgeMode::Wrap) as u128;
+        let shield = (self.health == HealthModel::Shield) as u128;
+        wrap | (self.retry_wave as u128) << 1 | shield << 2
+    }
+
+    fn from_bits(bits: u128) -> Self {
+        Self {
+            player_edges: if bits & 1 != 0 { EdgeMode::Wrap } else { EdgeMode::Clamp },
+            retry_wave: bits & 2 != 0,
+            health: if bits & 4 != 0 { HealthModel::Shield } else { HealthModel::Lives },
+        }
+    }
+}
+
+// 分享码：把模式、种子和附加规则编码成一串字母和数字，例如 "3F9KQ-0M2TZ-XH7AB"。
+// 朋友输入同一个分享码就能打完全相同的一局，不需要联网
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ShareCode {
+    pub mode: GameMode,
+    pub seed: u64,
+    pub mutators: Mutators,
+}
+
+impl ShareCode {
+    pub fn encode(&self) -> String {
+        let mode = GameMode::ALL.iter().position(|&mode| mode == self.mode).unwrap_or(0) as u128;
+        // 模式后来多于 4 种，第 3 位放在规则后面，旧的分享码仍然有效
+        let payload = self.seed as u128 | (mode & 3) << 64 | (self.mutators.bits() & 3) << 66 | (mode >> 2) << 68
+            | (self.mutators.bits() >> 2) << 69;  // 生命规则后来才加，排在模式第 3 位后面
+        let mut values: Vec<u8> = (0..PAYLOAD_CHARS).rev().map(|index| (payload >> (index * 5)) as u8 & 31).collect();
+        values.push(checksum(&values));
+        values
+            .chunks(GROUP)
+            .map(|group| group.iter().map(|&value| ALPHABET[value as usize] as char).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+
+    // 忽略分隔符和大小写，O 当作 0，I 和 L 当作 1
+    pub fn decode(code: &str) -> Result<Self, String> {
+        let mut values = Vec::new();
+        for c in code.chars().filter(|c| !matches!(c, '-' | ' ')) {
+            let c = match c.to_ascii_uppercase() {
+                'O' => '0',
+                'I' | 'L' => '1',
+                c => c,
+            };
+            let value = ALPHABET
+                .iter()
+                .position(|&symbol| symbol as char == c)
+                .ok_or_else(|| format!("分享码中有无效的字符: {}", c))?;
+            values.push(value as u8);
+        }
+        if values.len() != PAYLOAD_CHARS + 1 {
+            return Err(format!("分享码应为 {} 个字符", PAYLOAD_CHARS + 1));
+        }
+        if checksum(&values[..PAYLOAD_CHARS]) != values[PAYLOAD_CHARS] {
+            return Err("分享码校验失败，请检查是否抄错".to_string());
+        }
+        let payload = values[..PAYLOAD_CHARS].iter().fold(0, |payload, &value| payload << 5 | value as u128);
+        let mode = ((payload >> 64) & 3 | ((payload >> 68) & 1) << 2) as usize;
+        if payload >> 70 != 0 || mode >= GameMode::ALL.len() {
+            return Err("分享码无效".to_string());
+        }
+        Ok(Self {
+            mode: GameMode::ALL[mode],
+            seed: payload as u64,
+            mutators: Mutators::from_bits((payload >> 66) & 3 | (payload >> 69) << 2),
+        })
+    }
+}
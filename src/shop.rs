@@ -0,0 +1,67 @@
+use ggez::glam::Vec2;
+
+use crate::GameObject;
+
+pub const COIN_SIZE: f32 = 10.0;
+pub const COIN_FALL_SPEED: f32 = 120.0;
+pub const MAX_UPGRADE: u32 = 3;  // 射速和移动速度最多各升几级
+const FIRE_RATE_STEP: f32 = 0.15;  // 每级射速让主武器的冷却缩短的比例
+const SPEED_STEP: f32 = 0.1;  // 每级移动速度加快的比例
+
+// 击落敌人时掉落的金币，从敌人的位置往下落，碰到飞船时拾取
+pub fn coin(position: Vec2) -> GameObject {
+    let mut coin = GameObject::new(position.x, position.y, COIN_SIZE, COIN_SIZE);
+    coin.velocity.y = COIN_FALL_SPEED;
+    coin
+}
+
+// 波次之间的商店里能买的东西
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ShopItem {
+    FireRate,   // 主武器冷却缩短
+    Speed,      // 移动速度加快
+    ExtraLife,  // 生命加 1
+    Bomb,       // 炸弹加 1，不超过携带上限
+}
+
+impl ShopItem {
+    pub const ALL: [ShopItem; 4] = [ShopItem::FireRate, ShopItem::Speed, ShopItem::ExtraLife, ShopItem::Bomb];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ShopItem::FireRate => "射速强化",
+            ShopItem::Speed => "引擎强化",
+            ShopItem::ExtraLife => "额外生命",
+            ShopItem::Bomb => "炸弹",
+        }
+    }
+
+    // 价格，强化每升一级贵一些
+    pub fn price(self, upgrades: &Upgrades) -> u32 {
+        match self {
+            ShopItem::FireRate => 15 + upgrades.fire_rate * 10,
+            ShopItem::Speed => 10 + upgrades.speed * 10,
+            ShopItem::ExtraLife => 40,
+            ShopItem::Bomb => 20,
+        }
+    }
+}
+
+// 在商店里买到的强化，一局之内一直有效
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Upgrades {
+    pub fire_rate: u32,
+    pub speed: u32,
+}
+
+impl Upgrades {
+    // 主武器冷却的倍数
+    pub fn cooldown_factor(&self) -> f32 {
+        1.0 / (1.0 + self.fire_rate as f32 * FIRE_RATE_STEP)
+    }
+
+    // 移动速度的倍数
+    pub fn speed_factor(&self) -> f32 {
+        1.0 + self.speed as f32 * SPEED_STEP
+    }
+}
@@ -0,0 +1,111 @@
+use crate::events::GameEvent;
+use crate::world::{FrameInput, GameMode, World, FIXED_STEP};
+
+// 测试用的脚本化模拟：按固定步长推进游戏，记录期间发出的所有事件。
+// 输入既可以链式调用，也可以写成脚本，例如 "left 30, fire, wait 60"
+pub struct Simulation {
+    pub world: World,
+    pub frame: u64,
+    pub events: Vec<GameEvent>,
+}
+
+impl Simulation {
+    pub fn new(mode: GameMode, seed: u64) -> Self {
+        Self {
+            world: World::new(mode, seed),
+            frame: 0,
+            events: Vec::new(),
+        }
+    }
+
+    // 不会自动生成敌人的空场地，方便单独验证某条规则
+    pub fn quiet() -> Self {
+        let mut simulation = Self::new(GameMode::Endless, 0);
+        simulation.world.spawning = false;
+        simulation
+    }
+
+    pub fn step(&mut self, input: FrameInput) -> &mut Self {
+        self.world.apply_input(input);
+        self.events.extend(self.world.update(FIXED_STEP));
+        self.frame += 1;
+        self
+    }
+
+    pub fn hold(&mut self, input: FrameInput, frames: u32) -> &mut Self {
+        for _ in 0..frames {
+            self.step(input);
+        }
+        self
+    }
+
+    pub fn hold_left(&mut self, frames: u32) -> &mut Self {
+        self.hold(FrameInput { move_x: -1, ..FrameInput::default() }, frames)
+    }
+
+    pub fn hold_right(&mut self, frames: u32) -> &mut Self {
+        self.hold(FrameInput { move_x: 1, ..FrameInput::default() }, frames)
+    }
+
+    // 原地开一枪
+    pub fn fire(&mut self) -> &mut Self {
+        self.step(FrameInput { fire: true, ..FrameInput::default() })
+    }
+
+    pub fn wait(&mut self, frames: u32) -> &mut Self {
+        self.hold(FrameInput::default(), frames)
+    }
+
+    pub fn wait_seconds(&mut self, seconds: f32) -> &mut Self {
+        self.wait((seconds / FIXED_STEP).round() as u32)
+    }
+
+    // 执行用逗号或换行分隔的脚本：left/right/up/down/wait/fire/charge/dash/focus/emp/bomb 后面跟帧数，省略时为 1 帧；
+    // charge 按住开火键蓄力，之后的第一条别的指令就是松手；dash 原地闪避，冷却中重复按下不起作用；focus 按住专注键；
+    // weapon 后面跟栏位，切换主武器，只占 1 帧；spawn 和 drop 后面跟内容注册表中的 ID，生成一个敌人或掉落一个道具，不占帧
+    pub fn run(&mut self, script: &str) -> Result<&mut Self, String> {
+        for command in script.split([',', '\n']).map(str::trim).filter(|command| !command.is_empty()) {
+            let mut words = command.split_whitespace();
+            let name = words.next().unwrap_or_default();
+            if name == "spawn" || name == "drop" {
+                let id = words.next().ok_or_else(|| format!("缺少 ID: {}", command))?;
+                if name == "spawn" {
+                    self.world.spawn_by_id(id)?;
+                } else {
+                    self.world.drop_powerup(id)?;
+                }
+                continue;
+            }
+            let frames = match words.next() {
+                Some(frames) => frames.parse().map_err(|_| format!("帧数无效: {}", command))?,
+                None => 1,
+            };
+            if name == "weapon" {
+                let select = u8::try_from(frames).map_err(|_| format!("栏位无效: {}", command))?;
+                self.step(FrameInput { select, ..FrameInput::default() });
+                continue;
+            }
+            let input = match name {
+                "left" => FrameInput { move_x: -1, ..FrameInput::default() },
+                "right" => FrameInput { move_x: 1, ..FrameInput::default() },
+                "up" => FrameInput { move_y: -1, ..FrameInput::default() },
+                "down" => FrameInput { move_y: 1, ..FrameInput::default() },
+                "wait" => FrameInput::default(),
+                "fire" => FrameInput { fire: true, ..FrameInput::default() },
+                "charge" => FrameInput { charge: true, ..FrameInput::default() },
+                "dash" => FrameInput { dash: true, ..FrameInput::default() },
+                "focus" => FrameInput { focus: true, ..FrameInput::default() },
+                "emp" => FrameInput { emp: true, ..FrameInput::default() },
+                "bomb" => FrameInput { bomb: true, ..FrameInput::default() },
+                _ => return Err(format!("未知的指令: {}", command)),
+            };
+            self.hold(input, frames);
+        }
+        Ok(self)
+    }
+
+    // 到目前为止是否发出过满足条件的事件
+    pub fn saw(&self, matches: impl Fn(&GameEvent) -> bool) -> bool {
+        self.events.iter().any(matches)
+    }
+}
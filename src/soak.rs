@@ -0,0 +1,81 @@
+use crate::autopilot::Autopilot;
+use crate::events::GameEvent;
+use crate::world::{GameMode, World, FIXED_STEP as STEP};
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+
+const MAX_BULLETS: usize = 256;
+const MAX_ENEMIES: usize = 512;
+const FIELD_MARGIN: f32 = 400.0;  // 超出屏幕这么远仍存活的物体视为没有被回收
+
+// 浸泡测试：让自动驾驶在没有窗口的情况下全速连续游玩，
+// 每一局结束后换下一个模式，发现不变量被破坏时返回错误描述
+pub fn run(minutes: f32) -> Result<u32, String> {
+    let steps = (minutes * 60.0 / STEP) as u64;
+    let mut runs = 0;
+    let mut seed = 0;
+    let mut world = World::new(GameMode::ALL[0], seed);
+    let mut autopilot = Autopilot::default();
+    let mut last_score = 0;
+
+    for step in 0..steps {
+        let input = autopilot.decide(&world, STEP);
+        world.apply_input(input);
+        let events = world.update(STEP);
+
+        let time = step as f32 * STEP;
+        check(&world, last_score).map_err(|error| {
+            format!("第 {:.1} 秒 (模式 {}, 种子 {}): {}", time, world.mode.key(), world.seed, error)
+        })?;
+        last_score = world.score;
+
+        let finished = world.game_over
+            || events.iter().any(|event| matches!(event, GameEvent::CampaignCleared));
+        if finished {
+            runs += 1;
+            seed += 1;
+            let mode = GameMode::ALL[runs as usize % GameMode::ALL.len()];
+            world = World::new(mode, seed);
+            last_score = 0;
+        }
+    }
+    Ok(runs)
+}
+
+fn check(world: &World, last_score: u32) -> Result<(), String> {
+    if world.score < last_score {
+        return Err(format!("分数从 {} 减少到 {}", last_score, world.score));
+    }
+    if world.bullets.len() > MAX_BULLETS {
+        return Err(format!("子弹数量 {} 超过上限 {}", world.bullets.len(), MAX_BULLETS));
+    }
+    if world.enemies.len() > MAX_ENEMIES {
+        return Err(format!("敌人数量 {} 超过上限 {}", world.enemies.len(), MAX_ENEMIES));
+    }
+    if world.effects.particle_count() > world.limits.particles || world.effects.popup_count() > world.limits.popups {
+        return Err("爆炸碎片或得分提示超过上限".to_string());
+    }
+
+    let bullets = &world.bullets;
+    let objects = std::iter::once(("玩家", &world.player.game_object))
+        .chain(world.enemies.iter().map(|enemy| ("敌人", &enemy.game_object)))
+        .chain(world.boss.iter().map(|boss| ("头目", &boss.game_object)))
+        .chain(world.powerups.iter().map(|powerup| ("道具", &powerup.game_object)))
+        .map(|(name, object)| (name, object.position, object.velocity))
+        .chain(
+            bullets.positions.iter().zip(&bullets.velocities)
+                .map(|(&position, &velocity)| ("子弹", position, velocity)),
+        );
+    for (name, position, velocity) in objects {
+        if !position.is_finite() || !velocity.is_finite() {
+            return Err(format!("{}的位置或速度无效: {:?} {:?}", name, position, velocity));
+        }
+        let inside = position.x > -FIELD_MARGIN
+            && position.x < WINDOW_WIDTH + FIELD_MARGIN
+            && position.y > -FIELD_MARGIN
+            && position.y < WINDOW_HEIGHT + FIELD_MARGIN;
+        if !inside {
+            return Err(format!("{}离开屏幕后没有被回收: {:?}", name, position));
+        }
+    }
+    Ok(())
+}
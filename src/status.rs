@@ -0,0 +1,143 @@
+use ggez::{
+    glam::Vec2,
+    graphics::{self, Canvas, Color, DrawParam, Mesh, Rect},
+    Context, GameResult,
+};
+
+// 眩晕中的敌人显示的颜色
+pub const STUN_TINT: Color = Color::new(0.3, 0.5, 1.0, 1.0);
+const BURN_INTERVAL: f32 = 0.5;  // 燃烧每隔多少秒造成一点伤害
+const SLOW_FACTOR: f32 = 0.5;  // 减速期间移动速度的倍数
+const ICON_SIZE: f32 = 6.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StatusKind {
+    Burn,  // 持续掉血
+    Slow,  // 移动变慢
+    Stun,  // 不能转向也不能开火
+}
+
+impl StatusKind {
+    pub const ALL: [StatusKind; 3] = [StatusKind::Burn, StatusKind::Slow, StatusKind::Stun];
+
+    // 敌人头顶小图标的颜色
+    fn color(self) -> Color {
+        match self {
+            StatusKind::Burn => Color::new(1.0, 0.5, 0.1, 1.0),
+            StatusKind::Slow => Color::new(0.4, 1.0, 0.4, 1.0),
+            StatusKind::Stun => STUN_TINT,
+        }
+    }
+}
+
+// 某种敌人对哪些状态免疫
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Immunity {
+    pub burn: bool,
+    pub slow: bool,
+    pub stun: bool,
+}
+
+impl Immunity {
+    pub const NONE: Immunity = Immunity { burn: false, slow: false, stun: false };
+
+    // 蛇行的敌人本来就灵活，减速对它无效
+    pub const ZIGZAG: Immunity = Immunity { slow: true, ..Immunity::NONE };
+
+    // 水雷没有引擎可以瘫痪，外壳也烧不着
+    pub const MINE: Immunity = Immunity { burn: true, stun: true, ..Immunity::NONE };
+
+    pub fn blocks(self, kind: StatusKind) -> bool {
+        match kind {
+            StatusKind::Burn => self.burn,
+            StatusKind::Slow => self.slow,
+            StatusKind::Stun => self.stun,
+        }
+    }
+}
+
+// 挂在敌人和头目身上的状态效果，每种效果只记剩余秒数
+#[derive(Clone, Copy, Default, Debug)]
+pub struct StatusEffects {
+    pub burn: f32,
+    pub slow: f32,
+    pub stun: f32,
+    pub immune: Immunity,
+    burn_timer: f32,
+}
+
+impl StatusEffects {
+    pub fn new(immune: Immunity) -> Self {
+        Self { immune, ..Self::default() }
+    }
+
+    // 推进计时，返回这一帧燃烧造成的伤害
+    pub fn update(&mut self, dt: f32) -> u32 {
+        let mut damage = 0;
+        if self.burn > 0.0 {
+            self.burn_timer += dt.min(self.burn);
+            while self.burn_timer >= BURN_INTERVAL {
+                self.burn_timer -= BURN_INTERVAL;
+                damage += 1;
+            }
+        } else {
+            self.burn_timer = 0.0;
+        }
+        self.burn = (self.burn - dt).max(0.0);
+        self.slow = (self.slow - dt).max(0.0);
+        self.stun = (self.stun - dt).max(0.0);
+        damage
+    }
+
+    // 施加一种状态，免疫时不生效。重复施加时取较长的剩余时间，不叠加
+    pub fn apply(&mut self, kind: StatusKind, duration: f32) {
+        if self.immune.blocks(kind) {
+            return;
+        }
+        let remaining = match kind {
+            StatusKind::Burn => &mut self.burn,
+            StatusKind::Slow => &mut self.slow,
+            StatusKind::Stun => &mut self.stun,
+        };
+        *remaining = remaining.max(duration);
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        match kind {
+            StatusKind::Burn => self.burn > 0.0,
+            StatusKind::Slow => self.slow > 0.0,
+            StatusKind::Stun => self.stun > 0.0,
+        }
+    }
+
+    pub fn stunned(&self) -> bool {
+        self.has(StatusKind::Stun)
+    }
+
+    // 移动用的时间倍数，减速时变慢
+    pub fn speed_factor(&self) -> f32 {
+        if self.has(StatusKind::Slow) { SLOW_FACTOR } else { 1.0 }
+    }
+
+    // 绘制时使用的颜色，没有眩晕时用原来的颜色
+    pub fn tint(&self, base: Color) -> Color {
+        if self.stunned() { STUN_TINT } else { base }
+    }
+
+    // 在 top（机身上边缘的中点）上方画一排小方块，每种生效中的状态一个
+    pub fn draw_icons(&self, ctx: &mut Context, canvas: &mut Canvas, top: Vec2) -> GameResult<()> {
+        let active: Vec<StatusKind> = StatusKind::ALL.into_iter().filter(|&kind| self.has(kind)).collect();
+        let width = active.len() as f32 * (ICON_SIZE + 2.0) - 2.0;
+        for (index, kind) in active.into_iter().enumerate() {
+            let x = top.x - width / 2.0 + index as f32 * (ICON_SIZE + 2.0);
+            let icon = Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::fill(),
+                Rect::new(x, top.y - ICON_SIZE - 4.0, ICON_SIZE, ICON_SIZE),
+                kind.color(),
+            )?;
+            canvas.draw(&icon, DrawParam::default());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,602 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use directories::ProjectDirs;
+use ggez::glam::Vec2;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::bindings::Bindings;
+use crate::display::DisplayMode;
+use crate::health::HealthModel;
+use crate::heatmap::HeatMap;
+use crate::history::{RunHistory, RunSummary};
+use crate::ledger::Ledger;
+use crate::meta::MetaUpgrades;
+use crate::milestone::Milestone;
+use crate::playfield::EdgeMode;
+use crate::quality::Quality;
+use crate::streamer::Corner;
+use crate::theme::Theme;
+use crate::world::EntityLimits;
+
+const SETTINGS_FILE: &str = "settings.toml";
+const HIGH_SCORES_FILE: &str = "highscores.toml";
+const PROGRESS_FILE: &str = "progress.toml";
+const HISTORY_FILE: &str = "history.toml";
+const HEATMAP_FILE: &str = "heatmap.toml";
+const MODS_DIR: &str = "mods";  // 数据目录下放自制内容的文件夹
+const MAX_HIGH_SCORES: usize = 10;
+const PORTABLE_MARKER: &str = "portable.txt";
+const PORTABLE_DIR: &str = "data";
+const EXPORT_DIR: &str = "exports";  // 导出的得分记录放在数据目录下的这个子目录
+const STREAM_FILE: &str = "stream.txt";  // 直播模式写出的分数和波次，给 OBS 的文本源读取
+const CHECKSUM_PREFIX: &str = "# checksum: ";  // 存档第一行记录其余内容的校验和，是 TOML 注释
+const BACKUPS: usize = 2;  // 每个存档保留几份上一次完好的版本
+
+// 玩家可调整的设置，缺失的字段使用默认值，方便以后新增选项
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub idle_timeout: f32,  // 无操作多少秒后自动暂停
+    pub pause_on_focus_loss: bool,  // 切到后台（或手机上被来电打断）时自动暂停并停下音乐
+    pub hit_replay: bool,  // 失去一条命后在角落慢放被击中前的画面
+    pub player_edges: EdgeMode,  // 玩家碰到左右边缘时停下还是从另一侧出现
+    pub health: HealthModel,  // 几条命，还是一条命加一条会恢复的护盾条
+    pub quality: Quality,  // 画面质量，自动时帧时间持续超出 debug.frame_budget_ms 会逐档降低
+    pub window: WindowSettings,
+    pub theme: Theme,  // 飞船、子弹和 HUD 强调色的配色
+    pub bindings: Bindings,
+    pub limits: EntityLimits,
+    pub debug: DebugSettings,
+    pub assist: AssistSettings,
+    pub streamer: StreamerSettings,
+    pub twitch: TwitchSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            idle_timeout: 30.0,
+            pause_on_focus_loss: true,
+            hit_replay: true,
+            player_edges: EdgeMode::default(),
+            health: HealthModel::default(),
+            quality: Quality::default(),
+            window: WindowSettings::default(),
+            theme: Theme::default(),
+            bindings: Bindings::default(),
+            limits: EntityLimits::default(),
+            debug: DebugSettings::default(),
+            assist: AssistSettings::default(),
+            streamer: StreamerSettings::default(),
+            twitch: TwitchSettings::default(),
+        }
+    }
+}
+
+// 降低难度的辅助选项
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct AssistSettings {
+    pub retry_wave: bool,  // 游戏结束后可以从当前这一波开头重来，分数恢复为这一波开始时的值
+}
+
+// 直播模式：放大 HUD、隐藏种子和分享码，可以在一个角落留空给摄像头，
+// 并把当前分数和波次写到数据目录下的文本文件里
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct StreamerSettings {
+    pub enabled: bool,
+    pub hud_scale: f32,  // HUD 文字的放大倍数
+    pub webcam_corner: Option<Corner>,  // 留空给摄像头的角落，不设置时不留
+    pub overlay_file: bool,  // 写出给 OBS 读取的文本文件
+    pub state_port: Option<u16>,  // 开放只读状态接口的本地端口，需要启用 state-socket 特性
+}
+
+impl Default for StreamerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            hud_scale: 1.5,
+            webcam_corner: None,
+            overlay_file: true,
+            state_port: None,
+        }
+    }
+}
+
+// 直播间的聊天投票，需要启用 twitch 特性，设置 channel 后生效
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct TwitchSettings {
+    pub channel: Option<String>,  // 要连接的频道名
+    pub vote_window: f32,  // 第一票之后投票持续的秒数
+    pub cooldown: f32,  // 一次事件生效后多少秒内不接受新的投票
+}
+
+impl Default for TwitchSettings {
+    fn default() -> Self {
+        Self {
+            channel: None,
+            vote_window: 15.0,
+            cooldown: 60.0,
+        }
+    }
+}
+
+// 开发调试用的选项
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct DebugSettings {
+    pub frame_budget_ms: f32,  // 更新或绘制超过这么多毫秒时打印警告
+    pub show_budget_warnings: bool,  // 同时在画面上显示警告
+    pub show_memory: bool,  // 显示每帧的内存分配和实体池大小，游戏中按 F2 切换
+    pub show_spawn_lanes: bool,  // 画出战役中正在出场的这一波的生成区域，游戏中按 F4 切换
+}
+
+impl Default for DebugSettings {
+    fn default() -> Self {
+        Self {
+            frame_budget_ms: 4.0,
+            show_budget_warnings: false,
+            show_memory: false,
+            show_spawn_lanes: false,
+        }
+    }
+}
+
+// 上次退出时的窗口状态，尺寸为逻辑像素
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowSettings {
+    pub mode: DisplayMode,
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+    pub width: f32,
+    pub height: f32,
+    pub monitor: Option<String>,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            mode: DisplayMode::default(),
+            x: None,
+            y: None,
+            width: crate::WINDOW_WIDTH,
+            height: crate::WINDOW_HEIGHT,
+            monitor: None,
+        }
+    }
+}
+
+// 每个游戏模式一张排行榜
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct HighScores {
+    pub boards: BTreeMap<String, Vec<u32>>,  // 模式名 -> 从高到低排列的分数
+    pub paces: BTreeMap<String, Vec<u32>>,  // 模式名 -> 最高分那一局的得分进度，见 Ledger::pace
+    #[serde(skip_serializing)]
+    scores: Vec<u32>,  // 旧版只有一张排行榜，读取后并入无尽模式
+}
+
+impl HighScores {
+    fn migrate(&mut self) {
+        for score in std::mem::take(&mut self.scores) {
+            self.submit("endless", score);
+        }
+    }
+
+    pub fn best(&self, mode: &str) -> u32 {
+        self.boards
+            .get(mode)
+            .and_then(|scores| scores.first().copied())
+            .unwrap_or(0)
+    }
+
+    // 记录一局的分数，返回是否进入了排行榜
+    pub fn submit(&mut self, mode: &str, score: u32) -> bool {
+        if score == 0 {
+            return false;
+        }
+        let scores = self.boards.entry(mode.to_string()).or_default();
+        let index = scores.partition_point(|&s| s >= score);
+        if index >= MAX_HIGH_SCORES {
+            return false;
+        }
+        scores.insert(index, score);
+        scores.truncate(MAX_HIGH_SCORES);
+        true
+    }
+}
+
+// 跨局保留的解锁进度
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Progress {
+    pub campaign_cleared: bool,  // 通关后解锁金色涂装和新游戏+
+    pub credits: u32,  // 每局按分数得到的积分，在「机体强化」里花掉
+    // 无尽模式到过的里程碑，按波次排列，每个波次只留最近的一个。
+    // 要写在 meta 前面：没有里程碑时它是一个普通的值，TOML 里的值不能跟在表后面
+    pub milestones: Vec<Milestone>,
+    pub meta: MetaUpgrades,  // 用积分买下的永久强化
+}
+
+impl Progress {
+    // 记下一个里程碑，同一波次的旧记录被替换
+    pub fn reach_milestone(&mut self, milestone: Milestone) {
+        match self.milestones.binary_search_by_key(&milestone.wave, |reached| reached.wave) {
+            Ok(index) => self.milestones[index] = milestone,
+            Err(index) => self.milestones.insert(index, milestone),
+        }
+    }
+}
+
+// 各平台的配置与存档目录
+pub struct Storage {
+    config_dir: PathBuf,
+    data_dir: PathBuf,
+}
+
+impl Storage {
+    // 便携模式下所有文件都放在可执行文件旁边，否则使用系统目录。
+    // 明确要求了便携模式却找不到可执行文件的目录时报错，不能悄悄写进系统目录
+    pub fn locate(portable: bool) -> io::Result<Self> {
+        let exe_dir = std::env::current_exe().and_then(|exe| {
+            exe.parent()
+                .map(Path::to_path_buf)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "可执行文件没有所在的目录"))
+        });
+        match exe_dir {
+            Ok(exe_dir) if portable || exe_dir.join(PORTABLE_MARKER).exists() => return Ok(Self::portable(&exe_dir)),
+            Err(e) if portable => {
+                return Err(io::Error::new(e.kind(), format!("找不到可执行文件所在的目录，无法使用便携模式: {}", e)));
+            }
+            _ => {}
+        }
+
+        let dirs = ProjectDirs::from("", "luozijian1223", "space_shooter").ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "无法确定用户主目录")
+        })?;
+        Ok(Self {
+            config_dir: dirs.config_dir().to_path_buf(),
+            data_dir: dirs.data_dir().to_path_buf(),
+        })
+    }
+
+    fn portable(exe_dir: &Path) -> Self {
+        Self::at(exe_dir.join(PORTABLE_DIR))
+    }
+
+    // 设置和存档都放在同一个目录中
+    pub fn at(dir: PathBuf) -> Self {
+        Self {
+            config_dir: dir.clone(),
+            data_dir: dir,
+        }
+    }
+
+    // 首次启动时创建目录，并写入默认设置和空的排行榜
+    pub fn init(&self) -> io::Result<()> {
+        fs::create_dir_all(&self.config_dir)?;
+        fs::create_dir_all(&self.data_dir)?;
+
+        // 存档被删掉但备份还在时不写默认值，留给加载时从备份恢复
+        let missing = |path: &Path| !path.exists() && (1..=BACKUPS).all(|n| !backup_path(path, n).exists());
+        let settings_path = self.config_dir.join(SETTINGS_FILE);
+        if missing(&settings_path) {
+            write_toml(&settings_path, &Settings::default())?;
+        }
+        let scores_path = self.data_dir.join(HIGH_SCORES_FILE);
+        if missing(&scores_path) {
+            write_toml(&scores_path, &HighScores::default())?;
+        }
+        let progress_path = self.data_dir.join(PROGRESS_FILE);
+        if missing(&progress_path) {
+            write_toml(&progress_path, &Progress::default())?;
+        }
+        let history_path = self.data_dir.join(HISTORY_FILE);
+        if missing(&history_path) {
+            write_toml(&history_path, &RunHistory::default())?;
+        }
+        let heatmap_path = self.data_dir.join(HEATMAP_FILE);
+        if missing(&heatmap_path) {
+            write_toml(&heatmap_path, &HeatMap::default())?;
+        }
+        Ok(())
+    }
+
+    // 放自制内容定义的文件夹，不存在时只有内置内容
+    pub fn mods_dir(&self) -> PathBuf {
+        self.data_dir.join(MODS_DIR)
+    }
+
+    pub fn load_settings(&self) -> io::Result<Loaded<Settings>> {
+        load_toml(&self.config_dir.join(SETTINGS_FILE))
+    }
+
+    pub fn save_settings(&self, settings: &Settings) -> io::Result<()> {
+        write_toml(&self.config_dir.join(SETTINGS_FILE), settings)
+    }
+
+    pub fn load_high_scores(&self) -> io::Result<Loaded<HighScores>> {
+        load_toml(&self.data_dir.join(HIGH_SCORES_FILE))
+    }
+
+    pub fn save_high_scores(&self, high_scores: &HighScores) -> io::Result<()> {
+        write_toml(&self.data_dir.join(HIGH_SCORES_FILE), high_scores)
+    }
+
+    pub fn load_progress(&self) -> io::Result<Loaded<Progress>> {
+        load_toml(&self.data_dir.join(PROGRESS_FILE))
+    }
+
+    pub fn save_progress(&self, progress: &Progress) -> io::Result<()> {
+        write_toml(&self.data_dir.join(PROGRESS_FILE), progress)
+    }
+
+    pub fn load_history(&self) -> io::Result<Loaded<RunHistory>> {
+        load_toml(&self.data_dir.join(HISTORY_FILE))
+    }
+
+    pub fn save_history(&self, history: &RunHistory) -> io::Result<()> {
+        write_toml(&self.data_dir.join(HISTORY_FILE), history)
+    }
+
+    pub fn load_heatmap(&self) -> io::Result<Loaded<HeatMap>> {
+        load_toml(&self.data_dir.join(HEATMAP_FILE))
+    }
+
+    pub fn save_heatmap(&self, heatmap: &HeatMap) -> io::Result<()> {
+        write_toml(&self.data_dir.join(HEATMAP_FILE), heatmap)
+    }
+
+    pub fn write_stream_overlay(&self, text: &str) -> io::Result<()> {
+        fs::write(self.data_dir.join(STREAM_FILE), text)
+    }
+
+    // 把 files 中的 (扩展名, 内容) 写成同名的几个文件，返回所在目录
+    pub fn export(&self, name: &str, files: &[(&str, String)]) -> io::Result<PathBuf> {
+        let dir = self.data_dir.join(EXPORT_DIR);
+        fs::create_dir_all(&dir)?;
+        for (extension, contents) in files {
+            fs::write(dir.join(format!("{}.{}", name, extension)), contents)?;
+        }
+        Ok(dir)
+    }
+}
+
+// 当前时间，Unix 秒，用于导出文件名和历史记录
+pub fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+// 读出的存档，restored 表示原文件损坏或丢失、内容来自备份
+pub struct Loaded<T> {
+    pub value: T,
+    pub restored: bool,
+}
+
+// FNV-1a 校验和，只用来发现写了一半或被损坏的文件
+pub fn checksum(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// 在内容前加上校验和一行
+pub fn seal(text: &str) -> String {
+    format!("{}{:016x}\n{}", CHECKSUM_PREFIX, checksum(text), text)
+}
+
+// 检查校验和并去掉这一行。没有校验和的旧版存档原样接受
+pub fn unseal(text: &str) -> io::Result<&str> {
+    let Some(rest) = text.strip_prefix(CHECKSUM_PREFIX) else {
+        return Ok(text);
+    };
+    let (expected, body) = rest.split_once('\n').unwrap_or((rest, ""));
+    if u64::from_str_radix(expected.trim(), 16).ok() != Some(checksum(body)) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "校验和不符，文件已损坏"));
+    }
+    Ok(body)
+}
+
+// 第 n 份备份，1 是最近的一份
+fn backup_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}.bak", n));
+    PathBuf::from(name)
+}
+
+fn read_toml<T: DeserializeOwned>(path: &Path) -> io::Result<T> {
+    let text = fs::read_to_string(path)?;
+    toml::from_str(unseal(&text)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+// 读取存档，失败时依次尝试备份，用第一份完好的备份覆盖原文件。
+// 损坏的原文件改名为 .corrupt 留着，不会被之后的保存覆盖掉；全部失败时返回原文件的错误
+fn load_toml<T: DeserializeOwned>(path: &Path) -> io::Result<Loaded<T>> {
+    let error = match read_toml(path) {
+        Ok(value) => return Ok(Loaded { value, restored: false }),
+        Err(e) => e,
+    };
+    for n in 1..=BACKUPS {
+        let backup = backup_path(path, n);
+        if let Ok(value) = read_toml(&backup) {
+            if path.exists() {
+                fs::rename(path, path.with_extension("corrupt"))?;
+            }
+            fs::copy(&backup, path)?;
+            return Ok(Loaded { value, restored: true });
+        }
+    }
+    if path.exists() && error.kind() == io::ErrorKind::InvalidData {
+        fs::rename(path, path.with_extension("corrupt"))?;
+    }
+    Err(error)
+}
+
+// 取出读到的内容，来自备份时记下是哪个存档
+fn recover<T>(loaded: io::Result<Loaded<T>>, label: &'static str, restored: &mut Vec<&'static str>) -> io::Result<T> {
+    let loaded = loaded?;
+    if loaded.restored {
+        restored.push(label);
+    }
+    Ok(loaded.value)
+}
+
+// 先写到临时文件再改名，写到一半退出也不会留下残缺的存档。
+// 原文件完好时轮换进备份，损坏的原文件不会挤掉完好的备份
+fn write_toml<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    let text = toml::to_string_pretty(value)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let temp = path.with_extension("tmp");
+    fs::write(&temp, seal(&text))?;
+    if fs::read_to_string(path).is_ok_and(|old| unseal(&old).is_ok()) {
+        for n in (1..BACKUPS).rev() {
+            let older = backup_path(path, n);
+            if older.exists() {
+                fs::rename(&older, backup_path(path, n + 1))?;
+            }
+        }
+        fs::rename(path, backup_path(path, 1))?;
+    }
+    fs::rename(&temp, path)
+}
+
+// 当前玩家的设置与记录；存档目录不可用时只保存在内存中
+#[derive(Default)]
+pub struct Profile {
+    pub storage: Option<Storage>,
+    pub settings: Settings,
+    pub high_scores: HighScores,
+    pub progress: Progress,
+    pub history: RunHistory,  // 最近若干局的摘要
+    pub heatmap: HeatMap,  // 跨局累计的受击位置
+    pub restored: Vec<&'static str>,  // 启动时损坏、已从备份恢复的存档
+}
+
+impl Profile {
+    // 加载失败时不会中断游戏，而是返回一条给玩家看的错误信息
+    pub fn load(portable: bool) -> (Self, Option<String>) {
+        let storage = match Storage::locate(portable).and_then(|storage| {
+            storage.init()?;
+            Ok(storage)
+        }) {
+            Ok(storage) => storage,
+            Err(e) => {
+                return (Self::default(), Some(format!("无法创建存档目录: {}", e)));
+            }
+        };
+
+        let mut error = None;
+        let mut restored = Vec::new();
+        let settings = recover(storage.load_settings(), "设置", &mut restored).unwrap_or_else(|e| {
+            error = Some(format!("设置文件读取失败，已使用默认设置: {}", e));
+            Settings::default()
+        });
+        let mut high_scores = recover(storage.load_high_scores(), "排行榜", &mut restored).unwrap_or_else(|e| {
+            error = Some(format!("排行榜读取失败: {}", e));
+            HighScores::default()
+        });
+        high_scores.migrate();
+        let progress = recover(storage.load_progress(), "解锁进度", &mut restored).unwrap_or_else(|e| {
+            error = Some(format!("解锁进度读取失败: {}", e));
+            Progress::default()
+        });
+        let history = recover(storage.load_history(), "历史记录", &mut restored).unwrap_or_else(|e| {
+            error = Some(format!("历史记录读取失败: {}", e));
+            RunHistory::default()
+        });
+        let heatmap = recover(storage.load_heatmap(), "受击热图", &mut restored).unwrap_or_else(|e| {
+            error = Some(format!("受击热图读取失败: {}", e));
+            HeatMap::default()
+        });
+
+        let profile = Self {
+            storage: Some(storage),
+            settings,
+            high_scores,
+            progress,
+            history,
+            heatmap,
+            restored,
+        };
+        (profile, error)
+    }
+
+    // 记录本局分数并写入磁盘，失败时返回错误信息。刷新最高分时同时保存这一局的得分进度
+    pub fn record_score(&mut self, mode: &str, score: u32, pace: Vec<u32>) -> Option<String> {
+        let personal_best = score > self.high_scores.best(mode);
+        if !self.high_scores.submit(mode, score) {
+            return None;
+        }
+        if personal_best {
+            self.high_scores.paces.insert(mode.to_string(), pace);
+        }
+        let storage = self.storage.as_ref()?;
+        storage
+            .save_high_scores(&self.high_scores)
+            .err()
+            .map(|e| format!("排行榜保存失败: {}", e))
+    }
+
+    pub fn save_settings(&self) -> Option<String> {
+        let storage = self.storage.as_ref()?;
+        storage
+            .save_settings(&self.settings)
+            .err()
+            .map(|e| format!("设置保存失败: {}", e))
+    }
+
+    pub fn save_progress(&self) -> Option<String> {
+        let storage = self.storage.as_ref()?;
+        storage
+            .save_progress(&self.progress)
+            .err()
+            .map(|e| format!("解锁进度保存失败: {}", e))
+    }
+
+    // 把一局的摘要加入历史记录并写入磁盘
+    pub fn record_run(&mut self, run: RunSummary) -> Option<String> {
+        self.history.push(run);
+        let storage = self.storage.as_ref()?;
+        storage
+            .save_history(&self.history)
+            .err()
+            .map(|e| format!("历史记录保存失败: {}", e))
+    }
+
+    // 在受击热图上记一次被打中并写入磁盘，death 为这一下失去了最后一条命
+    pub fn record_hit(&mut self, position: Vec2, size: Vec2, death: bool) -> Option<String> {
+        self.heatmap.record(position, size, death);
+        let storage = self.storage.as_ref()?;
+        storage
+            .save_heatmap(&self.heatmap)
+            .err()
+            .map(|e| format!("受击热图保存失败: {}", e))
+    }
+
+    // 导出一局的得分记录（CSV 和 JSON 各一份），返回导出到的目录
+    pub fn write_stream_overlay(&self, text: &str) -> Option<String> {
+        let storage = self.storage.as_ref()?;
+        storage
+            .write_stream_overlay(text)
+            .err()
+            .map(|e| format!("直播文本文件写入失败: {}", e))
+    }
+
+    pub fn export_ledger(&self, mode: &str, ledger: &Ledger) -> Result<PathBuf, String> {
+        let storage = self.storage.as_ref().ok_or("存档目录不可用，无法导出得分记录")?;
+        let name = format!("ledger-{}-{}", mode, unix_time());
+        storage
+            .export(&name, &[("csv", ledger.to_csv()), ("json", ledger.to_json())])
+            .map_err(|e| format!("得分记录导出失败: {}", e))
+    }
+}
@@ -0,0 +1,180 @@
+#[cfg(feature = "state-socket")]
+use std::io::{self, Write};
+#[cfg(feature = "state-socket")]
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+
+use ggez::graphics::Rect;
+use serde::{Deserialize, Serialize};
+
+use crate::world::World;
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+
+const WEBCAM_WIDTH: f32 = 240.0;
+const WEBCAM_HEIGHT: f32 = 180.0;
+
+// 直播时留给摄像头画面的角落
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+// 摄像头角落在画面中的范围，游戏画面在这里涂黑，HUD 会避开
+pub fn webcam_rect(corner: Corner) -> Rect {
+    let x = match corner {
+        Corner::TopLeft | Corner::BottomLeft => 0.0,
+        Corner::TopRight | Corner::BottomRight => WINDOW_WIDTH - WEBCAM_WIDTH,
+    };
+    let y = match corner {
+        Corner::TopLeft | Corner::TopRight => 0.0,
+        Corner::BottomLeft | Corner::BottomRight => WINDOW_HEIGHT - WEBCAM_HEIGHT,
+    };
+    Rect::new(x, y, WEBCAM_WIDTH, WEBCAM_HEIGHT)
+}
+
+// 给 OBS 的文本源读取的内容，每项一行
+pub fn overlay_text(world: &World) -> String {
+    format!(
+        "模式: {}\n分数: {}\n波次: {}\n生命: {}\n",
+        world.mode.label(),
+        world.score,
+        world.wave(),
+        world.player.lives
+    )
+}
+
+// 只在内容变化时才重写文件，避免每帧都写磁盘
+#[derive(Default)]
+pub struct OverlayFile {
+    written: Option<String>,
+}
+
+impl OverlayFile {
+    // 返回需要写入的新内容，和上次写入的一样时返回 None
+    pub fn update(&mut self, world: &World) -> Option<String> {
+        let text = overlay_text(world);
+        if self.written.as_ref() == Some(&text) {
+            return None;
+        }
+        self.written = Some(text.clone());
+        Some(text)
+    }
+}
+
+// 头目的血量，只在头目在场时出现
+#[derive(Serialize, PartialEq, Debug)]
+pub struct BossState {
+    pub name: String,
+    pub hp: u32,
+    pub max_hp: u32,
+}
+
+// 推给外部工具的一帧游戏状态，序列化成一行 JSON
+#[derive(Serialize, PartialEq, Debug)]
+pub struct StateSnapshot {
+    pub mode: &'static str,
+    pub score: u32,
+    pub wave: u32,
+    pub lives: u32,
+    pub bombs: u32,
+    pub boss: Option<BossState>,
+    pub game_over: bool,
+}
+
+impl StateSnapshot {
+    pub fn new(world: &World) -> Self {
+        Self {
+            mode: world.mode.key(),
+            score: world.score,
+            wave: world.wave(),
+            lives: world.player.lives,
+            bombs: world.player.bombs,
+            boss: world.boss.as_ref().map(|boss| BossState {
+                name: boss.spec.name.clone(),
+                hp: boss.hp,
+                max_hp: boss.spec.hp,
+            }),
+            game_over: world.game_over,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+// 只读的本地状态接口：在 127.0.0.1 上监听 TCP 端口，状态变化时给每个连上的客户端发一行 JSON，
+// 新连上的客户端会先收到当前状态。直播插件、串流小部件或 LED 灯条可以据此做出反应。
+// 不读取客户端发来的任何内容，只有连接出错的客户端才断开
+#[cfg(feature = "state-socket")]
+pub struct StateServer {
+    listener: TcpListener,
+    clients: Vec<Client>,
+    last: Option<String>,
+}
+
+// 一个连上的客户端。发送缓冲区满时一行只能发出一部分，剩下的下一帧接着发，
+// 每行总是完整地发出去；其间又有新状态时只留最新的一行，中间的状态跳过
+#[cfg(feature = "state-socket")]
+struct Client {
+    stream: TcpStream,
+    sending: Vec<u8>,      // 正在发的这一行还没发出去的部分
+    next: Option<String>,  // 这一行发完之后要发的最新状态
+}
+
+#[cfg(feature = "state-socket")]
+impl Client {
+    // 尽量发出排着的内容，连接出错时返回 false
+    fn flush(&mut self) -> bool {
+        loop {
+            if self.sending.is_empty() {
+                match self.next.take() {
+                    Some(line) => self.sending = line.into_bytes(),
+                    None => return true,
+                }
+            }
+            match self.stream.write(&self.sending) {
+                Ok(0) => return false,
+                Ok(written) => {
+                    self.sending.drain(..written);
+                }
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => return true,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(_) => return false,
+            }
+        }
+    }
+}
+
+#[cfg(feature = "state-socket")]
+impl StateServer {
+    pub fn bind(port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        listener.set_nonblocking(true)?;
+        Ok(Self { listener, clients: Vec::new(), last: None })
+    }
+
+    pub fn port(&self) -> Option<u16> {
+        self.listener.local_addr().ok().map(|address| address.port())
+    }
+
+    // 每帧调用一次，不会阻塞游戏
+    pub fn publish(&mut self, world: &World) {
+        let line = StateSnapshot::new(world).to_json() + "\n";
+        while let Ok((stream, _)) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_ok() && stream.set_nodelay(true).is_ok() {
+                self.clients.push(Client { stream, sending: Vec::new(), next: Some(line.clone()) });
+            }
+        }
+        if self.last.as_ref() != Some(&line) {
+            for client in &mut self.clients {
+                client.next = Some(line.clone());
+            }
+            self.last = Some(line);
+        }
+        self.clients.retain_mut(Client::flush);
+    }
+}
@@ -0,0 +1,171 @@
+use ggez::glam::Vec2;
+use rand::{rngs::StdRng, Rng};
+
+use crate::enemy::Enemy;
+use crate::playfield::{Boundary, PlayField};
+
+const COLUMNS: usize = 10;
+const ROWS: usize = 4;
+const COLUMN_GAP: f32 = 50.0;
+const ROW_GAP: f32 = 40.0;
+const GRID_TOP: f32 = 40.0;  // 网格第一行离 HUD 下沿的距离
+const SWAY: f32 = 40.0;  // 整个网格左右摆动的幅度
+const SWAY_SPEED: f32 = 0.8;
+const ENTRY_TIME: f32 = 2.5;  // 沿入场曲线飞进网格用的秒数
+const ENTRY_GAP: f32 = 0.12;  // 同一组里前后两架敌机入场相差的进度
+const DIVE_TIME: f32 = 2.4;  // 俯冲一趟用的秒数
+const DIVE_INTERVAL: f32 = 3.0;  // 平均每隔多少秒有一架敌机离队俯冲
+
+// 三次贝塞尔曲线，入场和俯冲的路线
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Bezier {
+    pub points: [Vec2; 4],
+}
+
+impl Bezier {
+    // t 在 0 到 1 之间，超出范围时停在两端
+    pub fn point(&self, t: f32) -> Vec2 {
+        let t = t.clamp(0.0, 1.0);
+        let u = 1.0 - t;
+        let [a, b, c, d] = self.points;
+        a * (u * u * u) + b * (3.0 * u * u * t) + c * (3.0 * u * t * t) + d * (t * t * t)
+    }
+}
+
+// 编队中的一架敌机正在做什么
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Stage {
+    // 沿曲线飞向网格中的位置，曲线的终点跟着网格移动；progress 小于 0 时还在屏幕外排队
+    Entering { path: Bezier, progress: f32 },
+    Holding,  // 待在网格里，跟着整体左右摆动
+    // 离队俯冲，从玩家身边掠过飞出底部，再从顶部飞回原来的位置
+    Diving { path: Bezier, progress: f32 },
+}
+
+// 编队敌机各自的状态：在网格中的位置和当前阶段
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Flight {
+    pub slot: usize,
+    pub stage: Stage,
+}
+
+// Galaga 式的编队：一组组敌机沿曲线飞进屏幕上方的网格，之后不时有一架离队俯冲。
+// 敌机本身仍放在 World::enemies 中，这里记录网格的摆动和俯冲的节奏，并统一计算编队敌机的位置
+#[derive(Clone)]
+pub struct Swarm {
+    time: f32,
+    dive_timer: f32,
+}
+
+impl Default for Swarm {
+    fn default() -> Self {
+        Self { time: 0.0, dive_timer: DIVE_INTERVAL }
+    }
+}
+
+impl Swarm {
+    // 网格中一个位置当前的坐标
+    pub fn slot_position(&self, field: &PlayField, slot: usize) -> Vec2 {
+        let (row, column) = (slot / COLUMNS, slot % COLUMNS);
+        let center = field.width / 2.0 + (self.time * SWAY_SPEED).sin() * SWAY;
+        Vec2::new(
+            center + (column as f32 - (COLUMNS - 1) as f32 / 2.0) * COLUMN_GAP,
+            field.hud_top + GRID_TOP + row as f32 * ROW_GAP,
+        )
+    }
+
+    // 给新来的一组敌机分配网格里空着的位置，让它们从屏幕一侧的上方排成一串沿曲线入场。
+    // 网格满了之后多出来的敌机不加入
+    pub fn enlist(&self, field: &PlayField, enemies: &[Enemy], recruits: Vec<Enemy>, from_left: bool) -> Vec<Enemy> {
+        let taken: Vec<usize> = enemies.iter().filter_map(|enemy| enemy.flight.map(|flight| flight.slot)).collect();
+        let free = (0..COLUMNS * ROWS).filter(|slot| !taken.contains(slot));
+        let side = if from_left { 1.0 } else { -1.0 };
+        let start = Vec2::new(field.width / 2.0 - side * (field.width / 2.0 + 40.0), field.hud_top);
+        let path = Bezier {
+            points: [
+                start,
+                Vec2::new(field.width / 2.0 + side * field.width * 0.3, field.height * 0.6),
+                Vec2::new(field.width / 2.0 - side * field.width * 0.3, field.height * 0.35),
+                start,  // 终点每帧换成网格中的位置
+            ],
+        };
+        recruits
+            .into_iter()
+            .zip(free)
+            .enumerate()
+            .map(|(order, (mut enemy, slot))| {
+                enemy.game_object.position = start;
+                enemy.game_object.velocity = Vec2::ZERO;
+                enemy.boundary = Boundary::FREE;
+                enemy.flight = Some(Flight { slot, stage: Stage::Entering { path, progress: -(order as f32) * ENTRY_GAP } });
+                enemy
+            })
+            .collect()
+    }
+
+    // 推进网格的摆动，按时让一架待命的敌机俯冲，再把所有编队敌机移到各自路线上的位置。
+    // 减速时飞得慢，眩晕期间停在原地
+    pub fn update(&mut self, dt: f32, field: &PlayField, enemies: &mut [Enemy], player: Vec2, rng: &mut StdRng) {
+        self.time += dt;
+        self.dive_timer -= dt;
+        if self.dive_timer <= 0.0 {
+            let holding: Vec<usize> = (0..enemies.len())
+                .filter(|&index| enemies[index].flight.is_some_and(|flight| flight.stage == Stage::Holding))
+                .collect();
+            if !holding.is_empty() {
+                let enemy = &mut enemies[holding[rng.gen_range(0..holding.len())]];
+                if let Some(flight) = &mut enemy.flight {
+                    flight.stage = Stage::Diving { path: dive_path(enemy.game_object.position, player, field), progress: 0.0 };
+                }
+                self.dive_timer = rng.gen_range(0.5..1.5) * DIVE_INTERVAL;
+            }
+        }
+
+        for enemy in enemies {
+            let Some(flight) = &mut enemy.flight else {
+                continue;
+            };
+            if enemy.status.stunned() {
+                continue;
+            }
+            let dt = dt * enemy.status.speed_factor();
+            let slot = self.slot_position(field, flight.slot);
+            let position = &mut enemy.game_object.position;
+            match &mut flight.stage {
+                Stage::Entering { path, progress } => {
+                    *progress += dt / ENTRY_TIME;
+                    path.points[3] = slot;
+                    *position = path.point(*progress);
+                    if *progress >= 1.0 {
+                        flight.stage = Stage::Holding;
+                    }
+                }
+                Stage::Holding => *position = slot,
+                Stage::Diving { path, progress } => {
+                    *progress += dt / DIVE_TIME;
+                    *position = path.point(*progress);
+                    if *progress >= 1.0 {
+                        // 从底部飞出后回到顶部，直着飞回网格
+                        let top = Vec2::new(slot.x, field.spawn_y(enemy.game_object.size.y));
+                        *position = top;
+                        let path = Bezier { points: [top, top.lerp(slot, 1.0 / 3.0), top.lerp(slot, 2.0 / 3.0), slot] };
+                        flight.stage = Stage::Entering { path, progress: 0.0 };
+                    }
+                }
+            }
+        }
+    }
+}
+
+// 俯冲路线：先向外侧往上兜一个小圈，再冲向玩家当前的位置，从玩家身边飞出底部
+fn dive_path(from: Vec2, player: Vec2, field: &PlayField) -> Bezier {
+    let side = if from.x < player.x { 1.0 } else { -1.0 };
+    Bezier {
+        points: [
+            from,
+            from + Vec2::new(-side * 60.0, -80.0),
+            Vec2::new(player.x, player.y - 60.0),
+            Vec2::new(player.x + side * 120.0, field.height + 60.0),
+        ],
+    }
+}
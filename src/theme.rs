@@ -0,0 +1,133 @@
+use ggez::graphics::Color;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// 配色界面左右切换时依次经过的颜色
+pub const PALETTE: [(&str, Rgb); 9] = [
+    ("白色", Rgb(255, 255, 255)),
+    ("黄色", Rgb(255, 255, 0)),
+    ("橙色", Rgb(255, 140, 26)),
+    ("红色", Rgb(255, 64, 64)),
+    ("粉色", Rgb(255, 128, 200)),
+    ("紫色", Rgb(170, 110, 255)),
+    ("蓝色", Rgb(80, 140, 255)),
+    ("青色", Rgb(0, 255, 255)),
+    ("绿色", Rgb(80, 230, 110)),
+];
+
+// 一种颜色，存盘时写成 "#RRGGBB"
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    // 解析玩家输入的十六进制颜色，开头的 # 可以省略
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let hex = text.trim().trim_start_matches('#');
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("颜色应为 6 位十六进制数，例如 #FFAA00: {}", text));
+        }
+        let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).unwrap_or(0);
+        Ok(Self(channel(0), channel(2), channel(4)))
+    }
+
+    pub fn to_hex(self) -> String {
+        format!("#{:02X}{:02X}{:02X}", self.0, self.1, self.2)
+    }
+
+    pub fn color(self) -> Color {
+        Color::from_rgb(self.0, self.1, self.2)
+    }
+
+    // 调色板里的名字，自己输入的颜色显示十六进制
+    pub fn label(self) -> String {
+        PALETTE
+            .iter()
+            .find(|&&(_, rgb)| rgb == self)
+            .map_or_else(|| self.to_hex(), |&(name, _)| name.to_string())
+    }
+
+    // 调色板里的下一种颜色，不在调色板里的颜色从头开始
+    pub fn cycle(self, step: isize) -> Self {
+        let count = PALETTE.len() as isize;
+        let index = match PALETTE.iter().position(|&(_, rgb)| rgb == self) {
+            Some(index) => (index as isize + step).rem_euclid(count),
+            None if step < 0 => count - 1,
+            None => 0,
+        };
+        PALETTE[index as usize].1
+    }
+}
+
+impl Serialize for Rgb {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl<'de> Deserialize<'de> for Rgb {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Self::parse(&text).map_err(serde::de::Error::custom)
+    }
+}
+
+// 玩家可以换颜色的部分
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ThemePart {
+    Ship,
+    Bullets,
+    Accent,  // HUD 上需要突出的文字
+}
+
+impl ThemePart {
+    pub const ALL: [ThemePart; 3] = [ThemePart::Ship, ThemePart::Bullets, ThemePart::Accent];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ThemePart::Ship => "飞船",
+            ThemePart::Bullets => "子弹",
+            ThemePart::Accent => "HUD 强调色",
+        }
+    }
+}
+
+// 界面配色，存在设置里，每个存档各有一份。绘制时从这里取颜色，不再各处写死
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(default)]
+pub struct Theme {
+    pub ship: Rgb,
+    pub bullets: Rgb,  // 主炮和激光的子弹，霰弹仍然是橙色
+    pub accent: Rgb,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self { ship: Rgb(255, 255, 255), bullets: Rgb(255, 255, 0), accent: Rgb(255, 255, 0) }
+    }
+}
+
+impl Theme {
+    pub fn get(&self, part: ThemePart) -> Rgb {
+        match part {
+            ThemePart::Ship => self.ship,
+            ThemePart::Bullets => self.bullets,
+            ThemePart::Accent => self.accent,
+        }
+    }
+
+    pub fn set(&mut self, part: ThemePart, rgb: Rgb) {
+        match part {
+            ThemePart::Ship => self.ship = rgb,
+            ThemePart::Bullets => self.bullets = rgb,
+            ThemePart::Accent => self.accent = rgb,
+        }
+    }
+
+    // 飞船没换过颜色时，通关后显示金色涂装
+    pub fn ship_color(&self, campaign_cleared: bool) -> Color {
+        if campaign_cleared && self.ship == Self::default().ship {
+            Color::new(1.0, 0.84, 0.0, 1.0)
+        } else {
+            self.ship.color()
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use ggez::event::winit_event::TouchPhase;
+use ggez::glam::Vec2;
+use ggez::graphics::Rect;
+
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+
+pub const BUTTON_RADIUS: f32 = 32.0;
+const FOLLOW_RADIUS: f32 = 24.0;  // 飞船离目标位置超过这么多像素时全速移动，更近时逐渐减速
+
+// 屏幕上的触摸按钮
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TouchButton {
+    Bomb,   // 右下角，放炸弹
+    Pause,  // 右上角，暂停
+}
+
+impl TouchButton {
+    pub const ALL: [TouchButton; 2] = [TouchButton::Bomb, TouchButton::Pause];
+
+    // 按钮圆心，在 800x600 的游戏坐标中
+    pub fn center(self) -> Vec2 {
+        match self {
+            TouchButton::Bomb => Vec2::new(WINDOW_WIDTH - 56.0, WINDOW_HEIGHT - 56.0),
+            TouchButton::Pause => Vec2::new(WINDOW_WIDTH - 40.0, 100.0),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TouchButton::Bomb => "炸弹",
+            TouchButton::Pause => "暂停",
+        }
+    }
+
+    fn at(position: Vec2) -> Option<Self> {
+        Self::ALL.into_iter().find(|button| button.center().distance(position) <= BUTTON_RADIUS)
+    }
+}
+
+// 拖动中的手指：上一次的位置和飞船要去的位置
+#[derive(Clone, Copy, Debug)]
+struct Drag {
+    finger: Vec2,
+    target: Vec2,
+}
+
+// 触屏操作：手指在屏幕上任意位置按住拖动，飞船跟着手指的位移移动（相对移动，手指不会挡住飞船），
+// 按住期间自动开火。ggez 的触摸事件不带手指编号，同一时间只跟踪一根手指
+#[derive(Default)]
+pub struct TouchControls {
+    drag: Option<Drag>,
+    used: bool,  // 收到过触摸后才在画面上画出按钮
+}
+
+impl TouchControls {
+    // 处理一次触摸，position 是游戏坐标，area 是飞船能到达的范围。按下按钮时返回这个按钮
+    pub fn touch(&mut self, phase: TouchPhase, position: Vec2, ship: Vec2, area: Rect) -> Option<TouchButton> {
+        self.used = true;
+        match phase {
+            TouchPhase::Started => {
+                if let Some(button) = TouchButton::at(position) {
+                    return Some(button);
+                }
+                self.drag = Some(Drag { finger: position, target: ship });
+            }
+            TouchPhase::Moved => {
+                if let Some(drag) = &mut self.drag {
+                    // 目标限制在场地内，飞船停在边缘后手指往回拖马上就能跟着动
+                    let target = drag.target + position - drag.finger;
+                    drag.target = target.clamp(Vec2::new(area.left(), area.top()), Vec2::new(area.right(), area.bottom()));
+                    drag.finger = position;
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => self.drag = None,
+        }
+        None
+    }
+
+    // 手指离开屏幕或游戏切到后台时松手
+    pub fn release(&mut self) {
+        self.drag = None;
+    }
+
+    // 按住屏幕时飞船的移动方向，长度超过 1 时由 move_velocity 缩放到全速
+    pub fn direction(&self, ship: Vec2) -> Vec2 {
+        self.drag.map_or(Vec2::ZERO, |drag| (drag.target - ship) / FOLLOW_RADIUS)
+    }
+
+    pub fn held(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    pub fn used(&self) -> bool {
+        self.used
+    }
+}
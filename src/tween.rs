@@ -0,0 +1,89 @@
+use ggez::glam::Vec2;
+
+// 缓动曲线
+#[derive(Clone, Copy)]
+pub enum Easing {
+    EaseOutCubic,
+    EaseInOutQuad,
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::EaseOutCubic => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOutQuad => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+        }
+    }
+}
+
+pub trait Lerp: Copy {
+    fn lerp(self, to: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for Vec2 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        Vec2::lerp(self, to, t)
+    }
+}
+
+// 在给定时间内把数值从 from 过渡到 to
+#[derive(Clone, Copy)]
+pub struct Tween<T: Lerp> {
+    from: T,
+    to: T,
+    duration: f32,
+    elapsed: f32,
+    easing: Easing,
+}
+
+impl<T: Lerp> Tween<T> {
+    pub fn new(from: T, to: T, duration: f32, easing: Easing) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            elapsed: 0.0,
+            easing,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+    }
+
+    pub fn progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            1.0
+        } else {
+            self.elapsed / self.duration
+        }
+    }
+
+    pub fn value(&self) -> T {
+        self.from.lerp(self.to, self.easing.apply(self.progress()))
+    }
+
+    pub fn finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+// 二次贝塞尔曲线上的点
+pub fn quadratic_bezier(p0: Vec2, p1: Vec2, p2: Vec2, t: f32) -> Vec2 {
+    let a = p0.lerp(p1, t);
+    let b = p1.lerp(p2, t);
+    a.lerp(b, t)
+}
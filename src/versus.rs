@@ -0,0 +1,55 @@
+use crate::bullets::Bullets;
+use crate::combo::Combo;
+use crate::Player;
+
+// 双人对战的第二架飞船：自己的生命、子弹、连击和分数，它击落的敌人只记在它自己的分数上。
+// 和第一架飞船一样只有移动、开火和冲刺，没有道具和强化
+pub struct Rival {
+    pub player: Player,
+    pub bullets: Bullets,
+    pub combo: Combo,
+    pub score: u32,
+}
+
+impl Rival {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { player: Player::new(x, y), bullets: Bullets::default(), combo: Combo::default(), score: 0 }
+    }
+
+    // 被撞到或被敌人的子弹打中，护盾条模式下先由护盾挡下；返回这一下是否打掉了最后一条命
+    pub fn hit(&mut self) -> bool {
+        if self.player.absorb_hit() {
+            return false;
+        }
+        self.player.take_damage() && self.player.lives == 0
+    }
+}
+
+// 一局对战的结果
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Winner {
+    First,
+    Second,
+    Draw,
+}
+
+impl Winner {
+    // 先被击落的一方输，两边都还活着（时间到了）或同时被击落时比分数
+    pub fn decide(first_lives: u32, first_score: u32, second_lives: u32, second_score: u32) -> Self {
+        match (first_lives == 0, second_lives == 0) {
+            (true, false) => Winner::Second,
+            (false, true) => Winner::First,
+            _ if first_score > second_score => Winner::First,
+            _ if second_score > first_score => Winner::Second,
+            _ => Winner::Draw,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Winner::First => "1P 获胜",
+            Winner::Second => "2P 获胜",
+            Winner::Draw => "平局",
+        }
+    }
+}
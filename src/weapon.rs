@@ -0,0 +1,264 @@
+use ggez::glam::Vec2;
+use ggez::graphics::Rect;
+use rand::{rngs::StdRng, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::bullets::Modifiers;
+use crate::status::StatusKind;
+use crate::GameObject;
+
+pub const CHARGE_MIN: f32 = 0.4;  // 按住超过这么多秒再松开才打出蓄力弹，短按只打普通子弹
+pub const CHARGE_FULL: f32 = 1.5;  // 蓄满需要的秒数
+const CHARGE_BONUS: f32 = 2.0;  // 蓄满时的伤害是刚蓄好时的几倍
+pub const CHARGED_SIZE: (f32, f32) = (24.0, 40.0);
+pub const CHARGED_SPEED: f32 = 600.0;
+
+// 发射子弹的武器，决定暴击参数和命中时附加的状态
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Weapon {
+    #[default]
+    Cannon,     // 主炮，连射和散射道具也算主炮
+    Laser,      // 激光：瞬间打中正前方一整列的目标
+    Scatter,    // 霰弹：一次向前方扇形打出一排弹丸
+    Drone,      // 僚机的燃烧弹
+    Guns,       // 侧后炮
+    Reflected,  // 反射护盾弹回去的水雷和敌人的子弹
+    Charged,    // 按住开火键蓄力后松开打出的蓄力弹
+}
+
+impl Weapon {
+    // 玩家可以用数字键 1 到 3 切换的主武器，按栏位排列
+    pub const LOADOUT: [Weapon; 3] = [Weapon::Cannon, Weapon::Laser, Weapon::Scatter];
+
+    // 第 slot 个栏位（从 1 开始）的主武器
+    pub fn slot(slot: u8) -> Option<Weapon> {
+        Self::LOADOUT.get((slot as usize).checked_sub(1)?).copied()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Weapon::Cannon => "主炮",
+            Weapon::Laser => "激光",
+            Weapon::Scatter => "霰弹",
+            Weapon::Drone => "僚机",
+            Weapon::Guns => "侧后炮",
+            Weapon::Reflected => "反射弹",
+            Weapon::Charged => "蓄力弹",
+        }
+    }
+
+    pub fn status(self) -> Option<StatusKind> {
+        match self {
+            Weapon::Drone => Some(StatusKind::Burn),
+            Weapon::Guns => Some(StatusKind::Slow),
+            Weapon::Cannon | Weapon::Laser | Weapon::Scatter | Weapon::Reflected | Weapon::Charged => None,
+        }
+    }
+}
+
+// 一种武器的暴击、穿甲和伤害参数，cooldown 是主武器两次开火的最短间隔（秒），
+// pierce 和 bounces 是子弹能穿过几个敌人、能在屏幕边缘反弹几次
+#[derive(Deserialize, Clone, Copy)]
+pub struct WeaponSpec {
+    pub chance: f32,
+    pub multiplier: f32,
+    pub penetration: u32,
+    pub damage: u32,
+    #[serde(default)]
+    pub cooldown: f32,
+    #[serde(default)]
+    pub pierce: u32,
+    #[serde(default)]
+    pub bounces: u32,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct WeaponBalance {
+    pub cannon: WeaponSpec,
+    pub laser: WeaponSpec,
+    pub scatter: WeaponSpec,
+    pub drone: WeaponSpec,
+    pub guns: WeaponSpec,
+    pub reflected: WeaponSpec,
+    pub charged: WeaponSpec,
+}
+
+// 主炮某一级的火力：parallel 颗子弹并排向前，spread 大于 0 时两侧再各斜射一颗，
+// fire_rate 是连射速度的倍数
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct WeaponLevel {
+    pub parallel: u32,
+    pub spread: f32,
+    pub fire_rate: f32,
+}
+
+// 激光的光束：发射的那一帧结算命中，之后只在画面上停留一小会儿
+#[derive(Clone, Copy, Debug)]
+pub struct Beam {
+    pub bounds: Rect,
+    pub remaining: f32,  // 画面上还要显示的秒数
+    duration: f32,
+    struck: bool,
+}
+
+impl Beam {
+    pub fn new(bounds: Rect, duration: f32) -> Self {
+        Self { bounds, remaining: duration, duration, struck: false }
+    }
+
+    // 还没有结算过命中时返回光束的范围，每道光束只结算一次
+    pub fn strike(&mut self) -> Option<Rect> {
+        if self.struck {
+            return None;
+        }
+        self.struck = true;
+        Some(self.bounds)
+    }
+
+    pub fn finished(&self) -> bool {
+        self.struck && self.remaining <= 0.0
+    }
+
+    // 画面上的不透明度，从 1 渐变到 0
+    pub fn fade(&self) -> f32 {
+        (self.remaining / self.duration).clamp(0.0, 1.0)
+    }
+}
+
+// 按住开火键蓄力的进度，松开时蓄够了就打出一发蓄力弹
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Charge {
+    pub held: f32,  // 已经蓄了多少秒，最多到 CHARGE_FULL
+    holding: bool,
+}
+
+impl Charge {
+    // 记下这一帧有没有按住；刚松开且蓄够了时返回伤害倍数，之后从头蓄力
+    pub fn hold(&mut self, held: bool) -> Option<f32> {
+        if held {
+            self.holding = true;
+            return None;
+        }
+        let power = (self.holding && self.ready()).then(|| self.power());
+        self.cancel();
+        power
+    }
+
+    // 松手但不发射，切到后台或重新开始时用
+    pub fn cancel(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if self.holding {
+            self.held = (self.held + dt).min(CHARGE_FULL);
+        }
+    }
+
+    pub fn ready(&self) -> bool {
+        self.held >= CHARGE_MIN
+    }
+
+    // 蓄力条的长度，0 到 1
+    pub fn ratio(&self) -> f32 {
+        self.held / CHARGE_FULL
+    }
+
+    // 刚蓄好时为 1，蓄满时为 CHARGE_BONUS
+    fn power(&self) -> f32 {
+        let progress = ((self.held - CHARGE_MIN) / (CHARGE_FULL - CHARGE_MIN)).clamp(0.0, 1.0);
+        1.0 + (CHARGE_BONUS - 1.0) * progress
+    }
+}
+
+// 飞行中的蓄力弹：又大又慢，一路打穿沿途的敌人，每个目标只打一次
+pub struct ChargedShot {
+    pub game_object: GameObject,
+    pub damage: u32,  // 已经乘过蓄力倍数的基础伤害
+    pub serial: u32,  // 敌人记下打中过自己的编号，同一发不会重复造成伤害
+    pub hit_boss: bool,
+}
+
+impl ChargedShot {
+    pub fn new(position: Vec2, damage: u32, serial: u32) -> Self {
+        let mut game_object = GameObject::new(position.x, position.y, CHARGED_SIZE.0, CHARGED_SIZE.1);
+        game_object.velocity = Vec2::new(0.0, -CHARGED_SPEED);
+        Self { game_object, damage, serial, hit_boss: false }
+    }
+}
+
+// 一次命中的结算结果
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Hit {
+    pub damage: u32,
+    pub critical: bool,
+}
+
+impl WeaponBalance {
+    fn spec(&self, weapon: Weapon) -> WeaponSpec {
+        match weapon {
+            Weapon::Cannon => self.cannon,
+            Weapon::Laser => self.laser,
+            Weapon::Scatter => self.scatter,
+            Weapon::Drone => self.drone,
+            Weapon::Guns => self.guns,
+            Weapon::Reflected => self.reflected,
+            Weapon::Charged => self.charged,
+        }
+    }
+
+    // 一次命中的基础伤害：base 是狂暴等道具决定的倍数
+    pub fn damage(&self, weapon: Weapon, base: u32) -> u32 {
+        base * self.spec(weapon).damage
+    }
+
+    pub fn cooldown(&self, weapon: Weapon) -> f32 {
+        self.spec(weapon).cooldown
+    }
+
+    // 这种武器打出的子弹带的穿透和反弹
+    pub fn modifiers(&self, weapon: Weapon) -> Modifiers {
+        let spec = self.spec(weapon);
+        Modifiers { pierce: spec.pierce, bounces: spec.bounces }
+    }
+
+    // 暴击时伤害乘以倍率后四舍五入，至少比普通命中多 1 点
+    pub fn roll(&self, weapon: Weapon, base: u32, rng: &mut StdRng) -> Hit {
+        let crit = self.spec(weapon);
+        if rng.gen::<f32>() >= crit.chance {
+            return Hit { damage: base, critical: false };
+        }
+        let damage = ((base as f32 * crit.multiplier).round() as u32).max(base + 1);
+        Hit { damage, critical: true }
+    }
+
+    // 命中带护甲的目标时实际造成的伤害：护甲先被穿甲值抵消，剩下的每点护甲挡掉 1 点伤害，
+    // 全部挡掉时这一发被弹开
+    pub fn against_armor(&self, weapon: Weapon, damage: u32, armor: u32) -> u32 {
+        damage.saturating_sub(armor.saturating_sub(self.spec(weapon).penetration))
+    }
+}
+
+// 一局中的命中和暴击统计
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(default)]
+pub struct CritStats {
+    pub hits: u32,
+    pub crits: u32,
+    pub crit_damage: u32,  // 暴击比普通命中多打出的伤害
+}
+
+impl CritStats {
+    pub fn record(&mut self, hit: Hit, base: u32) {
+        self.hits += 1;
+        if hit.critical {
+            self.crits += 1;
+            self.crit_damage += hit.damage - base;
+        }
+    }
+
+    // 暴击率，还没有命中时为 0
+    pub fn rate(&self) -> f32 {
+        if self.hits == 0 { 0.0 } else { self.crits as f32 / self.hits as f32 }
+    }
+}
@@ -0,0 +1,2026 @@
+use ggez::{glam::Vec2, graphics::Rect};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::asteroid;
+use crate::boss::{Boss, BossSpec};
+use crate::bossgen;
+use crate::bullets::Bullets;
+use crate::campaign::{self, Campaign, CampaignData, CampaignSpawn, Formation, WaveSpec};
+use crate::chat::ChatEvent;
+use crate::collision::{self, Collider};
+use crate::combo::Combo;
+use crate::continues::{Continues, KEEP_SCORE};
+use crate::difficulty::{DifficultyCurve, DifficultyPoint, DynamicRank};
+use crate::effects::Effects;
+use crate::enemy::{self, BountyBalance, Carrier, Enemy, EnemyPath, Gun, CARRIER_BONUS};
+use crate::events::{EventBus, GameEvent};
+use crate::focus::Focus;
+use crate::grid::Grid;
+use crate::health::{HealthModel, ShieldBar, SHIELD_LIVES};
+use crate::killcam::{self, KillCam};
+use crate::ledger::{Ledger, ScoreReason};
+use crate::meta::MetaUpgrades;
+use crate::milestone::{self, Milestone};
+use crate::playfield::{Boundary, PlayField};
+use crate::powerup::{Balance, Buffs, ExtraLifeBalance, Powerup, PowerupBalance, PowerupKind, SPREAD_ANGLE};
+use crate::quest::{QuestOutcome, Quests, Reward};
+use crate::profiling::Profiler;
+use crate::registry::Registry;
+use crate::modes::{BossRushMode, CampaignMode, DailyMode, EndlessMode, ModeRules, NewGamePlusMode, RandomizerMode, TimeAttackMode, VersusMode};
+use crate::scoring::ScoringRules;
+use crate::shop::{self, ShopItem, Upgrades};
+use crate::status::{Immunity, StatusKind};
+use crate::swarm::Swarm;
+use crate::versus::{Rival, Winner};
+use crate::weapon::{Beam, Charge, ChargedShot, CritStats, Weapon, WeaponBalance, WeaponLevel};
+use crate::{GameObject, Player, MAX_BOMBS, PLAYER_SPEED, START_LIVES};
+
+const BULLET_SPEED: f32 = 400.0;
+const POWERUP_SEED: u64 = 0x9e37_79b9_7f4a_7c15;  // 道具使用单独的随机数，不打乱敌人的分布
+const CRIT_SEED: u64 = 0xc2b2_ae3d_27d4_eb4f;  // 暴击也单独使用一组随机数
+const ASTEROID_SEED: u64 = 0x1656_67b1_9e37_79f9;  // 小行星也单独使用一组随机数
+const BOUNTY_SEED: u64 = 0x27d4_eb2f_1656_67c5;  // 悬赏目标也单独使用一组随机数
+const EMP_RADIUS: f32 = 160.0;  // 电磁脉冲的作用范围
+const GRAZE_MARGIN: f32 = 16.0;  // 擦弹框比飞船每边大出的像素，敌人或子弹碰到擦弹框又没打中时算擦弹
+const EMP_STUN: f32 = 3.0;  // 被电磁脉冲击中后眩晕的秒数
+const EMP_COOLDOWN: f32 = 8.0;
+const BOMB_RADIUS: f32 = 900.0;  // 炸弹冲击波扩散到的半径，足够盖住整个画面
+const ENEMY_BULLET_SPEED: f32 = 260.0;
+const SWARM_POINTS: u32 = 30;  // 编队敌机会俯冲，比普通敌机值钱
+const PARALLEL_GAP: f32 = 10.0;  // 火力升级后并排子弹之间的距离
+const SCATTER_PELLETS: u32 = 5;  // 霰弹每次打出的弹丸数
+const SCATTER_STEP: f32 = 0.14;  // 相邻两颗霰弹之间的夹角（弧度）
+const LASER_WIDTH: f32 = 12.0;
+const LASER_FLASH: f32 = 0.15;  // 激光光束在画面上停留的秒数
+const DRONE_BURN: f32 = 2.0;  // 僚机的燃烧弹让目标燃烧的秒数
+const GUNS_SLOW: f32 = 2.0;  // 侧后炮的子弹让目标减速的秒数
+const BOSS_SCORE_INTERVAL: u32 = 500;  // 无尽模式每得这么多分出现一个头目
+const WAVES_PER_BOSS: u32 = 10;  // 头目生成器按每 10 波一个头目来配置强度
+const ENDLESS_WAVE_SIZE: u32 = 10;  // 无尽模式没有波次，每出现这么多敌人算一波
+const ELITE_SIZE: f32 = 48.0;
+const METEORS: u32 = 8;  // 一阵流星雨的石块数
+const METEOR_SIZE: f32 = 16.0;
+const CARRIER_ID: &str = "enemy.carrier";  // 无尽模式定时出现的母舰
+pub const LIFE_BONUS: u32 = 1000;  // 通关时每条剩余生命的奖励
+pub const FIXED_STEP: f32 = 1.0 / 60.0;  // 无窗口运行和回放使用的固定步长
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameMode {
+    Endless,      // 不断出现敌人，按分数出现头目
+    Campaign,     // 按关卡数据推进，击败最终头目后通关
+    NewGamePlus,  // 通关后解锁，强化过的战役
+    Randomizer,   // 按种子随机生成的关卡
+    TimeAttack,   // 限时三分钟，比谁的分数高
+    BossRush,     // 通关后解锁，只有头目一个接一个登场
+    Daily,        // 每天一套所有人相同的关卡
+    Versus,       // 两名玩家同屏比分数
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 8] = [
+        GameMode::Endless,
+        GameMode::Campaign,
+        GameMode::NewGamePlus,
+        GameMode::Randomizer,
+        GameMode::TimeAttack,
+        GameMode::BossRush,
+        GameMode::Daily,
+        GameMode::Versus,
+    ];
+
+    // 排行榜中使用的名字
+    pub fn key(self) -> &'static str {
+        match self {
+            GameMode::Endless => "endless",
+            GameMode::Campaign => "campaign",
+            GameMode::NewGamePlus => "new_game_plus",
+            GameMode::Randomizer => "randomizer",
+            GameMode::TimeAttack => "time_attack",
+            GameMode::BossRush => "boss_rush",
+            GameMode::Daily => "daily",
+            GameMode::Versus => "versus",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.key() == key)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GameMode::Endless => "无尽模式",
+            GameMode::Campaign => "战役模式",
+            GameMode::NewGamePlus => "新游戏+",
+            GameMode::Randomizer => "随机波次",
+            GameMode::TimeAttack => "计时赛",
+            GameMode::BossRush => "头目车轮战",
+            GameMode::Daily => "每日挑战",
+            GameMode::Versus => "双人对战",
+        }
+    }
+
+    // 这个模式的玩法规则。GameMode 只是存档、回放和分享码里记录的名字，玩法都在规则里
+    pub fn rules(self) -> Box<dyn ModeRules> {
+        match self {
+            GameMode::Endless => Box::new(EndlessMode),
+            GameMode::Campaign => Box::new(CampaignMode),
+            GameMode::NewGamePlus => Box::new(NewGamePlusMode),
+            GameMode::Randomizer => Box::new(RandomizerMode),
+            GameMode::TimeAttack => Box::new(TimeAttackMode),
+            GameMode::BossRush => Box::new(BossRushMode),
+            GameMode::Daily => Box::new(DailyMode),
+            GameMode::Versus => Box::new(VersusMode),
+        }
+    }
+}
+
+// 失去最后一条生命的原因
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DeathCause {
+    Collision,  // 撞上敌人
+    Escaped,    // 敌人突破了防线
+    Boss,       // 撞上头目
+}
+
+impl DeathCause {
+    pub fn label(self) -> &'static str {
+        match self {
+            DeathCause::Collision => "撞上敌人",
+            DeathCause::Escaped => "敌人突破防线",
+            DeathCause::Boss => "撞上头目",
+        }
+    }
+}
+
+// 每次伤害的具体来源，比死因更细，结算画面据此给出针对性的提示
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DamageSource {
+    Straight,  // 直线下落的敌人
+    Drift,     // 斜飞的敌人
+    Zigzag,    // 蛇行的敌人
+    Diver,     // 俯冲的敌人
+    Shot,      // 敌人发射的子弹
+    Mine,      // 头目炮台发射的水雷
+    Boss,      // 头目本体
+    Escaped,   // 飞出屏幕底部的敌人
+    Asteroid,  // 中立的小行星
+    Swarm,     // 编队中离队俯冲的敌机
+    Kamikaze,  // 锁定玩家后冲过来的自爆机
+    Carrier,   // 放出小飞机的母舰
+    Minion,    // 母舰放出的小飞机
+    Splitter,  // 击落时一分为二的敌人
+}
+
+impl DamageSource {
+    pub fn from_path(path: EnemyPath) -> Self {
+        match path {
+            EnemyPath::Straight => DamageSource::Straight,
+            EnemyPath::Drift => DamageSource::Drift,
+            EnemyPath::Zigzag => DamageSource::Zigzag,
+            EnemyPath::Diver => DamageSource::Diver,
+            EnemyPath::Kamikaze => DamageSource::Kamikaze,
+        }
+    }
+
+    pub fn cause(self) -> DeathCause {
+        match self {
+            DamageSource::Straight
+            | DamageSource::Drift
+            | DamageSource::Zigzag
+            | DamageSource::Diver
+            | DamageSource::Shot
+            | DamageSource::Mine
+            | DamageSource::Asteroid
+            | DamageSource::Swarm
+            | DamageSource::Kamikaze
+            | DamageSource::Carrier
+            | DamageSource::Minion
+            | DamageSource::Splitter => {
+                DeathCause::Collision
+            }
+            DamageSource::Boss => DeathCause::Boss,
+            DamageSource::Escaped => DeathCause::Escaped,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DamageSource::Straight => "直行的敌人",
+            DamageSource::Drift => "斜飞的敌人",
+            DamageSource::Zigzag => "蛇行的敌人",
+            DamageSource::Diver => "俯冲的敌人",
+            DamageSource::Shot => "敌人的子弹",
+            DamageSource::Mine => "头目的水雷",
+            DamageSource::Boss => "头目",
+            DamageSource::Escaped => "突破防线的敌人",
+            DamageSource::Asteroid => "小行星",
+            DamageSource::Swarm => "编队的敌机",
+            DamageSource::Kamikaze => "自爆机",
+            DamageSource::Carrier => "母舰",
+            DamageSource::Minion => "母舰放出的小飞机",
+            DamageSource::Splitter => "分裂的敌人",
+        }
+    }
+
+    // 结算画面上针对这种死法的提示
+    pub fn tip(self) -> &'static str {
+        match self {
+            DamageSource::Straight => "直行的敌人不会转向，看准落点提前让开",
+            DamageSource::Drift => "斜飞的敌人碰到屏幕边缘会折返，留意它的回头路线",
+            DamageSource::Zigzag => "蛇行的敌人左右各摆动 60 像素，躲到摆幅之外",
+            DamageSource::Diver => "俯冲的敌人进场时会停顿一下，趁它减速时击落",
+            DamageSource::Shot => "瞄准型的敌人朝你当前的位置开炮，开炮后横向移动就能躲开",
+            DamageSource::Mine => "水雷可以被击落，头目开炮后先清掉正上方的水雷",
+            DamageSource::Boss => "头目只在屏幕上方三分之一活动，不要贴上去",
+            DamageSource::Escaped => "漏掉的敌人也会扣生命，优先击落飞得最低的敌人",
+            DamageSource::Asteroid => "小行星要打好几发才碎，碎块会向两侧散开，别贴着它打",
+            DamageSource::Swarm => "离队的敌机会先向外兜一个圈再冲向你，看到它兜圈就横着躲开",
+            DamageSource::Kamikaze => "自爆机只朝锁定时你所在的位置冲，看到红色预警线就横着让开",
+            DamageSource::Carrier => "母舰又大又慢，但会一直放出小飞机，先集中火力把它打掉",
+            DamageSource::Minion => "小飞机从母舰两侧斜着冲下来，别待在母舰的正下方",
+            DamageSource::Splitter => "绿色的敌人被击落后会分成两个更快的，别在它正下方把它打碎",
+        }
+    }
+}
+
+// 各类物体同时存在的上限，超出时逐步降级，避免炸弹加头目爆炸时帧率崩掉：
+// 子弹和敌人不再新增，爆炸碎片淘汰最早的，得分提示合并到最近的一个
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct EntityLimits {
+    pub bullets: usize,
+    pub enemies: usize,
+    pub particles: usize,
+    pub popups: usize,
+}
+
+impl Default for EntityLimits {
+    fn default() -> Self {
+        Self {
+            bullets: 200,
+            enemies: 150,
+            particles: 600,
+            popups: 12,
+        }
+    }
+}
+
+// 物体所属的阵营，时间按阵营分别缩放
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Faction {
+    Player,  // 玩家、僚机和玩家的子弹
+    Enemy,   // 敌人、头目、水雷以及它们的生成
+}
+
+// 每一波开始时的状态，辅助模式下死亡后可以从这里重来。
+// 随机数也一起保存，重来的这一波和原来完全相同
+struct WaveCheckpoint {
+    wave: u32,
+    score: u32,
+    ledger_len: usize,
+    lives: u32,
+    shield: Option<ShieldBar>,
+    weapon_level: u32,
+    bombs: u32,
+    wallet: u32,
+    upgrades: Upgrades,
+    run_time: f32,
+    campaign: Option<Campaign>,
+    rng: StdRng,
+    powerup_rng: StdRng,
+    crit_rng: StdRng,
+    asteroid_rng: StdRng,
+    bounty_rng: StdRng,
+    crits: CritStats,
+    grazes: u32,
+    dynamic_rank: DynamicRank,
+    swarm: Swarm,
+    quests: Quests,
+    powerup_timer: f32,
+    asteroid_timer: f32,
+    bounty_timer: f32,
+    spawn_timer: f32,
+    enemies_spawned: u32,
+    carriers_spawned: u32,
+    bosses_defeated: u32,
+    next_boss_score: u32,
+    next_life_score: u32,
+}
+
+// 被击落的敌人留下的东西：小行星的碎块，或分裂敌人的两个小分身
+fn remains(enemy: &Enemy) -> Vec<Enemy> {
+    let mut remains = asteroid::fragments(enemy);
+    remains.extend(enemy::splinters(enemy));
+    remains
+}
+
+// 两架飞船中离 from 近的那架，只有一架时就是它
+fn nearest(from: Vec2, player: Vec2, rival: Option<Vec2>) -> Vec2 {
+    match rival {
+        Some(rival) if rival.distance_squared(from) < player.distance_squared(from) => rival,
+        _ => player,
+    }
+}
+
+// 按方向（每个分量在 -1 到 1 之间，来自按键或摇杆）得到飞船的速度，
+// 斜向移动时缩放到和横竖移动一样快
+pub fn move_velocity(direction: Vec2) -> Vec2 {
+    direction.clamp_length_max(1.0) * PLAYER_SPEED
+}
+
+// 一帧的玩家输入，回放和自动驾驶都通过它操作游戏
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct FrameInput {
+    pub move_x: i8,  // -1 向左，1 向右，0 不动
+    pub move_y: i8,  // -1 向上，1 向下，0 不动
+    pub fire: bool,
+    pub emp: bool,  // 副武器：电磁脉冲
+    pub bomb: bool,  // 使用一颗携带的炸弹
+    pub charge: bool,  // 按住开火键蓄力，松开的那一帧打出蓄力弹
+    pub dash: bool,  // 朝这一帧的移动方向冲刺
+    pub focus: bool,  // 按住专注键放慢敌方
+    pub select: u8,  // 切换到第几个栏位的主武器，0 表示不切换
+}
+
+// 一局游戏的全部状态和规则，不依赖窗口和 ggez 的 Context，
+// 画面、音乐、菜单和存档由外层处理，也可以在没有窗口时直接运行
+pub struct World {
+    pub mode: GameMode,
+    pub rules: Box<dyn ModeRules>,  // 这个模式的玩法，取自 mode
+    pub content: Registry,  // 按 ID 查找敌人和道具
+    pub scoring: Box<dyn ScoringRules>,  // 得多少分由它决定，开局时取自模式规则
+    pub campaign: Option<Campaign>,
+    pub seed: u64,  // 本局的随机种子，相同种子会得到相同的敌人分布
+    rng: StdRng,
+    pub player: Player,
+    pub rival: Option<Rival>,  // 双人对战时第二名玩家的飞船
+    pub bullets: Bullets,
+    pub enemy_bullets: Bullets,  // 敌人发射的子弹，只和玩家碰撞
+    pub enemies: Vec<Enemy>,
+    pub boss: Option<Boss>,
+    bosses_defeated: u32,
+    next_boss_score: u32,
+    next_life_score: u32,  // 下一条奖励生命的分数线
+    extra_life: ExtraLifeBalance,
+    pub cinematic: bool,  // 头目登场动画期间玩家不能操作
+    events: EventBus,
+    pub notice: Option<(String, f32)>,  // 屏幕上方的短暂提示及剩余时间
+    pub powerups: Vec<Powerup>,  // 正在下落的道具
+    pub coins: Vec<GameObject>,  // 正在下落的金币，只有战役类的模式会掉落
+    pub wallet: u32,  // 身上的金币，在波次之间的商店里花掉
+    pub upgrades: Upgrades,  // 在商店里买到的强化
+    pub meta: MetaUpgrades,  // 开局前用积分买下的永久强化
+    pub buffs: Buffs,  // 连射、僚机等限时道具的剩余时间
+    pub emp_cooldown: f32,  // 电磁脉冲再次可用前的秒数
+    pub weapon: Weapon,  // 当前使用的主武器
+    pub beam: Option<Beam>,  // 激光留在画面上的光束
+    pub charge: Charge,  // 按住开火键蓄力的进度
+    pub focus: Focus,  // 按住专注键放慢敌方，消耗专注槽
+    pub charged_shots: Vec<ChargedShot>,  // 飞行中的蓄力弹
+    pierce_serial: u32,  // 上一发蓄力弹或穿透子弹的编号
+    pub score: u32,
+    pub ledger: Ledger,  // 每一次得分的时间和来源
+    pub combo: Combo,  // 连续击落敌人的得分倍率
+    pub quests: Quests,  // 局内的小任务
+    pub best_pace: Option<Vec<u32>>,  // 这个模式最高分那一局的得分进度，HUD 用来比较
+    pub practice: bool,  // 从里程碑开始或练习头目的练习局，不记分数、历史和积分，也不再记里程碑
+    pub practice_phase: usize,  // 头目练习时头目从第几阶段开始
+    pub continues: Continues,  // 游戏结束后还能续关几次
+    pub game_over: bool,
+    pub killed_by: Option<DamageSource>,  // 造成最后一次伤害的来源
+    pub kill_cam: Option<KillCam>,  // 失去最后一条命后的慢镜头，放完才显示结算画面
+    pub spawning: bool,  // 关闭后不再自动生成敌人和头目，测试时使用
+    pub limits: EntityLimits,
+    pub field: PlayField,
+    pub effects: Effects,
+    spawn_timer: f32,
+    enemies_spawned: u32,  // 无尽模式已经出现的敌人数，用来折算波次
+    carriers_spawned: u32,  // 无尽模式已经轮到的母舰数，用来判断下一架什么时候出现
+    difficulty: DifficultyCurve,
+    dynamic_rank: DynamicRank,  // 叠加在难度曲线上的动态难度，不显示给玩家
+    pub run_time: f32,  // 本局已进行的时间，用来查询难度曲线
+    powerup_timer: f32,  // 道具生成计时器
+    powerup_rng: StdRng,
+    powerup_balance: PowerupBalance,
+    crit_rng: StdRng,
+    asteroid_rng: StdRng,
+    asteroid_timer: f32,  // 距离下一颗小行星出现的秒数，和敌人的生成互不影响
+    bounty: BountyBalance,
+    bounty_rng: StdRng,
+    bounty_timer: f32,  // 距离下一个悬赏目标的秒数，到时场上没有合适的敌人就等下一帧
+    weapon_balance: WeaponBalance,
+    weapon_levels: Vec<WeaponLevel>,
+    pub crits: CritStats,  // 本局的命中和暴击次数
+    pub grazes: u32,  // 本局的擦弹次数，HUD 上显示
+    pub profiler: Profiler,  // 记录每帧各阶段的耗时
+    grid: Grid,
+    swarm: Swarm,  // 编队的网格和俯冲节奏
+    checkpoint: Option<WaveCheckpoint>,  // 当前这一波开始时的状态
+}
+
+impl World {
+    pub fn new(mode: GameMode, seed: u64) -> Self {
+        Self::with_upgrades(mode, seed, MetaUpgrades::default())
+    }
+
+    // 带着永久强化开始一局，开局的检查点也包括强化后的生命
+    pub fn with_upgrades(mode: GameMode, seed: u64, meta: MetaUpgrades) -> Self {
+        Self::with_content(mode, seed, meta, Registry::builtin())
+    }
+
+    // 同上，关卡里的敌人 ID 按 content 展开，用来加载 mods 中的定义
+    pub fn with_content(mode: GameMode, seed: u64, meta: MetaUpgrades, content: Registry) -> Self {
+        let field = PlayField::default();
+        let rules = mode.rules();
+        // 双人对战两边一样强，不带永久强化，两架飞船左右分开出发
+        let meta = if rules.players() > 1 { MetaUpgrades::default() } else { meta };
+        let (player, rival) = if rules.players() > 1 {
+            let player = Player::new(field.width / 3.0, field.player_y);
+            (player, Some(Rival::new(field.width * 2.0 / 3.0, field.player_y)))
+        } else {
+            (Player::with_upgrades(field.width / 2.0, field.player_y, &meta), None)
+        };
+        let campaign = rules.campaign(seed).map(|data| Campaign::new(data.resolve(&content)));
+        let mut events = EventBus::default();
+        events.emit(GameEvent::RunStarted);
+        let balance = Balance::load();
+        let mut asteroid_rng = StdRng::seed_from_u64(seed ^ ASTEROID_SEED);
+        let mut bounty_rng = StdRng::seed_from_u64(seed ^ BOUNTY_SEED);
+
+        let mut world = Self {
+            mode,
+            scoring: rules.scoring(),
+            rules,
+            content,
+            campaign,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            player,
+            rival,
+            bullets: Bullets::default(),
+            enemy_bullets: Bullets::default(),
+            enemies: Vec::new(),
+            boss: None,
+            bosses_defeated: 0,
+            next_boss_score: BOSS_SCORE_INTERVAL,
+            next_life_score: balance.extra_life.score_interval,
+            extra_life: balance.extra_life,
+            cinematic: false,
+            events,
+            notice: None,
+            powerups: Vec::new(),
+            coins: Vec::new(),
+            wallet: 0,
+            upgrades: Upgrades::default(),
+            meta,
+            buffs: Buffs::default(),
+            emp_cooldown: 0.0,
+            weapon: Weapon::Cannon,
+            beam: None,
+            charge: Charge::default(),
+            focus: Focus::default(),
+            charged_shots: Vec::new(),
+            pierce_serial: 0,
+            score: 0,
+            ledger: Ledger::default(),
+            combo: Combo::default(),
+            quests: Quests::new(seed),
+            game_over: false,
+            killed_by: None,
+            kill_cam: None,
+            best_pace: None,
+            practice: false,
+            practice_phase: 0,
+            continues: Continues::default(),
+            spawning: true,
+            limits: EntityLimits::default(),
+            field,
+            effects: Effects::default(),
+            spawn_timer: 0.0,
+            enemies_spawned: 0,
+            carriers_spawned: 0,
+            difficulty: DifficultyCurve::load(),
+            dynamic_rank: DynamicRank::default(),
+            run_time: 0.0,
+            powerup_timer: 0.0,
+            powerup_rng: StdRng::seed_from_u64(seed ^ POWERUP_SEED),
+            powerup_balance: balance.powerups,
+            crit_rng: StdRng::seed_from_u64(seed ^ CRIT_SEED),
+            asteroid_timer: asteroid::next_interval(&mut asteroid_rng),
+            asteroid_rng,
+            bounty: balance.bounty,
+            bounty_timer: balance.bounty.next_interval(&mut bounty_rng),
+            bounty_rng,
+            weapon_balance: balance.weapons,
+            weapon_levels: balance.weapon_levels,
+            crits: CritStats::default(),
+            grazes: 0,
+            profiler: Profiler::default(),
+            grid: Grid::default(),
+            swarm: Swarm::default(),
+            checkpoint: None,
+        };
+        world.save_checkpoint();
+        world
+    }
+
+    // 本波还剩多少敌人：场上的加上还没出场的，只有战役模式有意义
+    pub fn wave_remaining(&self) -> Option<u32> {
+        let campaign = self.campaign.as_ref()?;
+        Some(campaign.pending() + self.enemies.iter().filter(|enemy| enemy.asteroid.is_none()).count() as u32)
+    }
+
+    // 当前的波次，从 1 开始
+    pub fn wave(&self) -> u32 {
+        match &self.campaign {
+            Some(campaign) => campaign.wave_number(),
+            None => self.enemies_spawned / ENDLESS_WAVE_SIZE + 1,
+        }
+    }
+
+    // 当前的难度等级，0 到 1：无尽模式查难度曲线，战役按已完成的关卡
+    pub fn rank(&self) -> f32 {
+        match &self.campaign {
+            Some(campaign) => campaign.progress(),
+            None => self.difficulty().rank,
+        }
+    }
+
+    // 无尽模式此刻的难度参数：难度曲线加上动态难度
+    pub fn difficulty(&self) -> DifficultyPoint {
+        self.difficulty.at(self.run_time).with_rank(&self.dynamic_rank, &self.difficulty.dynamic_rank)
+    }
+
+    // 动态难度的偏移，测试时查看
+    pub fn dynamic_rank(&self) -> f32 {
+        self.dynamic_rank.offset()
+    }
+
+    // 从里程碑开始一局无尽模式的练习，场上是空的，生命、火力和进度恢复为当时的值
+    pub fn practice(milestone: &Milestone, meta: MetaUpgrades, content: Registry) -> Self {
+        let mut world = Self::with_content(GameMode::Endless, milestone.seed ^ u64::from(milestone.wave), meta, content);
+        world.seed = milestone.seed;
+        world.practice = true;
+        world.score = milestone.score;
+        world.player.lives = milestone.lives;
+        world.player.weapon_level = milestone.weapon_level;
+        world.player.bombs = milestone.bombs;
+        world.run_time = milestone.run_time;
+        world.enemies_spawned = milestone.enemies_spawned;
+        world.carriers_spawned = milestone.carriers_spawned;
+        world.bosses_defeated = milestone.bosses_defeated;
+        world.next_boss_score = milestone.next_boss_score;
+        world.next_life_score = milestone.next_life_score;
+        world.notice = Some((format!("练习：从第 {} 波开始，不计分", milestone.wave), 2.0));
+        world.save_checkpoint();
+        world
+    }
+
+    // 练习第 stage 关的头目，不打前面的波次，头目登场时直接进入第 phase 阶段。
+    // 击败头目后练习结束，不算通关
+    pub fn boss_practice(stage: usize, phase: usize, meta: MetaUpgrades, content: Registry) -> Self {
+        let mut world = Self::with_content(GameMode::BossRush, stage as u64, meta, content);
+        world.campaign = Some(Campaign::new(CampaignData::load().boss_rush().only_stage(stage)));
+        world.practice = true;
+        world.practice_phase = phase;
+        world.notice = Some((format!("头目练习：从第 {} 阶段开始，不计分", phase + 1), 2.0));
+        world.save_checkpoint();
+        world
+    }
+
+    // 现在的状态，记里程碑时使用
+    pub fn milestone(&self) -> Milestone {
+        Milestone {
+            wave: self.wave(),
+            seed: self.seed,
+            score: self.score,
+            lives: self.player.lives,
+            weapon_level: self.player.weapon_level,
+            bombs: self.player.bombs,
+            run_time: self.run_time,
+            enemies_spawned: self.enemies_spawned,
+            carriers_spawned: self.carriers_spawned,
+            bosses_defeated: self.bosses_defeated,
+            next_boss_score: self.next_boss_score,
+            next_life_score: self.next_life_score,
+        }
+    }
+
+    // 当前这一波开始时的分数，辅助模式从这里重来
+    pub fn wave_start_score(&self) -> u32 {
+        self.checkpoint.as_ref().map_or(0, |checkpoint| checkpoint.score)
+    }
+
+    fn save_checkpoint(&mut self) {
+        self.checkpoint = Some(WaveCheckpoint {
+            wave: self.wave(),
+            score: self.score,
+            ledger_len: self.ledger.entries.len(),
+            lives: self.player.lives,
+            shield: self.player.shield,
+            weapon_level: self.player.weapon_level,
+            bombs: self.player.bombs,
+            wallet: self.wallet,
+            upgrades: self.upgrades,
+            run_time: self.run_time,
+            campaign: self.campaign.clone(),
+            rng: self.rng.clone(),
+            powerup_rng: self.powerup_rng.clone(),
+            crit_rng: self.crit_rng.clone(),
+            asteroid_rng: self.asteroid_rng.clone(),
+            bounty_rng: self.bounty_rng.clone(),
+            crits: self.crits,
+            grazes: self.grazes,
+            dynamic_rank: self.dynamic_rank,
+            swarm: self.swarm.clone(),
+            quests: self.quests.clone(),
+            powerup_timer: self.powerup_timer,
+            asteroid_timer: self.asteroid_timer,
+            bounty_timer: self.bounty_timer,
+            spawn_timer: self.spawn_timer,
+            enemies_spawned: self.enemies_spawned,
+            carriers_spawned: self.carriers_spawned,
+            bosses_defeated: self.bosses_defeated,
+            next_boss_score: self.next_boss_score,
+            next_life_score: self.next_life_score,
+        });
+    }
+
+    // 换一种生命的规则，开局时由外层按附加规则设置，开局的检查点也跟着更新
+    pub fn set_health(&mut self, model: HealthModel) {
+        self.player.shield = (model == HealthModel::Shield).then(ShieldBar::default);
+        if model == HealthModel::Shield {
+            self.player.lives = SHIELD_LIVES + self.meta.lives;
+        }
+        if let Some(rival) = &mut self.rival {
+            rival.player.shield = self.player.shield;
+            rival.player.lives = self.player.lives;
+        }
+        self.save_checkpoint();
+    }
+
+    // 快速重新开始时跳过片头，战役直接从第一波开始
+    pub fn skip_intro(&mut self) {
+        if let Some(campaign) = &mut self.campaign {
+            campaign.skip_banner();
+        }
+    }
+
+    // 辅助模式：回到当前这一波开始时的状态，场上清空，分数和生命恢复为当时的值
+    pub fn retry_wave(&mut self) {
+        if self.checkpoint.is_none() {
+            return;
+        }
+        self.restore_checkpoint();
+        self.notice = Some((format!("从第 {} 波重新开始", self.wave()), 2.0));
+        self.events.emit(GameEvent::WaveRestarted);
+    }
+
+    // 游戏结束后续关：和从本波重来一样回到这一波开头，难度进度不变，
+    // 生命恢复为开局时的数量，分数按 KEEP_SCORE 打折。不能续关时返回 false
+    pub fn continue_run(&mut self) -> bool {
+        if !self.can_continue() {
+            return false;
+        }
+        self.continues.take();
+        self.restore_checkpoint();
+        let before = self.ledger.total();
+        self.ledger.scale(KEEP_SCORE);
+        let penalty = before - self.ledger.total();
+        self.score = self.score.saturating_sub(penalty);
+        // 奖励生命和头目的分数线跟着往下挪，离下一条线还是那么远
+        self.next_life_score = self.next_life_score.saturating_sub(penalty);
+        self.next_boss_score = self.next_boss_score.saturating_sub(penalty);
+        let lives = if self.player.shield.is_some() { SHIELD_LIVES } else { START_LIVES };
+        self.player.lives = lives + self.meta.lives;
+        if let Some(shield) = &mut self.player.shield {
+            shield.refill();
+        }
+        // 打折后的状态当作这一波新的起点，之后从本波重来或再次续关都从这里开始
+        self.save_checkpoint();
+        self.notice = Some((format!("续关：从第 {} 波继续，还能续 {} 次", self.wave(), self.continues.left()), 2.0));
+        self.events.emit(GameEvent::Continued { left: self.continues.left() });
+        true
+    }
+
+    // 只有失去最后一条命才能续关，计时赛时间到、练习完成或双人对战时不行
+    pub fn can_continue(&self) -> bool {
+        self.game_over && self.rival.is_none() && self.player.lives == 0 && self.checkpoint.is_some() && self.continues.available()
+    }
+
+    // 回到检查点记下的状态，清空场上的一切
+    fn restore_checkpoint(&mut self) {
+        let Some(checkpoint) = &self.checkpoint else {
+            return;
+        };
+        self.score = checkpoint.score;
+        self.ledger.entries.truncate(checkpoint.ledger_len);
+        self.player.lives = checkpoint.lives;
+        self.player.shield = checkpoint.shield;
+        self.player.weapon_level = checkpoint.weapon_level;
+        self.player.bombs = checkpoint.bombs;
+        self.wallet = checkpoint.wallet;
+        self.upgrades = checkpoint.upgrades;
+        self.run_time = checkpoint.run_time;
+        self.campaign = checkpoint.campaign.clone();
+        self.rng = checkpoint.rng.clone();
+        self.powerup_rng = checkpoint.powerup_rng.clone();
+        self.crit_rng = checkpoint.crit_rng.clone();
+        self.asteroid_rng = checkpoint.asteroid_rng.clone();
+        self.bounty_rng = checkpoint.bounty_rng.clone();
+        self.crits = checkpoint.crits;
+        self.grazes = checkpoint.grazes;
+        self.dynamic_rank = checkpoint.dynamic_rank;
+        self.swarm = checkpoint.swarm.clone();
+        self.quests = checkpoint.quests.clone();
+        self.powerup_timer = checkpoint.powerup_timer;
+        self.asteroid_timer = checkpoint.asteroid_timer;
+        self.bounty_timer = checkpoint.bounty_timer;
+        self.spawn_timer = checkpoint.spawn_timer;
+        self.enemies_spawned = checkpoint.enemies_spawned;
+        self.carriers_spawned = checkpoint.carriers_spawned;
+        self.bosses_defeated = checkpoint.bosses_defeated;
+        self.next_boss_score = checkpoint.next_boss_score;
+        self.next_life_score = checkpoint.next_life_score;
+
+        self.player.invincible_timer = 0.0;
+        self.player.game_object.position = Vec2::new(self.field.width / 2.0, self.field.player_y);
+        self.player.game_object.velocity = Vec2::ZERO;
+        self.bullets = Bullets::default();
+        self.enemy_bullets = Bullets::default();
+        self.enemies.clear();
+        self.powerups.clear();
+        self.coins.clear();
+        self.boss = None;
+        self.buffs = Buffs::default();
+        self.emp_cooldown = 0.0;
+        self.player.fire_cooldown = 0.0;
+        self.beam = None;
+        self.charge.cancel();
+        self.charged_shots.clear();
+        self.focus = Focus::default();
+        self.combo = Combo::default();
+        self.effects = Effects::default();
+        self.cinematic = false;
+        self.game_over = false;
+        self.killed_by = None;
+        self.kill_cam = None;
+    }
+
+    pub fn death_cause(&self) -> Option<DeathCause> {
+        self.killed_by.map(DamageSource::cause)
+    }
+
+    // 某一阵营这一帧的时间倍数，时间冻结期间敌方停住，专注期间敌方变慢，慢镜头期间所有物体都变慢
+    pub fn time_scale(&self, faction: Faction) -> f32 {
+        let scale = if self.kill_cam.is_some() { killcam::TIME_SCALE } else { 1.0 };
+        match faction {
+            Faction::Player => scale,
+            Faction::Enemy if self.buffs.freezing() => 0.0,
+            Faction::Enemy if self.game_over => scale,
+            Faction::Enemy => scale * self.focus.time_scale(),
+        }
+    }
+
+    // 慢镜头期间敌人、子弹和特效按慢速继续移动，不再结算碰撞和得分
+    fn play_kill_cam(&mut self, dt: f32) {
+        let Some(kill_cam) = &mut self.kill_cam else {
+            return;
+        };
+        kill_cam.update(dt);
+        if kill_cam.finished() {
+            self.kill_cam = None;
+            return;
+        }
+        let player_dt = dt * self.time_scale(Faction::Player);
+        let enemy_dt = dt * self.time_scale(Faction::Enemy);
+        for enemy in &mut self.enemies {
+            enemy.game_object.position += enemy.game_object.velocity * enemy_dt;
+        }
+        if let Some(boss) = &mut self.boss {
+            boss.game_object.position += boss.game_object.velocity * enemy_dt;
+        }
+        self.bullets.integrate(player_dt, &self.field);
+        self.bullets.remove_dead();
+        self.enemy_bullets.integrate(enemy_dt, &self.field);
+        self.enemy_bullets.remove_dead();
+        self.effects.update(player_dt);
+    }
+
+    // 按稀有度权重生成一个道具
+    pub fn spawn_powerup(&mut self) {
+        let kind = self.powerup_balance.roll(self.rank(), &mut self.powerup_rng);
+        let x = self.powerup_rng.gen_range(self.field.spawn_x(10.0));
+        self.powerups.push(Powerup::new(x, self.field.spawn_y(20.0), kind, self.powerup_balance.fall_speed));
+    }
+
+    // 按 ID 在屏幕上方生成一个敌人，测试脚本和无尽模式的母舰用
+    pub fn spawn_by_id(&mut self, id: &str) -> Result<(), String> {
+        let def = *self.content.enemy(id).ok_or_else(|| format!("未知的敌人 ID: {}", id))?;
+        let wave = WaveSpec {
+            count: 1,
+            interval: 0.0,
+            speed: def.speed,
+            size: def.size,
+            formation: Formation::Single,
+            path: def.path,
+            hp: def.hp,
+            armor: def.armor,
+            fire_interval: def.fire_interval,
+            aimed: def.aimed,
+            launch_interval: def.launch_interval,
+            splits: def.splits,
+            lanes: Vec::new(),
+            enemy: Some(id.to_string()),
+        };
+        self.spawn_formation(&wave);
+        Ok(())
+    }
+
+    // 按 ID 在屏幕上方掉落一个道具，测试脚本用
+    pub fn drop_powerup(&mut self, id: &str) -> Result<(), String> {
+        let kind = self.content.powerup(id).ok_or_else(|| format!("未知的道具 ID: {}", id))?;
+        let x = self.powerup_rng.gen_range(self.field.spawn_x(10.0));
+        self.powerups.push(Powerup::new(x, self.field.spawn_y(20.0), kind, self.powerup_balance.fall_speed));
+        Ok(())
+    }
+
+    // 难度等级越高，越可能出现蛇行或斜飞的敌人
+    pub fn spawn_enemy(&mut self, speed: f32, rank: f32) {
+        let path = self.difficulty.pick_path(rank, &mut self.rng);
+        let size = path.endless_size();
+        let x = self.rng.gen_range(self.field.spawn_x(size / 2.0));
+        let mut enemy = Enemy::new(x, self.field.spawn_y(size), size, speed * path.endless_speed(), path);
+        enemy.gun = self.difficulty.pick_gun(rank, &mut self.rng);
+        if self.difficulty.pick_splits(rank, &mut self.rng) {
+            enemy.make_splitter();
+        }
+        self.add_enemy(enemy);
+        self.enemies_spawned += 1;
+    }
+
+    // 敌人数量达到上限时不再新增
+    fn add_enemy(&mut self, enemy: Enemy) {
+        if self.enemies.len() < self.limits.enemies {
+            self.enemies.push(enemy);
+        }
+    }
+
+    // 按波次的队形生成一组敌人
+    pub fn spawn_formation(&mut self, wave: &WaveSpec) {
+        if wave.formation == Formation::Swarm {
+            self.spawn_swarm(wave);
+            return;
+        }
+        let center = campaign::spawn_x(&wave.lanes, &self.field, wave.formation.half_width() + wave.size / 2.0, &mut self.rng);
+        // 大个的敌人也要完全从屏幕上方进入
+        let top = self.field.spawn_y(wave.size);
+        for offset in wave.formation.offsets() {
+            let mut enemy = Enemy::new(
+                center + offset.x,
+                top + offset.y,
+                wave.size,
+                wave.speed,
+                wave.path,
+            );
+            // 每点护甲多 10 分
+            enemy.set_hp(wave.hp);
+            enemy.armor = wave.armor;
+            enemy.points += wave.armor * 10;
+            if wave.fire_interval > 0.0 {
+                enemy.gun = Some(Gun::new(wave.fire_interval, wave.aimed));
+            }
+            if wave.launch_interval > 0.0 {
+                enemy.carrier = Some(Carrier::new(wave.launch_interval));
+                enemy.points += CARRIER_BONUS;
+                enemy.source = DamageSource::Carrier;
+            }
+            if wave.splits {
+                enemy.make_splitter();
+            }
+            if let Some(points) = self.defined_points(wave) {
+                enemy.points = points;
+            }
+            self.add_enemy(enemy);
+        }
+    }
+
+    // 波次引用的敌人定义里写明的得分
+    fn defined_points(&self, wave: &WaveSpec) -> Option<u32> {
+        self.content.enemy(wave.enemy.as_deref()?)?.points
+    }
+
+    // 一组编队敌机，随机从左边或右边入场。速度和路线由编队决定，不看 speed 和 path
+    fn spawn_swarm(&mut self, wave: &WaveSpec) {
+        let points = self.defined_points(wave).unwrap_or(SWARM_POINTS + wave.armor * 10);
+        let recruits = wave
+            .formation
+            .offsets()
+            .into_iter()
+            .map(|_| {
+                let mut enemy = Enemy::new(0.0, 0.0, wave.size, 0.0, EnemyPath::Straight);
+                enemy.set_hp(wave.hp);
+                enemy.armor = wave.armor;
+                enemy.points = points;
+                enemy.source = DamageSource::Swarm;
+                if wave.fire_interval > 0.0 {
+                    enemy.gun = Some(Gun::new(wave.fire_interval, wave.aimed));
+                }
+                enemy
+            })
+            .collect();
+        let from_left = self.rng.gen_bool(0.5);
+        for enemy in self.swarm.enlist(&self.field, &self.enemies, recruits, from_left) {
+            self.add_enemy(enemy);
+        }
+    }
+
+    // 观众投票选出的事件，和其他事件一样在本帧末尾生效
+    pub fn chat_event(&mut self, event: ChatEvent) {
+        self.events.emit(GameEvent::ChatVoteWon { event });
+    }
+
+    // 精英敌人：个头大、带护甲、会瞄准玩家射击
+    fn spawn_elite(&mut self) {
+        let x = self.rng.gen_range(self.field.spawn_x(ELITE_SIZE / 2.0));
+        let mut elite = Enemy::new(x, self.field.spawn_y(ELITE_SIZE), ELITE_SIZE, 60.0, EnemyPath::Straight);
+        elite.set_hp(4);
+        elite.armor = 1;
+        elite.points = 150;
+        elite.gun = Some(Gun::new(1.5, true));
+        self.add_enemy(elite);
+    }
+
+    // 从场上能挂悬赏的敌人里随机挑一个，没有合适的敌人时什么也不做，下一帧再试
+    fn mark_bounty(&mut self) {
+        let candidates: Vec<usize> = (0..self.enemies.len()).filter(|&index| self.enemies[index].can_carry_bounty()).collect();
+        if candidates.is_empty() {
+            return;
+        }
+        let enemy = &mut self.enemies[candidates[self.bounty_rng.gen_range(0..candidates.len())]];
+        enemy.mark_bounty(&self.bounty);
+        self.bounty_timer = self.bounty.next_interval(&mut self.bounty_rng);
+        self.events.emit(GameEvent::BountyMarked { points: enemy.points });
+    }
+
+    // 流星雨：一排高速下落的小石块，可以击落，飞出底部不扣生命
+    fn meteor_shower(&mut self) {
+        for _ in 0..METEORS {
+            let x = self.rng.gen_range(self.field.spawn_x(METEOR_SIZE / 2.0));
+            let y = self.field.spawn_y(METEOR_SIZE) - self.rng.gen_range(0.0..200.0);
+            let mut meteor = Enemy::new(x, y, METEOR_SIZE, self.rng.gen_range(250.0..400.0), EnemyPath::Straight);
+            meteor.escape_penalty = false;
+            meteor.points = 5;
+            self.add_enemy(meteor);
+        }
+    }
+
+    pub fn spawn_boss(&mut self, spec: BossSpec) {
+        let mut boss = Boss::new(spec);
+        if self.practice {
+            boss.start_at_phase(self.practice_phase);
+        }
+        self.boss = Some(boss);
+        self.events.emit(GameEvent::BossSpawned);
+    }
+
+    pub fn fire_bullet(&mut self) {
+        if self.player.fire_cooldown > 0.0 {
+            return;
+        }
+        self.player.fire_cooldown = self.weapon_balance.cooldown(self.weapon) * self.upgrades.cooldown_factor();
+        match self.weapon {
+            Weapon::Laser => self.fire_laser(),
+            Weapon::Scatter => self.fire_scatter(),
+            _ => self.fire_cannon(),
+        }
+        // 分身跟着一起开火，不管当前是什么主武器都射一发主炮子弹
+        for position in self.player.option_positions() {
+            self.fire_from(position - Vec2::new(0.0, 10.0), Vec2::NEG_Y);
+        }
+    }
+
+    // 每帧告诉游戏世界开火键有没有按住，蓄够了再松开时打出一发蓄力弹；双人对战时不能蓄力
+    pub fn hold_charge(&mut self, held: bool) {
+        if self.rival.is_some() {
+            return;
+        }
+        if let Some(power) = self.charge.hold(held) {
+            self.fire_charged(power);
+        }
+    }
+
+    // 蓄力弹：从机头向正前方打出，伤害随蓄力时间增加
+    fn fire_charged(&mut self, power: f32) {
+        let damage = (self.weapon_balance.damage(Weapon::Charged, self.base_damage()) as f32 * power).round() as u32;
+        self.pierce_serial += 1;
+        let position = self.player.game_object.position - Vec2::new(0.0, 30.0);
+        self.charged_shots.push(ChargedShot::new(position, damage, self.pierce_serial));
+        self.events.emit(GameEvent::ChargedShotFired { damage });
+    }
+
+    // 切换主武器，slot 是数字键对应的栏位，从 1 开始；双人对战时两边都只用主炮
+    pub fn select_weapon(&mut self, slot: u8) {
+        let Some(weapon) = Weapon::slot(slot).filter(|_| self.rival.is_none()) else {
+            return;
+        };
+        if weapon != self.weapon {
+            self.weapon = weapon;
+            self.notice = Some((format!("切换武器: {}", weapon.name()), 1.0));
+        }
+    }
+
+    // 主炮：按火力等级并排发射，散射道具生效时两侧再各斜射一颗
+    fn fire_cannon(&mut self) {
+        let position = self.player.game_object.position - Vec2::new(0.0, 20.0);
+        let level = self.weapon_level();
+        for index in 0..level.parallel {
+            let offset = (index as f32 - (level.parallel - 1) as f32 / 2.0) * PARALLEL_GAP;
+            self.fire_from(position + Vec2::new(offset, 0.0), Vec2::NEG_Y);
+        }
+        if level.spread > 0.0 {
+            for angle in [-level.spread, level.spread] {
+                self.fire_from(position, Vec2::from_angle(angle).rotate(Vec2::NEG_Y));
+            }
+        }
+        if self.buffs.spreading() {
+            for angle in [-SPREAD_ANGLE, SPREAD_ANGLE] {
+                self.fire_from(position, Vec2::from_angle(angle).rotate(Vec2::NEG_Y));
+            }
+        }
+    }
+
+    // 霰弹：以正前方为中心打出一排扇形弹丸
+    fn fire_scatter(&mut self) {
+        let position = self.player.game_object.position - Vec2::new(0.0, 20.0);
+        for index in 0..SCATTER_PELLETS {
+            let angle = (index as f32 - (SCATTER_PELLETS - 1) as f32 / 2.0) * SCATTER_STEP;
+            self.fire_with(position, Vec2::from_angle(angle).rotate(Vec2::NEG_Y), Weapon::Scatter);
+        }
+    }
+
+    // 激光：从机头到场地顶端的一道光束，在这一帧的碰撞阶段打中沿途的所有目标
+    fn fire_laser(&mut self) {
+        let area = self.field.area();
+        let x = self.player.game_object.position.x;
+        let top = self.player.game_object.bounds().top();
+        let bounds = Rect::new(x - LASER_WIDTH / 2.0, area.y, LASER_WIDTH, (top - area.y).max(0.0));
+        self.beam = Some(Beam::new(bounds, LASER_FLASH));
+    }
+
+    // 武器命中一次敌人：附加状态、掷暴击并扣掉护甲，返回敌人是否被击落。
+    // base 是子弹或光束本身的伤害
+    fn strike(
+        enemy: &mut Enemy,
+        weapon: Weapon,
+        base: u32,
+        balance: &WeaponBalance,
+        rng: &mut StdRng,
+        crits: &mut CritStats,
+        events: &mut EventBus,
+    ) -> bool {
+        if let Some(status) = weapon.status() {
+            enemy.status.apply(status, Self::status_duration(status));
+        }
+        let hit = balance.roll(weapon, base, rng);
+        crits.record(hit, base);
+        if hit.critical {
+            events.emit(GameEvent::CriticalHit { position: enemy.game_object.position });
+        }
+        let damage = balance.against_armor(weapon, hit.damage, enemy.armor);
+        damage > 0 && enemy.hit(damage)
+    }
+
+    // 当前火力等级的参数
+    pub fn weapon_level(&self) -> WeaponLevel {
+        let index = (self.player.weapon_level as usize).clamp(1, self.weapon_levels.len()) - 1;
+        self.weapon_levels[index]
+    }
+
+    pub fn max_weapon_level(&self) -> u32 {
+        self.weapon_levels.len() as u32
+    }
+
+    // 从 position 朝 direction（单位向量）发射一颗子弹
+    fn fire_from(&mut self, position: Vec2, direction: Vec2) {
+        self.fire_with(position, direction, Weapon::Cannon);
+    }
+
+    // 同上，指定发射的武器
+    fn fire_with(&mut self, position: Vec2, direction: Vec2, weapon: Weapon) {
+        if self.bullets.len() >= self.limits.bullets {
+            return;
+        }
+        let damage = self.weapon_balance.damage(weapon, self.base_damage());
+        let modifiers = self.weapon_balance.modifiers(weapon);
+        let serial = if modifiers.pierce > 0 {
+            self.pierce_serial += 1;
+            self.pierce_serial
+        } else {
+            0
+        };
+        let velocity = direction * BULLET_SPEED;
+        self.bullets.push_modified(position, velocity, weapon, damage, modifiers, serial);
+    }
+
+    // 副武器：不摧毁敌人，只让范围内的敌人和头目眩晕一段时间
+    pub fn fire_emp(&mut self) {
+        if self.emp_cooldown > 0.0 || self.rival.is_some() {
+            return;
+        }
+        self.emp_cooldown = EMP_COOLDOWN;
+        let center = self.player.game_object.position;
+        for enemy in &mut self.enemies {
+            if enemy.game_object.position.distance(center) <= EMP_RADIUS {
+                enemy.status.apply(StatusKind::Stun, EMP_STUN);
+            }
+        }
+        if let Some(boss) = self.boss.as_mut().filter(|boss| !boss.in_intro()) {
+            if boss.game_object.position.distance(center) <= EMP_RADIUS + boss.game_object.size.y / 2.0 {
+                boss.status.apply(StatusKind::Stun, EMP_STUN);
+            }
+        }
+        self.effects.shockwave(center, EMP_RADIUS);
+    }
+
+    // 乘上武器倍数之前的伤害：狂暴期间为 2，永久强化每级再加 1
+    fn base_damage(&self) -> u32 {
+        self.buffs.damage() + self.meta.damage
+    }
+
+    // 使用一颗携带的炸弹清场，没有炸弹或双人对战时什么也不做
+    pub fn use_bomb(&mut self) {
+        if self.player.bombs == 0 || self.rival.is_some() {
+            return;
+        }
+        self.player.bombs -= 1;
+        self.detonate_bomb();
+    }
+
+    // 朝当前的移动方向冲刺，不在移动时原地闪避；登场动画期间不能冲刺
+    pub fn dash(&mut self) {
+        if !self.cinematic {
+            let direction = self.player.game_object.velocity;
+            self.player.start_dash(direction);
+        }
+    }
+
+    // 应用一帧输入，头目登场动画期间和游戏结束后忽略
+    pub fn apply_input(&mut self, input: FrameInput) {
+        if self.cinematic || self.game_over {
+            return;
+        }
+        let direction = Vec2::new(input.move_x.signum() as f32, input.move_y.signum() as f32);
+        self.player.game_object.velocity = move_velocity(direction);
+        if input.fire {
+            self.fire_bullet();
+        }
+        self.hold_charge(input.charge);
+        self.focus.hold(input.focus);
+        if input.dash {
+            self.dash();
+        }
+        if input.emp {
+            self.fire_emp();
+        }
+        if input.bomb {
+            self.use_bomb();
+        }
+        if input.select > 0 {
+            self.select_weapon(input.select);
+        }
+    }
+
+    // 双人对战时第二名玩家的一帧输入，只用到移动、开火和冲刺；开火和第一架飞船的主炮一样快
+    pub fn apply_rival_input(&mut self, input: FrameInput) {
+        if self.cinematic || self.game_over {
+            return;
+        }
+        let damage = self.weapon_balance.damage(Weapon::Cannon, self.base_damage());
+        let Some(rival) = &mut self.rival else {
+            return;
+        };
+        let direction = Vec2::new(input.move_x.signum() as f32, input.move_y.signum() as f32);
+        rival.player.game_object.velocity = move_velocity(direction);
+        if input.dash {
+            let direction = rival.player.game_object.velocity;
+            rival.player.start_dash(direction);
+        }
+        if input.fire && rival.player.fire_cooldown <= 0.0 {
+            rival.player.fire_cooldown = self.weapon_balance.cooldown(Weapon::Cannon);
+            if rival.bullets.len() < self.limits.bullets {
+                let position = rival.player.game_object.position - Vec2::new(0.0, 20.0);
+                rival.bullets.push_with(position, Vec2::NEG_Y * BULLET_SPEED, Weapon::Cannon, damage);
+            }
+        }
+    }
+
+    // 双人对战结束后的胜负，其他模式和还在进行时返回 None
+    pub fn winner(&self) -> Option<Winner> {
+        let rival = self.rival.as_ref().filter(|_| self.game_over)?;
+        Some(Winner::decide(self.player.lives, self.score, rival.player.lives, rival.score))
+    }
+
+    // 推进一帧，返回本帧处理过的事件，外层据此切换音乐、记录分数等
+    pub fn update(&mut self, dt: f32) -> Vec<GameEvent> {
+        if self.game_over {
+            self.play_kill_cam(dt);
+            return self.events.take();
+        }
+
+        // 更新玩家状态，包括无敌时间
+        self.profiler.start("移动");
+        self.run_time += dt;
+        self.emp_cooldown = (self.emp_cooldown - dt).max(0.0);
+        self.combo.update(dt);
+        let moving = self.player.game_object.velocity != Vec2::ZERO;
+        // 小任务的奖励只给第一架飞船，双人对战时不出任务
+        let quests = self.spawning && self.rival.is_none();
+        if let Some(outcome) = self.quests.update(dt, moving, quests) {
+            self.events.emit(GameEvent::Quest { outcome });
+        }
+        self.charge.update(dt);
+        self.focus.update(dt);
+        if let Some(beam) = &mut self.beam {
+            beam.remaining -= dt;
+            if beam.finished() {
+                self.beam = None;
+            }
+        }
+        self.player.update(dt);
+        self.effects.update(dt);
+        if let Some((_, remaining)) = &mut self.notice {
+            *remaining -= dt;
+            if *remaining <= 0.0 {
+                self.notice = None;
+            }
+        }
+        if self.cinematic {
+            self.player.game_object.velocity = Vec2::ZERO;
+            self.focus.hold(false);
+        }
+        // 双人对战时只有第一名玩家有专注键，不让它放慢两边共同的敌人
+        if self.rival.is_some() {
+            self.focus.hold(false);
+        }
+
+        // 更新玩家位置
+        let speed = self.buffs.speed_factor() * self.upgrades.speed_factor() * self.meta.speed_factor();
+        let velocity = match self.player.dash_velocity() {
+            Some(velocity) if !self.cinematic => velocity,
+            _ => self.player.game_object.velocity * speed,
+        };
+        self.player.game_object.position += velocity * dt;
+
+        let area = self.field.area();
+
+        // 保持玩家在屏幕内
+        self.field.confine_player(&mut self.player.game_object);
+        self.player.record_position();
+
+        // 第二架飞船同样移动和限位，子弹另外存放，击落的敌人才能记在它的分数上
+        if let Some(rival) = &mut self.rival {
+            rival.player.update(dt);
+            rival.combo.update(dt);
+            if self.cinematic {
+                rival.player.game_object.velocity = Vec2::ZERO;
+            }
+            let velocity = match rival.player.dash_velocity() {
+                Some(velocity) if !self.cinematic => velocity,
+                _ => rival.player.game_object.velocity,
+            };
+            rival.player.game_object.position += velocity * dt;
+            self.field.confine_player(&mut rival.player.game_object);
+            rival.bullets.integrate(dt, &self.field);
+            rival.bullets.remove_dead();
+        }
+
+        // 连射、僚机和侧后炮自动开火，登场动画期间停火
+        let fire = self.buffs.update(dt, self.weapon_level().fire_rate);
+        if !self.cinematic {
+            if fire.rapid {
+                self.fire_bullet();
+            }
+            let drone = self.buffs.drone_position(self.player.game_object.position);
+            if let Some(position) = drone.filter(|_| fire.drone) {
+                self.fire_with(position, Vec2::NEG_Y, Weapon::Drone);
+            }
+            if fire.guns {
+                let position = self.player.game_object.position;
+                for direction in [Vec2::Y, Vec2::NEG_X, Vec2::X] {
+                    self.fire_with(position + direction * 20.0, direction, Weapon::Guns);
+                }
+            }
+        }
+
+        // 道具下落，碰到玩家时拾取
+        for powerup in &mut self.powerups {
+            let object = &mut powerup.game_object;
+            object.position += object.velocity * dt;
+            if Boundary::ENEMY.apply(object, area, self.field.enemy_despawn) {
+                object.alive = false;
+            } else if self.field.player_overlaps(&self.player.game_object, &object.bounds()) {
+                object.alive = false;
+                self.events.emit(GameEvent::PowerupCollected { kind: powerup.kind, position: object.position });
+            }
+        }
+        self.powerups.retain(|powerup| powerup.game_object.alive);
+
+        // 金币和道具一样下落，碰到飞船时收进钱包
+        for coin in &mut self.coins {
+            coin.position += coin.velocity * dt;
+            if Boundary::ENEMY.apply(coin, area, self.field.enemy_despawn) {
+                coin.alive = false;
+            } else if self.field.player_overlaps(&self.player.game_object, &coin.bounds()) {
+                coin.alive = false;
+                self.wallet += 1;
+                self.events.emit(GameEvent::CoinCollected { wallet: self.wallet });
+            }
+        }
+        self.coins.retain(|coin| coin.alive);
+
+        // 更新子弹位置，删除离开屏幕的子弹
+        self.bullets.integrate(dt, &self.field);
+        self.bullets.remove_dead();
+        for shot in &mut self.charged_shots {
+            shot.game_object.position += shot.game_object.velocity * dt;
+        }
+        self.charged_shots.retain(|shot| shot.game_object.bounds().bottom() > area.top());
+
+        let enemy_dt = dt * self.time_scale(Faction::Enemy);
+
+        // 反射护盾把水雷弹回去，瞄准发射它的头目，没有头目时直接向上；敌人的子弹另外弹回开火的位置
+        let reflect_target = self.boss.as_ref().map(|boss| boss.game_object.position);
+        let reflect_damage = self.weapon_balance.damage(Weapon::Reflected, self.base_damage());
+
+        // 编队敌机的位置由编队统一计算
+        let player = self.player.game_object.position;
+        let rival = self.rival.as_ref().map(|rival| rival.player.game_object.position);
+        self.swarm.update(enemy_dt, &self.field, &mut self.enemies, player, &mut self.rng);
+
+        // 更新敌人位置，烧死的敌人等遍历结束后再计分
+        let mut burned = Vec::new();
+        // 被击碎的小行星分裂出的碎块、分裂敌人的两个小分身和母舰放出的小飞机，
+        // 这一帧的碰撞都结算完后再加入，遍历敌人和结算子弹的过程中不会改动 self.enemies
+        let mut spawned = Vec::new();
+        for enemy in &mut self.enemies {
+            // 双人对战时敌人瞄准离自己近的那架飞船
+            let target = nearest(enemy.game_object.position, player, rival);
+            enemy.lock_on(target);
+            let burn = enemy.update(enemy_dt);
+            if burn > 0 && enemy.hit(burn) {
+                enemy.game_object.alive = false;
+                burned.push((enemy.game_object.position, enemy.points));
+                spawned.extend(remains(enemy));
+                continue;
+            }
+            spawned.extend(enemy.launch(enemy_dt));
+            if let Some(gun) = enemy.trigger(enemy_dt) {
+                if self.enemy_bullets.len() < self.limits.bullets {
+                    let position = enemy.game_object.position;
+                    let direction = if gun.aimed {
+                        (target - position).try_normalize().unwrap_or(Vec2::Y)
+                    } else {
+                        Vec2::Y
+                    };
+                    self.enemy_bullets.push_hostile(position, direction * ENEMY_BULLET_SPEED);
+                }
+            }
+            let escape_penalty = enemy.escape_penalty;
+            let source = enemy.source;
+            let collider = enemy.collider;
+            let removed = enemy.boundary.apply(&mut enemy.game_object, area, self.field.enemy_despawn);
+            let grazed = &mut enemy.grazed;
+            let enemy = &mut enemy.game_object;
+
+            // 敌人到达底部，玩家损失一条命；双人对战时不算在任何一方头上
+            if removed {
+                enemy.alive = false;
+                if escape_penalty && self.rival.is_none() && Self::damage(&mut self.player, &self.buffs, &mut self.events, DamageSource::Escaped) {
+                    self.game_over = true;
+                    self.kill_cam = Some(KillCam::new(self.player.game_object.position, enemy.bounds()));
+                }
+            }
+
+            // 水雷碰到反射护盾时改为玩家一方的子弹，不造成伤害
+            if source == DamageSource::Mine
+                && self.buffs.reflecting()
+                && self.field.player_overlaps(&self.player.game_object, &enemy.bounds())
+            {
+                enemy.alive = false;
+                if self.bullets.len() < self.limits.bullets {
+                    let direction = reflect_target
+                        .map(|target| (target - enemy.position).normalize_or_zero())
+                        .filter(|direction| *direction != Vec2::ZERO)
+                        .unwrap_or(Vec2::NEG_Y);
+                    self.bullets.push_with(enemy.position, direction * BULLET_SPEED, Weapon::Reflected, reflect_damage);
+                }
+                continue;
+            }
+
+            // 检测玩家与敌人碰撞，没撞上但碰到擦弹框时算一次擦弹
+            if !self.player.is_invincible() && Collider::PLAYER.interacts(collider) {
+                if self.field.player_overlaps(&self.player.game_object, &enemy.bounds()) {
+                    enemy.alive = false;
+                    if Self::damage(&mut self.player, &self.buffs, &mut self.events, source) {
+                        self.game_over = true;
+                        self.kill_cam = Some(KillCam::new(self.player.game_object.position, enemy.bounds()));
+                    }
+                } else if !*grazed && self.field.player_grazes(&self.player.game_object, &enemy.bounds(), GRAZE_MARGIN) {
+                    *grazed = true;
+                    self.events.emit(GameEvent::Grazed { position: enemy.position });
+                }
+            }
+
+            // 第二架飞船被撞到，打掉最后一条命时这一局结束
+            if let Some(rival) = &mut self.rival {
+                if enemy.alive
+                    && !rival.player.is_invincible()
+                    && Collider::PLAYER.interacts(collider)
+                    && self.field.player_overlaps(&rival.player.game_object, &enemy.bounds())
+                {
+                    enemy.alive = false;
+                    if rival.hit() {
+                        self.game_over = true;
+                        self.kill_cam = Some(KillCam::new(rival.player.game_object.position, enemy.bounds()));
+                    }
+                }
+            }
+        }
+
+        for (position, points) in burned {
+            self.destroy_enemy(position, points);
+        }
+
+        // 敌人的子弹和敌人一起被冻结，无敌期间直接穿过玩家，也不算擦弹；
+        // 反射护盾不管是否无敌都把碰到的子弹弹回开火的位置，不造成伤害
+        self.enemy_bullets.integrate(enemy_dt, &self.field);
+        let reflecting = self.buffs.reflecting();
+        if reflecting || !self.player.is_invincible() {
+            for bullet in 0..self.enemy_bullets.len() {
+                let swept = self.enemy_bullets.swept_bounds(bullet, enemy_dt);
+                if !self.enemy_bullets.is_alive(bullet) || !Collider::PLAYER.interacts(self.enemy_bullets.collider(bullet)) {
+                    continue;
+                }
+                let hit = self.field.player_overlaps(&self.player.game_object, &swept);
+                if hit && reflecting {
+                    self.enemy_bullets.kill(bullet);
+                    if self.bullets.len() < self.limits.bullets {
+                        let position = self.enemy_bullets.positions[bullet];
+                        let direction = Some((self.enemy_bullets.origin(bullet) - position).normalize_or_zero())
+                            .filter(|direction| *direction != Vec2::ZERO)
+                            .unwrap_or(Vec2::NEG_Y);
+                        self.bullets.push_with(position, direction * BULLET_SPEED, Weapon::Reflected, reflect_damage);
+                    }
+                    continue;
+                }
+                if self.player.is_invincible() {
+                    continue;
+                }
+                if hit {
+                    self.enemy_bullets.kill(bullet);
+                    if Self::damage(&mut self.player, &self.buffs, &mut self.events, DamageSource::Shot) {
+                        self.game_over = true;
+                        self.kill_cam = Some(KillCam::new(self.player.game_object.position, self.enemy_bullets.bounds(bullet)));
+                    }
+                    continue;
+                }
+                // 没打中但碰到了擦弹框，每颗子弹只算一次擦弹
+                if self.field.player_grazes(&self.player.game_object, &swept, GRAZE_MARGIN) && self.enemy_bullets.graze(bullet) {
+                    self.events.emit(GameEvent::Grazed { position: self.enemy_bullets.positions[bullet] });
+                }
+            }
+        }
+        // 第二架飞船没有反射护盾，也不算擦弹
+        if let Some(rival) = self.rival.as_mut().filter(|rival| !rival.player.is_invincible()) {
+            for bullet in 0..self.enemy_bullets.len() {
+                if !self.enemy_bullets.is_alive(bullet) || !Collider::PLAYER.interacts(self.enemy_bullets.collider(bullet)) {
+                    continue;
+                }
+                let swept = self.enemy_bullets.swept_bounds(bullet, enemy_dt);
+                if self.field.player_overlaps(&rival.player.game_object, &swept) {
+                    self.enemy_bullets.kill(bullet);
+                    if rival.hit() {
+                        self.game_over = true;
+                        self.kill_cam = Some(KillCam::new(rival.player.game_object.position, self.enemy_bullets.bounds(bullet)));
+                    }
+                    break;
+                }
+            }
+        }
+        self.enemy_bullets.remove_dead();
+
+        // 检测子弹与敌人碰撞
+        self.profiler.start("碰撞");
+        // 先用网格找出可能的碰撞，再按子弹、敌人的固定顺序结算，每个敌人的死亡只处理一次
+        let enemy_rects: Vec<Rect> = self.enemies.iter().map(|enemy| enemy.game_object.bounds()).collect();
+        let colliders: Vec<Collider> = self.enemies.iter().map(|enemy| enemy.collider).collect();
+        let alive: Vec<bool> = self.enemies.iter().map(|enemy| enemy.game_object.alive).collect();
+        let mut pairs = Self::contacts(&mut self.grid, &self.bullets, dt, &enemy_rects, &colliders);
+        // 穿透子弹已经穿过的敌人不再打一次
+        pairs.retain(|&(bullet, enemy)| {
+            let serial = self.bullets.serial(bullet);
+            serial == 0 || self.enemies[enemy].pierced != serial
+        });
+        let mut killed = collision::resolve(&pairs, self.bullets.len(), &alive, |bullet, enemy| {
+            if self.bullets.pierce(bullet) {
+                self.enemies[enemy].pierced = self.bullets.serial(bullet);
+            } else {
+                self.bullets.kill(bullet);
+            }
+            let (weapon, damage) = (self.bullets.weapon(bullet), self.bullets.damage(bullet));
+            Self::strike(&mut self.enemies[enemy], weapon, damage, &self.weapon_balance, &mut self.crit_rng, &mut self.crits, &mut self.events)
+        });
+        // 激光不会被第一个目标挡住，光束范围内还活着的敌人都会被打中
+        let beam = self.beam.as_mut().and_then(Beam::strike);
+        let beam_damage = self.weapon_balance.damage(Weapon::Laser, self.base_damage());
+        if let Some(beam) = beam {
+            for (index, enemy) in self.enemies.iter_mut().enumerate() {
+                if alive[index]
+                    && !killed.contains(&index)
+                    && Collider::PLAYER_SHOT.interacts(enemy.collider)
+                    && beam.overlaps(&enemy.game_object.bounds())
+                    && Self::strike(enemy, Weapon::Laser, beam_damage, &self.weapon_balance, &mut self.crit_rng, &mut self.crits, &mut self.events)
+                {
+                    killed.push(index);
+                }
+            }
+        }
+        // 蓄力弹不会被挡住，同一发对每个敌人只结算一次
+        for shot in &self.charged_shots {
+            let bounds = shot.game_object.bounds();
+            for (index, enemy) in self.enemies.iter_mut().enumerate() {
+                if alive[index]
+                    && !killed.contains(&index)
+                    && enemy.pierced != shot.serial
+                    && Collider::PLAYER_SHOT.interacts(enemy.collider)
+                    && bounds.overlaps(&enemy.game_object.bounds())
+                {
+                    enemy.pierced = shot.serial;
+                    if Self::strike(enemy, Weapon::Charged, shot.damage, &self.weapon_balance, &mut self.crit_rng, &mut self.crits, &mut self.events) {
+                        killed.push(index);
+                    }
+                }
+            }
+        }
+        for enemy in killed {
+            let enemy = &mut self.enemies[enemy];
+            enemy.game_object.alive = false;
+            spawned.extend(remains(enemy));
+            let (position, points) = (enemy.game_object.position, enemy.points);
+            self.destroy_enemy(position, points);
+        }
+
+        // 第二架飞船的子弹，击落的敌人按同一套计分规则记在它自己的分数上
+        if let Some(rival) = &mut self.rival {
+            let alive: Vec<bool> = self.enemies.iter().map(|enemy| enemy.game_object.alive).collect();
+            let pairs = Self::contacts(&mut self.grid, &rival.bullets, dt, &enemy_rects, &colliders);
+            let mut crits = CritStats::default();
+            let killed = collision::resolve(&pairs, rival.bullets.len(), &alive, |bullet, enemy| {
+                rival.bullets.kill(bullet);
+                let (weapon, damage) = (rival.bullets.weapon(bullet), rival.bullets.damage(bullet));
+                Self::strike(&mut self.enemies[enemy], weapon, damage, &self.weapon_balance, &mut self.crit_rng, &mut crits, &mut self.events)
+            });
+            for enemy in killed {
+                let enemy = &mut self.enemies[enemy];
+                enemy.game_object.alive = false;
+                spawned.extend(remains(enemy));
+                let score = self.scoring.on_kill(enemy.points, rival.combo.kill());
+                rival.score += score.base + score.combo;
+                self.effects.explode(enemy.game_object.position, self.limits.particles);
+                self.effects.popup(enemy.game_object.position, score.base + score.combo, self.limits.popups);
+            }
+            rival.bullets.remove_dead();
+        }
+
+        // 敌人的子弹默认只打玩家，mask 里包含 ENEMY_SHOT 的敌人和小行星才会被误伤，击落不计分
+        let alive: Vec<bool> = self.enemies.iter().map(|enemy| enemy.game_object.alive).collect();
+        let pairs = Self::contacts(&mut self.grid, &self.enemy_bullets, enemy_dt, &enemy_rects, &colliders);
+        let killed = collision::resolve(&pairs, self.enemy_bullets.len(), &alive, |bullet, enemy| {
+            self.enemy_bullets.kill(bullet);
+            self.enemies[enemy].hit(self.enemy_bullets.damage(bullet))
+        });
+        for enemy in killed {
+            let enemy = &mut self.enemies[enemy];
+            enemy.game_object.alive = false;
+            spawned.extend(remains(enemy));
+            self.effects.explode(enemy.game_object.position, self.limits.particles);
+        }
+        self.enemy_bullets.remove_dead();
+        self.enemies.retain(|enemy| enemy.game_object.alive);
+        for enemy in spawned {
+            self.add_enemy(enemy);
+        }
+
+        // 更新头目，登场动画期间不会受到伤害
+        self.profiler.start("头目");
+        if let Some(boss) = &mut self.boss {
+            let burn = boss.update(enemy_dt, &mut self.events);
+            if !boss.in_intro() {
+                boss.boundary.apply(&mut boss.game_object, self.field.boss_arena(), 0.0);
+                let mut defeated = burn > 0 && boss.hit(burn);
+                // 先结算激光，再按顺序结算子弹，头目被击败后剩下的子弹不再消耗，
+                // 穿透子弹打中头目也会消失
+                let bounds = boss.game_object.bounds();
+                let mut strikes: Vec<(Weapon, u32, Vec2, Option<usize>)> = beam
+                    .filter(|beam| beam.overlaps(&bounds))
+                    .map(|beam| (Weapon::Laser, beam_damage, Vec2::new(beam.center().x, bounds.bottom()), None))
+                    .into_iter()
+                    .collect();
+                for shot in self.charged_shots.iter_mut().filter(|shot| !shot.hit_boss) {
+                    if shot.game_object.bounds().overlaps(&bounds) {
+                        shot.hit_boss = true;
+                        strikes.push((Weapon::Charged, shot.damage, shot.game_object.position, None));
+                    }
+                }
+                for bullet in 0..self.bullets.len() {
+                    if self.bullets.is_alive(bullet)
+                        && Collider::BOSS.interacts(self.bullets.collider(bullet))
+                        && self.bullets.swept_bounds(bullet, dt).overlaps(&bounds)
+                    {
+                        let (weapon, damage) = (self.bullets.weapon(bullet), self.bullets.damage(bullet));
+                        strikes.push((weapon, damage, self.bullets.positions[bullet], Some(bullet)));
+                    }
+                }
+                for (weapon, base, position, bullet) in strikes {
+                    if defeated {
+                        break;
+                    }
+                    if let Some(bullet) = bullet {
+                        self.bullets.kill(bullet);
+                    }
+                    if let Some(status) = weapon.status() {
+                        boss.status.apply(status, Self::status_duration(status));
+                    }
+                    let hit = self.weapon_balance.roll(weapon, base, &mut self.crit_rng);
+                    self.crits.record(hit, base);
+                    self.effects.damage_number(position, hit, self.limits.popups);
+                    if hit.critical {
+                        self.events.emit(GameEvent::CriticalHit { position });
+                    }
+                    defeated = boss.hit(hit.damage);
+                }
+                if defeated {
+                    let position = boss.game_object.position;
+                    for offset in [Vec2::ZERO, Vec2::new(-40.0, 10.0), Vec2::new(40.0, -10.0)] {
+                        self.effects.explode(position + offset, self.limits.particles);
+                    }
+                    self.effects.popup(position, boss.bonus(), self.limits.popups);
+                    self.events.emit(GameEvent::BossDefeated { bonus: boss.bonus() });
+                }
+                if self.field.player_overlaps(&self.player.game_object, &boss.game_object.bounds())
+                    && Self::damage(&mut self.player, &self.buffs, &mut self.events, DamageSource::Boss)
+                {
+                    self.game_over = true;
+                    self.kill_cam = Some(KillCam::new(self.player.game_object.position, boss.game_object.bounds()));
+                }
+            }
+            if boss.hp == 0 {
+                self.boss = None;
+            }
+        }
+        self.bullets.remove_dead();
+
+        // 无尽模式分数达到阈值时出现头目
+        if self.rules.bosses_by_score()
+            && self.spawning
+            && self.boss.is_none()
+            && self.score >= self.next_boss_score
+        {
+            // 无尽模式还没有波次，每个头目相当于过了 10 波
+            let wave = (self.bosses_defeated + 1) * WAVES_PER_BOSS;
+            let spec = bossgen::generate(wave, &mut self.rng);
+            self.spawn_boss(spec);
+        }
+
+        self.profiler.start("生成");
+        if self.spawning {
+            self.spawn_waves(enemy_dt);
+            // 小行星和悬赏目标按自己的节奏出现，头目在场时暂停
+            if self.boss.is_none() {
+                self.asteroid_timer -= enemy_dt;
+                if self.asteroid_timer <= 0.0 {
+                    self.asteroid_timer = asteroid::next_interval(&mut self.asteroid_rng);
+                    let asteroid = asteroid::spawn(&self.field, &mut self.asteroid_rng);
+                    self.add_enemy(asteroid);
+                }
+                self.bounty_timer -= enemy_dt;
+                if self.bounty_timer <= 0.0 {
+                    self.mark_bounty();
+                }
+            }
+            if self.checkpoint.as_ref().is_some_and(|checkpoint| checkpoint.wave != self.wave()) {
+                self.save_checkpoint();
+                if self.rules.milestones() && !self.practice && milestone::due(self.wave()) {
+                    self.events.emit(GameEvent::MilestoneReached { milestone: self.milestone() });
+                }
+            }
+            // 道具只有第一架飞船拾取得到，双人对战时不掉落
+            self.powerup_timer += dt;
+            if self.rival.is_none() && self.powerup_timer >= self.powerup_balance.interval {
+                self.powerup_timer = 0.0;
+                self.spawn_powerup();
+            }
+        }
+
+        // 模式自己的结束条件，例如计时赛的时间到了
+        if !self.game_over && self.rules.finished(self) {
+            self.game_over = true;
+        }
+
+        // 本局刚刚结束
+        if self.game_over {
+            self.events.emit(GameEvent::GameOver);
+        }
+
+        self.profiler.start("事件");
+        let events = self.events.take();
+        for event in &events {
+            self.handle_event(event);
+        }
+        self.profiler.finish();
+        events
+    }
+
+    // 生成新敌人，头目出现期间暂停
+    fn spawn_waves(&mut self, dt: f32) {
+        if let Some(campaign) = &mut self.campaign {
+            // 小行星不算在波次里，不用全部打掉
+            let field_clear = self.enemies.iter().all(|enemy| enemy.asteroid.is_some()) && self.boss.is_none();
+            match campaign.update(dt, field_clear) {
+                Some(CampaignSpawn::Formation(wave)) => self.spawn_formation(&wave),
+                Some(CampaignSpawn::Boss(spec)) => self.spawn_boss(spec),
+                Some(CampaignSpawn::WaveCleared { wave }) => {
+                    let bonus = self.scoring.on_wave_clear(wave);
+                    self.events.emit(GameEvent::WaveCleared { wave, bonus });
+                }
+                None => (),
+            }
+        } else {
+            self.dynamic_rank.update(dt, self.combo.multiplier(), &self.difficulty.dynamic_rank);
+            let difficulty = self.difficulty();
+            self.spawn_timer += dt;
+            if self.spawn_timer >= difficulty.spawn_interval && self.boss.is_none() {
+                self.spawn_enemy(difficulty.enemy_speed, difficulty.rank);
+                self.spawn_timer = 0.0;
+            }
+            // 头目在场时推迟，头目退场后只补一架，不会一下子来好几架
+            let due = self.difficulty.carriers_due(self.run_time);
+            if due > self.carriers_spawned && self.boss.is_none() {
+                self.spawn_by_id(CARRIER_ID).expect("内置内容缺少母舰");
+                self.carriers_spawned = due;
+            }
+        }
+    }
+
+    // 玩家受到一次伤害并发出带来源的事件，返回这次伤害是否致命。
+    // 遍历 self.enemies 时也要调用，所以只借用玩家、道具状态和事件队列
+    fn damage(player: &mut Player, buffs: &Buffs, events: &mut EventBus, source: DamageSource) -> bool {
+        // 护盾挡得住撞击，挡不住敌人从底部逃走
+        if buffs.shielded() && source != DamageSource::Escaped {
+            return false;
+        }
+        // 护盾条模式下先由护盾挡下，打空之后才按下面的规则降级或掉命
+        if player.absorb_hit() {
+            events.emit(GameEvent::ShieldDamaged { source });
+            return false;
+        }
+        // 火力高于 1 级时被击中只降一级，敌人逃走照常掉命
+        if source != DamageSource::Escaped && player.downgrade() {
+            events.emit(GameEvent::WeaponDowngraded { level: player.weapon_level });
+            return false;
+        }
+        if !player.take_damage() {
+            return false;
+        }
+        events.emit(GameEvent::PlayerDamaged { source });
+        player.lives == 0
+    }
+
+    // 网格粗筛之后再按碰撞层过滤，得到 shots 中的子弹和 targets 中的目标所有会发生碰撞的下标对
+    fn contacts(grid: &mut Grid, shots: &Bullets, dt: f32, targets: &[Rect], colliders: &[Collider]) -> Vec<(usize, usize)> {
+        let rects: Vec<Rect> = (0..shots.len()).map(|bullet| shots.swept_bounds(bullet, dt)).collect();
+        let mut pairs = grid.overlapping_pairs(&rects, targets);
+        pairs.retain(|&(bullet, target)| shots.collider(bullet).interacts(colliders[target]));
+        pairs
+    }
+
+    // 所有得分都经过这里，同时记入本局的得分记录
+    fn add_score(&mut self, points: u32, reason: ScoreReason) {
+        self.score += points;
+        self.ledger.record(self.run_time, points, reason);
+        // 一次得分跨过好几条奖励线时每条都奖励，奖励线只往上走，不会重复发放；双人对战不奖励生命
+        let interval = if self.rival.is_some() { 0 } else { self.extra_life.score_interval };
+        while interval > 0 && self.score >= self.next_life_score {
+            self.next_life_score += interval;
+            self.award_life();
+        }
+    }
+
+    fn award_life(&mut self) {
+        self.player.lives += 1;
+        self.events.emit(GameEvent::ExtraLife { lives: self.player.lives });
+    }
+
+    // 击落一个敌人：按计分规则计分并播放爆炸，连击倍率多出来的分数单独记账
+    fn destroy_enemy(&mut self, position: Vec2, points: u32) {
+        let multiplier = self.combo.kill();
+        self.quests.on_kill();
+        let score = self.scoring.on_kill(points, multiplier);
+        self.add_score(score.base, ScoreReason::Enemy);
+        if score.combo > 0 {
+            self.add_score(score.combo, ScoreReason::Combo);
+        }
+        self.effects.explode(position, self.limits.particles);
+        self.effects.popup(position, score.base + score.combo, self.limits.popups);
+        // 只有有波次和商店的模式才掉金币，和敌人共用数量上限
+        if self.campaign.is_some() && self.coins.len() < self.limits.enemies {
+            self.coins.push(shop::coin(position));
+        }
+    }
+
+    // 商店里这件东西现在能不能买：生命和炸弹有上限，强化有最高等级
+    pub fn can_buy(&self, item: ShopItem) -> Result<u32, &'static str> {
+        let available = match item {
+            ShopItem::FireRate => self.upgrades.fire_rate < shop::MAX_UPGRADE,
+            ShopItem::Speed => self.upgrades.speed < shop::MAX_UPGRADE,
+            ShopItem::ExtraLife => true,
+            ShopItem::Bomb => self.player.bombs < MAX_BOMBS,
+        };
+        let price = item.price(&self.upgrades);
+        if !available {
+            Err("已经到上限了")
+        } else if self.wallet < price {
+            Err("金币不够")
+        } else {
+            Ok(price)
+        }
+    }
+
+    // 花金币买下一件东西，买不了时返回原因
+    pub fn buy(&mut self, item: ShopItem) -> Result<(), &'static str> {
+        let price = self.can_buy(item)?;
+        self.wallet -= price;
+        match item {
+            ShopItem::FireRate => self.upgrades.fire_rate += 1,
+            ShopItem::Speed => self.upgrades.speed += 1,
+            ShopItem::ExtraLife => self.player.lives += 1,
+            ShopItem::Bomb => self.player.add_bomb(),
+        }
+        self.events.emit(GameEvent::ItemBought { item });
+        Ok(())
+    }
+
+    // 各种子弹附带的状态持续多久
+    fn status_duration(status: StatusKind) -> f32 {
+        match status {
+            StatusKind::Burn => DRONE_BURN,
+            StatusKind::Slow => GUNS_SLOW,
+            StatusKind::Stun => EMP_STUN,
+        }
+    }
+
+    // 炸弹：场上的敌人（包括水雷）全部击毁并计分，敌人的子弹也一起清除
+    fn detonate_bomb(&mut self) {
+        self.effects.shockwave(self.player.game_object.position, BOMB_RADIUS);
+        self.enemy_bullets = Bullets::default();
+        for enemy in std::mem::take(&mut self.enemies) {
+            self.add_score(enemy.points, ScoreReason::Bomb);
+            self.effects.explode(enemy.game_object.position, self.limits.particles);
+            self.effects.popup(enemy.game_object.position, enemy.points, self.limits.popups);
+        }
+    }
+
+    // 处理影响游戏规则的事件
+    fn handle_event(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::BossSpawned => self.cinematic = true,
+            GameEvent::BossIntroFinished => self.cinematic = false,
+            GameEvent::BossDefeated { bonus } => {
+                self.add_score(bonus, ScoreReason::Boss);
+                self.bosses_defeated += 1;
+                self.next_boss_score = self.score + BOSS_SCORE_INTERVAL;
+                if let Some(campaign) = &mut self.campaign {
+                    if campaign.boss_defeated() {
+                        // 练习局打完头目就结束，不算通关
+                        if self.practice {
+                            self.game_over = true;
+                            self.notice = Some(("练习完成".to_string(), 2.0));
+                        } else {
+                            self.events.emit(GameEvent::CampaignCleared);
+                        }
+                    }
+                }
+            }
+            GameEvent::ChatVoteWon { event } => {
+                match event {
+                    ChatEvent::Elite => self.spawn_elite(),
+                    ChatEvent::Powerup => self.spawn_powerup(),
+                    ChatEvent::Meteors => self.meteor_shower(),
+                }
+                self.notice = Some((format!("观众投票: {}", event.label()), 2.0));
+            }
+            GameEvent::BossTurretFired { position } => {
+                // 水雷可以被击落，飞出屏幕不扣生命
+                let mut mine = Enemy::new(position.x, position.y, 12.0, 220.0, EnemyPath::Straight);
+                mine.escape_penalty = false;
+                mine.source = DamageSource::Mine;
+                mine.status.immune = Immunity::MINE;
+                mine.collider = Collider::MINE;
+                self.add_enemy(mine);
+            }
+            GameEvent::PowerupCollected { kind, .. } => {
+                match kind {
+                    PowerupKind::RapidFire => self.buffs.rapid_fire = self.powerup_balance.rapid_fire_time,
+                    PowerupKind::Spread => self.buffs.spread = self.powerup_balance.spread_time,
+                    PowerupKind::Upgrade => self.player.upgrade(self.max_weapon_level()),
+                    PowerupKind::Shield => self.buffs.shield = self.powerup_balance.shield_time,
+                    PowerupKind::Drone => self.buffs.drone = self.powerup_balance.drone_time,
+                    PowerupKind::Guns => self.buffs.guns = self.powerup_balance.guns_time,
+                    PowerupKind::Reflector => self.buffs.reflector = self.powerup_balance.reflector_time,
+                    PowerupKind::Freeze => self.buffs.freeze = self.powerup_balance.freeze_time,
+                    PowerupKind::Berserk => self.buffs.berserk = self.powerup_balance.cursed_time,
+                    PowerupKind::Bomb => self.detonate_bomb(),
+                    PowerupKind::BombRefill => self.player.add_bomb(),
+                    PowerupKind::ExtraLife => self.award_life(),
+                    PowerupKind::Option => {
+                        self.player.add_option();
+                    }
+                    PowerupKind::ComboKeeper => self.combo.keep(self.powerup_balance.combo_keeper_time),
+                }
+                self.notice = Some((format!("获得道具: {}", kind.name()), 1.5));
+            }
+            GameEvent::PlayerDamaged { source } => {
+                self.quests.on_damage();
+                self.dynamic_rank.on_death(&self.difficulty.dynamic_rank);
+                if self.scoring.on_damage() {
+                    self.combo.reset();
+                }
+                if self.player.lives == 0 {
+                    self.killed_by = Some(source);
+                }
+            }
+            GameEvent::Quest { ref outcome } => match outcome {
+                QuestOutcome::Started(label) => self.notice = Some((format!("新任务: {}", label), 2.5)),
+                QuestOutcome::Completed(Reward::Score(points)) => {
+                    let points = *points;
+                    self.add_score(points, ScoreReason::Quest);
+                    self.notice = Some((format!("任务完成  +{}", points), 2.0));
+                }
+                QuestOutcome::Completed(Reward::Powerup(id)) => {
+                    // 奖励的道具和拾取到的一样生效，提示也由拾取的事件给出
+                    if let Some(kind) = self.content.powerup(id) {
+                        let position = self.player.game_object.position;
+                        self.events.emit(GameEvent::PowerupCollected { kind, position });
+                    }
+                }
+                QuestOutcome::Failed => self.notice = Some(("任务失败".to_string(), 1.5)),
+            },
+            GameEvent::BountyMarked { points } => self.notice = Some((format!("悬赏目标出现! 击落得 {} 分", points), 2.0)),
+            GameEvent::ExtraLife { lives } => self.notice = Some((format!("奖励生命! 现在有 {} 条命", lives), 2.0)),
+            GameEvent::ShieldDamaged { .. } => {
+                if self.scoring.on_damage() {
+                    self.combo.reset();
+                }
+                self.quests.on_damage();
+            }
+            GameEvent::WeaponDowngraded { level } => {
+                self.combo.reset();
+                self.quests.on_damage();
+                self.notice = Some((format!("火力下降到 {} 级", level), 1.5));
+            }
+            GameEvent::BossPhaseChanged { phase } => {
+                self.notice = Some((format!("头目进入第 {} 阶段!", phase + 1), 2.0));
+            }
+            GameEvent::WaveCleared { wave, bonus } => {
+                self.add_score(bonus, ScoreReason::WaveBonus);
+                self.notice = Some((format!("第 {} 波完成  +{}", wave, bonus), 1.5));
+            }
+            // 通关时结算剩余生命奖励
+            GameEvent::CampaignCleared => self.add_score(self.player.lives * LIFE_BONUS, ScoreReason::LifeBonus),
+            GameEvent::ItemBought { item } => self.notice = Some((format!("购买了{}", item.label()), 1.5)),
+            GameEvent::MilestoneReached { milestone } => {
+                self.notice = Some((format!("第 {} 波里程碑已记录，可在标题菜单练习", milestone.wave), 2.0));
+            }
+            GameEvent::Grazed { position } => {
+                self.grazes += 1;
+                self.effects.spark(position, self.limits.particles);
+                self.quests.on_graze();
+                let points = self.scoring.on_graze();
+                if points > 0 {
+                    self.add_score(points, ScoreReason::Graze);
+                }
+            }
+            GameEvent::RunStarted
+            | GameEvent::WaveRestarted
+            | GameEvent::Continued { .. }
+            | GameEvent::CriticalHit { .. }
+            | GameEvent::ChargedShotFired { .. }
+            | GameEvent::CoinCollected { .. }
+            | GameEvent::GameOver => (),
+        }
+    }
+}
@@ -0,0 +1,2904 @@
+// 用脚本化输入验证具体的游戏规则
+
+use std::path::PathBuf;
+
+use ggez::event::winit_event::TouchPhase;
+use ggez::glam::Vec2;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use space_shooter::assets;
+use space_shooter::asteroid;
+use space_shooter::bindings::{Action, DoubleTap, HeldMoves, HoldTimer, RESTART_HOLD};
+use space_shooter::boss::{Boss, BossPattern, BossSpec, PhaseSpec};
+use space_shooter::bullets::{Bullets, Modifiers};
+use space_shooter::campaign::{self, BossDrill, Campaign, CampaignData, Formation, SpawnLane, WaveSpec, WaveTransform};
+use space_shooter::chat::{self, ChatEvent, ChatVote};
+use space_shooter::collision::{Collider, Layers};
+use space_shooter::combo::{self, Combo};
+use space_shooter::continues::{self, Continues};
+use space_shooter::difficulty::{DifficultyCurve, DynamicRank};
+use space_shooter::enemy::{self, Carrier, Enemy, EnemyPath, Gun, Kamikaze};
+use space_shooter::events::GameEvent;
+use space_shooter::focus::{self, Focus};
+use space_shooter::health::{HealthModel, ShieldBar};
+use space_shooter::heatmap::{self, HeatMap};
+use space_shooter::hitreplay::{self, ClipRecorder};
+use space_shooter::hud;
+use space_shooter::ledger::{self, Ledger, ScoreReason};
+use space_shooter::lifecycle::Lifecycle;
+use space_shooter::meta::{self, MetaUpgrade, MetaUpgrades};
+use space_shooter::milestone::{self, Milestone};
+use space_shooter::modes;
+use space_shooter::playfield::{Boundary, EdgeMode, EdgeRule, PlayField};
+use space_shooter::powerup::{Balance, Powerup, PowerupKind, Rarity};
+use space_shooter::profiling::Watchdog;
+use space_shooter::quality::{Quality, QualityControl};
+use space_shooter::quest::{Goal, QuestOutcome, QuestTemplate, Reward};
+use space_shooter::registry::Registry;
+use space_shooter::resources::ResourceHints;
+use space_shooter::scoring::{Classic, Daily, KillScore, ScoringRules, TimeAttack};
+use space_shooter::shop::ShopItem;
+use space_shooter::simulation::Simulation;
+use space_shooter::status::{Immunity, StatusEffects, StatusKind};
+use space_shooter::storage::{self, Progress, Storage, StreamerSettings};
+use space_shooter::streamer::{self, BossState, Corner, OverlayFile, StateSnapshot};
+use space_shooter::swarm::{Bezier, Stage, Swarm};
+use space_shooter::theme::{Rgb, Theme, ThemePart, PALETTE};
+use space_shooter::touch::{TouchButton, TouchControls};
+use space_shooter::versus::Winner;
+use space_shooter::weapon::{Hit, Weapon, WeaponBalance, WeaponSpec};
+use space_shooter::world::{self, DamageSource, DeathCause, Faction, FrameInput, GameMode, World};
+use space_shooter::{Dash, DASH_COOLDOWN, MAX_BOMBS, PLAYER_SPEED, WINDOW_HEIGHT, WINDOW_WIDTH};
+
+// 在玩家正上方放一个静止的敌人，下一帧就会撞上
+fn enemy_on_player(simulation: &mut Simulation) {
+    let position = simulation.world.player.game_object.position;
+    simulation.world.enemies.push(Enemy::new(position.x, position.y, 30.0, 0.0, EnemyPath::Straight));
+}
+
+#[test]
+fn holding_left_stops_at_the_edge() {
+    let mut simulation = Simulation::quiet();
+    simulation.run("left 30").unwrap();
+    let x = simulation.world.player.game_object.position.x;
+    assert!(x < WINDOW_WIDTH / 2.0 && x > 15.0);
+
+    simulation.run("left 120").unwrap();
+    assert_eq!(simulation.world.player.game_object.position.x, 15.0);
+}
+
+#[test]
+fn player_moves_up_only_within_the_lower_third() {
+    let mut simulation = Simulation::quiet();
+    let start = simulation.world.player.game_object.position.y;
+    simulation.run("up 10").unwrap();
+    assert!(simulation.world.player.game_object.position.y < start);
+
+    simulation.run("up 120").unwrap();
+    assert_eq!(simulation.world.player.game_object.position.y, WINDOW_HEIGHT * 2.0 / 3.0 + 15.0);
+    simulation.run("down 240").unwrap();
+    assert_eq!(simulation.world.player.game_object.position.y, WINDOW_HEIGHT - 15.0);
+}
+
+#[test]
+fn diagonal_movement_is_no_faster_than_straight() {
+    let mut simulation = Simulation::quiet();
+    simulation.step(FrameInput { move_x: 1, move_y: -1, ..FrameInput::default() });
+    let velocity = simulation.world.player.game_object.velocity;
+    assert!((velocity.length() - PLAYER_SPEED).abs() < 0.01);
+    assert!(velocity.x > 0.0 && velocity.y < 0.0);
+
+    let mut held = HeldMoves::default();
+    held.set(Action::MoveLeft, true);
+    held.set(Action::MoveDown, true);
+    held.set(Action::MoveRight, true);
+    assert_eq!(held.direction(), Vec2::new(0.0, 1.0));
+    assert!(!held.set(Action::Fire, true));
+}
+
+#[test]
+fn wrapping_player_reappears_on_the_other_side() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.field.player_edges = EdgeMode::Wrap;
+    simulation.run("left 120").unwrap();
+    let x = simulation.world.player.game_object.position.x;
+    assert!(x > WINDOW_WIDTH / 2.0, "应当从右侧出现, x = {}", x);
+}
+
+#[test]
+fn wrapping_player_is_hit_on_both_sides_of_the_edge() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.field.player_edges = EdgeMode::Wrap;
+    let y = simulation.world.player.game_object.position.y;
+    // 机身中心刚过左边缘，伸出去的一半在右侧
+    simulation.world.player.game_object.position.x = 5.0;
+    simulation.world.enemies.push(Enemy::new(WINDOW_WIDTH - 5.0, y, 10.0, 0.0, EnemyPath::Straight));
+    simulation.wait(1);
+    assert_eq!(simulation.world.player.lives, 2);
+}
+
+#[test]
+fn bullet_flies_up_and_is_removed_off_screen() {
+    let mut simulation = Simulation::quiet();
+    simulation.run("fire, wait 30").unwrap();
+    assert_eq!(simulation.world.bullets.len(), 1);
+    assert!(simulation.world.bullets.positions[0].y < WINDOW_HEIGHT - 100.0);
+
+    simulation.wait_seconds(2.0);
+    assert!(simulation.world.bullets.is_empty());
+}
+
+#[test]
+fn shooting_an_enemy_scores_ten() {
+    let mut simulation = Simulation::quiet();
+    let x = simulation.world.player.game_object.position.x;
+    simulation.world.enemies.push(Enemy::new(x, 200.0, 30.0, 0.0, EnemyPath::Straight));
+    simulation.run("fire, wait 90").unwrap();
+    assert!(simulation.world.enemies.is_empty());
+    assert_eq!(simulation.world.score, 10);
+}
+
+#[test]
+fn invincibility_lasts_two_seconds() {
+    let mut simulation = Simulation::quiet();
+    enemy_on_player(&mut simulation);
+    simulation.wait(1);
+    assert_eq!(simulation.world.player.lives, 2);
+    assert!(simulation.world.player.is_invincible());
+
+    // 无敌期间撞上敌人不扣命，敌人也不会被撞毁
+    simulation.wait_seconds(1.9);
+    enemy_on_player(&mut simulation);
+    simulation.wait(1);
+    assert_eq!(simulation.world.player.lives, 2);
+    assert_eq!(simulation.world.enemies.len(), 1);
+
+    // 无敌结束后还停在原地的敌人会再撞一次
+    simulation.wait_seconds(0.2);
+    assert_eq!(simulation.world.player.lives, 1);
+    assert!(simulation.world.enemies.is_empty());
+}
+
+#[test]
+fn escaped_enemy_costs_a_life_but_escaped_mine_does_not() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.enemies.push(Enemy::new(100.0, WINDOW_HEIGHT, 20.0, 200.0, EnemyPath::Straight));
+    simulation.wait(10);
+    assert_eq!(simulation.world.player.lives, 2);
+
+    simulation.wait_seconds(2.5);
+    let mut mine = Enemy::new(100.0, WINDOW_HEIGHT, 12.0, 220.0, EnemyPath::Straight);
+    mine.escape_penalty = false;
+    simulation.world.enemies.push(mine);
+    simulation.wait(10);
+    assert!(simulation.world.enemies.is_empty());
+    assert_eq!(simulation.world.player.lives, 2);
+}
+
+#[test]
+fn losing_the_last_life_ends_the_run() {
+    let mut simulation = Simulation::quiet();
+    for _ in 0..3 {
+        enemy_on_player(&mut simulation);
+        simulation.wait_seconds(2.1);
+    }
+    assert_eq!(simulation.world.player.lives, 0);
+    assert!(simulation.world.game_over);
+    assert!(simulation.saw(|event| matches!(event, GameEvent::GameOver)));
+}
+
+#[test]
+fn final_hit_plays_a_slow_motion_kill_cam() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.player.lives = 1;
+    let position = simulation.world.player.game_object.position;
+    simulation.world.enemies.push(Enemy::new(position.x, position.y, 30.0, 0.0, EnemyPath::Straight));
+    simulation.world.enemies.push(Enemy::new(100.0, 100.0, 30.0, 100.0, EnemyPath::Straight));
+    simulation.wait(1);
+    assert!(simulation.world.game_over);
+    let kill_cam = simulation.world.kill_cam.expect("最后一击应当触发慢镜头");
+    assert_eq!(kill_cam.focus, position);
+    assert_eq!(kill_cam.killer.center(), position.into());
+    assert_eq!(simulation.world.time_scale(Faction::Enemy), 0.2);
+
+    // 剩下的敌人按五分之一的速度继续下落，结束后恢复正常
+    let y = simulation.world.enemies[0].game_object.position.y;
+    simulation.wait_seconds(1.0);
+    let moved = simulation.world.enemies[0].game_object.position.y - y;
+    assert!((moved - 20.0).abs() < 1.0);
+    simulation.wait_seconds(1.1);
+    assert!(simulation.world.kill_cam.is_none());
+    assert_eq!(simulation.world.time_scale(Faction::Enemy), 1.0);
+    assert!(simulation.world.game_over);
+}
+
+#[test]
+fn game_over_remembers_what_dealt_the_last_hit() {
+    let mut simulation = Simulation::quiet();
+    for _ in 0..2 {
+        enemy_on_player(&mut simulation);
+        simulation.wait_seconds(2.1);
+    }
+    assert!(simulation.saw(|event| matches!(event, GameEvent::PlayerDamaged { source: DamageSource::Straight })));
+    assert_eq!(simulation.world.killed_by, None);
+
+    let position = simulation.world.player.game_object.position;
+    simulation.world.enemies.push(Enemy::new(position.x, position.y, 30.0, 0.0, EnemyPath::Zigzag));
+    simulation.wait(2);
+    assert!(simulation.world.game_over);
+    assert_eq!(simulation.world.killed_by, Some(DamageSource::Zigzag));
+    assert_eq!(simulation.world.death_cause(), Some(DeathCause::Collision));
+}
+
+#[test]
+fn retrying_a_wave_restores_its_starting_score_and_lives() {
+    let mut simulation = Simulation::quiet();
+    let x = simulation.world.player.game_object.position.x;
+    simulation.world.enemies.push(Enemy::new(x, 200.0, 30.0, 0.0, EnemyPath::Straight));
+    simulation.run("fire, wait 90").unwrap();
+    assert_eq!(simulation.world.score, 10);
+    for _ in 0..3 {
+        enemy_on_player(&mut simulation);
+        simulation.wait_seconds(2.1);
+    }
+    assert!(simulation.world.game_over);
+
+    simulation.world.retry_wave();
+    assert!(!simulation.world.game_over);
+    assert_eq!(simulation.world.wave(), 1);
+    assert_eq!(simulation.world.score, 0);
+    assert!(simulation.world.ledger.entries.is_empty());
+    assert_eq!(simulation.world.player.lives, 3);
+    assert!(simulation.world.enemies.is_empty());
+    simulation.wait(1);
+    assert!(simulation.saw(|event| matches!(event, GameEvent::WaveRestarted)));
+}
+
+#[test]
+fn meta_upgrades_cost_credits_and_stop_at_their_cap() {
+    let mut upgrades = MetaUpgrades::default();
+    let mut credits = MetaUpgrade::Lives.price(0) + MetaUpgrade::Lives.price(1);
+    assert_eq!(upgrades.buy(MetaUpgrade::Lives, &mut credits), Ok(20));
+    assert_eq!(upgrades.buy(MetaUpgrade::Lives, &mut credits), Ok(40));
+    assert_eq!(upgrades.buy(MetaUpgrade::Lives, &mut credits), Err("已经到上限了"));
+    assert_eq!((upgrades.lives, credits), (2, 0));
+    assert_eq!(upgrades.buy(MetaUpgrade::Damage, &mut credits), Err("积分不够"));
+    assert_eq!(upgrades.damage, 0);
+    assert_eq!(meta::credits_for(meta::CREDIT_SCORE * 3 - 1), 2);
+}
+
+#[test]
+fn meta_upgrades_apply_to_the_starting_ship() {
+    let meta = MetaUpgrades { lives: 2, damage: 1, speed: 0 };
+    let mut simulation = Simulation { world: World::with_upgrades(GameMode::Endless, 0, meta), frame: 0, events: Vec::new() };
+    simulation.world.spawning = false;
+    assert_eq!(simulation.world.player.lives, 5);
+    simulation.fire();
+    assert_eq!(simulation.world.bullets.damage(0), 2);
+
+    // 从这一波重来时仍然是强化后的生命
+    for _ in 0..5 {
+        enemy_on_player(&mut simulation);
+        simulation.wait_seconds(2.1);
+    }
+    assert!(simulation.world.game_over);
+    simulation.world.retry_wave();
+    assert_eq!(simulation.world.player.lives, 5);
+    assert_eq!(Simulation::quiet().world.player.lives, 3);
+}
+
+#[test]
+fn retried_campaign_wave_spawns_the_same_enemies_again() {
+    let mut simulation = Simulation::new(GameMode::Campaign, 7);
+    simulation.wait_seconds(4.0);
+    let wave = simulation.world.wave();
+    let positions = |simulation: &Simulation| {
+        simulation.world.enemies.iter().map(|enemy| enemy.game_object.position.x).collect::<Vec<_>>()
+    };
+    let before = positions(&simulation);
+    assert!(!before.is_empty());
+
+    let elapsed = simulation.world.run_time;
+    simulation.world.retry_wave();
+    let restart = simulation.world.run_time;
+    simulation.wait_seconds(elapsed - restart);
+    assert_eq!(simulation.world.wave(), wave);
+    assert_eq!(positions(&simulation), before);
+}
+
+#[test]
+fn campaign_kills_drop_coins_that_fall_into_the_wallet() {
+    let mut simulation = Simulation::new(GameMode::Campaign, 7);
+    simulation.world.spawning = false;
+    let position = simulation.world.player.game_object.position - Vec2::new(0.0, 150.0);
+    simulation.world.enemies.push(Enemy::new(position.x, position.y, 30.0, 0.0, EnemyPath::Straight));
+    simulation.run("fire 1, wait 30").unwrap();
+    assert!(simulation.world.enemies.is_empty());
+    assert_eq!(simulation.world.coins.len(), 1);
+
+    simulation.wait_seconds(1.5);
+    assert!(simulation.world.coins.is_empty());
+    assert_eq!(simulation.world.wallet, 1);
+    assert!(simulation.saw(|event| matches!(event, GameEvent::CoinCollected { wallet: 1 })));
+
+    // 无尽模式没有商店，也不掉金币
+    let mut endless = Simulation::quiet();
+    let position = endless.world.player.game_object.position - Vec2::new(0.0, 150.0);
+    endless.world.enemies.push(Enemy::new(position.x, position.y, 30.0, 0.0, EnemyPath::Straight));
+    endless.run("fire 1, wait 30").unwrap();
+    assert!(endless.world.enemies.is_empty() && endless.world.coins.is_empty());
+}
+
+#[test]
+fn the_shop_spends_coins_until_each_item_hits_its_cap() {
+    let mut simulation = Simulation::new(GameMode::Campaign, 7);
+    let world = &mut simulation.world;
+    assert_eq!(world.buy(ShopItem::FireRate), Err("金币不够"));
+
+    world.wallet = 200;
+    assert_eq!(world.buy(ShopItem::FireRate), Ok(()));
+    assert_eq!((world.wallet, world.upgrades.fire_rate), (185, 1));
+    world.fire_bullet();
+    assert!(world.player.fire_cooldown < 0.1);
+    assert!(world.buy(ShopItem::FireRate).is_ok() && world.buy(ShopItem::FireRate).is_ok());
+    assert_eq!(world.buy(ShopItem::FireRate), Err("已经到上限了"));
+
+    assert!(world.buy(ShopItem::ExtraLife).is_ok());
+    assert_eq!(world.player.lives, 4);
+    assert!(world.buy(ShopItem::Bomb).is_ok());
+    assert_eq!(world.buy(ShopItem::Bomb), Err("已经到上限了"));
+    assert_eq!(world.player.bombs, MAX_BOMBS);
+}
+
+#[test]
+fn engine_upgrades_make_the_ship_faster() {
+    let distance = |speed: u32| {
+        let mut simulation = Simulation::quiet();
+        simulation.world.upgrades.speed = speed;
+        let start = simulation.world.player.game_object.position.x;
+        simulation.run("right 20").unwrap();
+        simulation.world.player.game_object.position.x - start
+    };
+    assert!(distance(2) > distance(0) * 1.15);
+}
+
+#[test]
+fn clearing_a_campaign_wave_pays_a_bonus_before_the_next_wave() {
+    let mut simulation = Simulation::new(GameMode::Campaign, 7);
+    // 每帧清场，第一波 6 个敌人出完之后场上就是空的
+    while !simulation.saw(|event| matches!(event, GameEvent::WaveCleared { .. })) {
+        simulation.world.enemies.clear();
+        simulation.wait(1);
+        assert!(simulation.world.run_time < 20.0, "第一波迟迟没有结束");
+    }
+    assert!(simulation.saw(|event| matches!(event, GameEvent::WaveCleared { wave: 1, bonus: 50 })));
+    assert_eq!(simulation.world.score, 50);
+    assert_eq!(simulation.world.ledger.totals(), vec![(ScoreReason::WaveBonus, 50)]);
+    let banner = simulation.world.campaign.as_ref().and_then(|campaign| campaign.banner());
+    assert_eq!(banner.as_deref(), Some("第 2 波"));
+    assert_eq!(simulation.world.wave_remaining(), Some(0));
+
+    simulation.wait_seconds(1.6);
+    assert_eq!(simulation.world.wave(), 2);
+    assert!(simulation.world.campaign.as_ref().unwrap().banner().is_none());
+    assert_eq!(simulation.world.wave_remaining(), Some(10));
+}
+
+#[test]
+fn boss_intro_locks_player_input() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.spawn_boss(BossSpec {
+        name: "测试头目".to_string(),
+        hp: 5,
+        bonus: 100,
+        phases: BossSpec::default_phases(),
+        size: BossSpec::default_size(),
+        turrets: 0,
+    });
+    let x = simulation.world.player.game_object.position.x;
+    simulation.run("wait, left 30, fire").unwrap();
+    assert!(simulation.world.cinematic);
+    assert_eq!(simulation.world.player.game_object.position.x, x);
+    assert!(simulation.world.bullets.is_empty());
+
+    // 登场动画结束后恢复操作
+    simulation.wait_seconds(5.0);
+    assert!(!simulation.world.cinematic);
+    assert!(simulation.saw(|event| matches!(event, GameEvent::BossIntroFinished)));
+    simulation.run("left 30").unwrap();
+    assert!(simulation.world.player.game_object.position.x < x);
+}
+
+#[test]
+fn critical_hits_multiply_damage_and_always_add_at_least_one() {
+    let always = WeaponSpec { chance: 1.0, multiplier: 1.2, penetration: 0, damage: 1, cooldown: 0.0, pierce: 0, bounces: 0 };
+    let never = WeaponSpec { chance: 0.0, multiplier: 10.0, penetration: 0, damage: 1, cooldown: 0.0, pierce: 0, bounces: 0 };
+    let balance = WeaponBalance {
+        cannon: always,
+        laser: never,
+        scatter: never,
+        drone: never,
+        guns: always,
+        reflected: never,
+        charged: never,
+    };
+    let mut rng = StdRng::seed_from_u64(1);
+    assert_eq!(balance.roll(Weapon::Cannon, 1, &mut rng), Hit { damage: 2, critical: true });
+    assert_eq!(balance.roll(Weapon::Guns, 10, &mut rng), Hit { damage: 12, critical: true });
+    assert_eq!(balance.roll(Weapon::Drone, 1, &mut rng), Hit { damage: 1, critical: false });
+}
+
+#[test]
+fn armor_deflects_weapons_without_enough_penetration() {
+    let spec = |penetration| WeaponSpec { chance: 0.0, multiplier: 1.0, penetration, damage: 1, cooldown: 0.0, pierce: 0, bounces: 0 };
+    let balance = WeaponBalance {
+        cannon: spec(1),
+        laser: spec(2),
+        scatter: spec(0),
+        drone: spec(2),
+        guns: spec(0),
+        reflected: spec(3),
+        charged: spec(3),
+    };
+    assert_eq!(balance.against_armor(Weapon::Guns, 1, 0), 1);
+    assert_eq!(balance.against_armor(Weapon::Guns, 1, 1), 0);
+    assert_eq!(balance.against_armor(Weapon::Cannon, 1, 2), 0);
+    assert_eq!(balance.against_armor(Weapon::Cannon, 2, 2), 1);
+    assert_eq!(balance.against_armor(Weapon::Drone, 1, 2), 1);
+}
+
+#[test]
+fn heavy_enemy_survives_until_its_hp_runs_out() {
+    let mut simulation = Simulation::quiet();
+    let position = simulation.world.player.game_object.position;
+    let mut enemy = Enemy::new(position.x, position.y - 120.0, 44.0, 0.0, EnemyPath::Straight);
+    enemy.hp = 3;
+    enemy.armor = 1;
+    simulation.world.enemies.push(enemy);
+    simulation.run("fire 1, wait 20").unwrap();
+    assert_eq!(simulation.world.enemies.len(), 1);
+    simulation.run("fire 1, wait 20, fire 1, wait 20, fire 1, wait 20").unwrap();
+    assert!(simulation.world.enemies.is_empty());
+}
+
+#[test]
+fn damaged_enemies_flash_and_report_their_remaining_health() {
+    let mut simulation = Simulation::quiet();
+    let position = simulation.world.player.game_object.position;
+    let mut enemy = Enemy::new(position.x, position.y - 120.0, 44.0, 0.0, EnemyPath::Straight);
+    enemy.set_hp(4);
+    simulation.world.enemies.push(enemy);
+    assert_eq!(simulation.world.enemies[0].health(), None);
+
+    simulation.run("fire 1, wait 12").unwrap();
+    let enemy = &simulation.world.enemies[0];
+    assert_eq!(enemy.health(), Some(0.75));
+    assert!(enemy.flashing());
+    simulation.wait(10);
+    assert!(!simulation.world.enemies[0].flashing());
+}
+
+#[test]
+fn bullets_keep_the_damage_they_were_fired_with() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.buffs.berserk = 0.1;
+    simulation.fire();
+    assert_eq!(simulation.world.bullets.damage(0), 2);
+
+    // 狂暴结束前射出的子弹仍然是双倍伤害
+    simulation.wait(10);
+    let position = simulation.world.bullets.positions[0];
+    let mut enemy = Enemy::new(position.x, position.y - 40.0, 30.0, 0.0, EnemyPath::Straight);
+    enemy.set_hp(3);
+    simulation.world.enemies.push(enemy);
+    simulation.wait(10);
+    assert_eq!(simulation.world.enemies[0].hp, 1);
+}
+
+// 放在玩家斜上方、不会撞上的炮艇，每秒开一炮
+fn shooter_near_player(simulation: &mut Simulation, aimed: bool) {
+    let position = simulation.world.player.game_object.position;
+    let mut enemy = Enemy::new(position.x + 100.0, position.y - 200.0, 30.0, 0.0, EnemyPath::Straight);
+    enemy.gun = Some(Gun::new(1.0, aimed));
+    simulation.world.enemies.push(enemy);
+}
+
+#[test]
+fn aimed_shooter_hits_the_player_but_not_while_invincible() {
+    let mut simulation = Simulation::quiet();
+    shooter_near_player(&mut simulation, true);
+    simulation.wait_seconds(0.9);
+    assert!(simulation.world.enemy_bullets.is_empty());
+    simulation.wait_seconds(1.2);
+    assert!(simulation.saw(|event| matches!(event, GameEvent::PlayerDamaged { source: DamageSource::Shot })));
+    assert_eq!(simulation.world.player.lives, 2);
+
+    // 无敌期间子弹直接穿过
+    simulation.wait_seconds(1.0);
+    assert!(simulation.world.player.is_invincible());
+    assert_eq!(simulation.world.player.lives, 2);
+}
+
+#[test]
+fn straight_shooter_fires_downward_and_misses_a_player_to_the_side() {
+    let mut simulation = Simulation::quiet();
+    shooter_near_player(&mut simulation, false);
+    simulation.wait_seconds(1.1);
+    assert_eq!(simulation.world.enemy_bullets.len(), 1);
+    assert_eq!(simulation.world.enemy_bullets.velocities[0].x, 0.0);
+    assert!(simulation.world.enemy_bullets.velocities[0].y > 0.0);
+    simulation.wait_seconds(3.0);
+    assert_eq!(simulation.world.player.lives, 3);
+}
+
+#[test]
+fn stunned_shooter_holds_its_fire() {
+    let mut simulation = Simulation::quiet();
+    shooter_near_player(&mut simulation, true);
+    simulation.world.enemies[0].status.apply(StatusKind::Stun, 5.0);
+    simulation.wait_seconds(3.0);
+    assert!(simulation.world.enemy_bullets.is_empty());
+}
+
+#[test]
+fn colliders_only_meet_the_layers_in_their_masks() {
+    assert!(Collider::PLAYER_SHOT.interacts(Collider::ENEMY));
+    assert!(Collider::ENEMY.interacts(Collider::PLAYER_SHOT));
+    assert!(Collider::ENEMY_SHOT.interacts(Collider::PLAYER));
+    assert!(!Collider::ENEMY_SHOT.interacts(Collider::ENEMY));
+    assert!(!Collider::PLAYER_SHOT.interacts(Collider::ENEMY_SHOT));
+}
+
+#[test]
+fn changing_a_mask_lets_enemy_bullets_hit_enemies() {
+    let mut simulation = Simulation::quiet();
+    let position = simulation.world.player.game_object.position;
+    let mut shooter = Enemy::new(position.x, position.y - 300.0, 30.0, 0.0, EnemyPath::Straight);
+    shooter.gun = Some(Gun::new(1.0, false));
+    let mut target = Enemy::new(position.x, position.y - 150.0, 30.0, 0.0, EnemyPath::Straight);
+    target.collider.mask = Layers::PLAYER.with(Layers::ENEMY_SHOT);
+    simulation.world.enemies.push(shooter);
+    simulation.world.enemies.push(target);
+
+    simulation.wait_seconds(1.8);
+    assert_eq!(simulation.world.enemies.len(), 1);
+    assert!(simulation.world.enemies[0].gun.is_some());
+    assert_eq!(simulation.world.player.lives, 3);
+    assert_eq!(simulation.world.score, 0);
+}
+
+#[test]
+fn player_bullets_pass_through_colliders_without_their_layer() {
+    let mut simulation = Simulation::quiet();
+    let position = simulation.world.player.game_object.position;
+    let mut ghost = Enemy::new(position.x, position.y - 120.0, 30.0, 0.0, EnemyPath::Straight);
+    ghost.collider = Collider { layer: Layers::NONE, mask: Layers::NONE };
+    simulation.world.enemies.push(ghost);
+    simulation.run("fire 1, wait 30").unwrap();
+    assert_eq!(simulation.world.enemies.len(), 1);
+}
+
+#[test]
+fn two_bullets_on_one_enemy_score_it_once() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.enemies.push(Enemy::new(400.0, 300.0, 30.0, 0.0, EnemyPath::Straight));
+    simulation.world.bullets.push(Vec2::new(395.0, 310.0), Vec2::new(0.0, -1.0));
+    simulation.world.bullets.push(Vec2::new(405.0, 310.0), Vec2::new(0.0, -1.0));
+    simulation.wait(1);
+    assert!(simulation.world.enemies.is_empty());
+    assert_eq!(simulation.world.score, EnemyPath::Straight.points());
+    assert_eq!(simulation.world.bullets.len(), 1);
+}
+
+#[test]
+fn one_bullet_only_hits_one_of_two_overlapping_enemies() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.enemies.push(Enemy::new(400.0, 300.0, 30.0, 0.0, EnemyPath::Straight));
+    simulation.world.enemies.push(Enemy::new(405.0, 305.0, 30.0, 0.0, EnemyPath::Straight));
+    simulation.world.bullets.push(Vec2::new(402.0, 302.0), Vec2::new(0.0, -1.0));
+    simulation.wait(1);
+    assert_eq!(simulation.world.enemies.len(), 1);
+    assert_eq!(simulation.world.enemies[0].game_object.position, Vec2::new(405.0, 305.0));
+}
+
+#[test]
+fn boss_damage_adds_up_to_hits_plus_critical_bonus() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.spawn_boss(BossSpec {
+        name: "测试头目".to_string(),
+        hp: 1000,
+        bonus: 100,
+        phases: BossSpec::default_phases(),
+        size: BossSpec::default_size(),
+        turrets: 0,
+    });
+    simulation.wait_seconds(5.0);
+    powerup_on_player(&mut simulation, PowerupKind::RapidFire);
+    simulation.wait_seconds(8.0);
+    let crits = simulation.world.crits;
+    assert!(crits.hits > 10);
+    let lost = 1000 - simulation.world.boss.as_ref().unwrap().hp;
+    assert_eq!(lost, crits.hits + crits.crit_damage);
+}
+
+#[test]
+fn enemy_with_bounce_policy_turns_around_at_the_bottom() {
+    let mut simulation = Simulation::quiet();
+    let mut enemy = Enemy::new(100.0, WINDOW_HEIGHT - 40.0, 20.0, 200.0, EnemyPath::Straight);
+    enemy.boundary = Boundary { bottom: EdgeRule::Bounce, ..Boundary::ENEMY };
+    simulation.world.enemies.push(enemy);
+    simulation.wait(30);
+    assert_eq!(simulation.world.player.lives, 3);
+    let enemy = &simulation.world.enemies[0].game_object;
+    assert!(enemy.velocity.y < 0.0);
+    assert!(enemy.bounds().bottom() <= WINDOW_HEIGHT);
+}
+
+#[test]
+fn charging_boss_stays_in_the_top_third() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.spawn_boss(BossSpec {
+        name: "测试头目".to_string(),
+        hp: 5,
+        bonus: 100,
+        phases: vec![PhaseSpec { below: 1.0, pattern: BossPattern::Charge, speed: 2.0 }],
+        size: BossSpec::default_size(),
+        turrets: 0,
+    });
+    simulation.wait_seconds(5.0);
+    let mut lowest: f32 = 0.0;
+    for _ in 0..600 {
+        simulation.wait(1);
+        lowest = lowest.max(simulation.world.boss.as_ref().unwrap().game_object.bounds().bottom());
+    }
+    assert!(lowest <= WINDOW_HEIGHT / 3.0 + 0.001, "头目下沉到了 {}", lowest);
+}
+
+// 在玩家头顶放一个道具，下一帧就会捡到
+fn powerup_on_player(simulation: &mut Simulation, kind: PowerupKind) {
+    let position = simulation.world.player.game_object.position;
+    simulation.world.powerups.push(Powerup::new(position.x, position.y, kind, 0.0));
+}
+
+// 分身沿着飞船走过的路跟在后面，飞船停下时分身也停下，最多两个
+#[test]
+fn options_trail_the_ship_and_stop_when_it_stops() {
+    let mut simulation = Simulation::quiet();
+    for _ in 0..3 {
+        powerup_on_player(&mut simulation, PowerupKind::Option);
+        simulation.wait(1);
+    }
+    assert_eq!(simulation.world.player.options, 2);
+    simulation.run("right 60").unwrap();
+    let ship = simulation.world.player.game_object.position;
+    let options = simulation.world.player.option_positions();
+    assert!(options[0].x < ship.x && options[1].x < options[0].x);
+    simulation.wait(30);
+    assert_eq!(simulation.world.player.option_positions(), options);
+}
+
+// 分身在玩家开火时各射一发，失去一条命时全部消失
+#[test]
+fn options_fire_with_the_player_and_vanish_on_death() {
+    let mut simulation = Simulation::quiet();
+    powerup_on_player(&mut simulation, PowerupKind::Option);
+    simulation.run("wait, left 40, fire").unwrap();
+    assert_eq!(simulation.world.bullets.len(), 2);
+    simulation.world.player.weapon_level = 1;
+    simulation.world.player.invincible_timer = 0.0;
+    enemy_on_player(&mut simulation);
+    simulation.wait(1);
+    assert_eq!(simulation.world.player.lives, 2);
+    assert_eq!(simulation.world.player.options, 0);
+}
+
+// 冲刺时飞船沿移动方向冲出一大段，冲刺期间撞上敌人不扣命，也不会触发受伤后的无敌时间
+#[test]
+fn dash_covers_ground_and_dodges_hits() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.player.invincible_timer = 0.0;
+    let start = simulation.world.player.game_object.position.x;
+    simulation.step(FrameInput { move_x: 1, dash: true, ..FrameInput::default() });
+    assert!(simulation.world.player.dashing());
+    enemy_on_player(&mut simulation);
+    simulation.wait(1);
+    assert_eq!(simulation.world.player.lives, 3);
+    assert_eq!(simulation.world.player.invincible_timer, 0.0);
+    simulation.wait(10);
+    assert!(!simulation.world.player.dashing());
+    let moved = simulation.world.player.game_object.position.x - start;
+    assert!(moved > PLAYER_SPEED * 0.5, "冲刺移动了 {}", moved);
+}
+
+// 冲刺之后要等冷却结束才能再冲，HUD 上显示剩余时间
+#[test]
+fn dash_waits_for_its_cooldown() {
+    let mut simulation = Simulation::quiet();
+    simulation.run("dash, wait 20").unwrap();
+    assert!(matches!(simulation.world.player.dash, Dash::Cooldown(_)));
+    assert!(hud::dash(&simulation.world).ends_with('s'));
+    simulation.run("dash").unwrap();
+    assert!(!simulation.world.player.dashing());
+    simulation.wait((DASH_COOLDOWN / world::FIXED_STEP) as u32);
+    assert_eq!(hud::dash(&simulation.world), "冲刺 就绪");
+    simulation.run("dash").unwrap();
+    assert!(simulation.world.player.dashing());
+}
+
+// 同一个移动键很快按两下才算冲刺，换了方向或隔得太久都不算
+#[test]
+fn double_tap_needs_the_same_direction_in_quick_succession() {
+    let mut taps = DoubleTap::default();
+    assert!(!taps.press(Action::MoveLeft, 0.0));
+    assert!(!taps.press(Action::MoveRight, 0.1));
+    assert!(taps.press(Action::MoveRight, 0.2));
+    assert!(!taps.press(Action::MoveRight, 0.3));
+    assert!(!taps.press(Action::MoveUp, 1.0));
+    assert!(!taps.press(Action::MoveUp, 1.5));
+    assert!(!taps.press(Action::Fire, 1.6));
+}
+
+// 快速重新开始时不显示关卡名，第一波马上开始出场
+#[test]
+fn skipping_the_intro_starts_the_first_wave_right_away() {
+    let mut simulation = Simulation::new(GameMode::Campaign, 1);
+    let campaign = simulation.world.campaign.as_ref().unwrap();
+    assert!(campaign.banner().is_some() && campaign.current_wave().is_none());
+    simulation.world.skip_intro();
+    let campaign = simulation.world.campaign.as_ref().unwrap();
+    assert!(campaign.banner().is_none() && campaign.current_wave().is_some());
+    simulation.wait_seconds(campaign.current_wave().unwrap().interval + 0.1);
+    assert!(!simulation.world.enemies.is_empty());
+}
+
+// 按住满一秒才重新开始，中途松开从头计时，触发后要先松开才会再触发
+#[test]
+fn restart_needs_the_key_held_for_the_full_duration() {
+    let mut hold = HoldTimer::default();
+    assert!(!hold.update(true, 0.6, RESTART_HOLD));
+    assert!((hold.progress(RESTART_HOLD) - 0.6).abs() < 1e-6);
+    assert!(!hold.update(false, 0.1, RESTART_HOLD));
+    assert!(!hold.update(true, 0.6, RESTART_HOLD));
+    assert!(hold.update(true, 0.5, RESTART_HOLD));
+    assert!(!hold.update(true, 2.0, RESTART_HOLD));
+    assert_eq!(hold.progress(RESTART_HOLD), 0.0);
+    hold.update(false, 0.1, RESTART_HOLD);
+    assert!(hold.update(true, 1.0, RESTART_HOLD));
+}
+
+// 专注期间敌人放慢到 30%，玩家照常全速移动
+#[test]
+fn focus_slows_enemies_but_not_the_player() {
+    let mut runs = [false, true].map(|focus| {
+        let mut simulation = Simulation::quiet();
+        simulation.world.enemies.push(Enemy::new(100.0, 100.0, 30.0, 100.0, EnemyPath::Straight));
+        simulation.hold(FrameInput { move_x: 1, focus, ..FrameInput::default() }, 30);
+        simulation
+    });
+    let [normal, focused] = &mut runs;
+    let fall = |simulation: &Simulation| simulation.world.enemies[0].game_object.position.y - 100.0;
+    assert!((fall(focused) - fall(normal) * focus::SLOW_SCALE).abs() < 0.5, "{} {}", fall(focused), fall(normal));
+    assert_eq!(focused.world.player.game_object.position, normal.world.player.game_object.position);
+    assert!(focused.world.focus.meter() < 1.0);
+}
+
+// 专注槽用空后即使一直按住也会恢复正常速度，要松开再按才能重新专注，松开期间慢慢恢复
+#[test]
+fn focus_meter_runs_dry_and_refills() {
+    let mut focus = Focus::default();
+    focus.hold(true);
+    let mut frames = 0;
+    while focus.active() {
+        focus.update(world::FIXED_STEP);
+        focus.hold(true);
+        frames += 1;
+    }
+    assert!((149..=151).contains(&frames) && focus.meter() == 0.0);
+    focus.update(1.0);
+    focus.hold(true);
+    assert!(!focus.active());
+    focus.hold(false);
+    focus.update(2.0);
+    assert!((focus.meter() - 0.5).abs() < 1e-3);
+    focus.hold(true);
+    assert!(focus.active() && focus.time_scale() == focus::SLOW_SCALE);
+}
+
+// 在玩家正上方放一个值 points 分的敌人并把它打下来
+fn shoot_enemy_worth(simulation: &mut Simulation, points: u32) {
+    let x = simulation.world.player.game_object.position.x;
+    let mut enemy = Enemy::new(x, 200.0, 30.0, 0.0, EnemyPath::Straight);
+    enemy.points = points;
+    simulation.world.enemies.push(enemy);
+    simulation.run("fire, wait 90").unwrap();
+}
+
+// 敌人的子弹从飞船身边擦过时记一次擦弹，每颗子弹只算一次；经典规则给 1 分，每日挑战的规则给 5 分
+#[test]
+fn grazing_bullets_score_under_the_modes_rules() {
+    let runs = [Box::new(Classic) as Box<dyn ScoringRules>, Box::new(Daily)].map(|rules| {
+        let mut simulation = Simulation::quiet();
+        simulation.world.scoring = rules;
+        simulation.world.player.invincible_timer = 0.0;
+        let ship = &simulation.world.player.game_object;
+        let x = ship.position.x + ship.size.x / 2.0 + 8.0;
+        simulation.world.enemy_bullets.push_hostile(Vec2::new(x, ship.position.y - 100.0), Vec2::new(0.0, 300.0));
+        simulation.wait(60);
+        simulation
+    });
+    for simulation in &runs {
+        assert_eq!(simulation.events.iter().filter(|event| matches!(event, GameEvent::Grazed { .. })).count(), 1);
+        assert_eq!(simulation.world.player.lives, 3);
+    }
+    assert_eq!(runs[0].world.ledger.totals(), vec![(ScoreReason::Graze, 1)]);
+    assert_eq!(runs[1].world.ledger.totals(), vec![(ScoreReason::Graze, 5)]);
+}
+
+// 换一套计分规则就能改变击落和受伤的计分：每日挑战没有连击加成，计时赛的连击加成翻倍、受伤不清连击
+#[test]
+fn scoring_rules_decide_kill_and_damage_scores() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.scoring = Box::new(Daily);
+    for _ in 0..5 {
+        shoot_enemy_worth(&mut simulation, 10);
+    }
+    assert_eq!(simulation.world.ledger.totals(), vec![(ScoreReason::Enemy, 50)]);
+
+    assert_eq!(Classic.on_kill(10, 3), KillScore { base: 10, combo: 20 });
+    assert_eq!(TimeAttack.on_kill(10, 3), KillScore { base: 10, combo: 40 });
+    assert_eq!(TimeAttack.on_wave_clear(2), Classic.on_wave_clear(2) * 2);
+    let mut simulation = Simulation::quiet();
+    simulation.world.scoring = Box::new(TimeAttack);
+    for _ in 0..4 {
+        shoot_enemy_worth(&mut simulation, 10);
+    }
+    simulation.world.player.invincible_timer = 0.0;
+    simulation.world.player.weapon_level = 1;
+    enemy_on_player(&mut simulation);
+    simulation.wait(1);
+    assert_eq!(simulation.world.player.lives, 2);
+    assert_eq!(simulation.world.combo.multiplier(), 2);
+}
+
+#[test]
+fn every_thousand_points_awards_one_extra_life_once() {
+    let mut simulation = Simulation::quiet();
+    shoot_enemy_worth(&mut simulation, 990);
+    assert_eq!(simulation.world.player.lives, 3);
+    shoot_enemy_worth(&mut simulation, 10);
+    assert_eq!(simulation.world.score, 1000);
+    assert_eq!(simulation.world.player.lives, 4);
+    assert!(simulation.saw(|event| matches!(event, GameEvent::ExtraLife { lives: 4 })));
+
+    // 同一条奖励线不会再发，一次跨过两条线奖励两条命
+    simulation.world.combo = Combo::default();
+    shoot_enemy_worth(&mut simulation, 500);
+    assert_eq!(simulation.world.player.lives, 4);
+    simulation.world.combo = Combo::default();
+    shoot_enemy_worth(&mut simulation, 2000);
+    assert_eq!((simulation.world.score, simulation.world.player.lives), (3500, 6));
+}
+
+#[test]
+fn extra_life_powerup_adds_a_life() {
+    let mut simulation = Simulation::quiet();
+    assert_eq!(PowerupKind::ExtraLife.rarity(), Rarity::Epic);
+    powerup_on_player(&mut simulation, PowerupKind::ExtraLife);
+    simulation.wait(3);
+    assert_eq!(simulation.world.player.lives, 4);
+    assert!(simulation.saw(|event| matches!(event, GameEvent::ExtraLife { lives: 4 })));
+}
+
+#[test]
+fn bomb_clears_the_field_and_scores_each_enemy() {
+    let mut simulation = Simulation::quiet();
+    for x in [100.0, 300.0, 500.0] {
+        simulation.world.enemies.push(Enemy::new(x, 100.0, 30.0, 0.0, EnemyPath::Straight));
+    }
+    powerup_on_player(&mut simulation, PowerupKind::Bomb);
+    simulation.wait(2);
+    assert!(simulation.world.enemies.is_empty());
+    assert_eq!(simulation.world.score, 30);
+    assert!(simulation.saw(|event| matches!(event, GameEvent::PowerupCollected { kind: PowerupKind::Bomb, .. })));
+}
+
+#[test]
+fn bomb_key_spends_stock_to_clear_enemies_and_their_bullets() {
+    let mut simulation = Simulation::quiet();
+    for x in [100.0, 300.0] {
+        simulation.world.enemies.push(Enemy::new(x, 100.0, 30.0, 0.0, EnemyPath::Straight));
+    }
+    simulation.world.enemy_bullets.push_hostile(Vec2::new(400.0, 200.0), Vec2::new(0.0, 100.0));
+    simulation.run("bomb").unwrap();
+    assert!(simulation.world.enemies.is_empty());
+    assert!(simulation.world.enemy_bullets.is_empty());
+    assert_eq!(simulation.world.score, 20);
+    assert_eq!(hud::bombs(&simulation.world), "炸弹: 1");
+
+    // 用完之后按键没有反应
+    simulation.run("bomb 3").unwrap();
+    assert_eq!(simulation.world.player.bombs, 0);
+    simulation.world.enemies.push(Enemy::new(100.0, 100.0, 30.0, 0.0, EnemyPath::Straight));
+    simulation.run("bomb").unwrap();
+    assert_eq!(simulation.world.enemies.len(), 1);
+}
+
+#[test]
+fn bomb_refills_stack_up_to_the_limit() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.player.bombs = 0;
+    for _ in 0..MAX_BOMBS + 1 {
+        powerup_on_player(&mut simulation, PowerupKind::BombRefill);
+        simulation.wait(1);
+    }
+    assert_eq!(simulation.world.player.bombs, MAX_BOMBS);
+    assert!(simulation.world.enemies.is_empty());
+}
+
+#[test]
+fn ledger_records_every_point_with_its_reason() {
+    let mut simulation = Simulation::quiet();
+    let x = simulation.world.player.game_object.position.x;
+    simulation.world.enemies.push(Enemy::new(x, 200.0, 30.0, 0.0, EnemyPath::Straight));
+    simulation.run("fire, wait 90").unwrap();
+    simulation.world.enemies.push(Enemy::new(100.0, 100.0, 30.0, 0.0, EnemyPath::Straight));
+    powerup_on_player(&mut simulation, PowerupKind::Bomb);
+    simulation.wait(2);
+
+    let ledger = &simulation.world.ledger;
+    assert_eq!(ledger.total(), simulation.world.score);
+    assert_eq!(ledger.totals(), vec![(ScoreReason::Enemy, 10), (ScoreReason::Bomb, 10)]);
+    assert!(ledger.entries[0].time > 0.0 && ledger.entries[0].time < ledger.entries[1].time);
+
+    let csv = ledger.to_csv();
+    assert_eq!(csv.lines().count(), 3);
+    assert!(csv.lines().nth(2).unwrap().ends_with(",10,bomb"));
+    assert!(ledger.to_json().contains("\"reason\": \"enemy\""));
+}
+
+#[test]
+fn pace_samples_the_running_total_every_few_seconds() {
+    let mut ledger = Ledger::default();
+    ledger.record(1.0, 10, ScoreReason::Enemy);
+    ledger.record(4.0, 20, ScoreReason::Enemy);
+    ledger.record(12.0, 100, ScoreReason::Boss);
+    let pace = ledger.pace(13.0);
+    assert_eq!(pace, vec![30, 30, 130]);
+
+    assert_eq!(ledger::pace_at(&pace, 0.0), 0);
+    assert_eq!(ledger::pace_at(&pace, 2.5), 15);
+    assert_eq!(ledger::pace_at(&pace, 12.5), 80);
+    assert_eq!(ledger::pace_at(&pace, 15.0), 130);
+    assert_eq!(ledger::pace_at(&pace, 60.0), 130);
+    assert_eq!(ledger::pace_at(&[], 5.0), 0);
+}
+
+#[test]
+fn hud_compares_the_score_with_the_best_run() {
+    let mut simulation = Simulation::quiet();
+    assert_eq!(hud::pace(&simulation.world), None);
+
+    simulation.world.best_pace = Some(vec![100, 200]);
+    simulation.wait_seconds(5.0);
+    assert_eq!(hud::pace(&simulation.world), Some("最佳进度: -100".to_string()));
+    simulation.world.score = 420;
+    assert_eq!(hud::pace(&simulation.world), Some("最佳进度: +320".to_string()));
+}
+
+#[test]
+fn streamer_mode_enlarges_the_hud_and_keeps_it_out_of_the_webcam_corner() {
+    assert!(hud::HudStyle::new(&StreamerSettings::default()).show_seed);
+
+    let streamer = StreamerSettings { enabled: true, webcam_corner: Some(Corner::TopRight), ..Default::default() };
+    let style = hud::HudStyle::new(&streamer);
+    let blank = streamer::webcam_rect(Corner::TopRight);
+    assert!(!style.show_seed);
+    assert_eq!(style.scale, 1.5);
+    assert_eq!(style.blank, Some(blank));
+    assert_eq!(blank.right(), WINDOW_WIDTH);
+
+    let size = Vec2::new(200.0, 30.0);
+    let moved = style.place(Vec2::new(WINDOW_WIDTH - 200.0, 10.0), size);
+    assert_eq!(moved.y, blank.bottom());
+    assert_eq!(style.place(Vec2::new(10.0, 10.0), size), Vec2::new(10.0, 10.0));
+}
+
+#[test]
+fn stream_overlay_is_rewritten_only_when_it_changes() {
+    let mut simulation = Simulation::quiet();
+    let mut overlay = OverlayFile::default();
+    let text = overlay.update(&simulation.world).unwrap();
+    assert!(text.contains("分数: 0"));
+    assert!(text.contains("波次: 1"));
+    assert_eq!(overlay.update(&simulation.world), None);
+
+    simulation.world.score = 250;
+    assert!(overlay.update(&simulation.world).unwrap().contains("分数: 250"));
+}
+
+#[test]
+fn state_snapshot_reports_the_boss_only_while_it_is_on_screen() {
+    let mut simulation = Simulation::quiet();
+    assert_eq!(StateSnapshot::new(&simulation.world).boss, None);
+    assert!(StateSnapshot::new(&simulation.world).to_json().contains("\"boss\":null"));
+
+    simulation.world.spawn_boss(BossSpec {
+        name: "测试头目".to_string(),
+        hp: 5,
+        bonus: 100,
+        phases: BossSpec::default_phases(),
+        size: BossSpec::default_size(),
+        turrets: 0,
+    });
+    simulation.world.boss.as_mut().unwrap().hp = 3;
+    let snapshot = StateSnapshot::new(&simulation.world);
+    assert_eq!(snapshot.boss, Some(BossState { name: "测试头目".to_string(), hp: 3, max_hp: 5 }));
+    assert_eq!(snapshot.lives, simulation.world.player.lives);
+    assert_eq!(snapshot.mode, "endless");
+}
+
+#[cfg(feature = "state-socket")]
+#[test]
+fn state_socket_sends_the_current_state_and_every_change() {
+    use std::io::{BufRead, BufReader};
+
+    let mut simulation = Simulation::quiet();
+    let mut server = streamer::StateServer::bind(0).unwrap();
+    let client = std::net::TcpStream::connect(("127.0.0.1", server.port().unwrap())).unwrap();
+    let mut lines = BufReader::new(client).lines();
+    server.publish(&simulation.world);
+    assert_eq!(lines.next().unwrap().unwrap(), StateSnapshot::new(&simulation.world).to_json());
+
+    simulation.world.score = 250;
+    server.publish(&simulation.world);
+    assert!(lines.next().unwrap().unwrap().contains("\"score\":250"));
+}
+
+// 客户端一直不读，发送缓冲区满了也不断开、不发半行；再读时每一行都完整，最后收到的是最新的状态
+#[cfg(feature = "state-socket")]
+#[test]
+fn state_socket_keeps_slow_clients_and_only_sends_whole_lines() {
+    use std::io::{BufRead, BufReader};
+
+    const SNAPSHOTS: u32 = 100_000;
+    let mut simulation = Simulation::quiet();
+    let mut server = streamer::StateServer::bind(0).unwrap();
+    let client = std::net::TcpStream::connect(("127.0.0.1", server.port().unwrap())).unwrap();
+    for score in 0..SNAPSHOTS {
+        simulation.world.score = score;
+        server.publish(&simulation.world);
+    }
+
+    let reader = std::thread::spawn(move || {
+        let mut last = 0;
+        for line in BufReader::new(client).lines() {
+            let snapshot: serde_json::Value = serde_json::from_str(&line.unwrap()).expect("收到了不完整的一行");
+            let score = snapshot["score"].as_u64().unwrap();
+            assert!(score >= last);
+            last = score;
+            if score == u64::from(SNAPSHOTS - 1) {
+                return;
+            }
+        }
+        panic!("客户端被断开了");
+    });
+    while !reader.is_finished() {
+        server.publish(&simulation.world);
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+    reader.join().unwrap();
+}
+
+// 一个汉字被拆在两次发送之间时先留着半个字符，等整行收完再解码；PING 收到后回 PONG
+#[cfg(feature = "twitch")]
+#[test]
+fn twitch_chat_keeps_partial_characters_and_answers_pings() {
+    use std::io::{BufRead, BufReader, Write};
+
+    let listener = std::net::TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let mut chat = chat::TwitchChat::connect_to(listener.local_addr().unwrap(), "Channel").unwrap();
+    let (mut server, _) = listener.accept().unwrap();
+    let mut replies = BufReader::new(server.try_clone().unwrap());
+    let mut line = String::new();
+    replies.read_line(&mut line).unwrap();
+    assert!(line.starts_with("NICK justinfan"));
+    line.clear();
+    replies.read_line(&mut line).unwrap();
+    assert_eq!(line, "JOIN #channel\r\n");
+
+    let message = ":viewer!viewer@viewer.tmi.twitch.tv PRIVMSG #channel :!elite 冲\r\n".as_bytes();
+    let split = message.len() - 4;  // "冲" 的三个字节被拆开
+    server.write_all(&message[..split]).unwrap();
+    let poll = |chat: &mut chat::TwitchChat| {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        chat.poll().unwrap()
+    };
+    assert!(poll(&mut chat).is_empty());
+    server.write_all(&message[split..]).unwrap();
+    assert_eq!(poll(&mut chat), [("viewer".to_string(), "!elite 冲".to_string())]);
+
+    server.write_all(b"PING :tmi.twitch.tv\r\n").unwrap();
+    assert!(poll(&mut chat).is_empty());
+    line.clear();
+    replies.read_line(&mut line).unwrap();
+    assert_eq!(line, "PONG :tmi.twitch.tv\r\n");
+}
+
+#[test]
+fn chat_vote_picks_the_most_voted_event_then_cools_down() {
+    let mut vote = ChatVote::new(10.0, 30.0);
+    assert!(!vote.message("alice", "hello"));
+    assert!(vote.message("alice", "!meteor"));
+    assert!(!vote.message("Alice", "!elite"));  // 每人每轮一票
+    assert!(vote.message("bob", "!ELITE please"));
+    assert!(vote.message("carol", "!meteor"));
+    assert_eq!(vote.status(), Some("观众投票 10秒: !elite 1  !powerup 0  !meteor 2".to_string()));
+
+    assert_eq!(vote.update(9.0), None);
+    assert_eq!(vote.update(1.0), Some(ChatEvent::Meteors));
+    assert_eq!(vote.status(), None);
+    assert!(!vote.message("dave", "!powerup"));
+    assert_eq!(vote.update(30.0), None);
+
+    // 平票时按列出的顺序选
+    assert!(vote.message("dave", "!powerup"));
+    assert!(vote.message("erin", "!elite"));
+    assert_eq!(vote.update(10.0), Some(ChatEvent::Elite));
+}
+
+#[test]
+fn chat_messages_are_read_from_irc_privmsg_lines() {
+    let line = ":viewer!viewer@viewer.tmi.twitch.tv PRIVMSG #channel :!elite now\r\n";
+    assert_eq!(chat::parse_privmsg(line), Some(("viewer", "!elite now")));
+    assert_eq!(chat::parse_privmsg(":tmi.twitch.tv 001 justinfan1 :Welcome"), None);
+    assert_eq!(chat::parse_privmsg("PING :tmi.twitch.tv"), None);
+}
+
+#[test]
+fn chat_events_spawn_through_the_event_bus() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.chat_event(ChatEvent::Meteors);
+    simulation.wait(1);
+    assert_eq!(simulation.world.enemies.len(), 8);
+    assert!(simulation.world.enemies.iter().all(|meteor| !meteor.escape_penalty));
+
+    simulation.world.enemies.clear();
+    simulation.world.chat_event(ChatEvent::Elite);
+    simulation.world.chat_event(ChatEvent::Powerup);
+    simulation.wait(1);
+    assert_eq!(simulation.world.enemies.len(), 1);
+    assert_eq!((simulation.world.enemies[0].hp, simulation.world.enemies[0].armor), (4, 1));
+    assert_eq!(simulation.world.powerups.len(), 1);
+    assert!(simulation.saw(|event| matches!(event, GameEvent::ChatVoteWon { event: ChatEvent::Elite })));
+}
+
+#[test]
+fn resource_directory_prefers_explicit_paths_then_the_project_then_the_package() {
+    let hints = ResourceHints {
+        flag: Some("/flag".into()),
+        env: Some("/env".into()),
+        manifest_dir: Some("/project".into()),
+        exe: Some("/app/Contents/MacOS/space-shooter".into()),
+    };
+    let candidates: Vec<String> = hints.candidates().iter().map(|dir| dir.display().to_string()).collect();
+    assert_eq!(&candidates[..4], ["/flag", "/env", "/project/resources", "/app/Contents/MacOS/resources"]);
+    assert!(candidates.contains(&"/app/Contents/MacOS/../Resources".to_string()));
+    assert!(hints.resolve().unwrap_err().contains("/flag"));
+
+    let project = ResourceHints { manifest_dir: Some(env!("CARGO_MANIFEST_DIR").into()), ..Default::default() };
+    assert_eq!(project.resolve().unwrap(), Some(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources")));
+}
+
+#[test]
+fn embedded_assets_use_resource_paths_and_skip_compiled_data() {
+    for path in assets::embedded_paths() {
+        assert!(path.starts_with('/') && !path.contains('\\'), "{}", path);
+        assert!(!path.ends_with(".toml"), "{}", path);
+        assert!(assets::embedded(path).is_some());
+    }
+    if !assets::EMBEDDED {
+        assert_eq!(assets::embedded_paths().count(), 0);
+    }
+    assert!(assets::embedded("/sfx/missing.ogg").is_none());
+}
+
+#[test]
+fn next_stage_assets_are_wanted_once_only_the_boss_remains() {
+    let mut data = CampaignData::load();
+    for stage in &mut data.stages {
+        stage.waves.truncate(1);
+        stage.waves[0].count = 1;
+    }
+    let mut campaign = Campaign::new(data);
+    assert_eq!(campaign.stage_spec(1).unwrap().assets(), ["/music/void.ogg"]);
+    assert!(campaign.stage_spec(0).unwrap().assets().is_empty());
+    assert_eq!(campaign.upcoming_stage(), None);
+    for _ in 0..200 {
+        if campaign.upcoming_stage().is_some() {
+            break;
+        }
+        campaign.update(0.1, true);
+    }
+    assert_eq!((campaign.stage(), campaign.upcoming_stage()), (0, Some(1)));
+    campaign.boss_defeated();
+    assert_eq!((campaign.stage(), campaign.upcoming_stage()), (1, None));
+}
+
+#[test]
+fn stage_assets_count_the_bytes_they_hold() {
+    let dir = std::env::temp_dir().join(format!("space_shooter_stage_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("music")).unwrap();
+    std::fs::write(dir.join("music/theme.ogg"), [0u8; 2048]).unwrap();
+    let mut group = assets::StageAssets::start(1, Some(dir.clone()), vec!["/music/theme.ogg".to_string()]);
+    while !group.poll() {
+        std::thread::yield_now();
+    }
+    assert_eq!(group.bytes, 2048);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn asset_loader_reads_every_path_in_the_background() {
+    let resources = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources");
+    let paths = space_shooter::audio::asset_paths();
+    assert!(!paths.is_empty());
+    let mut loader = assets::AssetLoader::start(Some(resources), paths);
+    let started = std::time::Instant::now();
+    while !loader.poll() {
+        assert!(started.elapsed().as_secs() < 10, "资源读取没有结束");
+        std::thread::yield_now();
+    }
+    assert_eq!(loader.progress(), 1.0);
+
+    // 缺少的文件也算读完，不会让加载画面一直停着
+    let mut missing = assets::AssetLoader::start(None, vec!["/sfx/missing.ogg".to_string()]);
+    while !missing.poll() {
+        std::thread::yield_now();
+    }
+    assert!(missing.finished());
+    assert_eq!(assets::AssetLoader::start(None, Vec::new()).progress(), 1.0);
+}
+
+#[test]
+fn rapid_fire_and_drone_shoot_without_input_until_they_expire() {
+    let mut simulation = Simulation::quiet();
+    powerup_on_player(&mut simulation, PowerupKind::RapidFire);
+    powerup_on_player(&mut simulation, PowerupKind::Drone);
+    simulation.wait(60);
+    assert!(simulation.world.bullets.len() > 8);
+
+    simulation.wait_seconds(15.0);
+    assert!(simulation.world.bullets.is_empty());
+    assert!(simulation.world.buffs.drone_position(Vec2::ZERO).is_none());
+}
+
+#[test]
+fn hud_lists_active_powerups_with_their_timers() {
+    let mut simulation = Simulation::quiet();
+    assert!(hud::buffs(&simulation.world).is_empty());
+    powerup_on_player(&mut simulation, PowerupKind::Drone);
+    simulation.wait(1);
+    assert_eq!(hud::buffs(&simulation.world), vec!["僚机 12s".to_string()]);
+    assert_eq!(hud::wave(&simulation.world), "波次: 1");
+}
+
+#[test]
+fn side_and_rear_guns_fire_outwards_and_leave_through_every_edge() {
+    let mut simulation = Simulation::quiet();
+    powerup_on_player(&mut simulation, PowerupKind::Guns);
+    simulation.wait(2);
+    let velocities = &simulation.world.bullets.velocities;
+    assert!(velocities.iter().any(|velocity| velocity.y > 0.0));
+    assert!(velocities.iter().any(|velocity| velocity.x < 0.0));
+    assert!(velocities.iter().any(|velocity| velocity.x > 0.0));
+
+    simulation.wait_seconds(12.0);
+    assert!(simulation.world.bullets.is_empty());
+}
+
+#[test]
+fn reflector_turns_mines_into_player_bullets() {
+    let mut simulation = Simulation::quiet();
+    powerup_on_player(&mut simulation, PowerupKind::Reflector);
+    simulation.wait(1);
+    let position = simulation.world.player.game_object.position;
+    let mut mine = Enemy::new(position.x, position.y - 10.0, 12.0, 0.0, EnemyPath::Straight);
+    mine.escape_penalty = false;
+    mine.source = DamageSource::Mine;
+    simulation.world.enemies.push(mine);
+    simulation.wait(1);
+
+    assert!(simulation.world.enemies.is_empty());
+    assert_eq!(simulation.world.player.lives, 3);
+    assert_eq!(simulation.world.bullets.len(), 1);
+    assert!(simulation.world.bullets.velocities[0].y < 0.0);
+}
+
+// 反射护盾也把炮艇的子弹弹回去，朝开火的位置飞回去，不掉命
+#[test]
+fn reflector_sends_enemy_bullets_back_at_the_shooter() {
+    let mut simulation = Simulation::quiet();
+    powerup_on_player(&mut simulation, PowerupKind::Reflector);
+    simulation.wait(1);
+    let position = simulation.world.player.game_object.position;
+    let origin = position - Vec2::new(80.0, 200.0);
+    simulation.world.enemy_bullets.push_hostile(origin, (position - origin).normalize() * 300.0);
+    for _ in 0..90 {
+        if simulation.world.enemy_bullets.is_empty() {
+            break;
+        }
+        simulation.wait(1);
+    }
+
+    assert!(simulation.world.enemy_bullets.is_empty());
+    assert_eq!(simulation.world.player.lives, 3);
+    assert_eq!(simulation.world.bullets.len(), 1);
+    assert_eq!(simulation.world.bullets.weapon(0), Weapon::Reflected);
+    let direction = simulation.world.bullets.velocities[0].normalize();
+    assert!(direction.dot((origin - position).normalize()) > 0.99);
+}
+
+// 在玩家正上方放一颗静止的小行星，正好挡在子弹的路上
+fn asteroid_above_player(simulation: &mut Simulation, tier: u32) {
+    let position = simulation.world.player.game_object.position - Vec2::new(0.0, 150.0);
+    simulation.world.enemies.push(asteroid::new(position, Vec2::ZERO, tier));
+}
+
+#[test]
+fn asteroids_absorb_several_shots_then_split() {
+    let mut simulation = Simulation::quiet();
+    asteroid_above_player(&mut simulation, 2);
+    simulation.run("fire 1, wait 30").unwrap();
+    assert_eq!(simulation.world.enemies.len(), 1);
+    assert!(simulation.world.enemies[0].health().is_some());
+
+    for _ in 0..5 {
+        if simulation.world.enemies.len() != 1 {
+            break;
+        }
+        simulation.run("fire 1, wait 30").unwrap();
+    }
+    let pieces = &simulation.world.enemies;
+    assert_eq!(pieces.len(), 2);
+    assert!(pieces.iter().all(|piece| piece.asteroid == Some(1)));
+    assert!(pieces[0].game_object.position.x < pieces[1].game_object.position.x);
+    assert!(simulation.world.score > 0);
+}
+
+#[test]
+fn the_smallest_asteroids_shatter() {
+    let mut simulation = Simulation::quiet();
+    asteroid_above_player(&mut simulation, 1);
+    simulation.run("fire 1, wait 30").unwrap();
+    assert!(simulation.world.enemies.is_empty());
+}
+
+#[test]
+fn enemy_bullets_break_asteroids_without_scoring() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.enemies.push(asteroid::new(Vec2::new(400.0, 250.0), Vec2::ZERO, 1));
+    simulation.world.enemy_bullets.push_hostile(Vec2::new(400.0, 200.0), Vec2::new(0.0, 200.0));
+    simulation.wait(30);
+    assert!(simulation.world.enemies.is_empty());
+    assert_eq!(simulation.world.score, 0);
+}
+
+#[test]
+fn asteroids_hurt_on_contact_but_may_drift_past() {
+    let mut simulation = Simulation::quiet();
+    let position = simulation.world.player.game_object.position;
+    simulation.world.enemies.push(asteroid::new(position, Vec2::ZERO, 3));
+    simulation.wait(1);
+    assert!(simulation.saw(|event| matches!(event, GameEvent::PlayerDamaged { source: DamageSource::Asteroid })));
+    assert_eq!(simulation.world.player.lives, 2);
+
+    simulation.world.enemies.push(asteroid::new(Vec2::new(50.0, WINDOW_HEIGHT - 10.0), Vec2::new(0.0, 300.0), 1));
+    simulation.wait_seconds(1.0);
+    assert!(simulation.world.enemies.is_empty());
+    assert_eq!(simulation.world.player.lives, 2);
+}
+
+#[test]
+fn asteroids_drift_in_on_their_own_timer() {
+    let mut simulation = Simulation::new(GameMode::Campaign, 7);
+    let seen = (0..(asteroid::INTERVAL * 1.5 * 60.0) as u32 + 1).any(|_| {
+        simulation.wait(1);
+        simulation.world.enemies.iter().any(|enemy| enemy.asteroid == Some(3))
+    });
+    assert!(seen);
+}
+
+// 两翼权重高的波次大多从两侧进来，没有区域时照旧在整个宽度内均匀出现
+#[test]
+fn weighted_lanes_skew_where_enemies_spawn() {
+    let field = PlayField::default();
+    let flanks = [
+        SpawnLane { from: 0.0, to: 0.25, weight: 3 },
+        SpawnLane { from: 0.25, to: 0.75, weight: 1 },
+        SpawnLane { from: 0.75, to: 1.0, weight: 3 },
+    ];
+    let mut rng = StdRng::seed_from_u64(3);
+    let xs: Vec<f32> = (0..700).map(|_| campaign::spawn_x(&flanks, &field, 15.0, &mut rng)).collect();
+    let middle = xs.iter().filter(|&&x| (200.0..600.0).contains(&x)).count();
+    assert!((50..150).contains(&middle), "middle {}", middle);
+
+    let data = CampaignData::load();
+    assert!(data.stages[1].waves.iter().any(|wave| wave.lanes == flanks));
+    let mut uniform = StdRng::seed_from_u64(3);
+    let mut expected = StdRng::seed_from_u64(3);
+    assert_eq!(campaign::spawn_x(&[], &field, 15.0, &mut uniform), expected.gen_range(field.spawn_x(15.0)));
+}
+
+// 波次变换不改动原来的数据：倒序只换顺序，翻倍让数量加倍、间隔减半，新游戏+同时倒序和翻转
+#[test]
+fn wave_transforms_remix_copies_of_the_campaign() {
+    let data = CampaignData::load();
+    let waves = &data.stages[1].waves;
+    let reversed = WaveTransform::Reverse.apply(waves);
+    assert_eq!(reversed.first().map(|wave| wave.count), waves.last().map(|wave| wave.count));
+    let doubled = WaveTransform::Double.apply(waves);
+    assert!(doubled.iter().zip(waves).all(|(doubled, wave)| doubled.count == wave.count * 2 && doubled.interval == wave.interval / 2.0));
+
+    let flanks = waves.iter().position(|wave| !wave.lanes.is_empty()).unwrap();
+    let plus = data.clone().new_game_plus();
+    let remixed = &plus.stages[1].waves[waves.len() - 1 - flanks];
+    let lane = waves[flanks].lanes[0];
+    assert!(remixed.lanes.iter().any(|mirrored| mirrored.from == 1.0 - lane.to && mirrored.to == 1.0 - lane.from));
+    assert_eq!(data.stages[1].waves[flanks].lanes, waves[flanks].lanes);
+}
+
+fn swarm_wave() -> WaveSpec {
+    WaveSpec {
+        count: 1,
+        interval: 1.0,
+        speed: 0.0,
+        size: 26.0,
+        formation: Formation::Swarm,
+        path: EnemyPath::Straight,
+        hp: 1,
+        armor: 0,
+        fire_interval: 0.0,
+        aimed: false,
+        launch_interval: 0.0,
+        splits: false,
+        lanes: Vec::new(),
+        enemy: None,
+    }
+}
+
+#[test]
+fn bezier_paths_run_from_the_first_point_to_the_last() {
+    let path = Bezier { points: [Vec2::ZERO, Vec2::new(0.0, 100.0), Vec2::new(100.0, 100.0), Vec2::new(100.0, 0.0)] };
+    assert_eq!(path.point(0.0), Vec2::ZERO);
+    assert_eq!(path.point(1.0), Vec2::new(100.0, 0.0));
+    assert_eq!(path.point(-0.5), Vec2::ZERO);
+    assert_eq!(path.point(0.5), Vec2::new(50.0, 75.0));
+}
+
+#[test]
+fn swarm_enemies_fly_into_the_grid_then_peel_off_to_dive() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.spawn_formation(&swarm_wave());
+    assert_eq!(simulation.world.enemies.len(), 8);
+    assert!(simulation.world.enemies.iter().all(|enemy| matches!(enemy.flight.unwrap().stage, Stage::Entering { .. })));
+
+    simulation.wait_seconds(4.8);
+    let enemies = &simulation.world.enemies;
+    assert_eq!(enemies.len(), 8);
+    assert!(enemies.iter().all(|enemy| !matches!(enemy.flight.unwrap().stage, Stage::Entering { .. })));
+    let holding: Vec<_> = enemies.iter().filter(|enemy| enemy.flight.unwrap().stage == Stage::Holding).collect();
+    assert!(holding.len() >= 7);
+    assert!(holding.iter().all(|enemy| enemy.game_object.position.y < WINDOW_HEIGHT / 2.0));
+
+    // 俯冲的敌机从底部飞出后回到网格，不算逃走
+    let mut deepest = 0.0f32;
+    for _ in 0..600 {
+        simulation.wait(1);
+        for enemy in &simulation.world.enemies {
+            deepest = deepest.max(enemy.game_object.position.y);
+        }
+    }
+    assert!(deepest > WINDOW_HEIGHT);
+    assert!(!simulation.saw(|event| matches!(event, GameEvent::PlayerDamaged { source: DamageSource::Escaped })));
+}
+
+#[test]
+fn a_full_swarm_grid_turns_away_extra_recruits() {
+    let field = PlayField::default();
+    let recruits = (0..45).map(|_| Enemy::new(0.0, 0.0, 26.0, 0.0, EnemyPath::Straight)).collect();
+    let enlisted = Swarm::default().enlist(&field, &[], recruits, true);
+    assert_eq!(enlisted.len(), 40);
+    let mut slots: Vec<usize> = enlisted.iter().map(|enemy| enemy.flight.unwrap().slot).collect();
+    slots.dedup();
+    assert_eq!(slots.len(), 40);
+    assert!(enlisted.iter().all(|enemy| enemy.game_object.position.x < 0.0));
+}
+
+#[test]
+fn time_freeze_stops_enemies_but_not_the_player() {
+    let mut simulation = Simulation::quiet();
+    powerup_on_player(&mut simulation, PowerupKind::Freeze);
+    simulation.wait(1);
+    simulation.world.enemies.push(Enemy::new(100.0, 100.0, 30.0, 200.0, EnemyPath::Zigzag));
+    let start_x = simulation.world.player.game_object.position.x;
+    simulation.run("right 60").unwrap();
+    assert_eq!(simulation.world.enemies[0].game_object.position, Vec2::new(100.0, 100.0));
+    assert!(simulation.world.player.game_object.position.x > start_x + 100.0);
+    assert_eq!(simulation.world.time_scale(Faction::Player), 1.0);
+
+    simulation.wait_seconds(2.5);
+    assert_eq!(simulation.world.time_scale(Faction::Enemy), 1.0);
+    assert!(simulation.world.enemies[0].game_object.position.y > 150.0);
+}
+
+#[test]
+fn emp_stuns_nearby_enemies_without_destroying_them() {
+    let mut simulation = Simulation::quiet();
+    let position = simulation.world.player.game_object.position;
+    simulation.world.enemies.push(Enemy::new(position.x, position.y - 120.0, 30.0, 20.0, EnemyPath::Zigzag));
+    simulation.world.enemies.push(Enemy::new(position.x, 100.0, 30.0, 20.0, EnemyPath::Zigzag));
+    simulation.run("emp 1, wait 60").unwrap();
+    assert_eq!(simulation.world.enemies.len(), 2);
+    let (near, far) = (&simulation.world.enemies[0], &simulation.world.enemies[1]);
+    assert!(near.status.stunned());
+    assert_eq!(near.game_object.position.x, position.x);
+    assert!(!far.status.stunned());
+    assert_ne!(far.game_object.position.x, position.x);
+
+    // 冷却期间再按不会续上眩晕
+    simulation.run("emp 1").unwrap();
+    simulation.wait_seconds(2.5);
+    assert!(!simulation.world.enemies[0].status.stunned());
+    assert!(simulation.world.emp_cooldown > 0.0);
+}
+
+#[test]
+fn burning_ticks_damage_until_it_expires() {
+    let mut status = StatusEffects::default();
+    status.apply(StatusKind::Burn, 2.0);
+    let damage: u32 = (0..180).map(|_| status.update(1.0 / 60.0)).sum();
+    assert_eq!(damage, 4);
+    assert!(!status.has(StatusKind::Burn));
+
+    let mut mine = StatusEffects::new(Immunity::MINE);
+    mine.apply(StatusKind::Burn, 2.0);
+    mine.apply(StatusKind::Slow, 2.0);
+    assert!(!mine.has(StatusKind::Burn));
+    assert!(mine.has(StatusKind::Slow));
+}
+
+#[test]
+fn burning_enemy_is_destroyed_and_scored() {
+    let mut simulation = Simulation::quiet();
+    let mut enemy = Enemy::new(100.0, 100.0, 30.0, 0.0, EnemyPath::Straight);
+    enemy.hp = 3;
+    enemy.status.apply(StatusKind::Burn, 2.0);
+    simulation.world.enemies.push(enemy);
+    simulation.wait(40);
+    assert_eq!(simulation.world.enemies[0].hp, 2);
+    simulation.wait_seconds(1.0);
+    assert!(simulation.world.enemies.is_empty());
+    assert_eq!(simulation.world.score, 10);
+}
+
+#[test]
+fn slowed_enemy_moves_at_half_speed() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.enemies.push(Enemy::new(100.0, 100.0, 30.0, 60.0, EnemyPath::Straight));
+    simulation.world.enemies[0].status.apply(StatusKind::Slow, 5.0);
+    simulation.wait(60);
+    assert!((simulation.world.enemies[0].game_object.position.y - 130.0).abs() < 0.5);
+}
+
+#[test]
+fn diver_hovers_then_dives() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.enemies.push(Enemy::new(100.0, 100.0, 22.0, 100.0, EnemyPath::Diver));
+    simulation.wait(30);
+    let hover = simulation.world.enemies[0].game_object.position.y - 100.0;
+    simulation.wait(30);
+    let entry = simulation.world.enemies[0].game_object.position.y;
+    simulation.wait(30);
+    let dive = simulation.world.enemies[0].game_object.position.y - entry;
+    assert!((hover - 20.0).abs() < 0.5, "进场阶段下落了 {}", hover);
+    assert!((dive - 125.0).abs() < 0.5, "俯冲阶段下落了 {}", dive);
+}
+
+#[test]
+fn endless_enemy_mix_shifts_with_rank() {
+    let mut simulation = Simulation::quiet();
+    for _ in 0..50 {
+        simulation.world.spawn_enemy(0.0, 0.0);
+    }
+    assert!(simulation.world.enemies.iter().all(|enemy| enemy.path() == EnemyPath::Straight));
+
+    simulation.world.enemies.clear();
+    for _ in 0..50 {
+        simulation.world.spawn_enemy(0.0, 1.0);
+    }
+    for path in [EnemyPath::Drift, EnemyPath::Zigzag, EnemyPath::Diver] {
+        assert!(simulation.world.enemies.iter().any(|enemy| enemy.path() == path));
+    }
+}
+
+#[test]
+fn endless_shooters_only_appear_at_higher_rank() {
+    let mut simulation = Simulation::quiet();
+    for _ in 0..50 {
+        simulation.world.spawn_enemy(0.0, 0.0);
+    }
+    assert!(simulation.world.enemies.iter().all(|enemy| enemy.gun.is_none()));
+
+    simulation.world.enemies.clear();
+    for _ in 0..50 {
+        simulation.world.spawn_enemy(0.0, 1.0);
+    }
+    let armed = simulation.world.enemies.iter().filter(|enemy| enemy.gun.is_some()).count();
+    assert!(armed > 0 && armed < 50);
+}
+
+#[test]
+fn tougher_enemies_are_worth_more() {
+    let mut simulation = Simulation::quiet();
+    let position = simulation.world.player.game_object.position;
+    simulation.world.enemies.push(Enemy::new(position.x, position.y - 60.0, 30.0, 0.0, EnemyPath::Zigzag));
+    simulation.run("fire 1, wait 10").unwrap();
+    assert!(simulation.world.enemies.is_empty());
+    assert_eq!(simulation.world.score, EnemyPath::Zigzag.points());
+    assert!(EnemyPath::Diver.points() > EnemyPath::Straight.points());
+}
+
+#[test]
+fn spread_shot_fires_a_fan_of_three() {
+    let mut simulation = Simulation::quiet();
+    powerup_on_player(&mut simulation, PowerupKind::Spread);
+    simulation.wait(1);
+    simulation.run("fire 1").unwrap();
+    assert_eq!(simulation.world.bullets.len(), 3);
+}
+
+#[test]
+fn weapon_upgrades_add_bullets_up_to_the_top_level() {
+    let mut simulation = Simulation::quiet();
+    let mut counts = Vec::new();
+    for _ in 0..6 {
+        simulation.world.bullets = Default::default();
+        simulation.run("fire 1").unwrap();
+        counts.push(simulation.world.bullets.len());
+        powerup_on_player(&mut simulation, PowerupKind::Upgrade);
+        simulation.wait(10);  // 等主炮冷却结束
+    }
+    assert_eq!(counts, vec![1, 2, 4, 5, 5, 5]);
+    assert_eq!(simulation.world.player.weapon_level, simulation.world.max_weapon_level());
+    assert_eq!(hud::weapon(&simulation.world), "火力: 5/5");
+}
+
+#[test]
+fn getting_hit_drops_a_weapon_level_instead_of_a_life() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.player.weapon_level = 2;
+    enemy_on_player(&mut simulation);
+    simulation.wait(2);
+    assert_eq!(simulation.world.player.lives, 3);
+    assert_eq!(simulation.world.player.weapon_level, 1);
+    assert!(simulation.world.player.is_invincible());
+    assert!(simulation.saw(|event| matches!(event, GameEvent::WeaponDowngraded { level: 1 })));
+
+    simulation.wait_seconds(2.1);
+    enemy_on_player(&mut simulation);
+    simulation.wait(2);
+    assert_eq!(simulation.world.player.lives, 2);
+}
+
+#[test]
+fn number_keys_switch_weapons_with_their_own_cooldowns() {
+    let mut simulation = Simulation::quiet();
+    simulation.run("weapon 3, fire").unwrap();
+    assert_eq!(hud::loadout(&simulation.world), "武器: 霰弹");
+    assert_eq!(simulation.world.bullets.len(), 5);
+    assert!((0..5).all(|bullet| simulation.world.bullets.weapon(bullet) == Weapon::Scatter));
+
+    // 冷却期间再按开火没有反应，冷却结束后才能再打一排
+    simulation.run("fire 20").unwrap();
+    assert_eq!(simulation.world.bullets.len(), 5);
+    simulation.run("wait 10, fire").unwrap();
+    assert_eq!(simulation.world.bullets.len(), 10);
+
+    // 没有对应武器的栏位不会切换；切换武器不会清掉上一把武器剩下的冷却
+    simulation.run("weapon 9, weapon 1, fire").unwrap();
+    assert_eq!(simulation.world.bullets.len(), 10);
+    simulation.run("wait 30, fire").unwrap();
+    assert_eq!(simulation.world.weapon, Weapon::Cannon);
+    assert_eq!(simulation.world.bullets.len(), 11);
+}
+
+#[test]
+fn touch_drags_move_the_ship_by_the_finger_offset() {
+    let field = PlayField::default();
+    let area = field.player_area();
+    let ship = Vec2::new(400.0, 550.0);
+    let mut touch = TouchControls::default();
+    assert!(!touch.used());
+    assert_eq!(touch.touch(TouchPhase::Started, Vec2::new(200.0, 300.0), ship, area), None);
+    assert!(touch.held() && touch.used());
+    assert_eq!(touch.direction(ship), Vec2::ZERO);
+
+    // 手指往右上拖 100 像素，飞船朝同一个方向全速移动，不会飞到手指下面
+    touch.touch(TouchPhase::Moved, Vec2::new(300.0, 250.0), ship, area);
+    let velocity = world::move_velocity(touch.direction(ship));
+    assert!((velocity.length() - PLAYER_SPEED).abs() < 0.01);
+    assert!(velocity.x > 0.0 && velocity.y < 0.0);
+    assert!(touch.direction(Vec2::new(495.0, 500.0)).length() < 1.0);
+
+    // 拖出场地的部分不算，往回拖马上就能动
+    touch.touch(TouchPhase::Moved, Vec2::new(300.0, -400.0), ship, area);
+    assert_eq!(touch.direction(Vec2::new(500.0, area.top())), Vec2::ZERO);
+
+    touch.touch(TouchPhase::Ended, Vec2::new(300.0, -400.0), ship, area);
+    assert!(!touch.held());
+    assert_eq!(touch.direction(ship), Vec2::ZERO);
+}
+
+#[test]
+fn touch_buttons_do_not_start_a_drag() {
+    let area = PlayField::default().player_area();
+    let mut touch = TouchControls::default();
+    for button in TouchButton::ALL {
+        assert_eq!(touch.touch(TouchPhase::Started, button.center(), Vec2::ZERO, area), Some(button));
+        assert!(!touch.held());
+    }
+}
+
+// 启动时的 Resumed 前面没有 Suspended，不算回到前台；重复的事件只处理一次
+#[test]
+fn lifecycle_ignores_the_startup_resume_and_repeated_events() {
+    let mut lifecycle = Lifecycle::default();
+    assert!(!lifecycle.resume());
+    assert!(lifecycle.running());
+
+    assert!(lifecycle.suspend());
+    assert!(!lifecycle.suspend());
+    assert!(!lifecycle.running());
+
+    assert!(lifecycle.resume());
+    assert!(!lifecycle.resume());
+    assert!(lifecycle.running());
+}
+
+#[test]
+fn holding_fire_shoots_at_the_cannon_rate_and_mashing_is_no_faster() {
+    let mut held = Simulation::quiet();
+    held.run("fire 30").unwrap();
+    let mut mashed = Simulation::quiet();
+    mashed.run(&"fire, wait, ".repeat(15)).unwrap();
+    assert_eq!(held.world.bullets.len(), 5);
+    assert!(mashed.world.bullets.len() <= held.world.bullets.len());
+    assert!(held.world.player.fire_cooldown > 0.0);
+}
+
+#[test]
+fn laser_pierces_every_enemy_in_its_column() {
+    let mut simulation = Simulation::quiet();
+    let x = simulation.world.player.game_object.position.x;
+    simulation.world.enemies.push(Enemy::new(x, 300.0, 30.0, 0.0, EnemyPath::Straight));
+    simulation.world.enemies.push(Enemy::new(x + 5.0, 150.0, 30.0, 0.0, EnemyPath::Straight));
+    simulation.world.enemies.push(Enemy::new(x + 100.0, 300.0, 30.0, 0.0, EnemyPath::Straight));
+    simulation.run("weapon 2, fire").unwrap();
+    assert!(simulation.world.bullets.is_empty());
+    assert!(simulation.world.beam.is_some());
+    assert_eq!(simulation.world.enemies.len(), 1);
+    assert_eq!(simulation.world.enemies[0].game_object.position.x, x + 100.0);
+    // 光束只在发射的那一帧结算，之后飞进来的敌人不会被打中
+    simulation.world.enemies.push(Enemy::new(x, 300.0, 30.0, 0.0, EnemyPath::Straight));
+    simulation.wait(1);
+    assert_eq!(simulation.world.enemies.len(), 2);
+    simulation.wait_seconds(0.5);
+    assert!(simulation.world.beam.is_none());
+}
+
+// 按住不到蓄力门槛就松开不会打出蓄力弹，蓄得越久伤害越高
+#[test]
+fn releasing_a_held_charge_fires_a_charged_shot() {
+    let mut simulation = Simulation::quiet();
+    simulation.run("charge 20, wait").unwrap();
+    assert!(simulation.world.charged_shots.is_empty());
+    assert_eq!(simulation.world.charge.held, 0.0);
+
+    simulation.run("charge 30, wait").unwrap();
+    assert!(simulation.saw(|event| matches!(event, GameEvent::ChargedShotFired { damage: 4 })));
+    assert_eq!(simulation.world.charged_shots.len(), 1);
+    assert!(simulation.world.bullets.is_empty());
+
+    let mut full = Simulation::quiet();
+    full.run("charge 120, wait").unwrap();
+    assert!(full.saw(|event| matches!(event, GameEvent::ChargedShotFired { damage: 8 })));
+}
+
+// 蓄力弹穿过一整列敌人，同一发对每个敌人只造成一次伤害
+#[test]
+fn charged_shot_pierces_and_hits_each_enemy_once() {
+    let mut simulation = Simulation::quiet();
+    let x = simulation.world.player.game_object.position.x;
+    for y in [350.0, 250.0] {
+        simulation.world.enemies.push(Enemy::new(x, y, 30.0, 0.0, EnemyPath::Straight));
+    }
+    let mut tough = Enemy::new(x, 150.0, 30.0, 0.0, EnemyPath::Straight);
+    tough.set_hp(40);
+    simulation.world.enemies.push(tough);
+    simulation.run("charge 120, wait 60").unwrap();
+    assert_eq!(simulation.world.enemies.len(), 1);
+    let lost = 40 - simulation.world.enemies[0].hp;
+    assert!((8..=16).contains(&lost), "lost {}", lost);
+    assert!(simulation.world.charged_shots.is_empty());
+}
+
+// 在每个敌人身上放一颗子弹，同一帧全部击落
+fn shoot_down(simulation: &mut Simulation, count: usize) {
+    for index in 0..count {
+        let x = 100.0 + index as f32 * 60.0;
+        simulation.world.enemies.push(Enemy::new(x, 200.0, 30.0, 0.0, EnemyPath::Straight));
+        simulation.world.bullets.push(Vec2::new(x, 200.0), Vec2::new(0.0, -1.0));
+    }
+    simulation.wait(1);
+}
+
+#[test]
+fn quick_kills_build_a_combo_multiplier_that_wears_off() {
+    let mut simulation = Simulation::quiet();
+    shoot_down(&mut simulation, 4);
+    assert_eq!(simulation.world.combo.multiplier(), 2);
+    assert_eq!(simulation.world.score, 50);
+    assert_eq!(simulation.world.ledger.totals(), vec![(ScoreReason::Enemy, 40), (ScoreReason::Combo, 10)]);
+    assert_eq!(hud::combo(&simulation.world).as_deref(), Some("连击 x2  (4)"));
+
+    // 超过时间和宽限期都没有击落，倍率掉回 x1，连击结束
+    simulation.wait_seconds(2.1 + combo::GRACE);
+    assert_eq!(simulation.world.combo.kills(), 0);
+    assert_eq!(hud::combo(&simulation.world), None);
+}
+
+#[test]
+fn combo_decays_one_step_at_a_time_and_resets_on_damage() {
+    let mut combo = Combo::default();
+    let multipliers: Vec<u32> = (0..24).map(|_| combo.kill()).collect();
+    assert_eq!(multipliers[..9], [1, 1, 1, 2, 2, 2, 2, 3, 3]);
+    assert_eq!(combo.multiplier(), 5);
+    let mut decayed = Vec::new();
+    for _ in 0..5 {
+        combo.update(2.0 + combo::GRACE);
+        decayed.push(combo.multiplier());
+    }
+    assert_eq!(decayed, vec![4, 3, 2, 1, 1]);
+
+    let mut simulation = Simulation::quiet();
+    shoot_down(&mut simulation, 5);
+    enemy_on_player(&mut simulation);
+    simulation.wait(2);
+    assert_eq!(simulation.world.combo.kills(), 0);
+}
+
+#[test]
+fn shield_absorbs_collisions_until_it_runs_out() {
+    let mut simulation = Simulation::quiet();
+    powerup_on_player(&mut simulation, PowerupKind::Shield);
+    simulation.wait(1);
+    enemy_on_player(&mut simulation);
+    simulation.wait(1);
+    assert_eq!(simulation.world.player.lives, 3);
+    assert!(simulation.world.enemies.is_empty());
+
+    simulation.wait_seconds(6.0);
+    enemy_on_player(&mut simulation);
+    simulation.wait(1);
+    assert_eq!(simulation.world.player.lives, 2);
+}
+
+#[test]
+fn cursed_pickup_doubles_damage_but_halves_speed() {
+    let mut simulation = Simulation::quiet();
+    simulation.run("left 30").unwrap();
+    let start = simulation.world.player.game_object.position.x;
+    simulation.run("right 30").unwrap();
+    let normal = simulation.world.player.game_object.position.x - start;
+
+    powerup_on_player(&mut simulation, PowerupKind::Berserk);
+    simulation.wait(1);
+    let start = simulation.world.player.game_object.position.x;
+    simulation.run("right 30").unwrap();
+    let cursed = simulation.world.player.game_object.position.x - start;
+    assert!((cursed - normal / 2.0).abs() < 1.0, "正常 {} 诅咒 {}", normal, cursed);
+    assert_eq!(simulation.world.buffs.damage(), 2);
+
+    simulation.wait_seconds(20.0);
+    assert_eq!(simulation.world.buffs.damage(), 1);
+}
+
+#[test]
+fn higher_rank_drops_rarer_powerups() {
+    let balance = Balance::load().powerups;
+    let count_rare = |rank: f32| {
+        let mut rng = StdRng::seed_from_u64(7);
+        (0..5000)
+            .filter(|_| balance.roll(rank, &mut rng).rarity() != Rarity::Common)
+            .count()
+    };
+    assert!(count_rare(1.0) > count_rare(0.0) + 500);
+}
+
+#[test]
+fn unknown_script_command_is_rejected() {
+    let mut simulation = Simulation::quiet();
+    assert!(simulation.run("jump 3").is_err());
+    assert!(simulation.run("left many").is_err());
+}
+
+#[test]
+fn bullets_and_enemies_stop_at_their_caps() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.limits.bullets = 5;
+    simulation.run("fire 60").unwrap();
+    assert_eq!(simulation.world.bullets.len(), 5);
+
+    simulation.world.limits.enemies = 3;
+    for _ in 0..10 {
+        simulation.world.spawn_enemy(0.0, 0.0);
+    }
+    assert_eq!(simulation.world.enemies.len(), 3);
+}
+
+#[test]
+fn effects_degrade_instead_of_growing() {
+    let mut simulation = Simulation::quiet();
+    let effects = &mut simulation.world.effects;
+    for i in 0..100 {
+        effects.explode(Vec2::new(i as f32, 100.0), 50);
+        effects.popup(Vec2::new(i as f32, 100.0), 10, 4);
+    }
+    assert_eq!(effects.particle_count(), 50);
+    assert_eq!(effects.popup_count(), 4);
+}
+
+#[test]
+fn checksum_catches_damaged_saves() {
+    let sealed = storage::seal("campaign_cleared = true\n");
+    assert_eq!(storage::unseal(&sealed).unwrap(), "campaign_cleared = true\n");
+    assert!(storage::unseal(&sealed.replace("true", "fals")).is_err());
+    assert!(storage::unseal(&sealed[..sealed.len() - 3]).is_err());
+    // 没有校验和的旧版存档照常读取
+    assert_eq!(storage::unseal("campaign_cleared = true\n").unwrap(), "campaign_cleared = true\n");
+}
+
+#[test]
+fn damaged_save_is_restored_from_backup() {
+    let dir = std::env::temp_dir().join(format!("space_shooter_backup_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let storage = Storage::at(dir.clone());
+    storage.init().unwrap();
+    storage.save_progress(&Progress { campaign_cleared: true, ..Default::default() }).unwrap();
+    storage.save_progress(&Progress { campaign_cleared: true, ..Default::default() }).unwrap();
+    assert!(dir.join("progress.toml.1.bak").exists() && dir.join("progress.toml.2.bak").exists());
+
+    std::fs::write(dir.join("progress.toml"), "campaign_cleared = tr").unwrap();
+    let loaded = storage.load_progress().unwrap();
+    assert!(loaded.restored && loaded.value.campaign_cleared);
+    assert!(dir.join("progress.corrupt").exists());
+    // 恢复后原文件重新完好，下一次读取不再需要备份
+    assert!(!storage.load_progress().unwrap().restored);
+
+    // 原文件和备份都损坏时报错，损坏的原文件另存一份留给玩家自己处理
+    for name in ["progress.toml", "progress.toml.1.bak", "progress.toml.2.bak"] {
+        std::fs::write(dir.join(name), "???").unwrap();
+    }
+    assert!(storage.load_progress().is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+// 自动画面质量只在帧时间持续超出预算时降档，偶尔一两帧卡顿不算；固定档位不会变
+#[test]
+fn auto_quality_drops_only_when_frames_stay_over_budget() {
+    let mut quality = QualityControl::new(Quality::Auto);
+    assert_eq!(quality.level(), Quality::High);
+    for frame in 0..600 {
+        assert_eq!(quality.record(frame % 10 == 0), None);
+    }
+    let lowered: Vec<Quality> = (0..600).filter_map(|_| quality.record(true)).collect();
+    assert_eq!(lowered, [Quality::Medium, Quality::Low]);
+    assert_eq!(quality.detail().trail_length, 0);
+    assert!(quality.detail().particle_density < Quality::High.detail().particle_density);
+
+    let mut fixed = QualityControl::new(Quality::High);
+    assert!((0..600).all(|_| fixed.record(true).is_none()));
+    assert_eq!(fixed.level(), Quality::High);
+}
+
+#[test]
+fn watchdog_reports_each_overrun_once() {
+    let mut watchdog = Watchdog::new(1.0);
+    watchdog.check("更新", std::time::Duration::from_micros(500), &[]);
+    assert!(!watchdog.take_overrun());
+    watchdog.check("绘制", std::time::Duration::from_millis(3), &[]);
+    assert!(watchdog.take_overrun());
+    assert!(!watchdog.take_overrun());
+}
+
+// 计时赛时间一到就结算，生命还有剩余
+#[test]
+fn time_attack_ends_when_the_clock_runs_out() {
+    let mut world = World::new(GameMode::TimeAttack, 1);
+    assert_eq!(world.rules.hud_extra(&world).as_deref(), Some("剩余时间 3:00"));
+    world.run_time = modes::TIME_ATTACK_LIMIT - 0.5;
+    world.update(world::FIXED_STEP);
+    assert!(!world.game_over);
+    world.run_time = modes::TIME_ATTACK_LIMIT;
+    let events = world.update(world::FIXED_STEP);
+    assert!(world.game_over && world.player.lives > 0);
+    assert!(events.iter().any(|event| matches!(event, GameEvent::GameOver)));
+    assert!(world.campaign.is_none() && !world.rules.bosses_by_score());
+}
+
+// 头目车轮战没有波次，关卡名显示完就进入头目战；没通关战役时和新游戏+ 一样不列出
+#[test]
+fn boss_rush_skips_straight_to_the_bosses() {
+    let mut simulation = Simulation::new(GameMode::BossRush, 1);
+    simulation.world.skip_intro();
+    simulation.wait(2);
+    assert!(simulation.world.boss.is_some());
+    assert!(simulation.world.enemies.is_empty());
+    assert_eq!(simulation.world.rules.hud_extra(&simulation.world).as_deref(), Some("头目 1/3"));
+
+    let mut progress = Progress::default();
+    let unlocked = |progress: &Progress| GameMode::ALL.into_iter().filter(|mode| mode.rules().unlocked(progress)).collect::<Vec<_>>();
+    assert!(!unlocked(&progress).contains(&GameMode::BossRush));
+    assert!(!unlocked(&progress).contains(&GameMode::NewGamePlus));
+    progress.campaign_cleared = true;
+    assert_eq!(unlocked(&progress), GameMode::ALL);
+}
+
+// 只有随机波次模式的关卡由种子决定，HUD 只在这种模式下显示种子
+#[test]
+fn only_the_randomizer_shows_its_seed() {
+    let showing = GameMode::ALL.into_iter().filter(|mode| mode.rules().shows_seed()).collect::<Vec<_>>();
+    assert_eq!(showing, [GameMode::Randomizer]);
+}
+
+// 对战中一方写死的场景：敌人的来源照常生成，这里关掉
+fn versus() -> World {
+    let mut world = World::new(GameMode::Versus, 1);
+    world.spawning = false;
+    world
+}
+
+// 第二架飞船击落的敌人只记在它自己的分数上，第一名玩家的分数不变
+#[test]
+fn versus_scores_each_ship_for_its_own_kills() {
+    let mut world = versus();
+    let rival = world.rival.as_ref().unwrap().player.game_object.position;
+    assert!(rival.x > world.player.game_object.position.x, "两架飞船左右分开出发");
+    world.enemies.push(Enemy::new(rival.x, rival.y - 150.0, 30.0, 0.0, EnemyPath::Straight));
+    world.apply_rival_input(FrameInput { fire: true, ..FrameInput::default() });
+    for _ in 0..60 {
+        if world.enemies.is_empty() {
+            break;
+        }
+        world.update(world::FIXED_STEP);
+    }
+
+    assert!(world.enemies.is_empty());
+    assert!(world.rival.as_ref().unwrap().score > 0);
+    assert_eq!(world.score, 0);
+}
+
+// 先被击落的一方输，分数再高也一样；两边都活着时比分数
+#[test]
+fn versus_is_lost_by_the_ship_shot_down_first() {
+    let mut world = versus();
+    let rival = world.rival.as_mut().unwrap();
+    rival.score = 1000;
+    rival.player.lives = 1;
+    let position = rival.player.game_object.position;
+    world.enemy_bullets.push_hostile(position, Vec2::Y * 10.0);
+    assert_eq!(world.winner(), None);
+    world.update(world::FIXED_STEP);
+
+    assert!(world.game_over && !world.can_continue());
+    assert_eq!(world.winner(), Some(Winner::First));
+    assert_eq!(Winner::decide(2, 100, 1, 300), Winner::Second);
+    assert_eq!(Winner::decide(1, 300, 3, 300), Winner::Draw);
+    assert_eq!(Winner::decide(0, 0, 0, 10), Winner::Second);
+}
+
+// 两边都只有移动、开火和冲刺：第一名玩家的炸弹、电磁脉冲和换武器不起作用，也不掉道具、不奖励生命
+#[test]
+fn versus_keeps_both_ships_on_the_same_loadout() {
+    let mut world = versus();
+    let bombs = world.player.bombs;
+    world.use_bomb();
+    world.fire_emp();
+    world.select_weapon(2);
+    assert_eq!(world.player.bombs, bombs);
+    assert_eq!(world.emp_cooldown, 0.0);
+    assert_eq!(world.weapon, Weapon::Cannon);
+
+    world.spawning = true;
+    world.run_time = modes::VERSUS_LIMIT - 60.0;
+    for _ in 0..600 {
+        world.update(world::FIXED_STEP);
+    }
+    assert!(world.powerups.is_empty() && world.boss.is_none());
+    assert_eq!(world.rules.players(), 2);
+    assert!(GameMode::ALL.into_iter().filter(|&mode| mode != GameMode::Versus).all(|mode| World::new(mode, 1).rival.is_none()));
+}
+
+// 每日挑战用当天的日期当种子，按随机波次的模板生成关卡，用每日挑战的计分规则；其他模式每局随机
+#[test]
+fn daily_challenge_uses_the_date_as_its_seed() {
+    assert_eq!(GameMode::Daily.rules().seed(), Some(modes::today()));
+    assert!(GameMode::ALL.into_iter().filter(|&mode| mode != GameMode::Daily).all(|mode| mode.rules().seed().is_none()));
+
+    let world = World::new(GameMode::Daily, modes::today());
+    assert!(world.campaign.is_some() && !world.rules.bosses_by_score());
+    assert_eq!(world.scoring.on_graze(), Daily.on_graze());
+    assert_eq!(world.scoring.on_kill(10, 3), KillScore { base: 10, combo: 0 });
+}
+
+// 穿透一次的子弹打穿第一个敌人，打中第二个后消失，第三个敌人不受影响
+#[test]
+fn piercing_bullets_pass_through_up_to_their_count() {
+    let mut simulation = Simulation::quiet();
+    let x = simulation.world.player.game_object.position.x;
+    for y in [340.0, 260.0, 180.0] {
+        simulation.world.enemies.push(Enemy::new(x, y, 30.0, 0.0, EnemyPath::Straight));
+    }
+    let modifiers = Modifiers { pierce: 1, bounces: 0 };
+    let (position, velocity) = (Vec2::new(x, 420.0), Vec2::new(0.0, -400.0));
+    simulation.world.bullets.push_modified(position, velocity, Weapon::Cannon, 1, modifiers, 99);
+    simulation.wait(90);
+    assert_eq!(simulation.world.enemies.len(), 1);
+    assert_eq!(simulation.world.enemies[0].game_object.position.y, 180.0);
+    assert_eq!(simulation.world.score, 20);
+    assert!(simulation.world.bullets.is_empty());
+}
+
+// 反弹一次的子弹碰到左边缘折返，飞到右边缘时次数用完，飞出屏幕
+#[test]
+fn bouncing_bullets_ricochet_off_the_edges_a_limited_number_of_times() {
+    let field = PlayField::default();
+    let mut bullets = Bullets::default();
+    let modifiers = Modifiers { pierce: 0, bounces: 1 };
+    bullets.push_modified(Vec2::new(20.0, 300.0), Vec2::new(-400.0, 0.0), Weapon::Scatter, 1, modifiers, 0);
+    for _ in 0..10 {
+        bullets.integrate(world::FIXED_STEP, &field);
+    }
+    assert!(bullets.is_alive(0) && bullets.velocities[0].x > 0.0);
+    assert_eq!(bullets.modifiers(0).bounces, 0);
+    for _ in 0..((field.width / 400.0 / world::FIXED_STEP) as u32 + 10) {
+        bullets.integrate(world::FIXED_STEP, &field);
+    }
+    assert!(!bullets.is_alive(0));
+}
+
+// 内置的关卡只引用注册过的 ID；mods 按文件名顺序覆盖和新增定义，格式错误的文件整份跳过
+#[test]
+fn mods_override_and_extend_the_content_registry() {
+    let builtin = Registry::builtin();
+    assert!(builtin.check(&CampaignData::load()).is_empty());
+    assert_eq!(builtin.enemy("enemy.diver").unwrap().path, EnemyPath::Diver);
+    assert_eq!(builtin.powerup("powerup.spread"), Some(PowerupKind::Spread));
+    assert!(PowerupKind::ALL.iter().all(|kind| builtin.powerup(kind.id()) == Some(*kind)));
+
+    let dir = std::env::temp_dir().join(format!("space_shooter_mods_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("a.toml"), "[enemies.\"enemy.diver\"]\npath = \"diver\"\nhp = 3\n\n[enemies.\"enemy.ace\"]\nsize = 40.0\narmor = 2\n").unwrap();
+    std::fs::write(dir.join("b.toml"), "[enemies.\"boss.huge\"]\nsize = 90.0\n").unwrap();
+    std::fs::write(dir.join("notes.txt"), "不是内容定义").unwrap();
+    let (registry, errors) = Registry::with_mods(&dir);
+    let _ = std::fs::remove_dir_all(&dir);
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with("mods/b.toml"));
+    assert_eq!(registry.enemy("enemy.diver").unwrap().hp, 3);
+    assert_eq!(registry.enemy("enemy.ace").unwrap().armor, 2);
+    assert!(registry.enemy("boss.huge").is_none());
+    assert_eq!(registry.enemy_ids().count(), builtin.enemy_ids().count() + 1);
+
+    let mut data = CampaignData::load();
+    data.stages[0].waves[0].enemy = Some("enemy.ace".to_string());
+    data.stages[0].waves[1].enemy = Some("enemy.missing".to_string());
+    assert_eq!(registry.check(&data), vec!["第 1 关第 2 波: 未知的敌人 ID enemy.missing".to_string()]);
+    let data = data.resolve(&registry);
+    assert_eq!((data.stages[0].waves[0].size, data.stages[0].waves[0].armor), (40.0, 2));
+}
+
+// 脚本按 ID 生成敌人和掉落道具，没有注册的 ID 报错
+#[test]
+fn scripts_spawn_content_by_id() {
+    let mut simulation = Simulation::quiet();
+    simulation.run("spawn enemy.heavy, drop powerup.shield, wait").unwrap();
+    assert_eq!(simulation.world.enemies.len(), 1);
+    assert_eq!((simulation.world.enemies[0].armor, simulation.world.enemies[0].game_object.size.x), (1, 44.0));
+    assert_eq!(simulation.world.powerups.len(), 1);
+    assert_eq!(simulation.world.powerups[0].kind, PowerupKind::Shield);
+    assert!(simulation.run("spawn enemy.nothing").is_err());
+    assert!(simulation.run("drop powerup.nothing").is_err());
+    assert!(simulation.run("spawn").is_err());
+}
+
+// 自爆机停在上方，锁定玩家当时的位置并亮出预警线，之后沿锁定的方向加速冲刺，不再跟着玩家转向
+#[test]
+fn kamikaze_locks_onto_the_player_then_dives_along_that_line() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.enemies.push(Enemy::new(100.0, 0.0, 22.0, 200.0, EnemyPath::Kamikaze));
+    simulation.wait(40);
+    let hover = simulation.world.enemies[0].game_object.position;
+    assert!(matches!(simulation.world.enemies[0].kamikaze, Some(Kamikaze::Hovering(_))));
+    simulation.wait(20);
+    assert_eq!(simulation.world.enemies[0].game_object.position, hover);
+
+    simulation.wait(20);
+    let target = simulation.world.player.game_object.position;
+    let direction = simulation.world.enemies[0].warning().expect("锁定后应当显示预警线");
+    assert!(direction.angle_between(target - hover).abs() < 1e-3);
+    assert_eq!(simulation.world.enemies[0].game_object.position, hover);
+
+    // 锁定之后玩家躲开，自爆机仍然朝原来的方向冲
+    simulation.hold_right(5);
+    simulation.wait((enemy::KAMIKAZE_WARNING / world::FIXED_STEP) as u32);
+    let enemy = &simulation.world.enemies[0];
+    assert!(enemy.warning().is_none() && matches!(enemy.kamikaze, Some(Kamikaze::Diving { .. })));
+    let start = enemy.game_object.position;
+    simulation.wait(5);
+    let middle = simulation.world.enemies[0].game_object.position;
+    simulation.wait(5);
+    let end = simulation.world.enemies[0].game_object.position;
+    assert!((middle - start).angle_between(direction).abs() < 1e-3);
+    assert!((end - middle).length() > (middle - start).length());
+}
+
+// 冲过头飞出屏幕的自爆机直接删除，不算突破防线
+#[test]
+fn kamikaze_that_misses_leaves_without_costing_a_life() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.enemies.push(Enemy::new(20.0, 0.0, 22.0, 200.0, EnemyPath::Kamikaze));
+    simulation.world.player.game_object.position.x = WINDOW_WIDTH - 40.0;
+    simulation.wait(60);
+    simulation.hold_left(40);
+    simulation.wait(240);
+    assert!(simulation.world.enemies.is_empty());
+    assert_eq!(simulation.world.player.lives, 3);
+    assert_eq!(DamageSource::from_path(EnemyPath::Kamikaze), DamageSource::Kamikaze);
+}
+
+#[test]
+fn carrier_keeps_launching_minions_that_escape_for_free() {
+    let mut simulation = Simulation::quiet();
+    let mut carrier = Enemy::new(WINDOW_WIDTH / 2.0, 60.0, 56.0, 0.0, EnemyPath::Straight);
+    carrier.carrier = Some(Carrier::new(2.5));
+    simulation.world.enemies.push(carrier);
+    simulation.world.player.game_object.position.x = 40.0;
+    let minions = |world: &World| world.enemies.iter().filter(|enemy| enemy.source == DamageSource::Minion).count();
+    simulation.wait_seconds(2.4);
+    assert_eq!(minions(&simulation.world), 0);
+    simulation.wait_seconds(0.2);
+    assert_eq!(minions(&simulation.world), 2);
+    // 第一批早已飞出底部，第二批刚放出来
+    simulation.wait_seconds(2.5);
+    assert_eq!(minions(&simulation.world), 2);
+    assert_eq!(simulation.world.player.lives, 3);
+
+    simulation.world.enemies.retain(|enemy| enemy.carrier.is_none());
+    simulation.wait_seconds(5.0);
+    assert!(simulation.world.enemies.is_empty());
+    assert_eq!(simulation.world.player.lives, 3);
+}
+
+#[test]
+fn endless_sends_one_carrier_per_interval_after_the_start() {
+    let mut simulation = Simulation::new(GameMode::Endless, 7);
+    let carriers = |world: &World| world.enemies.iter().filter(|enemy| enemy.carrier.is_some()).count();
+    simulation.wait_seconds(1.0);
+    assert_eq!(carriers(&simulation.world), 0);
+    // 跳过好几个间隔也只补一架
+    simulation.world.run_time = 300.0;
+    simulation.wait_seconds(0.5);
+    assert_eq!(carriers(&simulation.world), 1);
+    let carrier = simulation.world.enemies.iter().find(|enemy| enemy.carrier.is_some()).unwrap();
+    assert_eq!(carrier.source, DamageSource::Carrier);
+    assert_eq!(carrier.points, 10 + 10 + enemy::CARRIER_BONUS);
+}
+
+#[test]
+fn bounty_targets_are_worth_ten_times_and_flee() {
+    let balance = Balance::load().bounty;
+    let mut simulation = Simulation::quiet();
+    let position = simulation.world.player.game_object.position;
+    let mut enemy = Enemy::new(position.x, position.y - 200.0, 30.0, 0.0, EnemyPath::Straight);
+    enemy.mark_bounty(&balance);
+    assert!(!enemy.can_carry_bounty());
+    simulation.world.enemies.push(enemy);
+    simulation.fire().wait(30);
+    assert!(simulation.world.enemies.is_empty());
+    assert_eq!(simulation.world.score, 10 * balance.multiplier);
+
+    // 停留够了就往上逃走，飞出顶部不扣生命
+    let mut enemy = Enemy::new(100.0, 150.0, 30.0, 0.0, EnemyPath::Straight);
+    enemy.mark_bounty(&balance);
+    simulation.world.enemies.push(enemy);
+    simulation.wait_seconds(balance.linger - 0.1);
+    assert!(!simulation.world.enemies[0].fleeing());
+    simulation.wait_seconds(0.2);
+    assert!(simulation.world.enemies[0].fleeing());
+    assert!(simulation.world.enemies[0].game_object.position.y < 150.0);
+    simulation.wait_seconds(2.0);
+    assert!(simulation.world.enemies.is_empty());
+    assert_eq!(simulation.world.player.lives, 3);
+
+    // 被打中没被击落时马上逃走
+    let mut enemy = Enemy::new(100.0, 150.0, 30.0, 0.0, EnemyPath::Straight);
+    enemy.set_hp(3);
+    enemy.mark_bounty(&balance);
+    assert!(!enemy.hit(1));
+    assert!(enemy.fleeing());
+}
+
+#[test]
+fn enemy_definitions_set_their_own_score() {
+    let mut content = Registry::builtin();
+    assert_eq!(content.enemy("enemy.gunship").unwrap().points, Some(30));
+    content.merge("[enemies.\"enemy.gunship\"]\nsize = 34.0\npoints = 75\n").unwrap();
+    let mut world = World::with_content(GameMode::Endless, 1, MetaUpgrades::default(), content);
+    world.spawning = false;
+    world.spawn_by_id("enemy.gunship").unwrap();
+    world.spawn_by_id("enemy.carrier").unwrap();
+    assert_eq!(world.enemies[0].points, 75);
+    assert_eq!(world.enemies[1].points, 110);
+}
+
+#[test]
+fn a_kill_during_the_grace_period_saves_the_combo() {
+    let mut combo = Combo::default();
+    for _ in 0..4 {
+        combo.kill();
+    }
+    combo.update(2.1);
+    assert!(combo.in_grace());
+    assert_eq!(combo.meter(), 0.0);
+    assert_eq!(combo.multiplier(), 2);
+    assert_eq!(combo.kill(), 2);
+    assert!(!combo.in_grace());
+    assert_eq!(combo.kills(), 5);
+
+    combo.update(2.0 + combo::GRACE);
+    assert_eq!(combo.multiplier(), 1);
+}
+
+#[test]
+fn combo_keeper_freezes_decay_but_not_damage() {
+    let mut simulation = Simulation::quiet();
+    shoot_down(&mut simulation, 4);
+    powerup_on_player(&mut simulation, PowerupKind::ComboKeeper);
+    simulation.wait(1);
+    assert!(simulation.world.combo.kept());
+    simulation.wait_seconds(9.5);
+    assert_eq!(simulation.world.combo.multiplier(), 2);
+    simulation.wait_seconds(0.6 + 2.0 + combo::GRACE);
+    assert!(!simulation.world.combo.kept());
+    assert_eq!(simulation.world.combo.multiplier(), 1);
+
+    shoot_down(&mut simulation, 4);
+    powerup_on_player(&mut simulation, PowerupKind::ComboKeeper);
+    simulation.wait(1);
+    enemy_on_player(&mut simulation);
+    simulation.wait(2);
+    assert_eq!(simulation.world.combo.kills(), 0);
+    assert_eq!(PowerupKind::ComboKeeper.rarity(), Rarity::Rare);
+}
+
+#[test]
+fn splitters_break_into_smaller_faster_halves_down_to_a_minimum_size() {
+    let mut simulation = Simulation::quiet();
+    let position = simulation.world.player.game_object.position;
+    let mut enemy = Enemy::new(position.x, position.y - 250.0, 40.0, 80.0, EnemyPath::Straight);
+    enemy.make_splitter();
+    enemy.points = 40;
+    let halves = enemy::splinters(&enemy);
+    assert_eq!(halves.len(), 2);
+    assert_eq!(halves[0].game_object.size.x, 28.0);
+    assert!(halves[0].game_object.velocity.y > 80.0);
+    assert_eq!(halves[0].points, 20);
+    assert!(halves[0].game_object.velocity.x < 0.0 && halves[1].game_object.velocity.x > 0.0);
+    // 40 → 28 → 19.6，再分就小于 14，不再分裂
+    let quarters = enemy::splinters(&halves[0]);
+    assert_eq!(quarters.len(), 2);
+    assert!(enemy::splinters(&quarters[0]).is_empty());
+
+    // 在子弹的碰撞结算中被击落，分身等这一帧结束后才加入
+    enemy.game_object.velocity = Vec2::ZERO;
+    simulation.world.enemies.push(enemy);
+    simulation.fire().wait(40);
+    assert_eq!(simulation.world.enemies.len(), 2);
+    assert!(simulation.world.enemies.iter().all(|enemy| enemy.splits && enemy.source == DamageSource::Splitter));
+    assert_eq!(simulation.world.score, 40);
+}
+
+#[test]
+fn splitter_content_appears_in_the_final_stage() {
+    let data = CampaignData::load().resolve(&Registry::builtin());
+    let last = data.stages.last().unwrap();
+    assert!(last.waves.iter().any(|wave| wave.splits && wave.enemy.as_deref() == Some("enemy.splitter")));
+
+    let mut world = World::new(GameMode::Endless, 3);
+    world.spawning = false;
+    world.spawn_by_id("enemy.splitter").unwrap();
+    assert!(world.enemies[0].splits);
+    assert_eq!(world.enemies[0].points, 40);
+}
+
+#[test]
+fn kill_quests_pay_out_when_finished_in_time() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.quests.start(QuestTemplate { goal: Goal::Kills(4), limit: 10.0, reward: Reward::Score(500) });
+    assert_eq!(hud::quest(&simulation.world).as_deref(), Some("任务: 10 秒内击落 4 个敌人 (0/4)"));
+    shoot_down(&mut simulation, 4);
+    simulation.wait(2);
+    assert!(simulation.world.quests.active().is_none());
+    assert!(simulation.saw(|event| matches!(event, GameEvent::Quest { outcome: QuestOutcome::Completed(Reward::Score(500)) })));
+    assert!(simulation.world.ledger.totals().contains(&(ScoreReason::Quest, 500)));
+
+    // 超时就算失败，没有奖励
+    simulation.world.quests.start(QuestTemplate { goal: Goal::Kills(4), limit: 1.0, reward: Reward::Score(500) });
+    shoot_down(&mut simulation, 2);
+    simulation.wait_seconds(1.1);
+    assert!(simulation.world.quests.active().is_none());
+    assert!(simulation.saw(|event| matches!(event, GameEvent::Quest { outcome: QuestOutcome::Failed })));
+    assert_eq!(simulation.world.ledger.totals().iter().filter(|(reason, _)| *reason == ScoreReason::Quest).count(), 1);
+}
+
+#[test]
+fn stillness_and_no_damage_quests_track_the_player() {
+    let mut simulation = Simulation::quiet();
+    let shield = Reward::Powerup(PowerupKind::Shield.id().to_string());
+    simulation.world.quests.start(QuestTemplate { goal: Goal::HoldStill(2.0), limit: 10.0, reward: shield });
+    simulation.wait_seconds(1.5);
+    // 动一下就从头计时
+    simulation.hold_left(5);
+    simulation.wait_seconds(1.5);
+    assert!(simulation.world.quests.active().is_some());
+    simulation.wait_seconds(0.6);
+    assert!(simulation.world.quests.active().is_none());
+    simulation.wait(2);
+    assert!(simulation.world.buffs.shielded());
+
+    simulation.world.quests.start(QuestTemplate { goal: Goal::NoDamage(5.0), limit: 6.0, reward: Reward::Score(100) });
+    simulation.world.buffs.shield = 0.0;
+    simulation.wait_seconds(1.0);
+    enemy_on_player(&mut simulation);
+    simulation.wait(3);
+    assert!(simulation.world.quests.active().is_none());
+    assert!(simulation.saw(|event| matches!(event, GameEvent::Quest { outcome: QuestOutcome::Failed })));
+
+    // 任务池里奖励的道具都是存在的 ID
+    let content = Registry::builtin();
+    for template in simulation.world.quests.templates() {
+        if let Reward::Powerup(id) = &template.reward {
+            assert!(content.powerup(id).is_some(), "{}", id);
+        }
+    }
+}
+
+#[test]
+fn endless_records_a_milestone_every_ten_waves_but_practice_does_not() {
+    assert!(!milestone::due(1) && !milestone::due(9) && milestone::due(10) && milestone::due(20));
+    let mut simulation = Simulation::new(GameMode::Endless, 5);
+    let mut snapshot = simulation.world.milestone();
+    snapshot.wave = 9;
+    snapshot.enemies_spawned = 9 * 10 - 1;
+    snapshot.lives = 2;
+    snapshot.weapon_level = 3;
+
+    // 练习局从里程碑的状态开始，到了第 10 波也不再记
+    simulation.world = World::practice(&snapshot, MetaUpgrades::default(), Registry::builtin());
+    assert!(simulation.world.practice);
+    assert_eq!(simulation.world.wave(), 9);
+    assert_eq!((simulation.world.player.lives, simulation.world.player.weapon_level), (2, 3));
+    assert_eq!(simulation.world.seed, snapshot.seed);
+    simulation.world.player.invincible_timer = 60.0;
+    simulation.wait_seconds(10.0);
+    assert!(simulation.world.wave() >= 10);
+    assert!(!simulation.saw(|event| matches!(event, GameEvent::MilestoneReached { .. })));
+
+    // 正常的一局到了第 10 波就发出里程碑
+    simulation.world = World::practice(&snapshot, MetaUpgrades::default(), Registry::builtin());
+    simulation.world.practice = false;
+    simulation.world.player.invincible_timer = 60.0;
+    simulation.wait_seconds(10.0);
+    assert!(simulation.saw(|event| matches!(event, GameEvent::MilestoneReached { milestone } if milestone.wave == 10)));
+}
+
+#[test]
+fn progress_keeps_the_latest_milestone_per_wave_in_order() {
+    let dir = std::env::temp_dir().join(format!("space_shooter_milestones_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let storage = Storage::at(dir.clone());
+    storage.init().unwrap();
+    let base = Simulation::new(GameMode::Endless, u64::MAX).world.milestone();
+    let at = |wave: u32, lives: u32| Milestone { wave, lives, ..base };
+    let mut progress = Progress::default();
+    progress.reach_milestone(at(20, 1));
+    progress.reach_milestone(at(10, 3));
+    progress.reach_milestone(at(20, 2));
+    assert_eq!(progress.milestones, vec![at(10, 3), at(20, 2)]);
+
+    // 大于 i64 的种子也能存进 TOML 再读回来
+    storage.save_progress(&progress).unwrap();
+    let loaded = storage.load_progress().unwrap().value;
+    assert_eq!(loaded.milestones, progress.milestones);
+    assert_eq!(loaded.milestones[0].seed, u64::MAX);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+// 敌人的机身擦着飞船飞过也算擦弹，每个敌人只算一次，HUD 上显示次数，飞船周围冒出火花
+#[test]
+fn enemies_passing_close_graze_once_each() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.player.invincible_timer = 0.0;
+    assert_eq!(hud::grazes(&simulation.world), None);
+    let (center, size) = (simulation.world.player.game_object.position, simulation.world.player.game_object.size);
+    // 敌人的左边缘离飞船右边缘 8 像素，在擦弹框内又撞不上
+    let x = center.x + size.x / 2.0 + 8.0 + 15.0;
+    let mut enemy = Enemy::new(x, center.y - 120.0, 30.0, 200.0, EnemyPath::Straight);
+    enemy.escape_penalty = false;
+    simulation.world.enemies.push(enemy);
+    while simulation.world.grazes == 0 && simulation.frame < 60 {
+        simulation.wait(1);
+    }
+    assert!(simulation.world.effects.particle_count() > 0);
+    simulation.wait(60);
+    let grazes = simulation.events.iter().filter(|event| matches!(event, GameEvent::Grazed { .. })).count();
+    assert_eq!(grazes, 1);
+    assert_eq!(simulation.world.player.lives, 3);
+    assert_eq!(simulation.world.ledger.totals(), vec![(ScoreReason::Graze, 1)]);
+    assert_eq!(hud::grazes(&simulation.world), Some("擦弹: 1".to_string()));
+
+    // 离得远的不算
+    let mut far = Enemy::new(x + 60.0, center.y - 120.0, 30.0, 200.0, EnemyPath::Straight);
+    far.escape_penalty = false;
+    simulation.world.enemies.push(far);
+    simulation.wait(60);
+    assert_eq!(simulation.world.grazes, 1);
+}
+
+// 动态难度随时间和连击升高，失去一条命时降低，始终在配置的范围内
+#[test]
+fn dynamic_rank_rises_with_play_and_drops_on_death() {
+    let curve = DifficultyCurve::load();
+    let tuning = curve.dynamic_rank;
+    let (mut calm, mut combo) = (DynamicRank::default(), DynamicRank::default());
+    calm.update(10.0, 1, &tuning);
+    combo.update(10.0, 5, &tuning);
+    assert!(calm.offset() > 0.0 && combo.offset() > calm.offset());
+    combo.update(10_000.0, 5, &tuning);
+    assert_eq!(combo.offset(), tuning.max);
+    combo.on_death(&tuning);
+    assert!((combo.offset() - (tuning.max - tuning.death)).abs() < 1e-6);
+
+    // 偏移为正时出现得更密更快，难度等级也更高
+    let base = curve.at(300.0);
+    let harder = base.with_rank(&combo, &tuning);
+    assert!(harder.spawn_interval < base.spawn_interval && harder.enemy_speed > base.enemy_speed && harder.rank > base.rank);
+    for _ in 0..10 {
+        combo.on_death(&tuning);
+    }
+    assert_eq!(combo.offset(), tuning.min);
+    assert!(base.with_rank(&combo, &tuning).spawn_interval > base.spawn_interval);
+}
+
+#[test]
+fn endless_rank_follows_how_the_player_is_doing() {
+    let mut simulation = Simulation::new(GameMode::Endless, 11);
+    let tuning = DifficultyCurve::load().dynamic_rank;
+    simulation.world.player.invincible_timer = 1000.0;
+    simulation.wait_seconds(20.0);
+    let risen = simulation.world.dynamic_rank();
+    assert!(risen > 0.0);
+    assert!(simulation.world.rank() > 0.0 && simulation.world.difficulty().spawn_interval < DifficultyCurve::load().at(simulation.world.run_time).spawn_interval);
+
+    // 被打中失去一条命后降下来
+    simulation.world.player.invincible_timer = 0.0;
+    simulation.world.enemies.clear();
+    let position = simulation.world.player.game_object.position;
+    simulation.world.enemy_bullets.push_hostile(position - Vec2::new(0.0, 30.0), Vec2::new(0.0, 300.0));
+    simulation.wait(10);
+    assert!(simulation.saw(|event| matches!(event, GameEvent::PlayerDamaged { .. })));
+    assert!(simulation.world.dynamic_rank() < risen - tuning.death + 0.05);
+}
+
+// 受击热图按格子累计，超出游戏区域的位置算在边上的格子里
+#[test]
+fn heatmap_counts_hits_and_deaths_per_cell() {
+    let size = Vec2::new(800.0, 600.0);
+    let mut map = HeatMap::default();
+    map.record(Vec2::new(10.0, 10.0), size, false);
+    map.record(Vec2::new(12.0, 20.0), size, true);
+    map.record(Vec2::new(900.0, 700.0), size, false);
+    assert_eq!(map.hits.len(), heatmap::COLUMNS * heatmap::ROWS);
+    assert_eq!(map.hits[0], 2);
+    assert_eq!(map.deaths[0], 1);
+    assert_eq!(map.hits[heatmap::COLUMNS * heatmap::ROWS - 1], 1);
+    assert_eq!(HeatMap::cell(Vec2::new(-5.0, -5.0), size), 0);
+    assert_eq!((map.total_hits(), map.total_deaths()), (3, 1));
+}
+
+#[test]
+fn heatmap_survives_a_save_and_load() {
+    let dir = std::env::temp_dir().join(format!("space_shooter_heatmap_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let storage = Storage::at(dir.clone());
+    storage.init().unwrap();
+    assert_eq!(storage.load_heatmap().unwrap().value, HeatMap::default());
+    let mut map = HeatMap::default();
+    map.record(Vec2::new(400.0, 500.0), Vec2::new(800.0, 600.0), true);
+    storage.save_heatmap(&map).unwrap();
+    assert_eq!(storage.load_heatmap().unwrap().value, map);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+// 受击回放只留最近一秒的画面，慢放两秒放完
+#[test]
+fn hit_replay_keeps_the_last_second_and_plays_it_slowly() {
+    let mut simulation = Simulation::quiet();
+    let mut clips = ClipRecorder::default();
+    assert!(clips.clip().is_none());
+    let dt = 1.0 / 60.0;
+    for _ in 0..120 {
+        simulation.wait(1);
+        clips.record(dt, &simulation.world);
+    }
+    assert!((60..=62).contains(&clips.len()));
+
+    let mut replay = clips.clip().unwrap();
+    let mut played = 0.0;
+    while !replay.finished() {
+        replay.update(dt);
+        played += dt;
+    }
+    let expected = hitreplay::CLIP_SECONDS / hitreplay::SLOW;
+    assert!((played - expected).abs() < 0.1, "放了 {} 秒", played);
+    clips.clear();
+    assert!(clips.is_empty());
+}
+
+#[test]
+fn hit_replay_frames_follow_what_happened() {
+    let mut simulation = Simulation::quiet();
+    let mut clips = ClipRecorder::default();
+    let dt = 1.0 / 60.0;
+    clips.record(dt, &simulation.world);
+    let start = simulation.world.player.game_object.bounds();
+    simulation.world.enemies.push(Enemy::new(100.0, 100.0, 30.0, 0.0, EnemyPath::Straight));
+    simulation.hold_left(30);
+    clips.record(dt, &simulation.world);
+
+    let mut replay = clips.clip().unwrap();
+    assert_eq!(replay.frame().player, start);
+    assert!(replay.frame().enemies.is_empty());
+    replay.update(dt / hitreplay::SLOW);
+    assert!(replay.frame().player.x < start.x);
+    assert_eq!(replay.frame().enemies.len(), 1);
+}
+
+// 护盾条模式只有一条命，护盾挡下三击之后的下一击才掉命，HUD 显示护盾剩下多少
+#[test]
+fn shield_bar_absorbs_hits_before_a_life_is_lost() {
+    let mut simulation = Simulation::quiet();
+    simulation.world.set_health(HealthModel::Shield);
+    assert_eq!(simulation.world.player.lives, 1);
+    assert_eq!(hud::lives(&simulation.world), "生命: 1  护盾: 100%");
+    for _ in 0..3 {
+        simulation.world.player.invincible_timer = 0.0;
+        enemy_on_player(&mut simulation);
+        simulation.wait(2);
+    }
+    let absorbed = simulation.events.iter().filter(|event| matches!(event, GameEvent::ShieldDamaged { .. })).count();
+    assert_eq!(absorbed, 3);
+    assert_eq!(simulation.world.player.lives, 1);
+    assert_eq!(hud::lives(&simulation.world), "生命: 1  护盾: 0%");
+    assert!(!simulation.world.game_over);
+
+    simulation.world.player.invincible_timer = 0.0;
+    enemy_on_player(&mut simulation);
+    simulation.wait(2);
+    assert!(simulation.saw(|event| matches!(event, GameEvent::PlayerDamaged { .. })));
+    assert_eq!(simulation.world.player.lives, 0);
+
+    // 默认的生命规则没有护盾条
+    let mut lives = Simulation::quiet();
+    lives.world.set_health(HealthModel::Lives);
+    assert!(lives.world.player.shield.is_none());
+    assert_eq!(hud::lives(&lives.world), "生命: 3");
+}
+
+// 一段时间没被打中后护盾慢慢回满，被打空后掉命时直接充满
+#[test]
+fn shield_bar_regenerates_after_a_quiet_spell() {
+    let mut shield = ShieldBar::default();
+    assert!(shield.absorb());
+    let hit = shield.value();
+    assert!(hit < 1.0);
+    shield.update(2.5);
+    assert_eq!(shield.value(), hit);
+    assert!(!shield.regenerating());
+    shield.update(1.0);
+    assert!(shield.regenerating() && shield.value() > hit);
+    shield.update(10.0);
+    assert_eq!(shield.value(), 1.0);
+
+    while shield.absorb() {}
+    assert_eq!(shield.value(), 0.0);
+    shield.refill();
+    assert_eq!(shield, ShieldBar::default());
+
+    // 检查点记下护盾，从这一波重来时恢复
+    let mut simulation = Simulation::quiet();
+    simulation.world.set_health(HealthModel::Shield);
+    simulation.world.player.shield.as_mut().unwrap().absorb();
+    simulation.world.retry_wave();
+    assert_eq!(simulation.world.player.shield, Some(ShieldBar::default()));
+}
+
+// 头目练习的菜单列出每个头目的每个阶段和招式，从中间的阶段开始时血量降到这一阶段的门槛
+#[test]
+fn boss_drills_list_every_phase_and_start_at_its_threshold() {
+    let data = CampaignData::load();
+    let drills = data.boss_drills();
+    let phases: usize = data.stages.iter().map(|stage| stage.boss.phases.len()).sum();
+    assert_eq!(drills.len(), phases);
+    assert_eq!(drills[0], BossDrill { stage: 0, phase: 0, label: "第一关 小行星带 深空巡洋舰  第 1 阶段: 左右摆动".to_string() });
+    assert!(drills.last().unwrap().label.ends_with("第 3 阶段: 俯冲撞击"));
+
+    let spec = data.stages[2].boss.clone();
+    let mut boss = Boss::new(spec.clone());
+    boss.start_at_phase(2);
+    assert_eq!(boss.phase(), 2);
+    assert_eq!(boss.hp, (spec.hp as f32 * 0.33) as u32);
+    assert!(boss.current_phase().unwrap().pattern == BossPattern::Charge);
+    // 超出范围按最后一个阶段
+    let mut boss = Boss::new(spec);
+    boss.start_at_phase(9);
+    assert_eq!(boss.phase(), 2);
+}
+
+// 头目练习直接进入选中的阶段，HUD 标出招式；击败头目后练习结束，不算通关
+#[test]
+fn boss_practice_starts_at_the_chosen_phase_and_ends_without_clearing() {
+    let world = World::boss_practice(2, 1, MetaUpgrades::default(), Registry::builtin());
+    let mut simulation = Simulation { world, frame: 0, events: Vec::new() };
+    simulation.world.skip_intro();
+    simulation.wait(2);
+    let boss = simulation.world.boss.as_ref().expect("头目应该直接登场");
+    assert_eq!(boss.phase(), 1);
+    assert_eq!(boss.spec.name, "星核毁灭者");
+    assert_eq!(hud::boss_pattern(&simulation.world), None);
+    simulation.wait_seconds(6.0);
+    assert_eq!(hud::boss_pattern(&simulation.world).as_deref(), Some("招式: 8 字盘旋  (第 2/3 阶段)"));
+    assert!(!simulation.saw(|event| matches!(event, GameEvent::BossPhaseChanged { .. })));
+
+    simulation.world.player.invincible_timer = 1000.0;
+    simulation.world.boss.as_mut().unwrap().hp = 1;
+    while simulation.world.boss.is_some() && simulation.frame < 2000 {
+        simulation.fire().wait(1);
+    }
+    assert!(simulation.saw(|event| matches!(event, GameEvent::BossDefeated { .. })));
+    assert!(simulation.world.game_over);
+    assert!(!simulation.saw(|event| matches!(event, GameEvent::CampaignCleared)));
+
+    // 不是练习局时 HUD 不标招式
+    let mut rush = Simulation::new(GameMode::BossRush, 1);
+    rush.world.skip_intro();
+    rush.wait_seconds(6.0);
+    assert!(rush.world.boss.is_some());
+    assert_eq!(hud::boss_pattern(&rush.world), None);
+}
+
+// 续关从这一波开头接着打：生命恢复为开局的数量，分数和各项得分减半，续关次数用完就不能再续
+#[test]
+fn continuing_keeps_the_run_going_at_a_score_penalty() {
+    let mut simulation = Simulation::quiet();
+    let x = simulation.world.player.game_object.position.x;
+    simulation.world.enemies.push(Enemy::new(x, 200.0, 30.0, 0.0, EnemyPath::Straight));
+    simulation.run("fire, wait 90").unwrap();
+    assert_eq!(simulation.world.score, 10);
+    // 换生命规则时会记下检查点，相当于这一波从 10 分开始
+    simulation.world.set_health(HealthModel::Lives);
+    let run_time = simulation.world.run_time;
+    assert!(!simulation.world.continue_run());
+
+    for left in (0..continues::CONTINUES).rev() {
+        while !simulation.world.game_over {
+            enemy_on_player(&mut simulation);
+            simulation.wait_seconds(2.1);
+        }
+        assert!(simulation.world.can_continue());
+        assert!(simulation.world.continue_run());
+        assert!(!simulation.world.game_over);
+        assert_eq!(simulation.world.player.lives, 3);
+        assert_eq!(simulation.world.continues.left(), left);
+        assert_eq!(simulation.world.wave_start_score(), simulation.world.score);
+        assert_eq!(simulation.world.score, simulation.world.ledger.total());
+        assert!(simulation.world.run_time >= run_time);
+        simulation.wait(1);
+        assert!(simulation.saw(|event| matches!(event, GameEvent::Continued { left: remaining } if *remaining == left)));
+    }
+    assert_eq!(simulation.world.score, 10 >> continues::CONTINUES);
+
+    while !simulation.world.game_over {
+        enemy_on_player(&mut simulation);
+        simulation.wait_seconds(2.1);
+    }
+    assert!(!simulation.world.can_continue());
+    assert!(!simulation.world.continue_run());
+    assert!(simulation.world.game_over);
+}
+
+// 游戏结束画面上的倒计时到头后不再提供续关，续关后倒计时重新开始
+#[test]
+fn continue_offer_runs_out_after_the_countdown() {
+    let mut offer = Continues::default();
+    assert_eq!(offer.seconds(), continues::COUNTDOWN as u32);
+    offer.update(continues::COUNTDOWN - 0.5);
+    assert_eq!(offer.seconds(), 1);
+    assert!(offer.take());
+    assert_eq!((offer.left(), offer.seconds()), (continues::CONTINUES - 1, continues::COUNTDOWN as u32));
+    offer.update(continues::COUNTDOWN + 1.0);
+    assert!(!offer.available() && !offer.take());
+    assert_eq!(offer.left(), continues::CONTINUES - 1);
+
+    // 计时赛时间到了不是被击落，不能续关
+    let mut simulation = Simulation::new(GameMode::TimeAttack, 3);
+    simulation.world.player.invincible_timer = 10_000.0;
+    while !simulation.world.game_over && simulation.frame < 60 * 400 {
+        simulation.wait(60);
+    }
+    assert!(simulation.world.game_over && simulation.world.player.lives > 0);
+    assert!(!simulation.world.can_continue());
+}
+
+// 十六进制颜色可以带或不带 #，调色板里的颜色显示名字，左右切换时首尾相接
+#[test]
+fn theme_colors_parse_hex_and_cycle_through_the_palette() {
+    assert_eq!(Rgb::parse("#FFAA00"), Ok(Rgb(255, 170, 0)));
+    assert_eq!(Rgb::parse("ffaa00"), Ok(Rgb(255, 170, 0)));
+    assert!(Rgb::parse("#FFAA0").is_err());
+    assert!(Rgb::parse("#GGAA00").is_err());
+    assert_eq!(Rgb(255, 170, 0).to_hex(), "#FFAA00");
+    assert_eq!(Rgb(255, 170, 0).label(), "#FFAA00");
+
+    let theme = Theme::default();
+    assert_eq!(theme.get(ThemePart::Ship).label(), "白色");
+    assert_eq!(theme.get(ThemePart::Accent).label(), "黄色");
+    assert_eq!(PALETTE[0].1.cycle(-1), PALETTE[PALETTE.len() - 1].1);
+    assert_eq!(PALETTE[PALETTE.len() - 1].1.cycle(1), PALETTE[0].1);
+    assert_eq!(Rgb(1, 2, 3).cycle(1), PALETTE[0].1);
+
+    // 飞船没换颜色时通关后是金色涂装，换过之后按玩家选的颜色
+    let mut theme = Theme::default();
+    assert_ne!(theme.ship_color(true), theme.ship.color());
+    theme.set(ThemePart::Ship, Rgb(0, 255, 255));
+    assert_eq!(theme.ship_color(true), Rgb(0, 255, 255).color());
+}
+
+// 配色存在设置文件里，写成十六进制字符串，读回来不变
+#[test]
+fn theme_is_saved_with_the_profile_settings() {
+    let dir = std::env::temp_dir().join(format!("space_shooter_theme_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let storage = Storage::at(dir.clone());
+    storage.init().unwrap();
+    assert_eq!(storage.load_settings().unwrap().value.theme, Theme::default());
+    let mut settings = storage.load_settings().unwrap().value;
+    settings.theme.set(ThemePart::Bullets, Rgb(80, 230, 110));
+    settings.theme.set(ThemePart::Accent, Rgb(18, 52, 86));
+    storage.save_settings(&settings).unwrap();
+    let text = std::fs::read_to_string(dir.join("settings.toml")).unwrap();
+    assert!(text.contains("accent = '#123456'"));
+    assert_eq!(storage.load_settings().unwrap().value.theme, settings.theme);
+    let _ = std::fs::remove_dir_all(dir);
+}
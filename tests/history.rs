@@ -0,0 +1,86 @@
+// 历史记录的保存上限、排序和存档格式
+
+use ggez::input::keyboard::KeyCode;
+
+use space_shooter::history::{HistoryView, RunHistory, RunSummary, SortKey, MAX_RUNS};
+use space_shooter::simulation::Simulation;
+use space_shooter::weapon::CritStats;
+use space_shooter::world::{DamageSource, DeathCause};
+
+fn run(score: u32, duration: f32, finished_at: u64) -> RunSummary {
+    RunSummary {
+        mode: "endless".to_string(),
+        score,
+        waves: score / 100,
+        duration,
+        cleared: false,
+        cause: Some(DeathCause::Collision),
+        killed_by: Some(DamageSource::Zigzag),
+        finished_at,
+        crits: CritStats { hits: 20, crits: 3, crit_damage: 3 },
+    }
+}
+
+#[test]
+fn history_keeps_only_the_most_recent_runs() {
+    let mut history = RunHistory::default();
+    for i in 0..MAX_RUNS as u64 + 20 {
+        history.push(run(i as u32, 1.0, i));
+    }
+    assert_eq!(history.runs.len(), MAX_RUNS);
+    assert_eq!(history.runs[0].finished_at, 20);
+    assert_eq!(history.runs.last().unwrap().finished_at, MAX_RUNS as u64 + 19);
+}
+
+#[test]
+fn view_sorts_by_the_selected_column() {
+    let mut history = RunHistory::default();
+    history.push(run(300, 10.0, 1));
+    history.push(run(100, 50.0, 2));
+    history.push(run(200, 30.0, 3));
+
+    let mut view = HistoryView::default();
+    let order = |view: &HistoryView| view.sorted(&history).iter().map(|run| run.finished_at).collect::<Vec<_>>();
+    assert_eq!(order(&view), vec![3, 2, 1]);
+    view.handle_key(KeyCode::Right, &history);
+    assert!(view.sort == SortKey::Score);
+    assert_eq!(order(&view), vec![1, 3, 2]);
+    view.handle_key(KeyCode::Right, &history);
+    view.handle_key(KeyCode::Right, &history);
+    assert!(view.sort == SortKey::Duration);
+    assert_eq!(order(&view), vec![2, 3, 1]);
+    assert!(!view.handle_key(KeyCode::Escape, &history));
+}
+
+#[test]
+fn history_survives_a_round_trip_through_toml() {
+    let mut history = RunHistory::default();
+    history.push(run(120, 42.5, 7));
+    let mut abandoned = run(0, 3.0, 8);
+    abandoned.cause = None;
+    abandoned.killed_by = None;
+    history.push(abandoned);
+
+    let text = toml::to_string_pretty(&history).unwrap();
+    let loaded: RunHistory = toml::from_str(&text).unwrap();
+    assert_eq!(loaded.runs.len(), 2);
+    assert_eq!(loaded.runs[0].cause, Some(DeathCause::Collision));
+    assert_eq!(loaded.runs[0].killed_by, Some(DamageSource::Zigzag));
+    assert_eq!(loaded.runs[1].outcome(), "放弃");
+    assert_eq!(loaded.runs[0].crits, CritStats { hits: 20, crits: 3, crit_damage: 3 });
+
+    // 旧版本的记录没有暴击统计
+    let old = text.replace("[runs.crits]", "[runs.unused]");
+    let loaded: RunHistory = toml::from_str(&old).unwrap();
+    assert_eq!(loaded.runs[0].crits, CritStats::default());
+}
+
+#[test]
+fn endless_waves_advance_as_enemies_spawn() {
+    let mut simulation = Simulation::quiet();
+    assert_eq!(simulation.world.wave(), 1);
+    for _ in 0..25 {
+        simulation.world.spawn_enemy(0.0, 0.0);
+    }
+    assert_eq!(simulation.world.wave(), 3);
+}
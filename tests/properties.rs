@@ -0,0 +1,251 @@
+// 碰撞、限位和生成位置的性质测试：每个性质用 proptest 生成大量随机输入检查，
+// 失败时自动缩小到最简单的反例，并记进 proptest-regressions 目录，之后每次都先重跑这些反例
+
+use ggez::glam::Vec2;
+use proptest::prelude::*;
+use proptest::test_runner::FileFailurePersistence;
+
+use space_shooter::campaign::{self, Formation, SpawnLane, WaveSpec};
+use space_shooter::collision;
+use space_shooter::enemy::EnemyPath;
+use space_shooter::health::HealthModel;
+use space_shooter::playfield::EdgeMode;
+use space_shooter::sharecode::{Mutators, ShareCode};
+use space_shooter::world::{GameMode, World};
+use space_shooter::{GameObject, WINDOW_WIDTH};
+
+const CASES: u32 = 2000;
+const FORMATIONS: [Formation; 4] = [Formation::Single, Formation::Line, Formation::Column, Formation::Vee];
+const PATHS: [EnemyPath; 4] = [EnemyPath::Straight, EnemyPath::Drift, EnemyPath::Zigzag, EnemyPath::Diver];
+
+// 屏幕内外都可能出现的位置
+fn position() -> impl Strategy<Value = Vec2> {
+    (-100.0..WINDOW_WIDTH + 100.0, -100.0f32..700.0).prop_map(|(x, y)| Vec2::new(x, y))
+}
+
+fn size() -> impl Strategy<Value = Vec2> {
+    (1.0f32..120.0, 1.0f32..120.0).prop_map(|(width, height)| Vec2::new(width, height))
+}
+
+fn velocity() -> impl Strategy<Value = Vec2> {
+    (-500.0f32..500.0, -500.0f32..500.0).prop_map(|(x, y)| Vec2::new(x, y))
+}
+
+prop_compose! {
+    fn object()(position in position(), size in size(), velocity in velocity()) -> GameObject {
+        let mut object = GameObject::new(position.x, position.y, size.x, size.y);
+        object.velocity = velocity;
+        object
+    }
+}
+
+fn lane() -> impl Strategy<Value = SpawnLane> {
+    (0.0f32..1.0, 0.0f32..0.5, 1u32..4).prop_map(|(from, width, weight)| SpawnLane { from, to: (from + width).min(1.0), weight })
+}
+
+fn mutators() -> impl Strategy<Value = Mutators> {
+    (any::<bool>(), any::<bool>(), any::<bool>()).prop_map(|(wrap, retry_wave, shield)| Mutators {
+        player_edges: if wrap { EdgeMode::Wrap } else { EdgeMode::Clamp },
+        retry_wave,
+        health: if shield { HealthModel::Shield } else { HealthModel::Lives },
+    })
+}
+
+// 只出一组的波次，其余字段取最普通的值
+fn wave(speed: f32, size: f32, formation: Formation, path: EnemyPath, lanes: Vec<SpawnLane>) -> WaveSpec {
+    WaveSpec {
+        count: 1,
+        interval: 1.0,
+        speed,
+        size,
+        formation,
+        path,
+        hp: 1,
+        armor: 0,
+        fire_interval: 0.0,
+        aimed: false,
+        launch_interval: 0.0,
+        splits: false,
+        lanes,
+        enemy: None,
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        failure_persistence: Some(Box::new(FileFailurePersistence::Direct("tests/proptest-regressions/properties.txt"))),
+        ..ProptestConfig::with_cases(CASES)
+    })]
+
+    #[test]
+    fn overlap_is_symmetric(a in object(), b in object()) {
+        prop_assert_eq!(a.collides_with(&b), b.collides_with(&a));
+    }
+
+    #[test]
+    fn object_overlaps_itself(a in object()) {
+        prop_assert!(a.collides_with(&a));
+    }
+
+    #[test]
+    fn separated_objects_do_not_overlap(a in object(), mut b in object(), gap in 0.01f32..50.0) {
+        b.position.x = a.position.x + (a.size.x + b.size.x) / 2.0 + gap;
+        prop_assert!(!a.collides_with(&b));
+        prop_assert!(!b.collides_with(&a));
+    }
+
+    #[test]
+    fn sweep_covers_both_ends_of_the_move(moving in object(), other in object(), dt in 0.001f32..0.2) {
+        let mut previous = GameObject::new(0.0, 0.0, moving.size.x, moving.size.y);
+        previous.position = moving.position - moving.velocity * dt;
+        if moving.collides_with(&other) || previous.collides_with(&other) {
+            prop_assert!(moving.sweep_collides_with(&other, dt));
+        }
+    }
+
+    // 子弹一帧移动的距离远大于敌人的高度，敌人位于起点和终点之间
+    #[test]
+    fn fast_bullet_does_not_tunnel_through_enemy(
+        dt in 0.05f32..0.25,
+        speed in 400.0f32..2000.0,
+        start_y in 300.0f32..600.0,
+        along in 0.0f32..1.0,
+        offset in -8.0f32..8.0,
+    ) {
+        let mut bullet = GameObject::new(400.0, start_y - speed * dt, 5.0, 10.0);
+        bullet.velocity = Vec2::new(0.0, -speed);
+        let enemy_y = start_y - speed * dt * (1.0 - along);
+        let enemy = GameObject::new(400.0 + offset, enemy_y, 12.0, 12.0);
+        prop_assert!(bullet.sweep_collides_with(&enemy, dt));
+    }
+
+    #[test]
+    fn clamp_keeps_object_inside_and_is_idempotent(mut object in object(), x in -1000.0f32..2000.0) {
+        object.position.x = x;
+        object.clamp_x(0.0, WINDOW_WIDTH);
+        let bounds = object.bounds();
+        prop_assert!(bounds.left() >= -0.001 && bounds.right() <= WINDOW_WIDTH + 0.001);
+
+        let clamped = object.position.x;
+        object.clamp_x(0.0, WINDOW_WIDTH);
+        prop_assert_eq!(object.position.x, clamped);
+    }
+
+    #[test]
+    fn formations_spawn_inside_the_playfield(
+        seed in any::<u64>(),
+        speed in 50.0f32..300.0,
+        size in 10.0f32..60.0,
+        formation in proptest::sample::select(FORMATIONS.to_vec()),
+        path in proptest::sample::select(PATHS.to_vec()),
+    ) {
+        let mut world = World::new(GameMode::Endless, seed);
+        world.spawn_formation(&wave(speed, size, formation, path, Vec::new()));
+        let gap = world.field.spawn_gap;
+        for enemy in &world.enemies {
+            let bounds = enemy.game_object.bounds();
+            prop_assert!(bounds.left() >= gap && bounds.right() <= WINDOW_WIDTH - gap);
+            prop_assert!(bounds.bottom() <= 0.0, "敌人应当从屏幕上方进入");
+        }
+    }
+
+    // 指定了生成区域时队形中心落在抽中的那条区域里，队形仍然完整地留在场地内
+    #[test]
+    fn lanes_keep_formations_inside_their_zone_and_the_playfield(
+        seed in any::<u64>(),
+        lane in lane(),
+        size in 10.0f32..60.0,
+        formation in proptest::sample::select(vec![Formation::Single, Formation::Line, Formation::Vee]),
+    ) {
+        let mut world = World::new(GameMode::Endless, seed);
+        let unused = SpawnLane { from: 0.0, to: 1.0, weight: 0 };
+        let wave = wave(100.0, size, formation, EnemyPath::Straight, vec![unused, lane]);
+        world.spawn_formation(&wave);
+        let half_width = wave.formation.half_width() + wave.size / 2.0;
+        let (min, max) = lane.range(&world.field, half_width);
+        let center = world.enemies.iter().map(|enemy| enemy.game_object.position.x).sum::<f32>() / world.enemies.len() as f32;
+        prop_assert!(center >= min - 0.01 && center <= max + 0.01);
+        let gap = world.field.spawn_gap;
+        for enemy in &world.enemies {
+            let bounds = enemy.game_object.bounds();
+            prop_assert!(bounds.left() >= gap - 0.01 && bounds.right() <= WINDOW_WIDTH - gap + 0.01);
+        }
+    }
+
+    // 翻转过的区域和原来的区域关于场地中线对称，翻转两次回到原样
+    #[test]
+    fn mirrored_lanes_sit_opposite_the_originals(lane in lane()) {
+        let world = World::new(GameMode::Endless, 0);
+        let wave = wave(100.0, 30.0, Formation::Single, EnemyPath::Straight, vec![lane]);
+        let mirrored = campaign::mirror(&wave);
+        let (min, max) = lane.range(&world.field, 15.0);
+        let (mirrored_min, mirrored_max) = mirrored.lanes[0].range(&world.field, 15.0);
+        prop_assert!((min + mirrored_max - WINDOW_WIDTH).abs() < 0.01);
+        prop_assert!((max + mirrored_min - WINDOW_WIDTH).abs() < 0.01);
+        prop_assert_eq!(mirrored.lanes[0].weight, lane.weight);
+        let back = campaign::mirror(&mirrored).lanes[0];
+        prop_assert!((back.from - lane.from).abs() < 1e-5 && (back.to - lane.to).abs() < 1e-5);
+    }
+
+    #[test]
+    fn endless_enemies_spawn_inside_the_playfield(seed in any::<u64>(), speed in 50.0f32..300.0, rank in 0.0f32..1.0) {
+        let mut world = World::new(GameMode::Endless, seed);
+        world.spawn_enemy(speed, rank);
+        let bounds = world.enemies[0].game_object.bounds();
+        let gap = world.field.spawn_gap;
+        prop_assert!(bounds.left() >= gap && bounds.right() <= WINDOW_WIDTH - gap);
+    }
+
+    #[test]
+    fn each_shot_hits_once_and_each_target_dies_once(
+        (shots, targets, mut pairs) in (1usize..20, 1usize..10)
+            .prop_flat_map(|(shots, targets)| (Just(shots), Just(targets), proptest::collection::vec((0..shots, 0..targets), 0..60))),
+        alive in proptest::collection::vec(proptest::bool::weighted(0.8), 10),
+        hp in proptest::collection::vec(1u32..4, 10),
+    ) {
+        pairs.sort_unstable();
+        pairs.dedup();
+        let alive = &alive[..targets];
+        let hp = &hp[..targets];
+
+        let mut damage = vec![0; targets];
+        let mut used = vec![false; shots];
+        let mut valid = true;
+        let killed = collision::resolve(&pairs, shots, alive, |shot, target| {
+            valid &= !used[shot] && alive[target] && damage[target] < hp[target];
+            used[shot] = true;
+            damage[target] += 1;
+            damage[target] == hp[target]
+        });
+        prop_assert!(valid, "同一发子弹命中了两次，或打中了已经死掉的目标");
+
+        let mut unique = killed.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        prop_assert_eq!(unique.len(), killed.len());
+        for target in 0..targets {
+            prop_assert_eq!(killed.contains(&target), damage[target] == hp[target]);
+        }
+    }
+
+    #[test]
+    fn share_codes_round_trip_and_catch_typos(
+        mode in proptest::sample::select(GameMode::ALL.to_vec()),
+        seed in any::<u64>(),
+        mutators in mutators(),
+        typo_index in any::<proptest::sample::Index>(),
+    ) {
+        let code = ShareCode { mode, seed, mutators };
+        let text = code.encode();
+        prop_assert_eq!(text.len(), 17);
+        prop_assert_eq!(ShareCode::decode(&text), Ok(code));
+        prop_assert_eq!(ShareCode::decode(&text.replace('-', "").to_lowercase()), Ok(code));
+
+        // 抄错一个字符会被校验位发现
+        let mut typo: Vec<char> = text.chars().filter(|&c| c != '-').collect();
+        let index = typo_index.index(typo.len());
+        typo[index] = if typo[index] == 'X' { 'Y' } else { 'X' };
+        let typo: String = typo.into_iter().collect();
+        prop_assert!(ShareCode::decode(&typo).is_err(), "{} -> {}", text, typo);
+    }
+}
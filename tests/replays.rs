@@ -0,0 +1,130 @@
+// 录像回归测试：重放 tests/replays 中录好的对局，结束状态必须和记录的完全一致，
+// 计时、随机数消耗顺序等任何改变模拟结果的修改都会让测试失败。
+// 有意修改了规则时，用 UPDATE_GOLDEN=1 cargo test --test replays 更新期望值；
+// 删除某个录像文件后运行测试会用自动驾驶重新录制
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use space_shooter::replay::{Replay, Snapshot};
+use space_shooter::world::GameMode;
+
+const FRAMES: u32 = 60 * 90;  // 每段录像最长 90 秒
+
+#[derive(Serialize, Deserialize)]
+struct Golden {
+    replay: Replay,
+    expected: Snapshot,
+}
+
+fn check(name: &str, mode: GameMode, seed: u64) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/replays")
+        .join(format!("{}.toml", name));
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+
+    let replay = match std::fs::read_to_string(&path) {
+        Ok(text) => {
+            let golden: Golden = toml::from_str(&text).expect("录像文件格式错误");
+            if !update {
+                let world = golden.replay.play().expect("录像无法重放");
+                assert_eq!(Snapshot::of(&world), golden.expected, "录像 {} 的结果发生了变化", name);
+                return;
+            }
+            golden.replay
+        }
+        Err(_) => Replay::record(mode, seed, FRAMES),
+    };
+
+    let world = replay.play().expect("录像无法重放");
+    let golden = Golden {
+        expected: Snapshot::of(&world),
+        replay,
+    };
+    std::fs::write(&path, toml::to_string(&golden).unwrap()).expect("无法写入录像文件");
+}
+
+#[test]
+fn endless_replay() {
+    check("endless", GameMode::Endless, 1);
+}
+
+#[test]
+fn campaign_replay() {
+    check("campaign", GameMode::Campaign, 2);
+}
+
+#[test]
+fn new_game_plus_replay() {
+    check("new_game_plus", GameMode::NewGamePlus, 3);
+}
+
+#[test]
+fn randomizer_replay() {
+    check("randomizer", GameMode::Randomizer, 4);
+}
+
+// 切换武器的输入也能压缩进录像，W 后面只跟一位栏位数字
+#[test]
+fn replay_inputs_include_weapon_switches() {
+    let replay: Replay = serde_json::from_str(r#"{"mode": "endless", "seed": 1, "inputs": "-W21 RF12 LEW31"}"#).unwrap();
+    let inputs = replay.inputs().unwrap();
+    assert_eq!(inputs.len(), 14);
+    assert_eq!(inputs[0].select, 2);
+    assert!(inputs[1..13].iter().all(|input| input.fire && input.select == 0));
+    assert_eq!((inputs[13].move_x, inputs[13].emp, inputs[13].select), (-1, true, 3));
+}
+
+// 按住蓄力记为 C，跟在开火后面
+#[test]
+fn replay_inputs_include_held_charge() {
+    let replay: Replay = serde_json::from_str(r#"{"mode": "endless", "seed": 1, "inputs": "-FC1 -C40 RCE1 -2"}"#).unwrap();
+    let inputs = replay.inputs().unwrap();
+    assert_eq!(inputs.len(), 44);
+    assert!(inputs[0].fire && inputs[0].charge);
+    assert!(inputs[1..42].iter().all(|input| input.charge));
+    assert!(inputs[41].emp && !inputs[42].charge);
+    assert_eq!(replay.play().unwrap().charged_shots.len(), 1);
+}
+
+// 冲刺记为 S，跟在蓄力后面
+#[test]
+fn replay_inputs_include_dashes() {
+    let replay: Replay = serde_json::from_str(r#"{"mode": "endless", "seed": 1, "inputs": "RS1 LFSE1 R3"}"#).unwrap();
+    let inputs = replay.inputs().unwrap();
+    assert_eq!(inputs.len(), 5);
+    assert!(inputs[0].dash && inputs[1].fire && inputs[1].dash && inputs[1].emp);
+    assert!(!inputs[2].dash);
+    assert!(replay.play().is_ok());
+}
+
+// 专注记为 T，跟在冲刺后面
+#[test]
+fn replay_inputs_include_focus() {
+    let replay: Replay = serde_json::from_str(r#"{"mode": "endless", "seed": 1, "inputs": "-T30 RFSTE1 -2"}"#).unwrap();
+    let inputs = replay.inputs().unwrap();
+    assert_eq!(inputs.len(), 33);
+    assert!(inputs[..31].iter().all(|input| input.focus));
+    assert!(inputs[30].dash && inputs[30].emp && !inputs[31].focus);
+    assert!(replay.play().unwrap().focus.meter() < 1.0);
+}
+
+// 上下移动跟在左右方向后面，旧录像里没有 U 和 D 时照常读取
+#[test]
+fn replay_inputs_include_vertical_movement() {
+    let replay: Replay = serde_json::from_str(r#"{"mode": "endless", "seed": 1, "inputs": "LU3 -DF2 R1"}"#).unwrap();
+    let inputs = replay.inputs().unwrap();
+    let moves: Vec<(i8, i8)> = inputs.iter().map(|input| (input.move_x, input.move_y)).collect();
+    assert_eq!(moves, [(-1, -1), (-1, -1), (-1, -1), (0, 1), (0, 1), (1, 0)]);
+    assert!(inputs[3].fire);
+}
+
+// 同一段录像连续重放两次必须得到同样的结果
+#[test]
+fn replay_is_deterministic() {
+    let replay = Replay::record(GameMode::Randomizer, 42, 60 * 30);
+    let first = Snapshot::of(&replay.play().unwrap());
+    let second = Snapshot::of(&replay.play().unwrap());
+    assert_eq!(first, second);
+}